@@ -143,6 +143,87 @@ fn dwarf_mostly(src: &Path, dst: &str) {
     run("strip", ["--only-keep-debug".as_ref(), dst.as_os_str()]).expect("failed to run `strip`")
 }
 
+/// Compress the DWARF debug sections of the ELF binary `src` using the
+/// given `compression` scheme (as understood by `objcopy`) and store
+/// the result in `dst`.
+fn compress_debug(src: &Path, dst: &str, compression: &str) {
+    let dst = src.with_file_name(dst);
+    println!("cargo:rerun-if-changed={}", src.display());
+    println!("cargo:rerun-if-changed={}", dst.display());
+
+    let compress = format!("--compress-debug-sections={compression}");
+    run(
+        "objcopy",
+        [compress.as_ref(), src.as_os_str(), dst.as_os_str()],
+    )
+    .expect("failed to run `objcopy`")
+}
+
+/// Compress the file `src` using gzip, storing the result in `<src>.gz`.
+fn gzip(src: &Path) {
+    let dst = src.with_file_name(format!("{}.gz", src.file_name().unwrap().to_string_lossy()));
+    println!("cargo:rerun-if-changed={}", src.display());
+    println!("cargo:rerun-if-changed={}", dst.display());
+
+    run("gzip", ["-k".as_ref(), "-f".as_ref(), src.as_os_str()]).expect("failed to run `gzip`")
+}
+
+/// Compress the file `src` using zstd, storing the result in `<src>.zst`.
+fn zstd(src: &Path) {
+    let dst = src.with_file_name(format!(
+        "{}.zst",
+        src.file_name().unwrap().to_string_lossy()
+    ));
+    println!("cargo:rerun-if-changed={}", src.display());
+    println!("cargo:rerun-if-changed={}", dst.display());
+
+    run(
+        "zstd",
+        [
+            "-q".as_ref(),
+            "-f".as_ref(),
+            src.as_os_str(),
+            "-o".as_ref(),
+            dst.as_os_str(),
+        ],
+    )
+    .expect("failed to run `zstd`")
+}
+
+/// Split the debug information of the ELF binary `src` off into `dbg`,
+/// strip the binary of it (and its symbol table), and store the result
+/// in `dst`, linked to `dbg` via a `.gnu_debuglink` section.
+fn debug_link(src: &Path, dst: &str, dbg: &str) {
+    let dst = src.with_file_name(dst);
+    let dbg = src.with_file_name(dbg);
+    println!("cargo:rerun-if-changed={}", src.display());
+    println!("cargo:rerun-if-changed={}", dst.display());
+    println!("cargo:rerun-if-changed={}", dbg.display());
+
+    run(
+        "objcopy",
+        [
+            "--only-keep-debug".as_ref(),
+            src.as_os_str(),
+            dbg.as_os_str(),
+        ],
+    )
+    .expect("failed to run `objcopy`");
+
+    let mut debug_link = OsString::from("--add-gnu-debuglink=");
+    let () = debug_link.push(&dbg);
+    run(
+        "objcopy",
+        [
+            "--strip-all".as_ref(),
+            debug_link.as_os_str(),
+            src.as_os_str(),
+            dst.as_os_str(),
+        ],
+    )
+    .expect("failed to run `objcopy`")
+}
+
 /// Unpack an xz compressed file.
 #[cfg(feature = "xz2")]
 fn unpack_xz(src: &Path, dst: &Path) {
@@ -236,12 +317,72 @@ fn prepare_test_files(crate_root: &Path) {
         "libtest-so-no-separate-code.so",
         &["-shared", "-fPIC", "-Wl,-z,noseparate-code"],
     );
+    cc(&src, "libtest-so-dwarf.so", &["-shared", "-fPIC", "-g"]);
+    let dwarf_so = crate_root.join("data").join("libtest-so-dwarf.so");
+    debug_link(
+        &dwarf_so,
+        "libtest-so-debug-link.so",
+        "libtest-so-debug-link.dbg",
+    );
+    cc(
+        &src,
+        "libtest-so-32.so",
+        &[
+            "-m32",
+            "-shared",
+            "-fPIC",
+            "-nostdlib",
+            "-Wl,--build-id=sha1",
+        ],
+    );
 
     let src = crate_root.join("data").join("test-exe.c");
     cc(&src, "test-no-debug.bin", &["-g0", "-Wl,--build-id=none"]);
     cc(&src, "test-dwarf-v4.bin", &["-gdwarf-4"]);
     cc(&src, "test-dwarf-v5.bin", &["-gdwarf-5"]);
 
+    cc(
+        &src,
+        "test-dwarf-build-id.bin",
+        &["-gdwarf-4", "-Wl,--build-id=sha1"],
+    );
+    let src = crate_root.join("data").join("test-dwarf-build-id.bin");
+    debug_link(&src, "test-debug-link.bin", "test-debug-link.dbg");
+
+    let src = crate_root.join("data").join("test-dwarf-v4.bin");
+    compress_debug(&src, "test-dwarf-zlib.bin", "zlib");
+    compress_debug(&src, "test-dwarf-zstd.bin", "zstd");
+    compress_debug(&src, "test-dwarf-zlib-gnu.bin", "zlib-gnu");
+
+    let src = crate_root.join("data").join("test-inlined.c");
+    cc(&src, "test-inlined-dwarf-v4.bin", &["-gdwarf-4", "-O1"]);
+    cc(&src, "test-inlined-dwarf-v5.bin", &["-gdwarf-5", "-O1"]);
+
+    let src = crate_root.join("data").join("test-inlined-dwarf-v4.bin");
+    gsym(&src, "test-inlined.gsym");
+
+    let src = crate_root.join("data").join("test-kmod.c");
+    cc(&src, "test-kmod.ko", &["-c", "-gdwarf-4", "-O0"]);
+
+    let src = crate_root.join("data").join("test-kmod.ko");
+    gzip(&src);
+    zstd(&src);
+
+    let src = crate_root.join("data").join("test-mangled.c");
+    cc(&src, "test-mangled.bin", &["-gdwarf-4"]);
+
+    let src = crate_root.join("data").join("test-ranges.c");
+    cc(
+        &src,
+        "test-ranges-dwarf-v4.bin",
+        &["-gdwarf-4", "-O2", "-freorder-blocks-and-partition"],
+    );
+    cc(
+        &src,
+        "test-ranges-dwarf-v5.bin",
+        &["-gdwarf-5", "-O2", "-freorder-blocks-and-partition"],
+    );
+
     let src = crate_root.join("data").join("test-stable-addresses.c");
     let src_cu2 = crate_root.join("data").join("test-stable-addresses-cu2.c");
     let src_cu2 = src_cu2.to_str().unwrap();
@@ -262,6 +403,23 @@ fn prepare_test_files(crate_root: &Path) {
             src_cu2,
         ],
     );
+    cc(
+        &src,
+        "test-stable-addresses-32.bin",
+        &[
+            "-m32",
+            "-fno-pic",
+            "-no-pie",
+            "-gdwarf-4",
+            "-T",
+            ld_script,
+            "-Wl,--build-id=none",
+            "-O0",
+            "-nostdlib",
+            // TODO: Eventually we may want to make `cc` multi-input-file aware.
+            src_cu2,
+        ],
+    );
     cc(
         &src,
         "test-stable-addresses-no-dwarf.bin",
@@ -0,0 +1,25 @@
+/*
+ * The sample program is used to generate test-inlined-dwarf-v*.bin, for
+ * testing the reporting of inlined functions.
+ */
+
+static volatile int value;
+
+static inline __attribute__((always_inline)) void
+inlined_call(int x) {
+  value += x;
+  value *= 3;
+}
+
+static inline __attribute__((always_inline)) void
+inlined_outer(int x) {
+  value -= 1;
+  inlined_call(x);
+  value -= 2;
+}
+
+int
+main(int argc, const char *argv[]) {
+  inlined_outer(argc);
+  return value;
+}
@@ -0,0 +1,23 @@
+/*
+ * The sample program is used to generate test-kmod.ko, a relocatable
+ * object file resembling a kernel module, with functions placed in
+ * different sections.
+ */
+
+int kmod_counter;
+
+__attribute__((noinline))
+int kmod_add(int a, int b) {
+  return a + b;
+}
+
+__attribute__((noinline))
+int kmod_work(int n) {
+  kmod_counter += kmod_add(n, 1);
+  return kmod_counter;
+}
+
+__attribute__((noinline, section(".init.text")))
+int kmod_init(void) {
+  return kmod_work(42);
+}
@@ -0,0 +1,25 @@
+/*
+ * The sample program is used to generate test-mangled.bin, for testing
+ * the demangling of symbol names. Functions are explicitly assigned
+ * names mangled according to the C++ and Rust (v0) schemes.
+ */
+
+/* test::cpp_function(int) */
+int cpp_function(int x) __asm__("_ZN4test12cpp_functionEi");
+/* test::rust_function */
+int rust_function(int x) __asm__("_RNvCs15kBYyAo9fc_4test13rust_function");
+
+__attribute__((noinline)) int
+cpp_function(int x) {
+  return x * 3;
+}
+
+__attribute__((noinline)) int
+rust_function(int x) {
+  return cpp_function(x) + 1;
+}
+
+int
+main(int argc, const char *argv[]) {
+  return rust_function(argc);
+}
@@ -0,0 +1,26 @@
+/*
+ * The sample program is used to generate test-ranges-dwarf-v*.bin, for
+ * testing the handling of functions not occupying a contiguous address
+ * range.
+ */
+
+#include <stdlib.h>
+
+static volatile int value;
+
+__attribute__((noinline)) int
+partitioned(int x) {
+  if (__builtin_expect(x > 1000, 0)) {
+    /* The unlikely path is moved into a separate `.text.unlikely`
+     * section. */
+    value = x * 7;
+    abort();
+  }
+  value += x;
+  return value;
+}
+
+int
+main(int argc, const char *argv[]) {
+  return partitioned(argc);
+}
@@ -4,6 +4,7 @@ use anyhow::bail;
 use anyhow::Context as _;
 use anyhow::Result;
 
+use blazesym::symbolize::InlinedFn;
 use blazesym::symbolize::Process;
 use blazesym::symbolize::Source;
 use blazesym::symbolize::SymbolizedResult;
@@ -30,23 +31,35 @@ print its symbol, the file name of the source, and the line number.",
         .with_context(|| format!("failed to parse address: {addr_str}"))?;
 
     let src = Source::Process(Process::new(pid.into()));
-    let symbolizer = Symbolizer::new();
+    let symbolizer = Symbolizer::builder().enable_inlined_fns(true).build();
     let symlist = symbolizer
         .symbolize(&src, &[addr])
         .with_context(|| format!("failed to symbolize address 0x{addr:x}"))?;
     if !symlist[0].is_empty() {
         let SymbolizedResult {
             symbol,
+            raw_symbol: _,
             addr: sym_addr,
             path,
             line,
             column: _,
+            module: _,
+            inlined,
         } = &symlist[0][0];
         println!(
             "0x{addr:x} {symbol}@0x{addr:x}+{} {}:{line}",
             addr - sym_addr,
             path.display(),
         );
+        for InlinedFn {
+            name,
+            call_path,
+            call_line,
+            ..
+        } in inlined
+        {
+            println!("  {name} (inlined at {}:{call_line})", call_path.display());
+        }
     } else {
         println!("0x{addr:x} is not found");
     }
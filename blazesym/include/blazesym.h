@@ -38,6 +38,10 @@ typedef enum blaze_user_addr_meta_kind {
    * [`blaze_user_addr_meta_variant::binary`] is valid.
    */
   BLAZE_USER_ADDR_BINARY,
+  /**
+   * [`blaze_user_addr_meta_variant::vdso`] is valid.
+   */
+  BLAZE_USER_ADDR_VDSO,
 } blaze_user_addr_meta_kind;
 
 /**
@@ -61,8 +65,18 @@ typedef struct blaze_inspector blaze_inspector;
  */
 typedef struct blaze_normalizer blaze_normalizer;
 
+/**
+ * A type for loading source code snippets for source code locations.
+ */
+typedef struct SnippetLoader SnippetLoader;
+
 /**
  * Symbolizer provides an interface to symbolize addresses.
+ *
+ * A `Symbolizer` is `Send` and `Sync`. All internal caches are
+ * synchronized, so that a single instance can be shared between
+ * threads (e.g., by means of an [`Arc`][std::sync::Arc]) and used
+ * to symbolize addresses concurrently.
  */
 typedef struct blaze_symbolizer blaze_symbolizer;
 
@@ -95,6 +109,18 @@ typedef struct blaze_inspect_elf_src {
   bool debug_info;
 } blaze_inspect_elf_src;
 
+/**
+ * An object representing a Gsym inspection source.
+ *
+ * C ABI compatible version of [`inspect::Gsym`].
+ */
+typedef struct blaze_inspect_gsym_src {
+  /**
+   * The path to the Gsym file. This member is always present.
+   */
+  const char *path;
+} blaze_inspect_gsym_src;
+
 /**
  * C compatible version of [`Binary`].
  */
@@ -113,6 +139,20 @@ typedef struct blaze_user_addr_meta_binary {
   uint8_t *build_id;
 } blaze_user_addr_meta_binary;
 
+/**
+ * C compatible version of [`Vdso`].
+ */
+typedef struct blaze_user_addr_meta_vdso {
+  /**
+   * The length of the build ID, in bytes.
+   */
+  size_t build_id_len;
+  /**
+   * The optional build ID of the vDSO, if found.
+   */
+  uint8_t *build_id;
+} blaze_user_addr_meta_vdso;
+
 /**
  * C compatible version of [`Unknown`].
  */
@@ -128,6 +168,10 @@ typedef union blaze_user_addr_meta_variant {
    * Valid on [`blaze_user_addr_meta_kind::BLAZE_USER_ADDR_BINARY`].
    */
   struct blaze_user_addr_meta_binary binary;
+  /**
+   * Valid on [`blaze_user_addr_meta_kind::BLAZE_USER_ADDR_VDSO`].
+   */
+  struct blaze_user_addr_meta_vdso vdso;
   /**
    * Valid on [`blaze_user_addr_meta_kind::BLAZE_USER_ADDR_UNKNOWN`].
    */
@@ -188,11 +232,97 @@ typedef struct blaze_normalized_user_addrs {
   struct blaze_normalized_addr *addrs;
 } blaze_normalized_user_addrs;
 
+/**
+ * The type of a snippet loader.
+ */
+typedef struct SnippetLoader blaze_snippet_loader;
+
+/**
+ * A path prefix substitution rule, as used by
+ * [`blaze_snippet_loader_opts`].
+ */
+typedef struct blaze_path_subst {
+  /**
+   * The path prefix to replace.
+   */
+  const char *from;
+  /**
+   * The path prefix to replace `from` with.
+   */
+  const char *to;
+} blaze_path_subst;
+
+/**
+ * Options for configuring `blaze_snippet_loader` objects.
+ */
+typedef struct blaze_snippet_loader_opts {
+  /**
+   * Array of directories to search for source files.
+   *
+   * `src_roots` is an array of `src_roots_len` C strings. It may be
+   * `NULL` if `src_roots_len` is zero.
+   */
+  const char *const *src_roots;
+  /**
+   * The number of elements in `src_roots`.
+   */
+  size_t src_roots_len;
+  /**
+   * Array of path prefix substitution rules, checked in order.
+   *
+   * `substitutions` is an array of `substitutions_len` objects. It
+   * may be `NULL` if `substitutions_len` is zero.
+   */
+  const struct blaze_path_subst *substitutions;
+  /**
+   * The number of elements in `substitutions`.
+   */
+  size_t substitutions_len;
+  /**
+   * The number of lines to report before and after the line of
+   * interest.
+   */
+  size_t context_lines;
+} blaze_snippet_loader_opts;
+
+/**
+ * A snippet of source code surrounding a line of interest.
+ *
+ * Instances are returned by [`blaze_snippet_load`] and should be
+ * freed by calling [`blaze_snippet_free`].
+ */
+typedef struct blaze_snippet {
+  /**
+   * The path of the source file on the local system.
+   */
+  const char *path;
+  /**
+   * The (1-based) number of the line of interest.
+   */
+  size_t line;
+  /**
+   * The (1-based) number of the first line in `lines`.
+   */
+  size_t first_line;
+  /**
+   * The number of lines in `lines`.
+   */
+  size_t line_cnt;
+  /**
+   * An array of `line_cnt` C strings, without line terminators.
+   */
+  const char *const *lines;
+} blaze_snippet;
+
 /**
  * A placeholder symbolizer for C API.
  *
  * It is returned by [`blaze_symbolizer_new`] and should be free by
  * [`blaze_symbolizer_free`].
+ *
+ * A `blaze_symbolizer` is thread safe: the same instance may be used
+ * by multiple threads concurrently, with the exception of
+ * [`blaze_symbolizer_free`], which must not race with any other use.
  */
 typedef struct blaze_symbolizer blaze_symbolizer;
 
@@ -210,8 +340,54 @@ typedef struct blaze_symbolizer_opts {
    * This setting implies `debug_syms` (and forces it to `true`).
    */
   bool src_location;
+  /**
+   * Whether to report functions inlined at symbolized addresses.
+   *
+   * This setting requires `debug_syms` to be enabled. Symbolizers
+   * created with `blaze_symbolizer_new` do not report inlined
+   * functions.
+   */
+  bool inlined_fns;
+  /**
+   * Whether to demangle Rust and C++ symbol names.
+   */
+  bool demangle;
+  /**
+   * Array of directories to search for separate debug files.
+   *
+   * If `NULL`, the default directories are searched. Otherwise,
+   * `debug_dirs` is an array of `debug_dirs_len` C strings.
+   */
+  const char *const *debug_dirs;
+  /**
+   * The number of elements in `debug_dirs`.
+   */
+  size_t debug_dirs_len;
 } blaze_symbolizer_opts;
 
+/**
+ * A function inlined at a symbolized address.
+ */
+typedef struct blaze_inlined_fn {
+  /**
+   * The name of the inlined function.
+   */
+  const char *name;
+  /**
+   * The path of the source file containing the call site of the
+   * function, i.e., the location it got inlined at.
+   */
+  const char *call_path;
+  /**
+   * The line number of the call site.
+   */
+  size_t call_line;
+  /**
+   * The column number of the call site.
+   */
+  size_t call_column;
+} blaze_inlined_fn;
+
 /**
  * The result of symbolization of an address.
  *
@@ -221,8 +397,14 @@ typedef struct blaze_symbolizer_opts {
 typedef struct blaze_sym {
   /**
    * The symbol name is where the given address should belong to.
+   *
+   * If demangling is enabled, this is the demangled name.
    */
   const char *symbol;
+  /**
+   * The symbol name before any demangling.
+   */
+  const char *raw_symbol;
   /**
    * The address (i.e.,the first byte) is where the symbol is located.
    *
@@ -234,11 +416,28 @@ typedef struct blaze_sym {
    * The path of the source file defining the symbol.
    */
   const char *path;
+  /**
+   * The name of the kernel module the symbol belongs to or `NULL` if
+   * the symbol is not part of a kernel module.
+   */
+  const char *module;
   /**
    * The line number on which the symbol was to be found in the source code.
    */
   size_t line;
   size_t column;
+  /**
+   * The number of functions inlined at the address.
+   */
+  size_t inlined_cnt;
+  /**
+   * The functions inlined at the address.
+   *
+   * `inlined` is an array of [`blaze_inlined_fn`] in the size
+   * `inlined_cnt`, ordered from the function inlined directly into
+   * `symbol` to the innermost one.
+   */
+  const struct blaze_inlined_fn *inlined;
 } blaze_sym;
 
 /**
@@ -322,6 +521,21 @@ typedef struct blaze_symbolize_src_kernel {
    * `"/usr/lib/debug/boot/"`.
    */
   const char *kernel_image;
+  /**
+   * The path of a directory containing kernel modules.
+   *
+   * Addresses inside of loaded kernel modules are symbolized using
+   * the module's `.ko` file found in this directory (or one of its
+   * sub-directories), as listed by its `modules.dep` file, if
+   * present. gzip (`.ko.gz`) and zstd (`.ko.zst`) compressed
+   * modules are supported if the `zlib` and `zstd` features,
+   * respectively, are enabled; xz (`.ko.xz`) compressed ones are
+   * not. For a `NULL` value, the modules directory of the running
+   * kernel, `"/lib/modules/<release>/"`, is used, unless `kallsyms`
+   * is set, in which case addresses inside of kernel modules are
+   * symbolized based on kallsyms alone.
+   */
+  const char *modules_dir;
 } blaze_symbolize_src_kernel;
 
 /**
@@ -379,11 +593,33 @@ const struct blaze_sym_info *const *blaze_inspect_syms_elf(const struct blaze_in
                                                            size_t name_cnt);
 
 /**
- * Free an array returned by [`blaze_inspect_syms_elf`].
+ * Lookup symbol information in a Gsym file.
+ *
+ * Return an array with the same size as the input names. The caller should
+ * free the returned array by calling [`blaze_inspect_syms_free`].
+ *
+ * Every name in the input name list may have more than one address.
+ * The respective entry in the returned array is an array containing
+ * all addresses and ended with a null (0x0).
+ *
+ * # Safety
+ * The `inspector` object should have been created using
+ * [`blaze_inspector_new`], `src` needs to point to a valid object, and `names`
+ * needs to be a valid pointer to `name_cnt` strings.
+ */
+const struct blaze_sym_info *const *blaze_inspect_syms_gsym(const struct blaze_inspector *inspector,
+                                                            const struct blaze_inspect_gsym_src *src,
+                                                            const char *const *names,
+                                                            size_t name_cnt);
+
+/**
+ * Free an array returned by [`blaze_inspect_syms_elf`] or
+ * [`blaze_inspect_syms_gsym`].
  *
  * # Safety
  *
- * The pointer must be returned by [`blaze_inspect_syms_elf`].
+ * The pointer must be returned by [`blaze_inspect_syms_elf`] or
+ * [`blaze_inspect_syms_gsym`].
  *
  */
 void blaze_inspect_syms_free(const struct blaze_sym_info *const *syms);
@@ -478,6 +714,104 @@ struct blaze_normalized_user_addrs *blaze_normalize_user_addrs_sorted(const stru
  */
 void blaze_user_addrs_free(struct blaze_normalized_user_addrs *addrs);
 
+/**
+ * Encode normalized user addresses in a compact and versioned binary
+ * format, suitable for transfer to a different system.
+ *
+ * C ABI compatible version of [`NormalizedUserAddrs::encode`]. On
+ * success, a pointer to the encoded data is returned and its length
+ * is stored in `len`. Returns `NULL` on error. The resulting data
+ * should be freed using [`blaze_user_addrs_encoded_free`].
+ *
+ * # Safety
+ * `addrs` needs to be a valid object, as returned by
+ * [`blaze_normalize_user_addrs`], for example, and `len` needs to be
+ * a valid pointer.
+ */
+uint8_t *blaze_user_addrs_encode(const struct blaze_normalized_user_addrs *addrs,
+                                 size_t *len);
+
+/**
+ * Free data as returned by [`blaze_user_addrs_encode`].
+ *
+ * # Safety
+ * `data` and `len` should have been returned by
+ * [`blaze_user_addrs_encode`].
+ */
+void blaze_user_addrs_encoded_free(uint8_t *data,
+                                   size_t len);
+
+/**
+ * Decode normalized user addresses, as encoded by
+ * [`blaze_user_addrs_encode`].
+ *
+ * C ABI compatible version of [`NormalizedUserAddrs::decode`].
+ * Returns `NULL` on error. The resulting object should be freed using
+ * [`blaze_user_addrs_free`].
+ *
+ * # Safety
+ * Callers need to pass in a valid `data` pointer, pointing to `len`
+ * bytes.
+ */
+struct blaze_normalized_user_addrs *blaze_user_addrs_decode(const uint8_t *data,
+                                                            size_t len);
+
+/**
+ * Create an instance of a snippet loader.
+ *
+ * The returned pointer should be released using
+ * [`blaze_snippet_loader_free`] once it is no longer needed.
+ */
+blaze_snippet_loader *blaze_snippet_loader_new(void);
+
+/**
+ * Create an instance of a snippet loader with configurable options.
+ *
+ * The returned pointer should be released using
+ * [`blaze_snippet_loader_free`] once it is no longer needed.
+ *
+ * # Safety
+ * `opts` needs to be a valid pointer. `opts->src_roots` and
+ * `opts->substitutions` need to be valid pointers to arrays of the
+ * respective number of elements, and all contained strings need to be
+ * valid C strings.
+ */
+blaze_snippet_loader *blaze_snippet_loader_new_opts(const struct blaze_snippet_loader_opts *opts);
+
+/**
+ * Free a snippet loader.
+ *
+ * # Safety
+ * The pointer must have been returned by [`blaze_snippet_loader_new`]
+ * or [`blaze_snippet_loader_new_opts`].
+ */
+void blaze_snippet_loader_free(blaze_snippet_loader *loader);
+
+/**
+ * Load the source code surrounding `line` of the source file `path`,
+ * as reported by symbolization (e.g., in [`blaze_sym::path`] and
+ * [`blaze_sym::line`]).
+ *
+ * Returns `NULL` on error, for example if the source file could not
+ * be found. The result should be freed using [`blaze_snippet_free`].
+ *
+ * # Safety
+ * `loader` needs to be a valid pointer as returned by any of the
+ * `blaze_snippet_loader_new*` variants. `path` needs to be a valid C
+ * string.
+ */
+const struct blaze_snippet *blaze_snippet_load(const blaze_snippet_loader *loader,
+                                               const char *path,
+                                               size_t line);
+
+/**
+ * Free a snippet as returned by [`blaze_snippet_load`].
+ *
+ * # Safety
+ * The pointer must have been returned by [`blaze_snippet_load`].
+ */
+void blaze_snippet_free(const struct blaze_snippet *snippet);
+
 /**
  * Create an instance of a symbolizer.
  */
@@ -487,7 +821,9 @@ blaze_symbolizer *blaze_symbolizer_new(void);
  * Create an instance of a symbolizer with configurable options.
  *
  * # Safety
- * `opts` needs to be a valid pointer.
+ * `opts` needs to be a valid pointer. `opts->debug_dirs` needs to be
+ * `NULL` or a valid pointer to an array of `opts->debug_dirs_len` valid
+ * C strings.
  */
 blaze_symbolizer *blaze_symbolizer_new_opts(const struct blaze_symbolizer_opts *opts);
 
@@ -0,0 +1,121 @@
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::ops::Range;
+
+use crate::util::ReadRaw as _;
+
+
+/// The magic number identifying BTF data (in native byte order).
+const BTF_MAGIC: u16 = 0xeb9f;
+
+
+/// BTF data, as used for looking up strings referenced by BPF line
+/// information.
+#[derive(Debug)]
+pub(crate) struct Btf {
+    /// The raw BTF data.
+    data: Vec<u8>,
+    /// The range of the string section inside of `data`.
+    strs: Range<usize>,
+}
+
+impl Btf {
+    /// Parse raw BTF data, as retrieved from the kernel.
+    pub fn parse(data: Vec<u8>) -> Result<Self> {
+        fn parse_impl(mut data: &[u8]) -> Option<Result<Range<usize>>> {
+            let magic = data.read_u16()?;
+            if magic != BTF_MAGIC {
+                return Some(Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("encountered invalid BTF magic: {magic:#x}"),
+                )))
+            }
+            let _version = data.read_u8()?;
+            let _flags = data.read_u8()?;
+            let hdr_len = data.read_u32()? as usize;
+            let _type_off = data.read_u32()?;
+            let _type_len = data.read_u32()?;
+            let str_off = data.read_u32()? as usize;
+            let str_len = data.read_u32()? as usize;
+
+            let start = hdr_len.checked_add(str_off)?;
+            let end = start.checked_add(str_len)?;
+            Some(Ok(start..end))
+        }
+
+        let strs = parse_impl(&data).unwrap_or_else(|| {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "failed to parse BTF header",
+            ))
+        })?;
+
+        if strs.end > data.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "BTF string section is out of bounds",
+            ))
+        }
+        Ok(Self { data, strs })
+    }
+
+    /// Retrieve the string at `offset` in the string section.
+    pub fn name(&self, offset: u32) -> Option<&str> {
+        let mut strs = self.data.get(self.strs.clone())?.get(offset as usize..)?;
+        let name = strs.read_cstr()?;
+        name.to_str().ok()
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use test_log::test;
+
+
+    /// Create raw BTF data without any types and with a string section
+    /// containing the provided strings (in addition to the mandatory
+    /// empty string at offset zero).
+    pub(crate) fn make_btf(strs: &[&str]) -> Vec<u8> {
+        let mut str_sec = vec![0];
+        for s in strs {
+            let () = str_sec.extend_from_slice(s.as_bytes());
+            let () = str_sec.push(0);
+        }
+
+        let hdr_len = 24u32;
+        let mut data = Vec::new();
+        let () = data.extend_from_slice(&BTF_MAGIC.to_ne_bytes());
+        let () = data.push(1);
+        let () = data.push(0);
+        let () = data.extend_from_slice(&hdr_len.to_ne_bytes());
+        let () = data.extend_from_slice(&0u32.to_ne_bytes());
+        let () = data.extend_from_slice(&0u32.to_ne_bytes());
+        let () = data.extend_from_slice(&0u32.to_ne_bytes());
+        let () = data.extend_from_slice(&(str_sec.len() as u32).to_ne_bytes());
+        let () = data.extend_from_slice(&str_sec);
+        data
+    }
+
+    /// Check that we can parse BTF data and look up strings.
+    #[test]
+    fn btf_parsing() {
+        let btf = Btf::parse(make_btf(&["/src/prog.bpf.c", "return 0;"])).unwrap();
+        assert_eq!(btf.name(0), Some(""));
+        assert_eq!(btf.name(1), Some("/src/prog.bpf.c"));
+        assert_eq!(btf.name(17), Some("return 0;"));
+        assert_eq!(btf.name(5), Some("/prog.bpf.c"));
+        assert_eq!(btf.name(100), None);
+
+        let mut data = make_btf(&[]);
+        data[0] = 0;
+        assert_eq!(Btf::parse(data).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let mut data = make_btf(&["abc"]);
+        let () = data.truncate(data.len() - 1);
+        assert_eq!(Btf::parse(data).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
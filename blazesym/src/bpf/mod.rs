@@ -0,0 +1,19 @@
+//! Support for symbolizing JIT-ed BPF programs.
+//!
+//! The kernel reports JIT-ed BPF programs as `bpf_prog_<tag>_<name>`
+//! symbols in kallsyms. Source code location information is provided
+//! by means of the programs' BTF based line information, which can be
+//! retrieved via the `bpf` system call.
+
+mod btf;
+mod prog;
+mod sys;
+
+pub(crate) use prog::parse_prog_tag;
+pub(crate) use prog::BpfProgCache;
+pub(crate) use prog::BpfResolver;
+
+#[cfg(test)]
+pub(crate) use prog::tests::make_prog;
+#[cfg(test)]
+pub(crate) use prog::tests::PROG_ADDR;
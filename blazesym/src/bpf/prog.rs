@@ -0,0 +1,627 @@
+use std::collections::HashSet;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::log::warn;
+use crate::symbolize::AddrLineInfo;
+use crate::util::Pod;
+use crate::util::ReadRaw as _;
+use crate::Addr;
+
+use super::btf::Btf;
+use super::sys;
+use super::sys::bpf_btf_info;
+use super::sys::bpf_prog_info;
+
+
+/// The tag of a BPF program, as reported by the kernel.
+pub(crate) type BpfTag = [u8; 8];
+
+
+/// Parse the tag of a BPF program from the name of the kallsyms symbol
+/// of one of its JIT-ed functions, of the form `bpf_prog_<tag>_<name>`.
+pub(crate) fn parse_prog_tag(sym: &str) -> Option<BpfTag> {
+    let rest = sym.strip_prefix("bpf_prog_")?;
+    let hex = rest.get(..16)?;
+    if !hex.is_ascii() || !(rest[16..].is_empty() || rest[16..].starts_with('_')) {
+        return None
+    }
+
+    let mut tag = BpfTag::default();
+    for (i, byte) in tag.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(tag)
+}
+
+
+/// `struct bpf_line_info` as defined by the kernel.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct BpfLineInfo {
+    /// The offset of the first instruction covered, in units of BPF
+    /// instructions.
+    pub insn_off: u32,
+    /// The offset of the source file name in the BTF string section.
+    pub file_name_off: u32,
+    /// The offset of the source line in the BTF string section.
+    pub line_off: u32,
+    /// The line number (upper 22 bits) and column (lower 10 bits).
+    pub line_col: u32,
+}
+
+// SAFETY: `BpfLineInfo` is valid for any bit pattern.
+unsafe impl Pod for BpfLineInfo {}
+
+impl BpfLineInfo {
+    #[inline]
+    fn line(&self) -> u32 {
+        self.line_col >> 10
+    }
+
+    #[inline]
+    fn column(&self) -> u32 {
+        self.line_col & 0x3ff
+    }
+}
+
+
+/// Read `count` records of `rec_size` bytes each from `data`, each
+/// starting with a `T`.
+fn read_records<T>(data: &[u8], count: usize, rec_size: usize) -> Result<Vec<T>>
+where
+    T: Pod,
+{
+    if count == 0 {
+        return Ok(Vec::new())
+    }
+    if rec_size < size_of::<T>() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("encountered unexpected BPF record size: {rec_size}"),
+        ))
+    }
+
+    data.chunks_exact(rec_size)
+        .take(count)
+        .map(|mut rec| {
+            rec.read_pod::<T>()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read BPF record"))
+        })
+        .collect()
+}
+
+/// Load the BTF object with the given ID from the kernel.
+fn load_btf(id: u32) -> Result<Btf> {
+    let fd = sys::btf_fd(id)?;
+    let mut info = bpf_btf_info::default();
+    let () = sys::obj_info(&fd, &mut info)?;
+
+    let mut data = vec![0u8; info.btf_size as usize];
+    let mut info = bpf_btf_info {
+        btf: data.as_mut_ptr() as u64,
+        btf_size: info.btf_size,
+        ..Default::default()
+    };
+    let () = sys::obj_info(&fd, &mut info)?;
+    let () = data.truncate(info.btf_size as usize);
+    Btf::parse(data)
+}
+
+
+/// Information about a JIT-ed BPF program, as necessary for
+/// symbolization.
+#[derive(Debug)]
+pub(crate) struct BpfProgInfo {
+    /// The start addresses of the program's JIT-ed functions.
+    pub jited_ksyms: Vec<Addr>,
+    /// The lengths of the program's JIT-ed functions, in the same order
+    /// as `jited_ksyms`.
+    pub jited_func_lens: Vec<u32>,
+    /// The program's line information.
+    pub line_info: Vec<BpfLineInfo>,
+    /// The JIT-ed addresses of each `line_info` entry.
+    pub jited_line_info: Vec<Addr>,
+    /// The BTF data the line information references, if available.
+    pub btf: Option<Btf>,
+    /// The tags of the program and all of its sub-programs.
+    pub tags: Vec<BpfTag>,
+}
+
+impl BpfProgInfo {
+    /// Load information about the BPF program with the given ID from
+    /// the kernel, if it or any of its sub-programs has one of the
+    /// provided `tags`.
+    pub fn load(id: u32, tags: &HashSet<BpfTag>) -> Result<Option<Self>> {
+        let fd = sys::prog_fd(id)?;
+        let mut info = bpf_prog_info::default();
+        let () = sys::obj_info(&fd, &mut info)?;
+
+        let mut prog_tags = vec![BpfTag::default(); info.nr_prog_tags as usize];
+        if !prog_tags.is_empty() {
+            let mut tag_info = bpf_prog_info {
+                nr_prog_tags: info.nr_prog_tags,
+                prog_tags: prog_tags.as_mut_ptr() as u64,
+                ..Default::default()
+            };
+            let () = sys::obj_info(&fd, &mut tag_info)?;
+            let () = prog_tags.truncate(tag_info.nr_prog_tags.min(info.nr_prog_tags) as usize);
+        }
+
+        let prog_tags = [info.tag].into_iter().chain(prog_tags).collect::<Vec<_>>();
+        if !prog_tags.iter().any(|tag| tags.contains(tag)) {
+            return Ok(None)
+        }
+
+        let line_rec_size = info.line_info_rec_size as usize;
+        let jited_line_rec_size = info.jited_line_info_rec_size as usize;
+        let mut jited_ksyms = vec![0u64; info.nr_jited_ksyms as usize];
+        let mut jited_func_lens = vec![0u32; info.nr_jited_func_lens as usize];
+        let mut line_info = vec![0u8; info.nr_line_info as usize * line_rec_size];
+        let mut jited_line_info = vec![0u8; info.nr_jited_line_info as usize * jited_line_rec_size];
+
+        let mut details = bpf_prog_info {
+            nr_jited_ksyms: info.nr_jited_ksyms,
+            jited_ksyms: jited_ksyms.as_mut_ptr() as u64,
+            nr_jited_func_lens: info.nr_jited_func_lens,
+            jited_func_lens: jited_func_lens.as_mut_ptr() as u64,
+            nr_line_info: info.nr_line_info,
+            line_info_rec_size: info.line_info_rec_size,
+            line_info: line_info.as_mut_ptr() as u64,
+            nr_jited_line_info: info.nr_jited_line_info,
+            jited_line_info_rec_size: info.jited_line_info_rec_size,
+            jited_line_info: jited_line_info.as_mut_ptr() as u64,
+            ..Default::default()
+        };
+        let () = sys::obj_info(&fd, &mut details)?;
+
+        let btf = if info.btf_id != 0 {
+            load_btf(info.btf_id)
+                .map_err(|err| warn!("failed to load BTF for BPF program {id}: {err}"))
+                .ok()
+        } else {
+            None
+        };
+
+        let prog = Self::parse(
+            &details,
+            jited_ksyms,
+            jited_func_lens,
+            &line_info,
+            &jited_line_info,
+            prog_tags,
+            btf,
+        )?;
+        Ok(Some(prog))
+    }
+
+    /// Create program information from the data reported by the
+    /// kernel.
+    ///
+    /// `details` is the `bpf_prog_info` object as filled in by the
+    /// kernel when asked for the remaining data. Its counts may be
+    /// larger than what was asked for (and fit into the buffers), if
+    /// the program changed in the meantime.
+    fn parse(
+        details: &bpf_prog_info,
+        mut jited_ksyms: Vec<u64>,
+        mut jited_func_lens: Vec<u32>,
+        line_info: &[u8],
+        jited_line_info: &[u8],
+        tags: Vec<BpfTag>,
+        btf: Option<Btf>,
+    ) -> Result<Self> {
+        let () = jited_ksyms.truncate(details.nr_jited_ksyms as usize);
+        let () = jited_func_lens.truncate(details.nr_jited_func_lens as usize);
+        let line_info = read_records::<BpfLineInfo>(
+            line_info,
+            details.nr_line_info as usize,
+            details.line_info_rec_size as usize,
+        )?;
+        let jited_line_info = read_records::<u64>(
+            jited_line_info,
+            details.nr_jited_line_info as usize,
+            details.jited_line_info_rec_size as usize,
+        )?;
+
+        let prog = Self {
+            jited_ksyms: jited_ksyms.into_iter().map(|addr| addr as Addr).collect(),
+            jited_func_lens,
+            line_info,
+            jited_line_info: jited_line_info
+                .into_iter()
+                .map(|addr| addr as Addr)
+                .collect(),
+            btf,
+            tags,
+        };
+        Ok(prog)
+    }
+
+    /// Find the address range of the JIT-ed function containing `addr`.
+    fn find_func(&self, addr: Addr) -> Option<(Addr, Addr)> {
+        self.jited_ksyms
+            .iter()
+            .zip(self.jited_func_lens.iter())
+            .map(|(start, len)| (*start, *start + *len as Addr))
+            .find(|(start, end)| (*start..*end).contains(&addr))
+    }
+
+    /// Check whether `addr` is part of this program's JIT-ed code.
+    pub fn contains(&self, addr: Addr) -> bool {
+        self.find_func(addr).is_some()
+    }
+
+    /// Find the source code location of the JIT-ed instruction at
+    /// `addr`.
+    pub fn find_line_info(&self, addr: Addr) -> Option<AddrLineInfo> {
+        let (start, end) = self.find_func(addr)?;
+        let btf = self.btf.as_ref()?;
+
+        let (_, info) = self
+            .jited_line_info
+            .iter()
+            .zip(self.line_info.iter())
+            .filter(|(jited, _)| (start..end).contains(*jited) && **jited <= addr)
+            .max_by_key(|(jited, _)| **jited)?;
+
+        let path = btf.name(info.file_name_off)?;
+        let info = AddrLineInfo {
+            path: PathBuf::from(path),
+            line: info.line() as usize,
+            column: info.column() as usize,
+        };
+        Some(info)
+    }
+}
+
+
+/// A resolver for addresses inside of JIT-ed BPF programs.
+#[derive(Debug)]
+pub(crate) struct BpfResolver {
+    progs: Vec<Arc<BpfProgInfo>>,
+}
+
+impl BpfResolver {
+    /// Create a resolver from already loaded program information.
+    pub fn from_progs<P>(progs: P) -> Self
+    where
+        P: IntoIterator<Item = Arc<BpfProgInfo>>,
+    {
+        Self {
+            progs: progs.into_iter().collect(),
+        }
+    }
+
+    /// Find the source code location of the JIT-ed instruction at
+    /// `addr`.
+    ///
+    /// `None` is returned if `addr` does not belong to any known BPF
+    /// program or if no line information is available for it.
+    pub fn find_line_info(&self, addr: Addr) -> Option<AddrLineInfo> {
+        self.progs
+            .iter()
+            .find(|prog| prog.contains(addr))?
+            .find_line_info(addr)
+    }
+}
+
+
+/// Cache of information about BPF programs loaded into the kernel.
+#[derive(Debug)]
+pub(crate) struct BpfProgCache {
+    progs: Mutex<Vec<Arc<BpfProgInfo>>>,
+}
+
+impl BpfProgCache {
+    pub fn new() -> Self {
+        Self {
+            progs: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create a resolver for the BPF programs containing the given
+    /// addresses, each paired with the tag of the program reported for
+    /// it by kallsyms.
+    ///
+    /// Information about programs not already cached is loaded from
+    /// the kernel, which requires sufficient privileges (typically
+    /// `CAP_SYS_ADMIN`).
+    pub fn get_resolver(&self, addrs: &[(BpfTag, Addr)]) -> Result<BpfResolver> {
+        let mut progs = self.progs.lock().unwrap();
+        // Programs may have been unloaded and others loaded in their
+        // place, so only consider a cached program if it still has the
+        // expected tag.
+        let find = |progs: &[Arc<BpfProgInfo>], tag: &BpfTag, addr: Addr| {
+            progs
+                .iter()
+                .find(|prog| prog.contains(addr) && prog.tags.contains(tag))
+                .cloned()
+        };
+
+        let missing = addrs
+            .iter()
+            .filter(|(tag, addr)| find(&progs, tag, *addr).is_none())
+            .map(|(tag, _addr)| *tag)
+            .collect::<HashSet<_>>();
+
+        if !missing.is_empty() {
+            let loaded = sys::prog_ids()?
+                .into_iter()
+                .filter_map(|id| {
+                    // Programs may get unloaded concurrently, so just
+                    // skip over ones we fail to load.
+                    BpfProgInfo::load(id, &missing)
+                        .map_err(|err| {
+                            warn!("failed to load information for BPF program {id}: {err}")
+                        })
+                        .ok()
+                        .flatten()
+                })
+                .map(Arc::new)
+                .collect::<Vec<_>>();
+
+            // Evict stale programs whose code got replaced.
+            let () = progs.retain(|prog| {
+                !loaded
+                    .iter()
+                    .any(|new| new.jited_ksyms.iter().any(|addr| prog.contains(*addr)))
+            });
+            let () = progs.extend(loaded);
+        }
+
+        let resolver = BpfResolver::from_progs(
+            addrs
+                .iter()
+                .filter_map(|(tag, addr)| find(&progs, tag, *addr)),
+        );
+        Ok(resolver)
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    use crate::bpf::btf::tests::make_btf;
+
+
+    /// The start address of the JIT-ed main function of the program
+    /// created by [`make_prog`].
+    pub(crate) const PROG_ADDR: Addr = 0xffffffffc0001000;
+    /// The tag of the program created by [`make_prog`].
+    pub(crate) const PROG_TAG: BpfTag = [0xd3, 0x5c, 0x0e, 0x43, 0x9a, 0x12, 0x25, 0x64];
+
+
+    /// Create information about a BPF program as it would be reported
+    /// by the kernel for a program consisting of two functions: the
+    /// main function at [`PROG_ADDR`] and a sub-program following it.
+    pub(crate) fn make_prog() -> BpfProgInfo {
+        // String offsets: "/src/prog.bpf.c" at 1, "/src/sub.h" at 17.
+        let btf = Btf::parse(make_btf(&["/src/prog.bpf.c", "/src/sub.h"])).unwrap();
+        let line = |insn_off, file_name_off, line: u32, col: u32| BpfLineInfo {
+            insn_off,
+            file_name_off,
+            line_off: 0,
+            line_col: (line << 10) | col,
+        };
+
+        BpfProgInfo {
+            jited_ksyms: vec![PROG_ADDR, PROG_ADDR + 0x100],
+            jited_func_lens: vec![0x80, 0x40],
+            line_info: vec![
+                line(0, 1, 20, 0),
+                line(3, 1, 22, 5),
+                line(8, 1, 25, 12),
+                line(0, 17, 7, 2),
+            ],
+            jited_line_info: vec![
+                PROG_ADDR,
+                PROG_ADDR + 0x14,
+                PROG_ADDR + 0x30,
+                PROG_ADDR + 0x100,
+            ],
+            btf: Some(btf),
+            tags: vec![PROG_TAG],
+        }
+    }
+
+    /// Check that we can look up source code locations of addresses in
+    /// JIT-ed BPF programs.
+    #[test]
+    fn line_info_lookup() {
+        let resolver = BpfResolver::from_progs([Arc::new(make_prog())]);
+
+        let info = resolver.find_line_info(PROG_ADDR).unwrap();
+        assert_eq!(info.path, PathBuf::from("/src/prog.bpf.c"));
+        assert_eq!(info.line, 20);
+        assert_eq!(info.column, 0);
+
+        let info = resolver.find_line_info(PROG_ADDR + 0x20).unwrap();
+        assert_eq!(info.line, 22);
+        assert_eq!(info.column, 5);
+
+        let info = resolver.find_line_info(PROG_ADDR + 0x7f).unwrap();
+        assert_eq!(info.line, 25);
+        assert_eq!(info.column, 12);
+
+        let info = resolver.find_line_info(PROG_ADDR + 0x108).unwrap();
+        assert_eq!(info.path, PathBuf::from("/src/sub.h"));
+        assert_eq!(info.line, 7);
+        assert_eq!(info.column, 2);
+
+        // Gap between the two functions.
+        assert!(resolver.find_line_info(PROG_ADDR + 0x90).is_none());
+        assert!(resolver.find_line_info(PROG_ADDR - 1).is_none());
+        assert!(resolver.find_line_info(PROG_ADDR + 0x140).is_none());
+    }
+
+    /// Check that we can read records larger than the type we are
+    /// interested in.
+    #[test]
+    fn record_reading() {
+        let data = [1, 0, 0, 0, 0xff, 0xff, 2, 0, 0, 0, 0xff, 0xff];
+        let values = read_records::<u32>(&data, 2, 6).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                u32::from_ne_bytes([1, 0, 0, 0]),
+                u32::from_ne_bytes([2, 0, 0, 0])
+            ]
+        );
+
+        let err = read_records::<u32>(&data, 2, 2).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(read_records::<u32>(&[], 0, 0).unwrap(), Vec::<u32>::new());
+    }
+
+    /// Check that we can parse program tags from kallsyms symbol names.
+    #[test]
+    fn prog_tag_parsing() {
+        assert_eq!(
+            parse_prog_tag("bpf_prog_d35c0e439a122564_handle_exec"),
+            Some(PROG_TAG)
+        );
+        assert_eq!(parse_prog_tag("bpf_prog_d35c0e439a122564"), Some(PROG_TAG));
+        assert_eq!(parse_prog_tag("bpf_prog_d35c0e439a12256_F"), None);
+        assert_eq!(parse_prog_tag("bpf_prog_d35c0e439a12256x_F"), None);
+        assert_eq!(parse_prog_tag("bpf_prog_d35c0e439a122564F"), None);
+        assert_eq!(parse_prog_tag("bpf_prog_\u{e4}5c0e439a12256_F"), None);
+        assert_eq!(parse_prog_tag("vfs_read"), None);
+    }
+
+    /// Check that we can parse program information as reported by the
+    /// kernel.
+    #[test]
+    fn prog_info_parsing() {
+        // A dump of the JIT-ed function and line information of a
+        // program with a single function, as retrieved via
+        // `BPF_OBJ_GET_INFO_BY_FD`. Line information records are
+        // `struct bpf_line_info` objects, jited line information
+        // records are the JIT-ed instruction addresses.
+        #[rustfmt::skip]
+        let line_info = [
+            // insn_off, file_name_off, line_off, line_col
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x00, 0x50, 0x00, 0x00,
+            0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00, 0x05, 0x58, 0x00, 0x00,
+            0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x60, 0x00, 0x00, 0x00, 0x0c, 0x64, 0x00, 0x00,
+        ];
+        #[rustfmt::skip]
+        let jited_line_info = [
+            0x00, 0x10, 0x00, 0xc0, 0xff, 0xff, 0xff, 0xff,
+            0x14, 0x10, 0x00, 0xc0, 0xff, 0xff, 0xff, 0xff,
+            0x30, 0x10, 0x00, 0xc0, 0xff, 0xff, 0xff, 0xff,
+        ];
+        // The parsing logic assumes native endianness, just like the
+        // kernel reports the data.
+        let line_info = line_info
+            .chunks_exact(4)
+            .flat_map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()).to_ne_bytes())
+            .collect::<Vec<_>>();
+        let jited_line_info = jited_line_info
+            .chunks_exact(8)
+            .flat_map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()).to_ne_bytes())
+            .collect::<Vec<_>>();
+
+        let details = bpf_prog_info {
+            tag: PROG_TAG,
+            nr_jited_ksyms: 1,
+            nr_jited_func_lens: 1,
+            nr_line_info: 3,
+            line_info_rec_size: 16,
+            nr_jited_line_info: 3,
+            jited_line_info_rec_size: 8,
+            ..Default::default()
+        };
+        let btf = Btf::parse(make_btf(&["/src/prog.bpf.c"])).unwrap();
+        let prog = BpfProgInfo::parse(
+            &details,
+            vec![PROG_ADDR as u64],
+            vec![0x80],
+            &line_info,
+            &jited_line_info,
+            vec![PROG_TAG],
+            Some(btf),
+        )
+        .unwrap();
+        assert_eq!(prog.jited_ksyms, vec![PROG_ADDR]);
+        assert_eq!(prog.jited_func_lens, vec![0x80]);
+        let lines = prog
+            .line_info
+            .iter()
+            .map(|info| {
+                (
+                    info.insn_off,
+                    info.file_name_off,
+                    info.line(),
+                    info.column(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(0, 1, 20, 0), (3, 1, 22, 5), (8, 1, 25, 12)]);
+        assert_eq!(prog.jited_line_info, make_prog().jited_line_info[..3]);
+
+        let info = prog.find_line_info(PROG_ADDR + 0x20).unwrap();
+        assert_eq!(info.path, PathBuf::from("/src/prog.bpf.c"));
+        assert_eq!(info.line, 22);
+        assert_eq!(info.column, 5);
+
+        // The kernel may report more records than we provided space
+        // for, if the program changed in the meantime.
+        let details = bpf_prog_info {
+            nr_line_info: 5,
+            nr_jited_line_info: 5,
+            nr_jited_ksyms: 2,
+            ..details
+        };
+        let prog = BpfProgInfo::parse(
+            &details,
+            vec![PROG_ADDR as u64],
+            vec![0x80],
+            &line_info,
+            &jited_line_info,
+            vec![PROG_TAG],
+            None,
+        )
+        .unwrap();
+        assert_eq!(prog.jited_ksyms.len(), 1);
+        assert_eq!(prog.line_info.len(), 3);
+        assert_eq!(prog.jited_line_info.len(), 3);
+
+        // A record size smaller than `struct bpf_line_info` is invalid.
+        let details = bpf_prog_info {
+            line_info_rec_size: 8,
+            ..details
+        };
+        let err = BpfProgInfo::parse(
+            &details,
+            vec![PROG_ADDR as u64],
+            vec![0x80],
+            &line_info,
+            &jited_line_info,
+            vec![PROG_TAG],
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// Check that we can load information about BPF programs from the
+    /// running kernel.
+    ///
+    /// This test requires sufficient privileges.
+    #[test]
+    #[ignore = "requires root"]
+    fn live_load() {
+        let cache = BpfProgCache::new();
+        let _resolver = cache.get_resolver(&[(PROG_TAG, PROG_ADDR)]).unwrap();
+    }
+}
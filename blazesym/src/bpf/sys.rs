@@ -0,0 +1,162 @@
+//! Thin wrappers around the `bpf` system call.
+
+use std::io::Error;
+use std::io::Result;
+use std::mem::size_of;
+use std::os::unix::io::AsRawFd as _;
+use std::os::unix::io::FromRawFd as _;
+use std::os::unix::io::OwnedFd;
+
+use libc::c_int;
+
+
+const BPF_PROG_GET_NEXT_ID: c_int = 11;
+const BPF_PROG_GET_FD_BY_ID: c_int = 13;
+const BPF_OBJ_GET_INFO_BY_FD: c_int = 15;
+const BPF_BTF_GET_FD_BY_ID: c_int = 19;
+
+
+/// The attributes of the `BPF_*_GET_NEXT_ID` and `BPF_*_GET_FD_BY_ID`
+/// commands.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct IdAttr {
+    id: u32,
+    next_id: u32,
+    open_flags: u32,
+}
+
+/// The attributes of the `BPF_OBJ_GET_INFO_BY_FD` command.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct InfoAttr {
+    bpf_fd: u32,
+    info_len: u32,
+    info: u64,
+}
+
+
+/// `struct bpf_prog_info` as defined by the kernel.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub(crate) struct bpf_prog_info {
+    pub type_: u32,
+    pub id: u32,
+    pub tag: [u8; 8],
+    pub jited_prog_len: u32,
+    pub xlated_prog_len: u32,
+    pub jited_prog_insns: u64,
+    pub xlated_prog_insns: u64,
+    pub load_time: u64,
+    pub created_by_uid: u32,
+    pub nr_map_ids: u32,
+    pub map_ids: u64,
+    pub name: [u8; 16],
+    pub ifindex: u32,
+    pub gpl_compatible: u32,
+    pub netns_dev: u64,
+    pub netns_ino: u64,
+    pub nr_jited_ksyms: u32,
+    pub nr_jited_func_lens: u32,
+    pub jited_ksyms: u64,
+    pub jited_func_lens: u64,
+    pub btf_id: u32,
+    pub func_info_rec_size: u32,
+    pub func_info: u64,
+    pub nr_func_info: u32,
+    pub nr_line_info: u32,
+    pub line_info: u64,
+    pub jited_line_info: u64,
+    pub nr_jited_line_info: u32,
+    pub line_info_rec_size: u32,
+    pub jited_line_info_rec_size: u32,
+    pub nr_prog_tags: u32,
+    pub prog_tags: u64,
+    pub run_time_ns: u64,
+    pub run_cnt: u64,
+    pub recursion_misses: u64,
+    pub verified_insns: u32,
+    pub attach_btf_obj_id: u32,
+    pub attach_btf_id: u32,
+}
+
+/// `struct bpf_btf_info` as defined by the kernel.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub(crate) struct bpf_btf_info {
+    pub btf: u64,
+    pub btf_size: u32,
+    pub id: u32,
+    pub name: u64,
+    pub name_len: u32,
+    pub kernel_btf: u32,
+}
+
+
+fn sys_bpf<T>(cmd: c_int, attr: &mut T) -> Result<c_int> {
+    // SAFETY: `attr` is valid for the provided size and the kernel
+    //         only accesses that much memory.
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            cmd,
+            attr as *mut T,
+            size_of::<T>() as libc::c_uint,
+        )
+    };
+    if rc < 0 {
+        return Err(Error::last_os_error())
+    }
+    Ok(rc as c_int)
+}
+
+fn fd_by_id(cmd: c_int, id: u32) -> Result<OwnedFd> {
+    let mut attr = IdAttr {
+        id,
+        ..Default::default()
+    };
+    let fd = sys_bpf(cmd, &mut attr)?;
+    // SAFETY: On success the kernel returned a new file descriptor
+    //         that we now own.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Retrieve the IDs of all loaded BPF programs.
+pub(crate) fn prog_ids() -> Result<Vec<u32>> {
+    let mut ids = Vec::new();
+    let mut attr = IdAttr::default();
+    loop {
+        match sys_bpf(BPF_PROG_GET_NEXT_ID, &mut attr) {
+            Ok(_) => (),
+            Err(err) if err.raw_os_error() == Some(libc::ENOENT) => break,
+            Err(err) => return Err(err),
+        }
+        let () = ids.push(attr.next_id);
+        attr.id = attr.next_id;
+    }
+    Ok(ids)
+}
+
+/// Open the BPF program with the given ID.
+pub(crate) fn prog_fd(id: u32) -> Result<OwnedFd> {
+    fd_by_id(BPF_PROG_GET_FD_BY_ID, id)
+}
+
+/// Open the BTF object with the given ID.
+pub(crate) fn btf_fd(id: u32) -> Result<OwnedFd> {
+    fd_by_id(BPF_BTF_GET_FD_BY_ID, id)
+}
+
+/// Retrieve information about the BPF object referenced by `fd`.
+///
+/// `info` is an in/out parameter: pointers and counts set in it
+/// determine what additional data the kernel fills in.
+pub(crate) fn obj_info<T>(fd: &OwnedFd, info: &mut T) -> Result<()> {
+    let mut attr = InfoAttr {
+        bpf_fd: fd.as_raw_fd() as u32,
+        info_len: size_of::<T>() as u32,
+        info: info as *mut T as u64,
+    };
+    let _rc = sys_bpf(BPF_OBJ_GET_INFO_BY_FD, &mut attr)?;
+    Ok(())
+}
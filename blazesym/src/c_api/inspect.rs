@@ -17,6 +17,7 @@ use std::ptr;
 #[cfg(doc)]
 use crate::inspect;
 use crate::inspect::Elf;
+use crate::inspect::Gsym;
 use crate::inspect::Inspector;
 use crate::inspect::Source;
 use crate::inspect::SymInfo;
@@ -85,6 +86,58 @@ impl From<&blaze_inspect_elf_src> for Elf {
 }
 
 
+/// An object representing a Gsym inspection source.
+///
+/// C ABI compatible version of [`inspect::Gsym`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct blaze_inspect_gsym_src {
+    /// The path to the Gsym file. This member is always present.
+    path: *const c_char,
+}
+
+impl From<Gsym> for blaze_inspect_gsym_src {
+    fn from(other: Gsym) -> Self {
+        let Gsym {
+            path,
+            _non_exhaustive: (),
+        } = other;
+        Self {
+            path: CString::new(path.into_os_string().into_vec())
+                .expect("encountered path with NUL bytes")
+                .into_raw(),
+        }
+    }
+}
+
+impl From<blaze_inspect_gsym_src> for Gsym {
+    fn from(other: blaze_inspect_gsym_src) -> Self {
+        let blaze_inspect_gsym_src { path } = other;
+
+        Gsym {
+            path: PathBuf::from(OsString::from_vec(
+                unsafe { CString::from_raw(path as *mut _) }.into_bytes(),
+            )),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl From<&blaze_inspect_gsym_src> for Gsym {
+    fn from(other: &blaze_inspect_gsym_src) -> Self {
+        let blaze_inspect_gsym_src { path } = other;
+
+        Gsym {
+            path: Path::new(OsStr::from_bytes(
+                unsafe { CStr::from_ptr(*path) }.to_bytes(),
+            ))
+            .to_path_buf(),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+
 /// The type of a symbol.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -159,9 +212,11 @@ fn convert_syms_list_to_c(syms_list: Vec<Vec<SymInfo>>) -> *const *const blaze_s
             name,
             addr,
             size,
+            ranges: _,
             sym_type,
             file_offset,
             obj_file_name,
+            module: _,
         } in syms
         {
             let name_ptr = str_ptr.cast();
@@ -242,6 +297,53 @@ pub unsafe extern "C" fn blaze_inspect_syms_elf(
     let inspector = unsafe { &*inspector };
     // SAFETY: The caller ensures that the pointer is valid.
     let src = Source::Elf(Elf::from(unsafe { &*src }));
+    // SAFETY: The caller ensures that the pointer is valid and the count
+    //         matches.
+    unsafe { inspect_syms(inspector, &src, names, name_cnt) }
+}
+
+
+/// Lookup symbol information in a Gsym file.
+///
+/// Return an array with the same size as the input names. The caller should
+/// free the returned array by calling [`blaze_inspect_syms_free`].
+///
+/// Every name in the input name list may have more than one address.
+/// The respective entry in the returned array is an array containing
+/// all addresses and ended with a null (0x0).
+///
+/// # Safety
+/// The `inspector` object should have been created using
+/// [`blaze_inspector_new`], `src` needs to point to a valid object, and `names`
+/// needs to be a valid pointer to `name_cnt` strings.
+#[no_mangle]
+pub unsafe extern "C" fn blaze_inspect_syms_gsym(
+    inspector: *const Inspector,
+    src: *const blaze_inspect_gsym_src,
+    names: *const *const c_char,
+    name_cnt: usize,
+) -> *const *const blaze_sym_info {
+    // SAFETY: The caller ensures that the pointer is valid.
+    let inspector = unsafe { &*inspector };
+    // SAFETY: The caller ensures that the pointer is valid.
+    let src = Source::Gsym(Gsym::from(unsafe { &*src }));
+    // SAFETY: The caller ensures that the pointer is valid and the count
+    //         matches.
+    unsafe { inspect_syms(inspector, &src, names, name_cnt) }
+}
+
+
+/// Look up the symbols with the provided names in `src` and convert the
+/// result to its C representation.
+///
+/// # Safety
+/// `names` needs to be a valid pointer to `name_cnt` strings.
+unsafe fn inspect_syms(
+    inspector: &Inspector,
+    src: &Source,
+    names: *const *const c_char,
+    name_cnt: usize,
+) -> *const *const blaze_sym_info {
     // SAFETY: The caller ensures that the pointer is valid and the count
     //         matches.
     let names = unsafe { slice_from_user_array(names, name_cnt) };
@@ -252,7 +354,7 @@ pub unsafe extern "C" fn blaze_inspect_syms_elf(
             unsafe { CStr::from_ptr(p) }.to_str().unwrap()
         })
         .collect::<Vec<_>>();
-    let result = inspector.lookup(&names, &src);
+    let result = inspector.lookup(&names, src);
     match result {
         Ok(syms) => convert_syms_list_to_c(syms),
         Err(err) => {
@@ -263,11 +365,13 @@ pub unsafe extern "C" fn blaze_inspect_syms_elf(
 }
 
 
-/// Free an array returned by [`blaze_inspect_syms_elf`].
+/// Free an array returned by [`blaze_inspect_syms_elf`] or
+/// [`blaze_inspect_syms_gsym`].
 ///
 /// # Safety
 ///
-/// The pointer must be returned by [`blaze_inspect_syms_elf`].
+/// The pointer must be returned by [`blaze_inspect_syms_elf`] or
+/// [`blaze_inspect_syms_gsym`].
 ///
 #[no_mangle]
 pub unsafe extern "C" fn blaze_inspect_syms_free(syms: *const *const blaze_sym_info) {
@@ -365,9 +469,11 @@ mod tests {
             name: "sym1".to_string(),
             addr: 0xdeadbeef,
             size: 42,
+            ranges: vec![0xdeadbeef..0xdeadbeef + 42],
             sym_type: SymType::Function,
             file_offset: 1337,
             obj_file_name: Some(PathBuf::from("/tmp/foobar.so")),
+            module: None,
         }]];
         test(syms);
 
@@ -377,17 +483,21 @@ mod tests {
                 name: "sym1".to_string(),
                 addr: 0xdeadbeef,
                 size: 42,
+                ranges: vec![0xdeadbeef..0xdeadbeef + 42],
                 sym_type: SymType::Function,
                 file_offset: 1337,
                 obj_file_name: Some(PathBuf::from("/tmp/foobar.so")),
+                module: None,
             },
             SymInfo {
                 name: "sym2".to_string(),
                 addr: 0xdeadbeef + 52,
                 size: 45,
+                ranges: vec![0xdeadbeef + 52..0xdeadbeef + 52 + 45],
                 sym_type: SymType::Unknown,
                 file_offset: 1338,
                 obj_file_name: Some(PathBuf::from("other.so")),
+                module: None,
             },
         ]];
         test(syms);
@@ -398,17 +508,21 @@ mod tests {
                 name: "sym1".to_string(),
                 addr: 0xdeadbeef,
                 size: 42,
+                ranges: vec![0xdeadbeef..0xdeadbeef + 42],
                 sym_type: SymType::Function,
                 file_offset: 1337,
                 obj_file_name: Some(PathBuf::from("/tmp/foobar.so")),
+                module: None,
             }],
             vec![SymInfo {
                 name: "sym2".to_string(),
                 addr: 0xdeadbeef + 52,
                 size: 45,
+                ranges: vec![0xdeadbeef + 52..0xdeadbeef + 52 + 45],
                 sym_type: SymType::Unknown,
                 file_offset: 1338,
                 obj_file_name: Some(PathBuf::from("other.so")),
+                module: None,
             }],
         ];
         test(syms);
@@ -418,9 +532,11 @@ mod tests {
             name: "sym1".to_string(),
             addr: 0xdeadbeef,
             size: 42,
+            ranges: vec![0xdeadbeef..0xdeadbeef + 42],
             sym_type: SymType::Function,
             file_offset: 1337,
             obj_file_name: Some(PathBuf::from("/tmp/foobar.so")),
+            module: None,
         };
         let syms = vec![(0..200).map(|_| sym.clone()).collect()];
         test(syms);
@@ -1,12 +1,34 @@
 //! C API bindings for the library.
 
+use std::ffi::CStr;
+use std::ffi::OsStr;
+use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt as _;
+use std::path::Path;
+use std::path::PathBuf;
+
 #[allow(non_camel_case_types)]
 mod inspect;
 #[allow(non_camel_case_types)]
 mod normalize;
 #[allow(non_camel_case_types)]
+mod snippet;
+#[allow(non_camel_case_types)]
 mod symbolize;
 
 pub use inspect::*;
 pub use normalize::*;
+pub use snippet::*;
 pub use symbolize::*;
+
+
+/// Create a `PathBuf` from a pointer of C string
+///
+/// # Safety
+/// The provided `cstr` should be terminated with a NUL byte.
+unsafe fn from_cstr(cstr: *const c_char) -> PathBuf {
+    Path::new(OsStr::from_bytes(
+        unsafe { CStr::from_ptr(cstr) }.to_bytes(),
+    ))
+    .to_path_buf()
+}
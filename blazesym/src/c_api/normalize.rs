@@ -1,10 +1,13 @@
+use std::ffi::CStr;
 use std::ffi::CString;
+use std::ffi::OsStr;
 use std::ffi::OsString;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
 use std::mem::ManuallyDrop;
 use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt as _;
 use std::os::unix::ffi::OsStringExt as _;
 use std::path::PathBuf;
 use std::ptr;
@@ -16,6 +19,7 @@ use crate::normalize::NormalizedUserAddrs;
 use crate::normalize::Normalizer;
 use crate::normalize::Unknown;
 use crate::normalize::UserAddrMeta;
+use crate::normalize::Vdso;
 use crate::util::slice_from_user_array;
 use crate::Addr;
 
@@ -77,6 +81,8 @@ pub enum blaze_user_addr_meta_kind {
     BLAZE_USER_ADDR_UNKNOWN,
     /// [`blaze_user_addr_meta_variant::binary`] is valid.
     BLAZE_USER_ADDR_BINARY,
+    /// [`blaze_user_addr_meta_variant::vdso`] is valid.
+    BLAZE_USER_ADDR_VDSO,
 }
 
 
@@ -144,6 +150,60 @@ impl From<blaze_user_addr_meta_binary> for Binary {
 }
 
 
+/// C compatible version of [`Vdso`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct blaze_user_addr_meta_vdso {
+    /// The length of the build ID, in bytes.
+    build_id_len: usize,
+    /// The optional build ID of the vDSO, if found.
+    build_id: *mut u8,
+}
+
+impl From<Vdso> for blaze_user_addr_meta_vdso {
+    fn from(other: Vdso) -> Self {
+        let Vdso {
+            build_id,
+            _non_exhaustive: (),
+        } = other;
+        Self {
+            build_id_len: build_id
+                .as_ref()
+                .map(|build_id| build_id.len())
+                .unwrap_or(0),
+            build_id: build_id
+                .map(|build_id| {
+                    // SAFETY: We know the pointer is valid because it
+                    //         came from a `Box`.
+                    unsafe {
+                        Box::into_raw(build_id.into_boxed_slice())
+                            .as_mut()
+                            .unwrap()
+                            .as_mut_ptr()
+                    }
+                })
+                .unwrap_or_else(ptr::null_mut),
+        }
+    }
+}
+
+impl From<blaze_user_addr_meta_vdso> for Vdso {
+    fn from(other: blaze_user_addr_meta_vdso) -> Self {
+        let blaze_user_addr_meta_vdso {
+            build_id_len,
+            build_id,
+        } = other;
+
+        Vdso {
+            build_id: (!build_id.is_null()).then(|| unsafe {
+                Box::<[u8]>::from_raw(slice::from_raw_parts_mut(build_id, build_id_len)).into_vec()
+            }),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+
 /// C compatible version of [`Unknown`].
 #[repr(C)]
 #[derive(Debug)]
@@ -175,6 +235,8 @@ impl From<blaze_user_addr_meta_unknown> for Unknown {
 pub union blaze_user_addr_meta_variant {
     /// Valid on [`blaze_user_addr_meta_kind::BLAZE_USER_ADDR_BINARY`].
     pub binary: ManuallyDrop<blaze_user_addr_meta_binary>,
+    /// Valid on [`blaze_user_addr_meta_kind::BLAZE_USER_ADDR_VDSO`].
+    pub vdso: ManuallyDrop<blaze_user_addr_meta_vdso>,
     /// Valid on [`blaze_user_addr_meta_kind::BLAZE_USER_ADDR_UNKNOWN`].
     pub unknown: ManuallyDrop<blaze_user_addr_meta_unknown>,
 }
@@ -206,6 +268,12 @@ impl From<UserAddrMeta> for blaze_user_addr_meta {
                     binary: ManuallyDrop::new(blaze_user_addr_meta_binary::from(binary)),
                 },
             },
+            UserAddrMeta::Vdso(vdso) => Self {
+                kind: blaze_user_addr_meta_kind::BLAZE_USER_ADDR_VDSO,
+                variant: blaze_user_addr_meta_variant {
+                    vdso: ManuallyDrop::new(blaze_user_addr_meta_vdso::from(vdso)),
+                },
+            },
             UserAddrMeta::Unknown(unknown) => Self {
                 kind: blaze_user_addr_meta_kind::BLAZE_USER_ADDR_UNKNOWN,
                 variant: blaze_user_addr_meta_variant {
@@ -379,6 +447,9 @@ pub unsafe extern "C" fn blaze_user_addrs_free(addrs: *mut blaze_normalized_user
                     addr_meta.variant.binary
                 }));
             }
+            blaze_user_addr_meta_kind::BLAZE_USER_ADDR_VDSO => {
+                let _vdso = Vdso::from(ManuallyDrop::into_inner(unsafe { addr_meta.variant.vdso }));
+            }
             blaze_user_addr_meta_kind::BLAZE_USER_ADDR_UNKNOWN => {
                 let _unknown = Unknown::from(ManuallyDrop::into_inner(unsafe {
                     addr_meta.variant.unknown
@@ -389,6 +460,137 @@ pub unsafe extern "C" fn blaze_user_addrs_free(addrs: *mut blaze_normalized_user
 }
 
 
+/// Create a [`UserAddrMeta`] from a borrowed [`blaze_user_addr_meta`].
+///
+/// # Safety
+/// `meta` has to be a valid object, as created by one of the
+/// functions returning [`blaze_normalized_user_addrs`].
+unsafe fn user_addr_meta_from_c(meta: &blaze_user_addr_meta) -> UserAddrMeta {
+    let build_id = |build_id: *const u8, build_id_len| {
+        (!build_id.is_null())
+            .then(|| unsafe { slice_from_user_array(build_id, build_id_len) }.to_vec())
+    };
+
+    match meta.kind {
+        blaze_user_addr_meta_kind::BLAZE_USER_ADDR_BINARY => {
+            let binary = unsafe { &meta.variant.binary };
+            let path = unsafe { CStr::from_ptr(binary.path) };
+            UserAddrMeta::Binary(Binary {
+                path: PathBuf::from(OsStr::from_bytes(path.to_bytes())),
+                build_id: build_id(binary.build_id, binary.build_id_len),
+                _non_exhaustive: (),
+            })
+        }
+        blaze_user_addr_meta_kind::BLAZE_USER_ADDR_VDSO => {
+            let vdso = unsafe { &meta.variant.vdso };
+            UserAddrMeta::Vdso(Vdso {
+                build_id: build_id(vdso.build_id, vdso.build_id_len),
+                _non_exhaustive: (),
+            })
+        }
+        blaze_user_addr_meta_kind::BLAZE_USER_ADDR_UNKNOWN => UserAddrMeta::Unknown(Unknown {
+            _non_exhaustive: (),
+        }),
+    }
+}
+
+
+/// Encode normalized user addresses in a compact and versioned binary
+/// format, suitable for transfer to a different system.
+///
+/// C ABI compatible version of [`NormalizedUserAddrs::encode`]. On
+/// success, a pointer to the encoded data is returned and its length
+/// is stored in `len`. Returns `NULL` on error. The resulting data
+/// should be freed using [`blaze_user_addrs_encoded_free`].
+///
+/// # Safety
+/// `addrs` needs to be a valid object, as returned by
+/// [`blaze_normalize_user_addrs`], for example, and `len` needs to be
+/// a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn blaze_user_addrs_encode(
+    addrs: *const blaze_normalized_user_addrs,
+    len: *mut usize,
+) -> *mut u8 {
+    // SAFETY: The caller needs to ensure that `addrs` is a valid
+    //         pointer.
+    let addrs = unsafe { &*addrs };
+    // SAFETY: The caller needs to ensure that `addrs` contains valid
+    //         arrays of the advertised lengths.
+    let metas = unsafe { slice_from_user_array(addrs.metas, addrs.meta_count) };
+    let norm_addrs = unsafe { slice_from_user_array(addrs.addrs, addrs.addr_count) };
+
+    let user_addrs = NormalizedUserAddrs {
+        addrs: norm_addrs
+            .iter()
+            .map(|addr| (addr.addr, addr.meta_idx))
+            .collect(),
+        meta: metas
+            .iter()
+            .map(|meta| unsafe { user_addr_meta_from_c(meta) })
+            .collect(),
+    };
+
+    match user_addrs.encode() {
+        Ok(data) => {
+            let data = data.into_boxed_slice();
+            // SAFETY: The caller needs to ensure that `len` is a valid
+            //         pointer.
+            let () = unsafe { len.write(data.len()) };
+            Box::into_raw(data).cast::<u8>()
+        }
+        Err(err) => {
+            error!("failed to encode normalized user addresses: {err}");
+            ptr::null_mut()
+        }
+    }
+}
+
+
+/// Free data as returned by [`blaze_user_addrs_encode`].
+///
+/// # Safety
+/// `data` and `len` should have been returned by
+/// [`blaze_user_addrs_encode`].
+#[no_mangle]
+pub unsafe extern "C" fn blaze_user_addrs_encoded_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        // SAFETY: The caller needs to ensure that `data` and `len`
+        //         describe a buffer returned by
+        //         `blaze_user_addrs_encode`.
+        drop(unsafe { Box::<[u8]>::from_raw(slice::from_raw_parts_mut(data, len)) });
+    }
+}
+
+
+/// Decode normalized user addresses, as encoded by
+/// [`blaze_user_addrs_encode`].
+///
+/// C ABI compatible version of [`NormalizedUserAddrs::decode`].
+/// Returns `NULL` on error. The resulting object should be freed using
+/// [`blaze_user_addrs_free`].
+///
+/// # Safety
+/// Callers need to pass in a valid `data` pointer, pointing to `len`
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn blaze_user_addrs_decode(
+    data: *const u8,
+    len: usize,
+) -> *mut blaze_normalized_user_addrs {
+    // SAFETY: The caller needs to ensure that `data` is a valid pointer
+    //         and that it points to `len` bytes.
+    let data = unsafe { slice_from_user_array(data, len) };
+    match NormalizedUserAddrs::decode(data) {
+        Ok(addrs) => Box::into_raw(Box::new(blaze_normalized_user_addrs::from(addrs))),
+        Err(err) => {
+            error!("failed to decode normalized user addresses: {err}");
+            ptr::null_mut()
+        }
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -406,6 +608,25 @@ mod tests {
         assert_eq!(unknown_new, unknown);
     }
 
+    /// Check that we can convert a [`Vdso`] into a
+    /// [`blaze_user_addr_meta_vdso`] and back.
+    #[test]
+    fn vdso_conversion() {
+        let vdso = Vdso {
+            build_id: Some(vec![0xde, 0xad, 0xbe, 0xef]),
+            _non_exhaustive: (),
+        };
+        let vdso_new = Vdso::from(blaze_user_addr_meta_vdso::from(vdso.clone()));
+        assert_eq!(vdso_new, vdso);
+
+        let vdso = Vdso {
+            build_id: None,
+            _non_exhaustive: (),
+        };
+        let vdso_new = Vdso::from(blaze_user_addr_meta_vdso::from(vdso.clone()));
+        assert_eq!(vdso_new, vdso);
+    }
+
     /// Check that we correctly format the debug representation of a
     /// [`blaze_user_addr_meta_variant`].
     #[test]
@@ -416,4 +637,51 @@ mod tests {
         };
         assert_eq!(format!("{variant:?}"), "blaze_user_addr_meta_variant");
     }
+
+    /// Check that we can encode and decode normalized user addresses
+    /// via the C API.
+    #[test]
+    fn encode_decode() {
+        let addrs = NormalizedUserAddrs {
+            addrs: vec![(0x1000, 0), (0x2000, 1), (0x3000, 2)],
+            meta: vec![
+                UserAddrMeta::Binary(Binary {
+                    path: PathBuf::from("/usr/lib/libfoo.so"),
+                    build_id: Some(vec![0x01, 0x02, 0x03]),
+                    _non_exhaustive: (),
+                }),
+                UserAddrMeta::Vdso(Vdso {
+                    build_id: None,
+                    _non_exhaustive: (),
+                }),
+                UserAddrMeta::Unknown(Unknown {
+                    _non_exhaustive: (),
+                }),
+            ],
+        };
+        let c_addrs = Box::into_raw(Box::new(blaze_normalized_user_addrs::from(addrs.clone())));
+
+        let mut len = 0;
+        let data = unsafe { blaze_user_addrs_encode(c_addrs, &mut len) };
+        assert!(!data.is_null());
+        let () = unsafe { blaze_user_addrs_free(c_addrs) };
+
+        let encoded = unsafe { slice::from_raw_parts(data, len) };
+        assert_eq!(encoded, addrs.encode().unwrap());
+
+        let decoded = unsafe { blaze_user_addrs_decode(data, len) };
+        assert!(!decoded.is_null());
+        let () = unsafe { blaze_user_addrs_encoded_free(data, len) };
+
+        let metas = unsafe { slice::from_raw_parts((*decoded).metas, (*decoded).meta_count) };
+        let metas = metas
+            .iter()
+            .map(|meta| unsafe { user_addr_meta_from_c(meta) })
+            .collect::<Vec<_>>();
+        assert_eq!(metas, addrs.meta);
+        let () = unsafe { blaze_user_addrs_free(decoded) };
+
+        let decoded = unsafe { blaze_user_addrs_decode(b"foobar".as_ptr(), 6) };
+        assert!(decoded.is_null());
+    }
 }
@@ -0,0 +1,241 @@
+use std::ffi::CString;
+use std::fmt::Debug;
+use std::os::raw::c_char;
+use std::os::unix::ffi::OsStrExt as _;
+use std::ptr;
+
+use crate::log::error;
+use crate::snippet::SnippetLoader;
+use crate::util::slice_from_user_array;
+
+use super::from_cstr;
+
+#[cfg(doc)]
+use super::blaze_sym;
+
+
+/// A path prefix substitution rule, as used by
+/// [`blaze_snippet_loader_opts`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct blaze_path_subst {
+    /// The path prefix to replace.
+    pub from: *const c_char,
+    /// The path prefix to replace `from` with.
+    pub to: *const c_char,
+}
+
+
+/// Options for configuring `blaze_snippet_loader` objects.
+#[repr(C)]
+#[derive(Debug)]
+pub struct blaze_snippet_loader_opts {
+    /// Array of directories to search for source files.
+    ///
+    /// `src_roots` is an array of `src_roots_len` C strings. It may be
+    /// `NULL` if `src_roots_len` is zero.
+    pub src_roots: *const *const c_char,
+    /// The number of elements in `src_roots`.
+    pub src_roots_len: usize,
+    /// Array of path prefix substitution rules, checked in order.
+    ///
+    /// `substitutions` is an array of `substitutions_len` objects. It
+    /// may be `NULL` if `substitutions_len` is zero.
+    pub substitutions: *const blaze_path_subst,
+    /// The number of elements in `substitutions`.
+    pub substitutions_len: usize,
+    /// The number of lines to report before and after the line of
+    /// interest.
+    pub context_lines: usize,
+}
+
+
+/// The type of a snippet loader.
+pub type blaze_snippet_loader = SnippetLoader;
+
+
+/// A snippet of source code surrounding a line of interest.
+///
+/// Instances are returned by [`blaze_snippet_load`] and should be
+/// freed by calling [`blaze_snippet_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct blaze_snippet {
+    /// The path of the source file on the local system.
+    pub path: *const c_char,
+    /// The (1-based) number of the line of interest.
+    pub line: usize,
+    /// The (1-based) number of the first line in `lines`.
+    pub first_line: usize,
+    /// The number of lines in `lines`.
+    pub line_cnt: usize,
+    /// An array of `line_cnt` C strings, without line terminators.
+    pub lines: *const *const c_char,
+}
+
+
+/// The allocation backing a [`blaze_snippet`].
+///
+/// The [`blaze_snippet`] is guaranteed to be located at the start, so
+/// that pointers to the two can be converted into one another.
+#[repr(C)]
+#[derive(Debug)]
+struct SnippetBox {
+    snippet: blaze_snippet,
+    strs: Vec<CString>,
+    line_ptrs: Vec<*const c_char>,
+}
+
+
+/// Create an instance of a snippet loader.
+///
+/// The returned pointer should be released using
+/// [`blaze_snippet_loader_free`] once it is no longer needed.
+#[no_mangle]
+pub extern "C" fn blaze_snippet_loader_new() -> *mut blaze_snippet_loader {
+    let loader = SnippetLoader::new();
+    let loader_box = Box::new(loader);
+    Box::into_raw(loader_box)
+}
+
+/// Create an instance of a snippet loader with configurable options.
+///
+/// The returned pointer should be released using
+/// [`blaze_snippet_loader_free`] once it is no longer needed.
+///
+/// # Safety
+/// `opts` needs to be a valid pointer. `opts->src_roots` and
+/// `opts->substitutions` need to be valid pointers to arrays of the
+/// respective number of elements, and all contained strings need to be
+/// valid C strings.
+#[no_mangle]
+pub unsafe extern "C" fn blaze_snippet_loader_new_opts(
+    opts: *const blaze_snippet_loader_opts,
+) -> *mut blaze_snippet_loader {
+    // SAFETY: The caller ensures that the pointer is valid.
+    let opts = unsafe { &*opts };
+    let blaze_snippet_loader_opts {
+        src_roots,
+        src_roots_len,
+        substitutions,
+        substitutions_len,
+        context_lines,
+    } = opts;
+
+    // SAFETY: The caller ensures that the pointer is valid and the count
+    //         matches.
+    let src_roots = unsafe { slice_from_user_array(*src_roots, *src_roots_len) };
+    // SAFETY: The caller ensures that the pointer is valid and the count
+    //         matches.
+    let substitutions = unsafe { slice_from_user_array(*substitutions, *substitutions_len) };
+
+    let builder = SnippetLoader::builder()
+        // SAFETY: The caller ensures that all strings are valid.
+        .set_src_roots(src_roots.iter().map(|dir| unsafe { from_cstr(*dir) }))
+        .set_context_lines(*context_lines);
+    let builder = substitutions.iter().fold(builder, |builder, subst| {
+        // SAFETY: The caller ensures that all strings are valid.
+        let (from, to) = unsafe { (from_cstr(subst.from), from_cstr(subst.to)) };
+        builder.add_path_substitution(from, to)
+    });
+    let loader = builder.build();
+    let loader_box = Box::new(loader);
+    Box::into_raw(loader_box)
+}
+
+/// Free a snippet loader.
+///
+/// # Safety
+/// The pointer must have been returned by [`blaze_snippet_loader_new`]
+/// or [`blaze_snippet_loader_new_opts`].
+#[no_mangle]
+pub unsafe extern "C" fn blaze_snippet_loader_free(loader: *mut blaze_snippet_loader) {
+    if !loader.is_null() {
+        // SAFETY: The caller needs to ensure that `loader` is a valid
+        //         pointer.
+        drop(unsafe { Box::from_raw(loader) });
+    }
+}
+
+/// Load the source code surrounding `line` of the source file `path`,
+/// as reported by symbolization (e.g., in [`blaze_sym::path`] and
+/// [`blaze_sym::line`]).
+///
+/// Returns `NULL` on error, for example if the source file could not
+/// be found. The result should be freed using [`blaze_snippet_free`].
+///
+/// # Safety
+/// `loader` needs to be a valid pointer as returned by any of the
+/// `blaze_snippet_loader_new*` variants. `path` needs to be a valid C
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn blaze_snippet_load(
+    loader: *const blaze_snippet_loader,
+    path: *const c_char,
+    line: usize,
+) -> *const blaze_snippet {
+    // SAFETY: The caller needs to ensure that `loader` is a valid
+    //         pointer.
+    let loader = unsafe { &*loader };
+    // SAFETY: The caller needs to ensure that `path` is a valid C
+    //         string.
+    let path = unsafe { from_cstr(path) };
+    let snippet = match loader.load(&path, line) {
+        Ok(snippet) => snippet,
+        Err(err) => {
+            error!(
+                "failed to load snippet for {}:{line}: {err}",
+                path.display()
+            );
+            return ptr::null()
+        }
+    };
+
+    // Source code may contain NUL bytes in theory, but we cannot
+    // represent them in C strings.
+    let make_cstr = |bytes: &[u8]| {
+        CString::new(
+            bytes
+                .iter()
+                .copied()
+                .filter(|b| *b != 0)
+                .collect::<Vec<_>>(),
+        )
+        .expect("filtered string contains NUL bytes")
+    };
+
+    let mut strs = Vec::with_capacity(snippet.lines.len() + 1);
+    let () = strs.push(make_cstr(snippet.path.as_os_str().as_bytes()));
+    let () = strs.extend(snippet.lines.iter().map(|line| make_cstr(line.as_bytes())));
+    let line_ptrs = strs[1..]
+        .iter()
+        .map(|line| line.as_ptr())
+        .collect::<Vec<_>>();
+
+    let snippet_box = Box::new(SnippetBox {
+        snippet: blaze_snippet {
+            path: strs[0].as_ptr(),
+            line: snippet.line,
+            first_line: snippet.first_line,
+            line_cnt: line_ptrs.len(),
+            lines: line_ptrs.as_ptr(),
+        },
+        strs,
+        line_ptrs,
+    });
+    Box::into_raw(snippet_box) as *const blaze_snippet
+}
+
+/// Free a snippet as returned by [`blaze_snippet_load`].
+///
+/// # Safety
+/// The pointer must have been returned by [`blaze_snippet_load`].
+#[no_mangle]
+pub unsafe extern "C" fn blaze_snippet_free(snippet: *const blaze_snippet) {
+    if !snippet.is_null() {
+        // SAFETY: The caller needs to ensure that `snippet` was
+        //         returned by `blaze_snippet_load`, which allocated a
+        //         `SnippetBox` with it located at the start.
+        drop(unsafe { Box::from_raw(snippet as *mut SnippetBox) });
+    }
+}
@@ -1,14 +1,11 @@
 use std::alloc::alloc;
 use std::alloc::dealloc;
 use std::alloc::Layout;
-use std::ffi::CStr;
 use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::mem;
 use std::os::raw::c_char;
 use std::os::unix::ffi::OsStrExt as _;
-use std::path::Path;
-use std::path::PathBuf;
 use std::ptr;
 
 use crate::log::error;
@@ -23,6 +20,8 @@ use crate::symbolize::Symbolizer;
 use crate::util::slice_from_user_array;
 use crate::Addr;
 
+use super::from_cstr;
+
 
 /// The parameters to load symbols and debug information from an ELF.
 ///
@@ -72,6 +71,19 @@ pub struct blaze_symbolize_src_kernel {
     /// kernel image of the running kernel in `"/boot/"` or
     /// `"/usr/lib/debug/boot/"`.
     pub kernel_image: *const c_char,
+    /// The path of a directory containing kernel modules.
+    ///
+    /// Addresses inside of loaded kernel modules are symbolized using
+    /// the module's `.ko` file found in this directory (or one of its
+    /// sub-directories), as listed by its `modules.dep` file, if
+    /// present. gzip (`.ko.gz`) and zstd (`.ko.zst`) compressed
+    /// modules are supported if the `zlib` and `zstd` features,
+    /// respectively, are enabled; xz (`.ko.xz`) compressed ones are
+    /// not. For a `NULL` value, the modules directory of the running
+    /// kernel, `"/lib/modules/<release>/"`, is used, unless `kallsyms`
+    /// is set, in which case addresses inside of kernel modules are
+    /// symbolized based on kallsyms alone.
+    pub modules_dir: *const c_char,
 }
 
 impl From<&blaze_symbolize_src_kernel> for Kernel {
@@ -79,10 +91,12 @@ impl From<&blaze_symbolize_src_kernel> for Kernel {
         let blaze_symbolize_src_kernel {
             kallsyms,
             kernel_image,
+            modules_dir,
         } = kernel;
         Self {
             kallsyms: (!kallsyms.is_null()).then(|| unsafe { from_cstr(*kallsyms) }),
             kernel_image: (!kernel_image.is_null()).then(|| unsafe { from_cstr(*kernel_image) }),
+            modules_dir: (!modules_dir.is_null()).then(|| unsafe { from_cstr(*modules_dir) }),
             _non_exhaustive: (),
         }
     }
@@ -137,9 +151,28 @@ impl From<&blaze_symbolize_src_gsym> for Gsym {
 ///
 /// It is returned by [`blaze_symbolizer_new`] and should be free by
 /// [`blaze_symbolizer_free`].
+///
+/// A `blaze_symbolizer` is thread safe: the same instance may be used
+/// by multiple threads concurrently, with the exception of
+/// [`blaze_symbolizer_free`], which must not race with any other use.
 pub type blaze_symbolizer = Symbolizer;
 
 
+/// A function inlined at a symbolized address.
+#[repr(C)]
+#[derive(Debug)]
+pub struct blaze_inlined_fn {
+    /// The name of the inlined function.
+    pub name: *const c_char,
+    /// The path of the source file containing the call site of the
+    /// function, i.e., the location it got inlined at.
+    pub call_path: *const c_char,
+    /// The line number of the call site.
+    pub call_line: usize,
+    /// The column number of the call site.
+    pub call_column: usize,
+}
+
 /// The result of symbolization of an address.
 ///
 /// A `blaze_sym` is the information of a symbol found for an
@@ -148,7 +181,11 @@ pub type blaze_symbolizer = Symbolizer;
 #[derive(Debug)]
 pub struct blaze_sym {
     /// The symbol name is where the given address should belong to.
+    ///
+    /// If demangling is enabled, this is the demangled name.
     pub symbol: *const c_char,
+    /// The symbol name before any demangling.
+    pub raw_symbol: *const c_char,
     /// The address (i.e.,the first byte) is where the symbol is located.
     ///
     /// The address is already relocated to the address space of
@@ -156,9 +193,20 @@ pub struct blaze_sym {
     pub addr: Addr,
     /// The path of the source file defining the symbol.
     pub path: *const c_char,
+    /// The name of the kernel module the symbol belongs to or `NULL` if
+    /// the symbol is not part of a kernel module.
+    pub module: *const c_char,
     /// The line number on which the symbol was to be found in the source code.
     pub line: usize,
     pub column: usize,
+    /// The number of functions inlined at the address.
+    pub inlined_cnt: usize,
+    /// The functions inlined at the address.
+    ///
+    /// `inlined` is an array of [`blaze_inlined_fn`] in the size
+    /// `inlined_cnt`, ordered from the function inlined directly into
+    /// `symbol` to the innermost one.
+    pub inlined: *const blaze_inlined_fn,
 }
 
 /// `blaze_entry` is the output of symbolization for an address for C API.
@@ -193,18 +241,6 @@ pub struct blaze_result {
     pub entries: [blaze_entry; 0],
 }
 
-/// Create a `PathBuf` from a pointer of C string
-///
-/// # Safety
-/// The provided `cstr` should be terminated with a NUL byte.
-unsafe fn from_cstr(cstr: *const c_char) -> PathBuf {
-    Path::new(OsStr::from_bytes(
-        unsafe { CStr::from_ptr(cstr) }.to_bytes(),
-    ))
-    .to_path_buf()
-}
-
-
 /// Options for configuring `blaze_symbolizer` objects.
 #[repr(C)]
 #[derive(Debug)]
@@ -215,6 +251,21 @@ pub struct blaze_symbolizer_opts {
     ///
     /// This setting implies `debug_syms` (and forces it to `true`).
     pub src_location: bool,
+    /// Whether to report functions inlined at symbolized addresses.
+    ///
+    /// This setting requires `debug_syms` to be enabled. Symbolizers
+    /// created with `blaze_symbolizer_new` do not report inlined
+    /// functions.
+    pub inlined_fns: bool,
+    /// Whether to demangle Rust and C++ symbol names.
+    pub demangle: bool,
+    /// Array of directories to search for separate debug files.
+    ///
+    /// If `NULL`, the default directories are searched. Otherwise,
+    /// `debug_dirs` is an array of `debug_dirs_len` C strings.
+    pub debug_dirs: *const *const c_char,
+    /// The number of elements in `debug_dirs`.
+    pub debug_dirs_len: usize,
 }
 
 
@@ -229,7 +280,9 @@ pub extern "C" fn blaze_symbolizer_new() -> *mut blaze_symbolizer {
 /// Create an instance of a symbolizer with configurable options.
 ///
 /// # Safety
-/// `opts` needs to be a valid pointer.
+/// `opts` needs to be a valid pointer. `opts->debug_dirs` needs to be
+/// `NULL` or a valid pointer to an array of `opts->debug_dirs_len` valid
+/// C strings.
 #[no_mangle]
 pub unsafe extern "C" fn blaze_symbolizer_new_opts(
     opts: *const blaze_symbolizer_opts,
@@ -239,12 +292,27 @@ pub unsafe extern "C" fn blaze_symbolizer_new_opts(
     let blaze_symbolizer_opts {
         debug_syms,
         src_location,
+        inlined_fns,
+        demangle,
+        debug_dirs,
+        debug_dirs_len,
     } = opts;
 
-    let symbolizer = Symbolizer::builder()
+    let builder = Symbolizer::builder()
         .enable_debug_syms(*debug_syms)
         .enable_src_location(*src_location)
-        .build();
+        .enable_inlined_fns(*inlined_fns)
+        .enable_demangling(*demangle);
+    let builder = if debug_dirs.is_null() {
+        builder
+    } else {
+        // SAFETY: The caller ensures that the pointer is valid and the
+        //         count matches.
+        let debug_dirs = unsafe { slice_from_user_array(*debug_dirs, *debug_dirs_len) };
+        // SAFETY: The caller ensures that all strings are valid.
+        builder.set_debug_dirs(debug_dirs.iter().map(|dir| unsafe { from_cstr(*dir) }))
+    };
+    let symbolizer = builder.build();
     let symbolizer_box = Box::new(symbolizer);
     Box::into_raw(symbolizer_box)
 }
@@ -271,15 +339,30 @@ unsafe fn convert_symbolizedresults_to_c(
     results: Vec<Vec<SymbolizedResult>>,
 ) -> *const blaze_result {
     // Allocate a buffer to contain a blaze_result, all
-    // blaze_sym, and C strings of symbol and path.
+    // blaze_sym, all blaze_inlined_fn, and C strings of symbol, raw
+    // symbol, path, and module.
     let strtab_size = results.iter().flatten().fold(0, |acc, result| {
-        acc + result.symbol.len() + result.path.as_os_str().len() + 2
+        let inlined_size = result.inlined.iter().fold(0, |acc, inlined| {
+            acc + inlined.name.len() + inlined.call_path.as_os_str().len() + 2
+        });
+        let module_size = result.module.as_ref().map(|module| module.len() + 1);
+        acc + result.symbol.len()
+            + result.raw_symbol.len()
+            + result.path.as_os_str().len()
+            + 3
+            + module_size.unwrap_or(0)
+            + inlined_size
     });
     let all_csym_size = results.iter().flatten().count();
+    let all_inlined_size = results
+        .iter()
+        .flatten()
+        .fold(0, |acc, result| acc + result.inlined.len());
     let buf_size = strtab_size
         + mem::size_of::<blaze_result>()
         + mem::size_of::<blaze_entry>() * results.len()
-        + mem::size_of::<blaze_sym>() * all_csym_size;
+        + mem::size_of::<blaze_sym>() * all_csym_size
+        + mem::size_of::<blaze_inlined_fn>() * all_inlined_size;
     let raw_buf_with_sz =
         unsafe { alloc(Layout::from_size_align(buf_size + mem::size_of::<u64>(), 8).unwrap()) };
     if raw_buf_with_sz.is_null() {
@@ -296,12 +379,20 @@ unsafe fn convert_symbolizedresults_to_c(
     let mut csym_last = unsafe {
         raw_buf.add(mem::size_of::<blaze_result>() + mem::size_of::<blaze_entry>() * results.len())
     } as *mut blaze_sym;
-    let mut cstr_last = unsafe {
+    let mut inlined_last = unsafe {
         raw_buf.add(
             mem::size_of::<blaze_result>()
                 + mem::size_of::<blaze_entry>() * results.len()
                 + mem::size_of::<blaze_sym>() * all_csym_size,
         )
+    } as *mut blaze_inlined_fn;
+    let mut cstr_last = unsafe {
+        raw_buf.add(
+            mem::size_of::<blaze_result>()
+                + mem::size_of::<blaze_entry>() * results.len()
+                + mem::size_of::<blaze_sym>() * all_csym_size
+                + mem::size_of::<blaze_inlined_fn>() * all_inlined_size,
+        )
     } as *mut c_char;
 
     let mut make_cstr = |src: &OsStr| {
@@ -323,15 +414,38 @@ unsafe fn convert_symbolizedresults_to_c(
 
         for r in entry {
             let symbol_ptr = make_cstr(OsStr::new(&r.symbol));
+            let raw_symbol_ptr = make_cstr(OsStr::new(&r.raw_symbol));
 
             let path_ptr = make_cstr(r.path.as_os_str());
+            let module_ptr = r
+                .module
+                .as_ref()
+                .map(|module| make_cstr(OsStr::new(module)) as *const c_char)
+                .unwrap_or_else(ptr::null);
 
             let csym_ref = unsafe { &mut *csym_last };
             csym_ref.symbol = symbol_ptr;
+            csym_ref.raw_symbol = raw_symbol_ptr;
             csym_ref.addr = r.addr;
             csym_ref.path = path_ptr;
+            csym_ref.module = module_ptr;
             csym_ref.line = r.line;
             csym_ref.column = r.column;
+            csym_ref.inlined_cnt = r.inlined.len();
+            csym_ref.inlined = inlined_last;
+
+            for inlined in &r.inlined {
+                let name_ptr = make_cstr(OsStr::new(&inlined.name));
+                let call_path_ptr = make_cstr(inlined.call_path.as_os_str());
+
+                let inlined_ref = unsafe { &mut *inlined_last };
+                inlined_ref.name = name_ptr;
+                inlined_ref.call_path = call_path_ptr;
+                inlined_ref.call_line = inlined.call_line;
+                inlined_ref.call_column = inlined.call_column;
+
+                inlined_last = unsafe { inlined_last.add(1) };
+            }
 
             csym_last = unsafe { csym_last.add(1) };
         }
@@ -485,6 +599,8 @@ pub unsafe extern "C" fn blaze_result_free(results: *const blaze_result) {
 mod tests {
     use super::*;
 
+    use std::path::PathBuf;
+
 
     /// Check that we can convert an [`Unknown`] into a
     /// [`blaze_user_addr_meta_unknown`] and back.
@@ -493,17 +609,21 @@ mod tests {
         let kernel = blaze_symbolize_src_kernel {
             kallsyms: ptr::null(),
             kernel_image: ptr::null(),
+            modules_dir: ptr::null(),
         };
         let kernel = Kernel::from(&kernel);
         assert_eq!(kernel.kallsyms, None);
         assert_eq!(kernel.kernel_image, None);
+        assert_eq!(kernel.modules_dir, None);
 
         let kernel = blaze_symbolize_src_kernel {
             kallsyms: b"/proc/kallsyms\0" as *const _ as *const c_char,
             kernel_image: b"/boot/image\0" as *const _ as *const c_char,
+            modules_dir: b"/lib/modules\0" as *const _ as *const c_char,
         };
         let kernel = Kernel::from(&kernel);
         assert_eq!(kernel.kallsyms, Some(PathBuf::from("/proc/kallsyms")));
         assert_eq!(kernel.kernel_image, Some(PathBuf::from("/boot/image")));
+        assert_eq!(kernel.modules_dir, Some(PathBuf::from("/lib/modules")));
     }
 }
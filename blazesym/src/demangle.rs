@@ -0,0 +1,72 @@
+#[cfg(feature = "demangle")]
+fn demangle_impl(name: &str, params: bool) -> Option<String> {
+    // Legacy Rust names are also valid Itanium C++ names, so we have to
+    // try Rust demangling first.
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        // The alternate format omits the hash suffix.
+        return Some(format!("{demangled:#}"))
+    }
+
+    let symbol = cpp_demangle::Symbol::new(name).ok()?;
+    let opts = cpp_demangle::DemangleOptions::new();
+    let opts = if params { opts } else { opts.no_params() };
+    symbol.demangle(&opts).ok()
+}
+
+#[cfg(not(feature = "demangle"))]
+fn demangle_impl(_name: &str, _params: bool) -> Option<String> {
+    None
+}
+
+/// Demangle a symbol name.
+///
+/// Rust names mangled according to the legacy or the v0 scheme as well
+/// as C++ names mangled according to the Itanium ABI are supported.
+/// `None` is returned if `name` is not mangled or could not be
+/// demangled or if support for demangling is disabled.
+pub(crate) fn demangle(name: &str) -> Option<String> {
+    demangle_impl(name, true)
+}
+
+/// Demangle a symbol name, omitting the parameter list of C++
+/// functions.
+///
+/// The result is suitable for looking up functions by their
+/// (qualified) name only, e.g., `ns::func` instead of `ns::func(int)`.
+pub(crate) fn demangle_without_params(name: &str) -> Option<String> {
+    demangle_impl(name, false)
+}
+
+
+#[cfg(test)]
+#[cfg(feature = "demangle")]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+
+    /// Check that we can demangle names of the supported schemes.
+    #[test]
+    fn demangle_names() {
+        assert_eq!(
+            demangle("_ZN4core3ptr13drop_in_place17h1d2c1c3b4a5f6e7dE").as_deref(),
+            Some("core::ptr::drop_in_place")
+        );
+        assert_eq!(
+            demangle("_RNvCs15kBYyAo9fc_7mycrate7example").as_deref(),
+            Some("mycrate::example")
+        );
+        assert_eq!(demangle("_ZN3foo3barEv").as_deref(), Some("foo::bar()"));
+        assert_eq!(
+            demangle_without_params("_ZN3foo3barEi").as_deref(),
+            Some("foo::bar")
+        );
+        assert_eq!(
+            demangle_without_params("_RNvCs15kBYyAo9fc_7mycrate7example").as_deref(),
+            Some("mycrate::example")
+        );
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("_Z"), None);
+    }
+}
@@ -1,23 +1,35 @@
 pub const DW_UT_compile: u8 = 0x1;
 pub const DW_UT_type: u8 = 0x2;
 
-pub const DW_TAG_array_type: u8 = 0x1;
-pub const DW_TAG_enumeration_type: u8 = 0x4;
-pub const DW_TAG_compile_unit: u8 = 0x11;
-pub const DW_TAG_subprogram: u8 = 0x2e;
-pub const DW_TAG_variable: u8 = 0x34;
-pub const DW_TAG_namespace: u8 = 0x39;
+pub const DW_TAG_array_type: u16 = 0x1;
+pub const DW_TAG_enumeration_type: u16 = 0x4;
+pub const DW_TAG_lexical_block: u16 = 0xb;
+pub const DW_TAG_compile_unit: u16 = 0x11;
+pub const DW_TAG_inlined_subroutine: u16 = 0x1d;
+pub const DW_TAG_subprogram: u16 = 0x2e;
+pub const DW_TAG_variable: u16 = 0x34;
+pub const DW_TAG_namespace: u16 = 0x39;
 
 pub const DW_CHILDREN_no: u8 = 0x00;
 pub const DW_CHILDREN_yes: u8 = 0x01;
 
-pub const DW_AT_sibling: u8 = 0x01;
-pub const DW_AT_location: u8 = 0x02;
-pub const DW_AT_name: u8 = 0x03;
-pub const DW_AT_lo_pc: u8 = 0x11;
-pub const DW_AT_hi_pc: u8 = 0x12;
-pub const DW_AT_entry_pc: u8 = 0x52;
-pub const DW_AT_linkage_name: u8 = 0x6e;
+pub const DW_AT_sibling: u16 = 0x01;
+pub const DW_AT_location: u16 = 0x02;
+pub const DW_AT_name: u16 = 0x03;
+pub const DW_AT_stmt_list: u16 = 0x10;
+pub const DW_AT_lo_pc: u16 = 0x11;
+pub const DW_AT_hi_pc: u16 = 0x12;
+pub const DW_AT_abstract_origin: u16 = 0x31;
+pub const DW_AT_specification: u16 = 0x47;
+pub const DW_AT_entry_pc: u16 = 0x52;
+pub const DW_AT_ranges: u16 = 0x55;
+pub const DW_AT_call_column: u16 = 0x57;
+pub const DW_AT_call_file: u16 = 0x58;
+pub const DW_AT_call_line: u16 = 0x59;
+pub const DW_AT_linkage_name: u16 = 0x6e;
+pub const DW_AT_str_offsets_base: u16 = 0x72;
+pub const DW_AT_addr_base: u16 = 0x73;
+pub const DW_AT_rnglists_base: u16 = 0x74;
 
 pub const DW_FORM_addr: u8 = 0x01;
 pub const DW_FORM_block2: u8 = 0x03;
@@ -62,3 +74,18 @@ pub const DW_FORM_addrx1: u8 = 0x29;
 pub const DW_FORM_addrx2: u8 = 0x2a;
 pub const DW_FORM_addrx3: u8 = 0x2b;
 pub const DW_FORM_addrx4: u8 = 0x2c;
+
+pub const DW_LNCT_path: u64 = 0x1;
+pub const DW_LNCT_directory_index: u64 = 0x2;
+pub const DW_LNCT_timestamp: u64 = 0x3;
+pub const DW_LNCT_size: u64 = 0x4;
+pub const DW_LNCT_MD5: u64 = 0x5;
+
+pub const DW_RLE_end_of_list: u8 = 0x0;
+pub const DW_RLE_base_addressx: u8 = 0x1;
+pub const DW_RLE_startx_endx: u8 = 0x2;
+pub const DW_RLE_startx_length: u8 = 0x3;
+pub const DW_RLE_offset_pair: u8 = 0x4;
+pub const DW_RLE_base_address: u8 = 0x5;
+pub const DW_RLE_start_end: u8 = 0x6;
+pub const DW_RLE_start_length: u8 = 0x7;
@@ -1,6 +1,8 @@
 //! Parse the `.debug_info` section to get Debug Information Entries.
 //!
-//! It supports DWARFv4 now. (See <https://dwarfstd.org/doc/DWARF4.pdf>)
+//! It supports DWARFv4 and DWARFv5 compile units. (See
+//! <https://dwarfstd.org/doc/DWARF4.pdf> and
+//! <https://dwarfstd.org/doc/DWARF5.pdf>)
 //! It parse DIEs from the `.debug_info` section and Abbreviations
 //! from the `.debg_abbrev` section.
 //!
@@ -107,7 +109,7 @@ impl UnitHeader {
 
 #[derive(Clone)]
 pub struct AbbrevAttr {
-    name: u8,
+    name: u16,
     form: u8,
     opt: u128,
 }
@@ -125,7 +127,7 @@ pub struct Abbrev {
     ///
     /// It can be a DW_TAG_compile (a compile unit),
     /// DW_TAG_subprogram, DW_TAG_variable, ... etc.
-    pub tag: u8,
+    pub tag: u16,
     pub has_children: bool,
 
     parsed_attrs: Vec<AbbrevAttr>,
@@ -178,7 +180,7 @@ fn parse_abbrev(data: &[u8]) -> Option<(Abbrev, usize)> {
     Some((
         Abbrev {
             abbrev_code: abbrev_code as u32,
-            tag: tag as u8,
+            tag: tag as u16,
             has_children,
             parsed_attrs,
         },
@@ -190,7 +192,7 @@ fn parse_abbrev(data: &[u8]) -> Option<(Abbrev, usize)> {
 ///
 /// Return the name, form, optional value and size of an abbreviation.
 #[inline]
-fn parse_abbrev_attr(data: &[u8]) -> Option<(u8, u8, u128, usize)> {
+fn parse_abbrev_attr(data: &[u8]) -> Option<(u16, u8, u128, usize)> {
     let mut pos = 0; // Track the size of this abbreviation.
     let (name, bytes) = decode_leb128_128(&data[pos..])?;
     pos += bytes as usize;
@@ -203,7 +205,7 @@ fn parse_abbrev_attr(data: &[u8]) -> Option<(u8, u8, u128, usize)> {
     } else {
         0
     };
-    Some((name as u8, form as u8, opt, pos))
+    Some((name as u16, form as u8, opt, pos))
 }
 
 #[derive(Clone, Debug)]
@@ -363,7 +365,7 @@ fn extract_attr_value_impl<'data>(
 /// * `addr_sz` - The size of an address of the target platform. (4 for 32-bits and 8 for 64-bits)
 ///
 /// Return AttrValue and the number of bytes it takes.
-fn extract_attr_value(
+pub fn extract_attr_value(
     mut data: &[u8],
     form: u8,
     dwarf_sz: usize,
@@ -517,8 +519,11 @@ fn parse_unit_header(data: &[u8]) -> Option<UnitHeader> {
 /// unit.
 #[allow(clippy::upper_case_acronyms)]
 pub struct DIE<'a> {
-    pub tag: u8,
+    pub tag: u16,
     pub abbrev: Option<&'a Abbrev>,
+    /// The offset of the DIE from the start of the `.debug_info`
+    /// section.
+    pub offset: usize,
     abbrev_attrs: &'a [AbbrevAttr],
     abbrev_attrs_idx: usize,
     data: &'a [u8],
@@ -562,7 +567,7 @@ impl<'a> DIE<'a> {
 
 impl<'a> Iterator for DIE<'a> {
     // name, form, opt, value
-    type Item = (u8, u8, u128, AttrValue<'a>);
+    type Item = (u16, u8, u128, AttrValue<'a>);
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
@@ -604,6 +609,9 @@ impl<'a> Iterator for DIE<'a> {
 /// The iterator of DIEs in an Unit.
 pub struct DIEIter<'a> {
     data: &'a [u8],
+    /// The offset of the unit in the `.debug_info` section.
+    unit_off: usize,
+    version: u16,
     dwarf_sz: usize,
     addr_sz: usize,
     off: usize,
@@ -616,6 +624,35 @@ pub struct DIEIter<'a> {
 }
 
 impl<'a> DIEIter<'a> {
+    /// Retrieve the offset of the unit this iterator walks in the
+    /// `.debug_info` section.
+    ///
+    /// Attributes of the `DW_FORM_ref*` forms (except
+    /// `DW_FORM_ref_addr`) are relative to it.
+    #[inline]
+    pub fn unit_offset(&self) -> usize {
+        self.unit_off
+    }
+
+    /// Retrieve the DWARF version of the unit.
+    #[inline]
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Retrieve the size of offsets in the unit's DWARF format (4 for
+    /// 32-bit DWARF and 8 for 64-bit DWARF).
+    #[inline]
+    pub fn dwarf_size(&self) -> usize {
+        self.dwarf_sz
+    }
+
+    /// Retrieve the size of an address on the target platform.
+    #[inline]
+    pub fn addr_size(&self) -> usize {
+        self.addr_sz
+    }
+
     pub fn die_finish_reading(&mut self, size: usize) {
         self.die_reading_done = true;
         self.off += size;
@@ -665,6 +702,7 @@ impl<'a> Iterator for DIEIter<'a> {
             return None
         }
 
+        let offset = self.unit_off + self.off_delta + self.off;
         let (abbrev_idx, bytes) = decode_leb128_128(&self.data[self.off..])?;
         self.off += bytes as usize;
 
@@ -676,6 +714,7 @@ impl<'a> Iterator for DIEIter<'a> {
             Some(DIE {
                 tag: 0,
                 abbrev: None,
+                offset,
                 abbrev_attrs: &[],
                 abbrev_attrs_idx: 0,
                 data: &self.data[self.off..],
@@ -688,12 +727,17 @@ impl<'a> Iterator for DIEIter<'a> {
             self.abbrev = Some(abbrev);
             if abbrev.has_children {
                 self.cur_depth += 1;
+            } else if self.cur_depth == 0 {
+                // A unit DIE without children (as emitted by assemblers,
+                // for example) is all there is to the unit.
+                self.done = true;
             }
 
             self.die_reading_done = false;
             Some(DIE {
                 tag: abbrev.tag,
                 abbrev: Some(abbrev),
+                offset,
                 abbrev_attrs: abbrev.all_attrs(),
                 abbrev_attrs_idx: 0,
                 data: &self.data[self.off..],
@@ -743,34 +787,45 @@ impl<'a> Iterator for UnitIter<'a> {
             let hdr_sz = uh.header_size();
             self.off += uh.unit_size();
 
-            match uh {
-                UnitHeader::CompileV4(ref cuh) => {
-                    let dwarf_sz = if cuh.bits64 { 8 } else { 4 };
-                    let addr_sz = cuh.address_size as usize;
-                    let (abbrevs, _) =
-                        parse_cu_abbrevs(&self.abbrev_data[cuh.debug_abbrev_offset as usize..])?;
-                    break Some((
-                        uh,
-                        DIEIter {
-                            data: &self.info_data[off + hdr_sz..],
-                            dwarf_sz,
-                            addr_sz,
-                            off: 0,
-                            off_delta: hdr_sz,
-                            cur_depth: 0,
-                            abbrevs,
-                            abbrev: None,
-                            die_reading_done: true,
-                            done: false,
-                        },
-                    ))
-                }
-                // blazesym supports only v4 so far.
-                UnitHeader::CompileV5(ref _cuh) => {
-                    log::warn!("ignoring unsupported DWARF v5 unit header");
+            let (bits64, version, address_size, debug_abbrev_offset) = match uh {
+                UnitHeader::CompileV4(ref cuh) => (
+                    cuh.bits64,
+                    cuh.version,
+                    cuh.address_size,
+                    cuh.debug_abbrev_offset,
+                ),
+                UnitHeader::CompileV5(ref cuh) => (
+                    cuh.bits64,
+                    cuh.version,
+                    cuh.address_size,
+                    cuh.debug_abbrev_offset,
+                ),
+                UnitHeader::Unknown(ref _cuh) => {
+                    log::debug!("ignoring unit of unsupported type at offset {off}");
+                    continue
                 }
-                UnitHeader::Unknown(ref _cuh) => (),
-            }
+            };
+
+            let dwarf_sz = if bits64 { 8 } else { 4 };
+            let addr_sz = address_size as usize;
+            let (abbrevs, _) = parse_cu_abbrevs(&self.abbrev_data[debug_abbrev_offset as usize..])?;
+            break Some((
+                uh,
+                DIEIter {
+                    data: &self.info_data[off + hdr_sz..],
+                    unit_off: off,
+                    version,
+                    dwarf_sz,
+                    addr_sz,
+                    off: 0,
+                    off_delta: hdr_sz,
+                    cur_depth: 0,
+                    abbrevs,
+                    abbrev: None,
+                    die_reading_done: true,
+                    done: false,
+                },
+            ))
         }
     }
 }
@@ -877,8 +932,42 @@ mod tests {
         assert_eq!(attr_cnt, 275310);
     }
 
-    /// Make sure check we do not panic when handling DWARFv5 (which is not
-    /// currently supported).
+    /// Check that iteration of a unit stops after its unit DIE if the
+    /// latter does not have any children.
+    #[test]
+    fn test_unititer_childless_unit() {
+        #[rustfmt::skip]
+        let abbrev = [
+            // Code 1: DW_TAG_compile_unit, DW_CHILDREN_no
+            0x01, 0x11, 0x00,
+            // DW_AT_name, DW_FORM_string
+            0x03, 0x08,
+            0x00, 0x00,
+            0x00,
+        ];
+        #[rustfmt::skip]
+        let info = [
+            // Unit length, version 4, abbrev offset, address size
+            0x0a, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08,
+            // The unit DIE, without any children
+            0x01, b'a', 0x00,
+            // Unit length, version 4, abbrev offset, address size
+            0x0a, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08,
+            // The unit DIE, without any children
+            0x01, b'b', 0x00,
+        ];
+
+        let iter = UnitIter::new(&info, &abbrev);
+        let mut cnt = 0;
+        for (_uh, dieiter) in iter {
+            cnt += 1;
+            let tags = dieiter.map(|die| die.tag).collect::<Vec<_>>();
+            assert_eq!(tags, vec![DW_TAG_compile_unit]);
+        }
+        assert_eq!(cnt, 2);
+    }
+
+    /// Make sure that we can iterate over the DIEs of DWARFv5 units.
     #[test]
     fn test_unititer_v5() {
         let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
@@ -891,6 +980,15 @@ mod tests {
         let info = elfparser.read_section_raw(info_idx).unwrap();
 
         let iter = UnitIter::new(info, abbrev);
-        let () = iter.for_each(|(_header, _die)| ());
+        let mut subprog_cnt = 0;
+        for (header, dieiter) in iter {
+            assert!(matches!(header, UnitHeader::CompileV5(..)));
+            for die in dieiter {
+                if die.tag == DW_TAG_subprogram {
+                    subprog_cnt += 1;
+                }
+            }
+        }
+        assert_ne!(subprog_cnt, 0);
     }
 }
@@ -1,13 +1,11 @@
+use std::collections::HashMap;
 #[cfg(test)]
 use std::env;
-use std::ffi::CStr;
 use std::ffi::OsStr;
 use std::fmt::Debug;
-use std::fmt::Formatter;
-use std::fmt::Result as FmtResult;
 use std::io::Error;
 use std::io::ErrorKind;
-use std::mem;
+use std::ops::Range;
 use std::path::Path;
 
 use crate::elf::ElfParser;
@@ -18,7 +16,6 @@ use crate::util::decode_udword;
 use crate::util::decode_uhalf;
 use crate::util::decode_uword;
 use crate::util::find_match_or_lower_bound_by;
-use crate::util::Pod;
 use crate::util::ReadRaw as _;
 use crate::Addr;
 
@@ -26,53 +23,21 @@ use super::constants;
 use super::debug_info;
 
 
-#[repr(C, packed)]
-pub(crate) struct DebugLinePrologueV2 {
-    total_length: u32,
-    version: u16,
-    prologue_length: u32,
-    minimum_instruction_length: u8,
-    default_is_stmt: u8,
-    line_base: i8,
-    line_range: u8,
-    opcode_base: u8,
-}
-
-// SAFETY: `DebugLinePrologueV2` is valid for any bit pattern.
-unsafe impl Pod for DebugLinePrologueV2 {}
-
-
-/// DebugLinePrologue is actually a V4.
+/// The prologue (header) of a line number program.
 ///
-/// DebugLinePrologueV2 will be converted to this type.
-#[repr(C, packed)]
+/// The prologues of all supported DWARF versions are converted to
+/// this type.
+#[derive(Debug)]
 pub(crate) struct DebugLinePrologue {
-    total_length: u32,
     version: u16,
-    prologue_length: u32,
     minimum_instruction_length: u8,
-    maximum_ops_per_instruction: u8,
+    _maximum_ops_per_instruction: u8,
     default_is_stmt: u8,
     line_base: i8,
     line_range: u8,
     opcode_base: u8,
 }
 
-impl Debug for DebugLinePrologue {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let total_length = self.total_length;
-        let version = self.version;
-
-        f.debug_struct("DebugLinePrologue")
-            .field("total_length", &total_length)
-            .field("version", &version)
-            .finish()
-    }
-}
-
-// SAFETY: `DebugLinePrologue` is valid for any bit pattern.
-unsafe impl Pod for DebugLinePrologue {}
-
 
 /// The file information of a file for a CU.
 #[derive(Debug)]
@@ -86,6 +51,8 @@ pub(crate) struct DebugLineFileInfo {
 /// Represent a Compile Unit (CU) in a .debug_line section.
 #[derive(Debug)]
 pub(crate) struct DebugLineCU {
+    /// The offset of the CU's line number program in `.debug_line`.
+    pub offset: usize,
     pub prologue: DebugLinePrologue,
     pub _standard_opcode_lengths: Vec<u8>,
     pub include_directories: Vec<String>,
@@ -107,24 +74,48 @@ impl DebugLineCU {
 
     pub(crate) fn stringify_row(&self, idx: usize) -> Option<(&Path, &OsStr, usize)> {
         let states = &self.matrix[idx];
-        let (dir, file) = {
-            if states.file > 0 {
-                let file = &self.files[states.file - 1];
-                let dir = {
-                    if file.dir_idx == 0 {
-                        Path::new("")
-                    } else {
-                        Path::new(&self.include_directories[file.dir_idx as usize - 1])
-                    }
-                };
-                (dir, OsStr::new(&file.name))
-            } else {
-                (Path::new(""), OsStr::new(""))
-            }
-        };
+        let (dir, file) = self
+            .find_file(states.file)
+            .unwrap_or((Path::new(""), OsStr::new("")));
 
         Some((dir, file, states.line))
     }
+
+    /// Find the directory and name of a file given its index into the
+    /// file table of the CU.
+    pub(crate) fn find_file(&self, file_idx: usize) -> Option<(&Path, &OsStr)> {
+        if self.prologue.version >= 5 {
+            // DWARFv5 uses 0-based indices, with the primary source file
+            // and the compilation directory being part of the tables.
+            let file = self.files.get(file_idx)?;
+            let dir = Path::new(self.include_directories.get(file.dir_idx as usize)?);
+            Some((dir, OsStr::new(&file.name)))
+        } else {
+            let file = self.files.get(file_idx.checked_sub(1)?)?;
+            let dir = if file.dir_idx == 0 {
+                Path::new("")
+            } else {
+                Path::new(self.include_directories.get(file.dir_idx as usize - 1)?)
+            };
+            Some((dir, OsStr::new(&file.name)))
+        }
+    }
+}
+
+/// Read the NUL terminated string at offset `off` of a string section.
+fn read_str_at(data: &[u8], off: u64) -> Result<&str, Error> {
+    let mut data = data
+        .get(off as usize..)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "encountered invalid string offset"))?;
+    data.read_cstr()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "failed to find NUL terminated string",
+            )
+        })?
+        .to_str()
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "Invalid UTF-8 string"))
 }
 
 /// Parse the list of directory paths for a CU.
@@ -193,65 +184,203 @@ fn parse_debug_line_files(data: &mut &[u8]) -> Result<Vec<DebugLineFileInfo>, Er
     }
 }
 
-fn parse_debug_line_cu(data: &mut &[u8], addresses: &[Addr]) -> Result<DebugLineCU, Error> {
-    let prologue_v2_size: usize = mem::size_of::<DebugLinePrologueV2>();
-    let prologue_v4_size: usize = mem::size_of::<DebugLinePrologue>();
+/// A single entry of a DWARFv5 directory or file name table.
+#[derive(Debug, Default)]
+struct DebugLineEntryV5 {
+    path: String,
+    dir_idx: u64,
+    mod_tm: u64,
+    size: u64,
+}
+
+/// Parse a DWARFv5 directory or file name table, including the
+/// description of the entry format preceding it.
+fn parse_debug_line_entries_v5(
+    data: &mut &[u8],
+    dwarf_sz: usize,
+    addr_sz: usize,
+    str_data: &[u8],
+    line_str_data: &[u8],
+) -> Result<Vec<DebugLineEntryV5>, Error> {
+    let format_cnt = data
+        .read_u8()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read entry format count"))?;
+    let mut formats = Vec::with_capacity(format_cnt.into());
+    for _ in 0..format_cnt {
+        let (content_type, _bytes) = data
+            .read_u128_leb128()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read content type"))?;
+        let (form, _bytes) = data
+            .read_u128_leb128()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read entry form"))?;
+        let () = formats.push((content_type as u64, form as u8));
+    }
+
+    let (entry_cnt, _bytes) = data
+        .read_u128_leb128()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read entry count"))?;
+    let mut entries = Vec::new();
+    for _ in 0..entry_cnt {
+        let mut entry = DebugLineEntryV5::default();
+        for (content_type, form) in &formats {
+            let (value, bytes) = debug_info::extract_attr_value(data, *form, dwarf_sz, addr_sz)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("failed to read entry with form {form:#x}"),
+                    )
+                })?;
+            let _slice = data.read_slice(bytes);
+
+            match *content_type {
+                constants::DW_LNCT_path => {
+                    let path = match (*form, value) {
+                        (_, debug_info::AttrValue::String(s)) => s,
+                        (constants::DW_FORM_line_strp, debug_info::AttrValue::Unsigned(off)) => {
+                            read_str_at(line_str_data, off)?
+                        }
+                        (constants::DW_FORM_strp, debug_info::AttrValue::Unsigned(off)) => {
+                            read_str_at(str_data, off)?
+                        }
+                        _ => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                format!("encountered unsupported path form: {form:#x}"),
+                            ))
+                        }
+                    };
+                    entry.path = path.to_string();
+                }
+                constants::DW_LNCT_directory_index => {
+                    entry.dir_idx = attr_value_unsigned(*form, 0, &value).unwrap_or(0);
+                }
+                constants::DW_LNCT_timestamp => {
+                    entry.mod_tm = attr_value_unsigned(*form, 0, &value).unwrap_or(0);
+                }
+                constants::DW_LNCT_size => {
+                    entry.size = attr_value_unsigned(*form, 0, &value).unwrap_or(0);
+                }
+                _ => (),
+            }
+        }
+        let () = entries.push(entry);
+    }
+    Ok(entries)
+}
 
-    let mut head = *data;
-    let v2 = data
-        .read_pod::<DebugLinePrologueV2>()
+fn parse_debug_line_cu(
+    data: &mut &[u8],
+    offset: usize,
+    str_data: &[u8],
+    line_str_data: &[u8],
+    addresses: &[Addr],
+) -> Result<DebugLineCU, Error> {
+    let unit_length = data
+        .read_u32()
         .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read debug line prologue"))?;
+    // A unit length of 0xffffffff indicates the 64-bit DWARF format,
+    // in which case the actual length follows as a 64 bit value.
+    let (unit_length, dwarf_sz) = if unit_length == 0xffffffff {
+        let unit_length = data.read_u64().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "failed to read debug line prologue")
+        })?;
+        (unit_length, 8)
+    } else {
+        (u64::from(unit_length), 4)
+    };
+
+    // Consume the entire CU, so that the next one can be parsed
+    // independently of how much of this one we understand.
+    let mut unit = usize::try_from(unit_length)
+        .ok()
+        .and_then(|len| data.read_slice(len))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "encountered insufficient debug line information data",
+            )
+        })?;
 
-    if v2.version != 2 && v2.version != 4 {
-        let version = v2.version;
+    let version = unit
+        .read_u16()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read debug line prologue"))?;
+    if version != 2 && version != 4 && version != 5 {
         return Err(Error::new(
             ErrorKind::Unsupported,
             format!("encountered unsupported DWARF version: {version}"),
         ))
     }
 
-    let (prologue, prologue_size) = if v2.version == 4 {
-        // Upgrade to V4.
-        // V4 has more fields to read.
-        let prologue_v4 = head.read_pod::<DebugLinePrologue>().ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "failed to read debug line v4 prologue",
-            )
-        })?;
-        (*data) = head;
-        (prologue_v4, prologue_v4_size)
+    // Only DWARFv5 encodes the address size in the prologue. Earlier
+    // versions don't need it.
+    let addr_sz = if version >= 5 {
+        let addr_sz = unit.read_u8();
+        let _segment_selector_sz = unit.read_u8();
+        addr_sz.map(usize::from)
     } else {
-        // Convert V2 to V4
-        let prologue_v4 = DebugLinePrologue {
-            total_length: v2.total_length,
-            version: v2.version,
-            prologue_length: v2.prologue_length,
-            minimum_instruction_length: v2.minimum_instruction_length,
-            maximum_ops_per_instruction: 0,
-            default_is_stmt: v2.default_is_stmt,
-            line_base: v2.line_base,
-            line_range: v2.line_range,
-            opcode_base: v2.opcode_base,
-        };
-        (prologue_v4, prologue_v2_size)
+        Some(0)
     };
+    let prologue_length = if dwarf_sz == 8 {
+        unit.read_u64()
+    } else {
+        unit.read_u32().map(u64::from)
+    };
+    let (addr_sz, prologue_length) = addr_sz
+        .zip(prologue_length)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read debug line prologue"))?;
+
+    // The prologue length counts from the end of its own field.
+    let (mut header, stmts) = usize::try_from(prologue_length)
+        .ok()
+        .and_then(|len| unit.get(..len).zip(unit.get(len..)))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "encountered invalid header length"))?;
 
-    let to_read = prologue.total_length as usize + 4 - prologue_size;
-    let () = data.ensure(to_read).ok_or_else(|| {
-        Error::new(
-            ErrorKind::InvalidData,
-            "encountered insufficient debug line information data",
-        )
-    })?;
+    fn prologue_impl(header: &mut &[u8], version: u16) -> Option<DebugLinePrologue> {
+        let minimum_instruction_length = header.read_u8()?;
+        let maximum_ops_per_instruction = if version >= 4 { header.read_u8()? } else { 0 };
+
+        let prologue = DebugLinePrologue {
+            version,
+            minimum_instruction_length,
+            _maximum_ops_per_instruction: maximum_ops_per_instruction,
+            default_is_stmt: header.read_u8()?,
+            line_base: header.read_u8()? as i8,
+            line_range: header.read_u8()?,
+            opcode_base: header.read_u8()?,
+        };
+        Some(prologue)
+    }
+
+    let prologue = prologue_impl(&mut header, version)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read debug line prologue"))?;
 
     let std_op_num = (prologue.opcode_base - 1) as usize;
-    let std_op_lengths = data
+    let std_op_lengths = header
         .read_slice(std_op_num)
         .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read std op lengths"))?;
-    let inc_dirs = parse_debug_line_dirs(data)?;
-    let files = parse_debug_line_files(data)?;
-    let matrix = run_debug_line_stmts(data, &prologue, addresses)?;
+    let (inc_dirs, files) = if prologue.version == 5 {
+        let dirs =
+            parse_debug_line_entries_v5(&mut header, dwarf_sz, addr_sz, str_data, line_str_data)?
+                .into_iter()
+                .map(|entry| entry.path)
+                .collect();
+        let files =
+            parse_debug_line_entries_v5(&mut header, dwarf_sz, addr_sz, str_data, line_str_data)?
+                .into_iter()
+                .map(|entry| DebugLineFileInfo {
+                    name: entry.path,
+                    dir_idx: entry.dir_idx as u32,
+                    _mod_tm: entry.mod_tm,
+                    _size: entry.size as usize,
+                })
+                .collect();
+        (dirs, files)
+    } else {
+        let inc_dirs = parse_debug_line_dirs(&mut header)?;
+        let files = parse_debug_line_files(&mut header)?;
+        (inc_dirs, files)
+    };
+    let matrix = run_debug_line_stmts(stmts, &prologue, addresses)?;
 
     #[cfg(debug_assertions)]
     for i in 1..matrix.len() {
@@ -267,6 +396,7 @@ fn parse_debug_line_cu(data: &mut &[u8], addresses: &[Addr]) -> Result<DebugLine
     }
 
     Ok(DebugLineCU {
+        offset,
         prologue,
         _standard_opcode_lengths: std_op_lengths.to_vec(),
         include_directories: inc_dirs,
@@ -284,7 +414,7 @@ pub(crate) struct DebugLineStates {
     discriminator: u64,
     is_stmt: bool,
     basic_block: bool,
-    end_sequence: bool,
+    pub end_sequence: bool,
     prologue_end: bool,
     should_reset: bool,
 }
@@ -527,6 +657,7 @@ fn run_debug_line_stmts(
     let mut ip = 0;
     let mut matrix = Vec::<DebugLineStates>::new();
     let mut should_sort = false;
+    let mut last_addr = 0;
     let mut states_cur = DebugLineStates::new(prologue);
     let mut states_last = states_cur.clone();
     let mut last_ip_pushed = false;
@@ -545,6 +676,13 @@ fn run_debug_line_stmts(
                         force_no_emit = true;
                     }
                     if !force_no_emit {
+                        // Sequences are not necessarily emitted in
+                        // address order.
+                        if states_cur.addr < last_addr {
+                            should_sort = true;
+                        }
+                        last_addr = states_cur.addr;
+
                         if !addresses.is_empty() {
                             let mut pushed = false;
                             for addr in addresses {
@@ -568,9 +706,6 @@ fn run_debug_line_stmts(
                         } else {
                             matrix.push(states_cur.clone());
                         }
-                        if states_last.addr > states_cur.addr {
-                            should_sort = true;
-                        }
                     }
                 }
                 if states_cur.should_reset {
@@ -583,12 +718,24 @@ fn run_debug_line_stmts(
     }
 
     if should_sort {
-        matrix.sort_by_key(|x| x.addr);
+        // Make sure that the end of a sequence sorts after the start of
+        // another one at the same address.
+        matrix.sort_by_key(|x| (x.addr, x.end_sequence));
     }
 
     Ok(matrix)
 }
 
+/// Retrieve the data of the section with the given name, or an empty
+/// slice if the section does not exist.
+fn optional_section_data<'a>(parser: &'a ElfParser, name: &str) -> Result<&'a [u8], Error> {
+    match parser.find_section(name) {
+        Ok(idx) => parser.section_data(idx),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(&[]),
+        Err(err) => Err(err),
+    }
+}
+
 /// If addresses is empty, it returns a full version of debug_line matrix.
 /// If addresses is not empty, return only data needed to resolve given addresses.
 pub(crate) fn parse_debug_line_elf_parser(
@@ -596,18 +743,19 @@ pub(crate) fn parse_debug_line_elf_parser(
     addresses: &[Addr],
 ) -> Result<Vec<DebugLineCU>, Error> {
     let debug_line_idx = parser.find_section(".debug_line")?;
-    let debug_line_sz = parser.get_section_size(debug_line_idx)?;
-    let mut remain_sz = debug_line_sz;
-    let prologue_size: usize = mem::size_of::<DebugLinePrologueV2>();
     let mut not_found = Vec::from(addresses);
 
+    let debug_line_sz = parser.get_section_size(debug_line_idx)?;
     let data = &mut parser.section_data(debug_line_idx)?;
+    // String sections are only referenced by DWARFv5 line number
+    // programs.
+    let str_data = optional_section_data(parser, ".debug_str")?;
+    let line_str_data = optional_section_data(parser, ".debug_line_str")?;
 
     let mut all_cus = Vec::<DebugLineCU>::new();
-    while remain_sz > prologue_size {
-        let debug_line_cu = parse_debug_line_cu(data, &not_found)?;
-        let prologue = &debug_line_cu.prologue;
-        remain_sz -= prologue.total_length as usize + 4;
+    while !data.is_empty() {
+        let offset = debug_line_sz - data.len();
+        let debug_line_cu = parse_debug_line_cu(data, offset, str_data, line_str_data, &not_found)?;
 
         if debug_line_cu.matrix.is_empty() {
             continue
@@ -639,24 +787,76 @@ pub(crate) fn parse_debug_line_elf_parser(
         }
     }
 
-    if remain_sz != 0 {
-        return Err(Error::new(
-            ErrorKind::InvalidData,
-            "encountered remaining garbage data at the end",
-        ))
-    }
-
     Ok(all_cus)
 }
 
 
+/// A function inlined into another one, as extracted out of DWARF.
+#[derive(Clone, Debug)]
+pub(crate) struct DWInlinedFn<'a> {
+    pub name: &'a str,
+    /// The address ranges of the code inlined.
+    pub ranges: Vec<Range<Addr>>,
+    /// The index of the file containing the call site in the file table
+    /// of the line number program referenced by the containing
+    /// [`DWSymInfo`].
+    pub call_file: usize,
+    pub call_line: usize,
+    pub call_column: usize,
+}
+
 /// The symbol information extracted out of DWARF.
 #[derive(Clone, Debug)]
 pub(crate) struct DWSymInfo<'a> {
     pub name: &'a str,
+    /// The entry address of the symbol.
     pub addr: Addr,
-    pub size: usize,
     pub sym_type: SymType, // A function or a variable.
+    /// All address ranges covered by the symbol, starting with the one
+    /// containing `addr`.
+    ///
+    /// Functions may not be contiguous, e.g., if the compiler moved
+    /// rarely executed parts of them into a separate section.
+    pub ranges: Vec<Range<Addr>>,
+    /// The offset of the line number program of the symbol's compile
+    /// unit in the `.debug_line` section.
+    pub stmt_list: Option<usize>,
+    /// The functions inlined into this symbol.
+    ///
+    /// Callers precede the functions inlined into them.
+    pub inlined: Vec<DWInlinedFn<'a>>,
+}
+
+/// The names of subprograms, indexed by the offset of their DIEs.
+///
+/// Every entry has the name of the subprogram, if any, and the offset
+/// of the DIE it refers to through `DW_AT_abstract_origin` or
+/// `DW_AT_specification`, if any.
+type DIENames<'a> = HashMap<usize, (Option<&'a str>, Option<usize>)>;
+
+/// The attributes of a subprogram or an inlined subroutine.
+#[derive(Debug, Default)]
+struct FnAttrs<'a> {
+    name: Option<&'a str>,
+    addr: Option<Addr>,
+    size: usize,
+    /// All address ranges of the function. The first one starts at
+    /// `addr`.
+    ranges: Vec<Range<Addr>>,
+    /// The offset of the DIE referred to by `DW_AT_abstract_origin` or
+    /// `DW_AT_specification`.
+    origin: Option<usize>,
+    call_file: usize,
+    call_line: usize,
+    call_column: usize,
+}
+
+/// A subprogram, the DIEs of which are still being walked.
+struct PendingSym<'a> {
+    /// The nesting level of the subprogram's DIE.
+    level: usize,
+    attrs: FnAttrs<'a>,
+    inlined: Vec<FnAttrs<'a>>,
 }
 
 fn find_die_sibling(die: &mut debug_info::DIE<'_>) -> Option<usize> {
@@ -671,87 +871,462 @@ fn find_die_sibling(die: &mut debug_info::DIE<'_>) -> Option<usize> {
     None
 }
 
-/// Parse a DIE that declares a subprogram. (a function)
+/// Interpret the value of an attribute as an unsigned integer.
+fn attr_value_unsigned(form: u8, opt: u128, value: &debug_info::AttrValue<'_>) -> Option<u64> {
+    if form == constants::DW_FORM_implicit_const {
+        return u64::try_from(opt).ok()
+    }
+
+    match value {
+        debug_info::AttrValue::Unsigned(v) => Some(*v),
+        debug_info::AttrValue::Unsigned128(v) => u64::try_from(*v).ok(),
+        debug_info::AttrValue::Signed128(v) => u64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// The contents of the sections referred to by attributes of DIEs.
+#[derive(Clone, Copy, Debug, Default)]
+struct DebugSections<'a> {
+    /// The content of the `.debug_str` section.
+    str_data: &'a [u8],
+    /// The content of the `.debug_line_str` section.
+    line_str_data: &'a [u8],
+    /// The content of the `.debug_str_offsets` section.
+    str_offsets_data: &'a [u8],
+    /// The content of the `.debug_addr` section.
+    addr_data: &'a [u8],
+    /// The content of the `.debug_ranges` section.
+    ranges_data: &'a [u8],
+    /// The content of the `.debug_rnglists` section.
+    rnglists_data: &'a [u8],
+}
+
+/// Information about the unit that DIEs being parsed belong to.
+#[derive(Debug)]
+struct UnitCtx<'a> {
+    sections: DebugSections<'a>,
+    /// The offset of the unit in `.debug_info`.
+    unit_off: usize,
+    version: u16,
+    dwarf_sz: usize,
+    addr_sz: usize,
+    /// The base address of the unit, which addresses in range lists
+    /// are relative to by default.
+    base_addr: Addr,
+    /// The offset of the unit's line number program in `.debug_line`.
+    stmt_list: Option<usize>,
+    /// The offset of the unit's contribution to `.debug_str_offsets`.
+    str_offsets_base: Option<usize>,
+    /// The offset of the unit's contribution to `.debug_addr`.
+    addr_base: Option<usize>,
+    /// The offset of the unit's contribution to `.debug_rnglists`.
+    rnglists_base: Option<usize>,
+}
+
+impl<'a> UnitCtx<'a> {
+    fn new(sections: DebugSections<'a>, dieiter: &debug_info::DIEIter<'_>) -> Self {
+        Self {
+            sections,
+            unit_off: dieiter.unit_offset(),
+            version: dieiter.version(),
+            dwarf_sz: dieiter.dwarf_size(),
+            addr_sz: dieiter.addr_size(),
+            base_addr: 0,
+            stmt_list: None,
+            str_offsets_base: None,
+            addr_base: None,
+            rnglists_base: None,
+        }
+    }
+
+    /// Read an entry of size `size` at index `idx` of the table starting
+    /// at `base` in `data`.
+    fn read_entry(data: &[u8], base: Option<usize>, idx: u64, size: usize) -> Option<u64> {
+        let off = base?.checked_add((idx as usize).checked_mul(size)?)?;
+        let mut data = data.get(off..)?;
+        match size {
+            4 => data.read_u32().map(u64::from),
+            8 => data.read_u64(),
+            _ => None,
+        }
+    }
+
+    /// Retrieve the string an attribute value refers to.
+    fn str(&self, form: u8, value: debug_info::AttrValue<'a>) -> Result<&'a str, Error> {
+        let (data, off) = match (form, value) {
+            (_, debug_info::AttrValue::String(s)) => return Ok(s),
+            (constants::DW_FORM_strp, debug_info::AttrValue::Unsigned(off)) => {
+                (self.sections.str_data, Some(off))
+            }
+            (constants::DW_FORM_line_strp, debug_info::AttrValue::Unsigned(off)) => {
+                (self.sections.line_str_data, Some(off))
+            }
+            (
+                constants::DW_FORM_strx
+                | constants::DW_FORM_str1
+                | constants::DW_FORM_str2
+                | constants::DW_FORM_str3
+                | constants::DW_FORM_str4,
+                debug_info::AttrValue::Unsigned(idx),
+            ) => (
+                self.sections.str_data,
+                Self::read_entry(
+                    self.sections.str_offsets_data,
+                    self.str_offsets_base,
+                    idx,
+                    self.dwarf_sz,
+                ),
+            ),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("encountered unsupported string form: {form:#x}"),
+                ))
+            }
+        };
+
+        let off = off.ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "encountered invalid string index")
+        })?;
+        read_str_at(data, off)
+    }
+
+    /// Retrieve the address an attribute value refers to.
+    fn addr(&self, form: u8, value: debug_info::AttrValue<'_>) -> Result<Addr, Error> {
+        match (form, value) {
+            (
+                constants::DW_FORM_addrx
+                | constants::DW_FORM_addrx1
+                | constants::DW_FORM_addrx2
+                | constants::DW_FORM_addrx3
+                | constants::DW_FORM_addrx4,
+                debug_info::AttrValue::Unsigned(idx),
+            ) => Self::read_entry(self.sections.addr_data, self.addr_base, idx, self.addr_sz)
+                .map(|addr| addr as Addr)
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::InvalidData, "encountered invalid address index")
+                }),
+            (_, debug_info::AttrValue::Unsigned(addr)) => Ok(addr as Addr),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("encountered unsupported address form: {form:#x}"),
+            )),
+        }
+    }
+
+    /// Read an address of the unit's address size from `data`.
+    fn read_addr(&self, data: &mut &[u8]) -> Option<u64> {
+        match self.addr_sz {
+            4 => data.read_u32().map(u64::from),
+            8 => data.read_u64(),
+            _ => None,
+        }
+    }
+
+    /// Retrieve the address at index `idx` of the unit's contribution
+    /// to `.debug_addr`.
+    fn addrx(&self, idx: u64) -> Option<u64> {
+        Self::read_entry(self.sections.addr_data, self.addr_base, idx, self.addr_sz)
+    }
+
+    /// Parse a range list in the format of the `.debug_ranges` section
+    /// (DWARF v4 and before).
+    fn parse_debug_ranges(&self, mut data: &[u8]) -> Option<Vec<Range<Addr>>> {
+        let max_addr = if self.addr_sz == 4 {
+            u64::from(u32::MAX)
+        } else {
+            u64::MAX
+        };
+        let mut base = self.base_addr as u64;
+        let mut ranges = Vec::new();
+
+        loop {
+            let begin = self.read_addr(&mut data)?;
+            let end = self.read_addr(&mut data)?;
+            if begin == 0 && end == 0 {
+                break
+            }
+            if begin == max_addr {
+                // A base address selection entry.
+                base = end;
+                continue
+            }
+            if begin < end {
+                ranges.push(base.wrapping_add(begin) as Addr..base.wrapping_add(end) as Addr);
+            }
+        }
+        Some(ranges)
+    }
+
+    /// Parse a range list in the format of the `.debug_rnglists`
+    /// section (DWARF v5).
+    fn parse_debug_rnglist(&self, mut data: &[u8]) -> Option<Vec<Range<Addr>>> {
+        fn read_uleb(data: &mut &[u8]) -> Option<u64> {
+            data.read_u128_leb128()
+                .and_then(|(value, _)| u64::try_from(value).ok())
+        }
+
+        let mut base = self.base_addr as u64;
+        let mut ranges = Vec::new();
+
+        loop {
+            let (begin, end) = match data.read_u8()? {
+                constants::DW_RLE_end_of_list => break,
+                constants::DW_RLE_base_addressx => {
+                    base = self.addrx(read_uleb(&mut data)?)?;
+                    continue
+                }
+                constants::DW_RLE_startx_endx => {
+                    let begin = self.addrx(read_uleb(&mut data)?)?;
+                    let end = self.addrx(read_uleb(&mut data)?)?;
+                    (begin, end)
+                }
+                constants::DW_RLE_startx_length => {
+                    let begin = self.addrx(read_uleb(&mut data)?)?;
+                    let len = read_uleb(&mut data)?;
+                    (begin, begin.wrapping_add(len))
+                }
+                constants::DW_RLE_offset_pair => {
+                    let begin = read_uleb(&mut data)?;
+                    let end = read_uleb(&mut data)?;
+                    (base.wrapping_add(begin), base.wrapping_add(end))
+                }
+                constants::DW_RLE_base_address => {
+                    base = self.read_addr(&mut data)?;
+                    continue
+                }
+                constants::DW_RLE_start_end => {
+                    let begin = self.read_addr(&mut data)?;
+                    let end = self.read_addr(&mut data)?;
+                    (begin, end)
+                }
+                constants::DW_RLE_start_length => {
+                    let begin = self.read_addr(&mut data)?;
+                    let len = read_uleb(&mut data)?;
+                    (begin, begin.wrapping_add(len))
+                }
+                _ => return None,
+            };
+            if begin < end {
+                ranges.push(begin as Addr..end as Addr);
+            }
+        }
+        Some(ranges)
+    }
+
+    /// Retrieve the address ranges the value of a `DW_AT_ranges`
+    /// attribute refers to.
+    fn ranges(&self, form: u8, value: u64) -> Result<Vec<Range<Addr>>, Error> {
+        let ranges = if self.version < 5 {
+            self.sections
+                .ranges_data
+                .get(value as usize..)
+                .and_then(|data| self.parse_debug_ranges(data))
+        } else {
+            let data = self.sections.rnglists_data;
+            let off = if form == constants::DW_FORM_rnglistx {
+                // The entries of the offset table are relative to the
+                // table itself.
+                Self::read_entry(data, self.rnglists_base, value, self.dwarf_sz)
+                    .and_then(|off| self.rnglists_base?.checked_add(off as usize))
+            } else {
+                Some(value as usize)
+            };
+            off.and_then(|off| data.get(off..))
+                .and_then(|data| self.parse_debug_rnglist(data))
+        };
+
+        ranges.ok_or_else(|| Error::new(ErrorKind::InvalidData, "encountered invalid range list"))
+    }
+}
+
+/// Parse a DIE that describes a compile unit, extracting the attributes
+/// that are relevant for interpreting the unit's other DIEs.
+fn parse_die_compile_unit<'a>(die: &mut debug_info::DIE<'a>, unit: &mut UnitCtx<'a>) {
+    let mut lo_pc = None;
+
+    for (name, form, opt, value) in die {
+        if name == constants::DW_AT_lo_pc {
+            lo_pc = Some((form, value));
+            continue
+        }
+
+        let value = attr_value_unsigned(form, opt, &value).map(|value| value as usize);
+        match name {
+            constants::DW_AT_stmt_list => unit.stmt_list = value,
+            constants::DW_AT_str_offsets_base => unit.str_offsets_base = value,
+            constants::DW_AT_addr_base => unit.addr_base = value,
+            constants::DW_AT_rnglists_base => unit.rnglists_base = value,
+            _ => (),
+        }
+    }
+
+    // The low PC may be given as an index into `.debug_addr`, so we can
+    // only resolve it once we know the unit's `DW_AT_addr_base`.
+    if let Some((form, value)) = lo_pc {
+        unit.base_addr = unit.addr(form, value).unwrap_or(0);
+    }
+}
+
+/// Parse a DIE that declares a subprogram (a function) or an inlined
+/// subroutine.
 ///
-/// We already know the given DIE is a declaration of a subprogram.
-/// This function trys to extract the address of the subprogram and
-/// other information from the DIE.
+/// This function tries to extract the address, the name, and the call
+/// site of the function from the DIE.
 ///
 /// # Arguments
 ///
 /// * `die` - is a DIE.
-/// * `str_data` - is the content of the `.debug_str` section.
-///
-/// Return a [`DWSymInfo`] if it finds the address of the subprogram.
-fn parse_die_subprogram<'a>(
+/// * `unit` - is the context of the DIE's unit.
+fn parse_die_fn<'a>(
     die: &mut debug_info::DIE<'a>,
-    str_data: &'a [u8],
-) -> Result<Option<DWSymInfo<'a>>, Error> {
-    let mut addr: Option<Addr> = None;
-    let mut name_str: Option<&str> = None;
-    let mut size = 0;
+    unit: &UnitCtx<'a>,
+) -> Result<FnAttrs<'a>, Error> {
+    let mut attrs = FnAttrs::default();
+    let mut hi_pc = None;
+    let mut ranges = None;
 
-    for (name, _form, _opt, value) in die {
+    for (name, form, opt, value) in die {
         match name {
             constants::DW_AT_linkage_name | constants::DW_AT_name => {
-                if name_str.is_some() {
+                if attrs.name.is_some() {
                     continue
                 }
-                name_str = Some(match value {
-                    debug_info::AttrValue::Unsigned(str_off) => unsafe {
-                        CStr::from_ptr(str_data[str_off as usize..].as_ptr().cast())
-                            .to_str()
-                            .map_err(|_e| {
-                                Error::new(
-                                    ErrorKind::InvalidData,
-                                    "fail to extract the name of a subprogram",
-                                )
-                            })?
-                    },
-                    debug_info::AttrValue::String(s) => s,
-                    _ => {
-                        return Err(Error::new(
-                            ErrorKind::InvalidData,
-                            "fail to parse DW_AT_linkage_name {}",
-                        ))
-                    }
-                });
+                attrs.name = Some(unit.str(form, value)?);
             }
-            constants::DW_AT_lo_pc => match value {
-                debug_info::AttrValue::Unsigned(pc) => {
-                    addr = Some(pc as Addr);
+            constants::DW_AT_lo_pc => {
+                attrs.addr = Some(unit.addr(form, value)?);
+            }
+            constants::DW_AT_hi_pc => match form {
+                constants::DW_FORM_addr
+                | constants::DW_FORM_addrx
+                | constants::DW_FORM_addrx1
+                | constants::DW_FORM_addrx2
+                | constants::DW_FORM_addrx3
+                | constants::DW_FORM_addrx4 => {
+                    hi_pc = Some((true, unit.addr(form, value)?));
                 }
                 _ => {
-                    return Err(Error::new(
-                        ErrorKind::InvalidData,
-                        "fail to parse DW_AT_lo_pc",
-                    ))
+                    let size = attr_value_unsigned(form, opt, &value).ok_or_else(|| {
+                        Error::new(ErrorKind::InvalidData, "fail to parse DW_AT_hi_pc")
+                    })?;
+                    hi_pc = Some((false, size as Addr));
                 }
             },
-            constants::DW_AT_hi_pc => match value {
-                debug_info::AttrValue::Unsigned(sz) => {
-                    size = sz;
-                }
-                _ => {
-                    return Err(Error::new(
+            constants::DW_AT_ranges => {
+                let value = attr_value_unsigned(form, opt, &value).ok_or_else(|| {
+                    Error::new(ErrorKind::InvalidData, "fail to parse DW_AT_ranges")
+                })?;
+                ranges = Some((form, value));
+            }
+            constants::DW_AT_abstract_origin | constants::DW_AT_specification => {
+                let off = attr_value_unsigned(form, opt, &value).ok_or_else(|| {
+                    Error::new(
                         ErrorKind::InvalidData,
-                        "fail to parse DW_AT_lo_pc",
-                    ))
-                }
-            },
+                        "fail to parse reference to the origin of a subprogram",
+                    )
+                })? as usize;
+                // All reference forms but `DW_FORM_ref_addr` are
+                // relative to the start of the unit.
+                attrs.origin = Some(if form == constants::DW_FORM_ref_addr {
+                    off
+                } else {
+                    unit.unit_off + off
+                });
+            }
+            constants::DW_AT_call_file => {
+                attrs.call_file = attr_value_unsigned(form, opt, &value).unwrap_or(0) as usize;
+            }
+            constants::DW_AT_call_line => {
+                attrs.call_line = attr_value_unsigned(form, opt, &value).unwrap_or(0) as usize;
+            }
+            constants::DW_AT_call_column => {
+                attrs.call_column = attr_value_unsigned(form, opt, &value).unwrap_or(0) as usize;
+            }
             _ => {}
         }
     }
 
-    match (addr, name_str) {
-        (Some(addr), Some(name)) => Ok(Some(DWSymInfo {
-            name,
-            addr,
-            size: size as usize,
-            sym_type: SymType::Function,
-        })),
-        _ => Ok(None),
+    if let Some((is_addr, hi_pc)) = hi_pc {
+        // With an address form the attribute is the address of the first
+        // byte after the function. Otherwise it is the function's size.
+        attrs.size = if is_addr {
+            let lo_pc = attrs.addr.unwrap_or(hi_pc);
+            hi_pc.saturating_sub(lo_pc)
+        } else {
+            hi_pc
+        };
     }
+
+    if let Some((form, value)) = ranges {
+        attrs.ranges = unit.ranges(form, value)?;
+        // Compilers list the range containing the entry point first. An
+        // explicit low PC takes precedence, though.
+        if attrs.addr.is_none() {
+            if let Some(range) = attrs.ranges.first() {
+                attrs.addr = Some(range.start);
+                attrs.size = range.end - range.start;
+            }
+        }
+    } else if let Some(addr) = attrs.addr {
+        attrs.ranges.push(addr..addr + attrs.size);
+    }
+    Ok(attrs)
+}
+
+/// Find the name of a function, following references to the DIEs
+/// describing its origin if the function does not have a name itself.
+fn resolve_fn_name<'a>(attrs: &FnAttrs<'a>, names: &DIENames<'a>) -> Option<&'a str> {
+    // Compilers emit chains of just a few references; cap the number of
+    // ones we follow to not loop forever on malformed data.
+    const MAX_REFS: usize = 8;
+
+    if attrs.name.is_some() {
+        return attrs.name
+    }
+
+    let mut origin = attrs.origin;
+    for _ in 0..MAX_REFS {
+        let (name, next) = names.get(&origin?)?;
+        if name.is_some() {
+            return *name
+        }
+        origin = *next;
+    }
+    None
+}
+
+/// Convert a fully walked subprogram into a [`DWSymInfo`].
+fn finish_sym<'a>(
+    sym: PendingSym<'a>,
+    stmt_list: Option<usize>,
+    names: &DIENames<'a>,
+) -> Option<DWSymInfo<'a>> {
+    let PendingSym { attrs, inlined, .. } = sym;
+    let addr = attrs.addr?;
+    let name = resolve_fn_name(&attrs, names)?;
+    let inlined = inlined
+        .into_iter()
+        .filter_map(|inlined| {
+            Some(DWInlinedFn {
+                name: resolve_fn_name(&inlined, names)?,
+                ranges: inlined.ranges,
+                call_file: inlined.call_file,
+                call_line: inlined.call_line,
+                call_column: inlined.call_column,
+            })
+        })
+        .collect();
+
+    Some(DWSymInfo {
+        name,
+        addr,
+        sym_type: SymType::Function,
+        ranges: attrs.ranges,
+        stmt_list,
+        inlined,
+    })
 }
 
 /// Walk through all DIEs of a compile unit to extract symbols.
@@ -761,40 +1336,109 @@ fn parse_die_subprogram<'a>(
 /// * `dieiter` - is an iterator returned by the iterator that is
 ///               returned by an [`UnitIter`].  [`UnitIter`] returns
 ///               an [`UnitHeader`] and an [`DIEIter`].
-/// * `str_data` - is the content of the `.debug_str` section.
+/// * `sections` - are the contents of the sections referred to by
+///                attributes.
+/// * `names` - the names of subprograms found so far, used for
+///             resolving the names of functions referring to others.
 /// * `found_syms` - the Vec to append the found symbols.
 fn debug_info_parse_symbols_cu<'a>(
     mut dieiter: debug_info::DIEIter<'a>,
-    str_data: &'a [u8],
+    sections: DebugSections<'a>,
+    names: &mut DIENames<'a>,
     found_syms: &mut Vec<DWSymInfo<'a>>,
 ) {
+    let mut unit = UnitCtx::new(sections, &dieiter);
+    // The nesting level of the next DIE.
+    let mut level: usize = 0;
+    // Subprograms with children that we have not fully walked yet. The
+    // last one is the innermost.
+    let mut pending = Vec::<PendingSym>::new();
+    let mut walked = Vec::<PendingSym>::new();
+
     while let Some(mut die) = dieiter.next() {
-        if die.tag == 0 || die.tag == constants::DW_TAG_namespace {
+        if die.tag == 0 {
+            // A null entry ends the list of children of the parent DIE.
+            level = level.saturating_sub(1);
+            if pending.last().map(|sym| sym.level) == Some(level) {
+                walked.push(pending.pop().unwrap());
+            }
             continue
         }
 
         assert!(die.abbrev.is_some());
-        if die.tag != constants::DW_TAG_subprogram {
-            if die.abbrev.unwrap().has_children {
-                if let Some(sibling_off) = find_die_sibling(&mut die) {
-                    dieiter.seek_to_sibling(sibling_off);
-                    continue
+        let has_children = die.abbrev.unwrap().has_children;
+        match die.tag {
+            constants::DW_TAG_compile_unit => {
+                let () = parse_die_compile_unit(&mut die, &mut unit);
+            }
+            constants::DW_TAG_namespace | constants::DW_TAG_lexical_block => (),
+            constants::DW_TAG_subprogram => {
+                let offset = die.offset;
+                if let Ok(attrs) = parse_die_fn(&mut die, &unit) {
+                    let _prev = names.insert(offset, (attrs.name, attrs.origin));
+                    let sym = PendingSym {
+                        level,
+                        attrs,
+                        inlined: Vec::new(),
+                    };
+                    if has_children {
+                        pending.push(sym);
+                    } else {
+                        walked.push(sym);
+                    }
+                } else if has_children {
+                    // Keep track of the subprogram anyway, so that
+                    // inlined subroutines don't get attributed to an
+                    // enclosing one.
+                    pending.push(PendingSym {
+                        level,
+                        attrs: FnAttrs::default(),
+                        inlined: Vec::new(),
+                    });
+                }
+            }
+            constants::DW_TAG_inlined_subroutine => {
+                if let Some(sym) = pending.last_mut() {
+                    if let Ok(attrs) = parse_die_fn(&mut die, &unit) {
+                        sym.inlined.push(attrs);
+                    }
+                }
+            }
+            _ => {
+                if has_children {
+                    if let Some(sibling_off) = find_die_sibling(&mut die) {
+                        dieiter.seek_to_sibling(sibling_off);
+                        continue
+                    }
+                    // Skip this DIE quickly, or the iterator will
+                    // recalculate the size of the DIE.
+                    die.exhaust().unwrap();
                 }
-                // Skip this DIE quickly, or the iterator will
-                // recalculate the size of the DIE.
-                die.exhaust().unwrap();
             }
-            continue
         }
 
-        if let Ok(Some(syminfo)) = parse_die_subprogram(&mut die, str_data) {
-            found_syms.push(syminfo);
+        if has_children {
+            level += 1;
         }
     }
+
+    // Subprograms could only still be pending on malformed data, but
+    // there is no reason to not report them.
+    walked.extend(pending);
+    found_syms.extend(
+        walked
+            .into_iter()
+            .filter_map(|sym| finish_sym(sym, unit.stmt_list, names)),
+    );
 }
 
 /// Parse the addresses of symbols from the `.debug_info` section.
 ///
+/// Names of functions that are given only by reference to another
+/// DIE (through `DW_AT_abstract_origin` or `DW_AT_specification`) are
+/// resolved if the DIE referred to is part of the same or an earlier
+/// unit.
+///
 /// # Arguments
 ///
 /// * `parser` - is an ELF parser.
@@ -812,26 +1456,33 @@ pub(crate) fn debug_info_parse_symbols<'a>(
     let units = debug_info::UnitIter::new(info_data, abbrev_data);
     let str_sect_idx = parser.find_section(".debug_str")?;
     let str_data = parser.section_data(str_sect_idx)?;
+    let sections = DebugSections {
+        str_data,
+        // The remaining sections are optional or only present with
+        // DWARFv5.
+        line_str_data: optional_section_data(parser, ".debug_line_str")?,
+        str_offsets_data: optional_section_data(parser, ".debug_str_offsets")?,
+        addr_data: optional_section_data(parser, ".debug_addr")?,
+        ranges_data: optional_section_data(parser, ".debug_ranges")?,
+        rnglists_data: optional_section_data(parser, ".debug_rnglists")?,
+    };
 
     let mut syms = Vec::<DWSymInfo>::new();
+    let mut names = DIENames::new();
 
     if let Some(cond) = cond {
-        'outer: for (uhdr, dieiter) in units {
-            if let debug_info::UnitHeader::CompileV4(_) = uhdr {
-                let saved_sz = syms.len();
-                debug_info_parse_symbols_cu(dieiter, str_data, &mut syms);
-                for sym in &syms[saved_sz..] {
-                    if !cond(sym) {
-                        break 'outer
-                    }
+        'outer: for (_uhdr, dieiter) in units {
+            let saved_sz = syms.len();
+            debug_info_parse_symbols_cu(dieiter, sections, &mut names, &mut syms);
+            for sym in &syms[saved_sz..] {
+                if !cond(sym) {
+                    break 'outer
                 }
             }
         }
     } else {
-        for (uhdr, dieiter) in units {
-            if let debug_info::UnitHeader::CompileV4(_) = uhdr {
-                debug_info_parse_symbols_cu(dieiter, str_data, &mut syms);
-            }
+        for (_uhdr, dieiter) in units {
+            debug_info_parse_symbols_cu(dieiter, sections, &mut names, &mut syms);
         }
     }
     Ok(syms)
@@ -876,9 +1527,11 @@ mod tests {
         // Size of the header
         let mut pos = 12;
 
-        // Padding to align with the size of addresses on the target system.
-        pos += addr_sz as usize - 1;
-        pos -= pos % addr_sz as usize;
+        // Padding to align with the size of an address/length tuple on
+        // the target system.
+        let tuple_sz = 2 * addr_sz as usize;
+        pos += tuple_sz - 1;
+        pos -= pos % tuple_sz;
 
         let mut aranges = Vec::<(u64, u64)>::new();
         match addr_sz {
@@ -956,6 +1609,122 @@ mod tests {
         let _line = parse_debug_line_elf_parser(&parser, &[]).unwrap();
     }
 
+    /// Check that we can parse DWARFv5 line number information.
+    #[test]
+    fn parse_debug_line_elf_v5() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-dwarf-v5.bin");
+
+        let parser = ElfParser::open(bin_name.as_ref()).unwrap();
+        let cus = parse_debug_line_elf_parser(&parser, &[]).unwrap();
+        assert_eq!(cus.len(), 1);
+
+        let cu = cus.first().unwrap();
+        let version = cu.prologue.version;
+        assert_eq!(version, 5);
+        // The first row should be attributed to the primary source file,
+        // which DWARFv5 references by index 0.
+        let (dir, file, _line) = cu.stringify_row(0).unwrap();
+        assert_eq!(file, OsStr::new("test-exe.c"));
+        assert!(dir.is_absolute());
+    }
+
+    /// Make sure that we can parse line number information of multiple
+    /// compile units.
+    #[test]
+    fn parse_debug_line_elf_multi_cu() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses.bin");
+
+        let parser = ElfParser::open(bin_name.as_ref()).unwrap();
+        let cus = parse_debug_line_elf_parser(&parser, &[]).unwrap();
+        assert_eq!(cus.len(), 2);
+        assert!(cus.iter().all(|cu| !cu.matrix.is_empty()));
+    }
+
+    /// Create a line number program of the given version, in the 32-bit
+    /// or 64-bit DWARF format, mapping address 0x1000 to line 10 of
+    /// `test.c`.
+    ///
+    /// DWARFv5 programs reference their paths in `.debug_line_str` data
+    /// of `/src\0test.c\0`.
+    fn make_line_program(version: u16, bits64: bool) -> Vec<u8> {
+        let offset = |off: u64| {
+            if bits64 {
+                off.to_ne_bytes().to_vec()
+            } else {
+                (off as u32).to_ne_bytes().to_vec()
+            }
+        };
+
+        let mut header = vec![1, 1, 1, -5i8 as u8, 14, 13];
+        let () = header.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        let file_idx = if version >= 5 {
+            // Directory table: format, count, entries.
+            let () = header.extend_from_slice(&[1, 0x1, constants::DW_FORM_line_strp, 1]);
+            let () = header.extend(offset(0));
+            // File name table: format, count, entries.
+            let () = header.extend_from_slice(&[2, 0x1, constants::DW_FORM_line_strp]);
+            let () = header.extend_from_slice(&[0x2, constants::DW_FORM_udata, 1]);
+            let () = header.extend(offset(5));
+            let () = header.push(0);
+            0
+        } else {
+            let () = header.extend_from_slice(b"src\0\0");
+            let () = header.extend_from_slice(b"test.c\0\x01\0\0\0");
+            1
+        };
+
+        let mut stmts = vec![0x00, 9, 0x02];
+        let () = stmts.extend_from_slice(&0x1000u64.to_ne_bytes());
+        // DW_LNS_set_file, DW_LNS_advance_line, DW_LNS_copy
+        let () = stmts.extend_from_slice(&[0x04, file_idx, 0x03, 9, 0x01]);
+        // DW_LNS_advance_pc, DW_LNE_end_sequence
+        let () = stmts.extend_from_slice(&[0x02, 4, 0x00, 1, 0x01]);
+
+        let mut unit = version.to_ne_bytes().to_vec();
+        if version >= 5 {
+            // Address and segment selector size.
+            let () = unit.extend_from_slice(&[8, 0]);
+        }
+        let () = unit.extend(offset(header.len() as u64));
+        let () = unit.extend(header);
+        let () = unit.extend(stmts);
+
+        let mut program = Vec::new();
+        if bits64 {
+            let () = program.extend_from_slice(&0xffffffffu32.to_ne_bytes());
+        }
+        let () = program.extend(offset(unit.len() as u64));
+        let () = program.extend(unit);
+        program
+    }
+
+    /// Check that we can parse line number programs in the 32-bit as
+    /// well as the 64-bit DWARF format.
+    #[test]
+    fn parse_debug_line_cu_dwarf64() {
+        let line_str = b"/src\0test.c\0";
+
+        for (version, dir) in [(4, "src"), (5, "/src")] {
+            let mut data = make_line_program(version, false);
+            let () = data.extend(make_line_program(version, true));
+            let mut data = data.as_slice();
+
+            for _ in 0..2 {
+                let cu = parse_debug_line_cu(&mut data, 0, &[], line_str, &[]).unwrap();
+                assert_eq!(cu.prologue.version, version);
+                let (dir_ret, file_ret, line_ret) = cu.find_line(0x1000).unwrap();
+                assert_eq!(dir_ret, Path::new(dir));
+                assert_eq!(file_ret, OsStr::new("test.c"));
+                assert_eq!(line_ret, 10);
+            }
+            assert!(data.is_empty());
+        }
+    }
+
     #[test]
     fn test_run_debug_line_stmts_1() {
         let stmts = [
@@ -963,11 +1732,9 @@ mod tests {
             0x01, 0x05, 0x06, 0x0a, 0x08, 0x30, 0x02, 0x05, 0x00, 0x01, 0x01,
         ];
         let prologue = DebugLinePrologue {
-            total_length: 0,
             version: 4,
-            prologue_length: 0,
             minimum_instruction_length: 1,
-            maximum_ops_per_instruction: 1,
+            _maximum_ops_per_instruction: 1,
             default_is_stmt: 1,
             line_base: -5,
             line_range: 14,
@@ -1023,11 +1790,9 @@ mod tests {
             0x0e, 0x00, 0x01, 0x01,
         ];
         let prologue = DebugLinePrologue {
-            total_length: 0,
             version: 4,
-            prologue_length: 0,
             minimum_instruction_length: 1,
-            maximum_ops_per_instruction: 1,
+            _maximum_ops_per_instruction: 1,
             default_is_stmt: 1,
             line_base: -5,
             line_range: 14,
@@ -1075,6 +1840,15 @@ mod tests {
             .join("test-dwarf-v4.bin");
 
         let _aranges = parse_aranges_elf(bin_name.as_ref()).unwrap();
+
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses-32.bin");
+        let aranges = parse_aranges_elf(bin_name.as_ref()).unwrap();
+        assert!(aranges
+            .iter()
+            .flat_map(|acu| acu.aranges.iter())
+            .any(|&(start, size)| start == 0x2000100 && size != 0));
     }
 
     #[test]
@@ -1088,6 +1862,160 @@ mod tests {
         assert!(syms.iter().any(|sym| sym.name == "fibonacci"))
     }
 
+    /// Check that we can extract symbols from DWARFv5 units.
+    #[test]
+    fn debug_info_parse_symbols_v5() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-dwarf-v5.bin");
+
+        let parser = ElfParser::open(bin_name.as_ref()).unwrap();
+        let syms = debug_info_parse_symbols(&parser, None).unwrap();
+        let sym = syms.iter().find(|sym| sym.name == "fibonacci").unwrap();
+        assert_ne!(sym.addr, 0);
+        assert_eq!(sym.ranges.len(), 1);
+        assert_eq!(sym.ranges[0].start, sym.addr);
+        assert!(!sym.ranges[0].is_empty());
+        assert_eq!(sym.stmt_list, Some(0));
+    }
+
+    /// Check that we can resolve string and address indices through the
+    /// tables referenced by a unit.
+    #[test]
+    fn unit_str_addr_indices() {
+        let sections = DebugSections {
+            str_data: b"\0foo\0bar\0",
+            line_str_data: b"baz\0",
+            // A header of eight bytes, followed by offsets of `foo` and
+            // `bar`.
+            str_offsets_data: &[
+                0x0c, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00,
+                0x00, 0x00,
+            ],
+            addr_data: &[
+                0x0c, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            ..Default::default()
+        };
+        let unit = UnitCtx {
+            sections,
+            unit_off: 0,
+            version: 5,
+            dwarf_sz: 4,
+            addr_sz: 8,
+            base_addr: 0,
+            stmt_list: None,
+            str_offsets_base: Some(8),
+            addr_base: Some(8),
+            rnglists_base: None,
+        };
+
+        let value = |v| debug_info::AttrValue::Unsigned(v);
+        assert_eq!(unit.str(constants::DW_FORM_strp, value(5)).unwrap(), "bar");
+        assert_eq!(
+            unit.str(constants::DW_FORM_line_strp, value(0)).unwrap(),
+            "baz"
+        );
+        assert_eq!(unit.str(constants::DW_FORM_strx, value(0)).unwrap(), "foo");
+        assert_eq!(unit.str(constants::DW_FORM_str1, value(1)).unwrap(), "bar");
+        assert!(unit.str(constants::DW_FORM_strx, value(2)).is_err());
+
+        assert_eq!(
+            unit.addr(constants::DW_FORM_addr, value(0x42)).unwrap(),
+            0x42
+        );
+        assert_eq!(
+            unit.addr(constants::DW_FORM_addrx, value(0)).unwrap(),
+            0x1000
+        );
+        assert_eq!(
+            unit.addr(constants::DW_FORM_addrx1, value(1)).unwrap(),
+            0x2000
+        );
+        assert!(unit.addr(constants::DW_FORM_addrx, value(2)).is_err());
+
+        // Indices can't be resolved without the base being known.
+        let unit = UnitCtx {
+            str_offsets_base: None,
+            addr_base: None,
+            ..unit
+        };
+        assert!(unit.str(constants::DW_FORM_strx, value(0)).is_err());
+        assert!(unit.addr(constants::DW_FORM_addrx, value(0)).is_err());
+    }
+
+    /// Check that we can parse range lists in the `.debug_ranges` and
+    /// `.debug_rnglists` formats.
+    #[test]
+    fn unit_ranges() {
+        #[rustfmt::skip]
+        let ranges_data = [
+            // A range relative to the unit's base address.
+            0x10, 0, 0, 0, 0, 0, 0, 0, 0x20, 0, 0, 0, 0, 0, 0, 0,
+            // A base address selection entry.
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x50, 0, 0, 0, 0, 0, 0,
+            0x00, 0, 0, 0, 0, 0, 0, 0, 0x08, 0, 0, 0, 0, 0, 0, 0,
+            // The end of the list.
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        #[rustfmt::skip]
+        let rnglists_data = [
+            // A header of twelve bytes, followed by an offset table with
+            // a single entry.
+            0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x04, 0x00, 0x00, 0x00,
+            // The range list the offset table entry refers to.
+            constants::DW_RLE_offset_pair, 0x10, 0x20,
+            constants::DW_RLE_base_address, 0x00, 0x60, 0, 0, 0, 0, 0, 0,
+            constants::DW_RLE_offset_pair, 0x00, 0x08,
+            constants::DW_RLE_start_length, 0x00, 0x70, 0, 0, 0, 0, 0, 0, 0x04,
+            constants::DW_RLE_startx_length, 0x00, 0x02,
+            constants::DW_RLE_end_of_list,
+        ];
+        let sections = DebugSections {
+            addr_data: &[
+                0x0c, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+            ranges_data: &ranges_data,
+            rnglists_data: &rnglists_data,
+            ..Default::default()
+        };
+        let unit = UnitCtx {
+            sections,
+            unit_off: 0,
+            version: 4,
+            dwarf_sz: 4,
+            addr_sz: 8,
+            base_addr: 0x1000,
+            stmt_list: None,
+            str_offsets_base: None,
+            addr_base: Some(8),
+            rnglists_base: Some(12),
+        };
+
+        let ranges = unit.ranges(constants::DW_FORM_sec_offset, 0).unwrap();
+        assert_eq!(ranges, vec![0x1010..0x1020, 0x5000..0x5008]);
+        // Without the base address selection entry, the range is
+        // relative to the unit's base address.
+        let ranges = unit.ranges(constants::DW_FORM_sec_offset, 32).unwrap();
+        assert_eq!(ranges, vec![0x1000..0x1008]);
+
+        let unit = UnitCtx { version: 5, ..unit };
+        let expected = vec![
+            0x1010..0x1020,
+            0x6000..0x6008,
+            0x7000..0x7004,
+            0x8000..0x8002,
+        ];
+        let ranges = unit.ranges(constants::DW_FORM_rnglistx, 0).unwrap();
+        assert_eq!(ranges, expected);
+        let ranges = unit.ranges(constants::DW_FORM_sec_offset, 16).unwrap();
+        assert_eq!(ranges, expected);
+        assert!(unit.ranges(constants::DW_FORM_rnglistx, 1).is_err());
+    }
+
     /// Benchmark the [`debug_info_parse_symbols`] function.
     #[cfg(feature = "nightly")]
     #[bench]
@@ -1,4 +1,3 @@
-use std::cell::RefCell;
 #[cfg(test)]
 use std::env;
 use std::ffi::OsStr;
@@ -6,13 +5,16 @@ use std::fmt::Debug;
 use std::io::Error;
 use std::io::ErrorKind;
 use std::mem;
+use std::ops::Range;
 use std::path::Path;
-use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 use crate::elf::ElfParser;
 use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
 use crate::inspect::SymType;
+use crate::symbolize::InlinedFn;
 use crate::util::find_match_or_lower_bound_by;
 use crate::Addr;
 
@@ -22,14 +24,46 @@ use super::parser::DWSymInfo;
 use super::parser::DebugLineCU;
 
 
+/// Symbol information extracted from the `.debug_info` section.
+#[derive(Debug)]
+struct DebugInfoSyms {
+    /// All symbols, sorted by name.
+    by_name: Vec<DWSymInfo<'static>>,
+    /// The address ranges of all symbols, along with the index of the
+    /// respective symbol in `by_name`, sorted by start address.
+    by_addr: Vec<(Range<Addr>, usize)>,
+}
+
+impl DebugInfoSyms {
+    /// Find the symbol covering an address.
+    fn find_sym(&self, addr: Addr) -> Option<&DWSymInfo<'static>> {
+        let idx = find_match_or_lower_bound_by(&self.by_addr, addr, |(range, _)| range.start)?;
+        // There may be multiple ranges starting at the same address.
+        // Pick the first one actually covering `addr`.
+        self.by_addr[idx..]
+            .iter()
+            .take_while(|(range, _)| range.start <= addr)
+            .find(|(range, _)| range.contains(&addr))
+            .map(|(_, idx)| &self.by_name[*idx])
+    }
+}
+
 /// DwarfResolver provides abilities to query DWARF information of binaries.
 #[derive(Debug)]
 pub(crate) struct DwarfResolver {
-    parser: Rc<ElfParser>,
+    parser: Arc<ElfParser>,
+    /// The parser for the file containing the DWARF debug information.
+    ///
+    /// That is the file represented by `parser`, unless debug
+    /// information is stored in a separate file.
+    debug_parser: Arc<ElfParser>,
     debug_line_cus: Vec<DebugLineCU>,
-    addr_to_dlcu: Vec<(Addr, u32)>,
+    /// The address ranges of all line number program sequences, along
+    /// with the index of the respective CU in `debug_line_cus`, sorted
+    /// by start address.
+    addr_to_dlcu: Vec<(Range<Addr>, u32)>,
     enable_debug_info_syms: bool,
-    debug_info_syms: RefCell<Option<Vec<DWSymInfo<'static>>>>,
+    debug_info_syms: Mutex<Option<DebugInfoSyms>>,
 }
 
 impl DwarfResolver {
@@ -38,33 +72,80 @@ impl DwarfResolver {
     }
 
     pub fn from_parser_for_addresses(
-        parser: Rc<ElfParser>,
+        parser: Arc<ElfParser>,
+        addresses: &[Addr],
+        line_number_info: bool,
+        debug_info_symbols: bool,
+    ) -> Result<DwarfResolver, Error> {
+        let debug_parser = Arc::clone(&parser);
+        Self::from_parsers_for_addresses(
+            parser,
+            debug_parser,
+            addresses,
+            line_number_info,
+            debug_info_symbols,
+        )
+    }
+
+    /// Create a `DwarfResolver` for the ELF file represented by `parser`,
+    /// with DWARF debug information being read from the (separate) debug
+    /// file represented by `debug_parser`.
+    pub fn from_parsers(
+        parser: Arc<ElfParser>,
+        debug_parser: Arc<ElfParser>,
+        line_number_info: bool,
+        debug_info_symbols: bool,
+    ) -> Result<DwarfResolver, Error> {
+        Self::from_parsers_for_addresses(
+            parser,
+            debug_parser,
+            &[],
+            line_number_info,
+            debug_info_symbols,
+        )
+    }
+
+    fn from_parsers_for_addresses(
+        parser: Arc<ElfParser>,
+        debug_parser: Arc<ElfParser>,
         addresses: &[Addr],
         line_number_info: bool,
         debug_info_symbols: bool,
     ) -> Result<DwarfResolver, Error> {
         let debug_line_cus: Vec<DebugLineCU> = if line_number_info {
-            parse_debug_line_elf_parser(&parser, addresses).unwrap_or_default()
+            parse_debug_line_elf_parser(&debug_parser, addresses).unwrap_or_default()
         } else {
             vec![]
         };
 
+        // The address ranges covered by different CUs may be
+        // interleaved, so we map each sequence of a CU's line number
+        // program individually.
         let mut addr_to_dlcu = Vec::with_capacity(debug_line_cus.len());
         for (idx, dlcu) in debug_line_cus.iter().enumerate() {
-            if dlcu.matrix.is_empty() {
-                continue
+            let mut start = None;
+            for row in dlcu.matrix.iter() {
+                let seq_start = *start.get_or_insert(row.addr);
+                if row.end_sequence {
+                    addr_to_dlcu.push((seq_start..row.addr, idx as u32));
+                    start = None;
+                }
+            }
+            // When only loading data for a set of addresses, the last
+            // sequence may not be terminated.
+            if let Some(seq_start) = start {
+                addr_to_dlcu.push((seq_start..Addr::MAX, idx as u32));
             }
-            let first_addr = dlcu.matrix[0].addr;
-            addr_to_dlcu.push((first_addr, idx as u32));
         }
-        addr_to_dlcu.sort_by_key(|v| v.0);
+        addr_to_dlcu.sort_by_key(|(range, _)| range.start);
 
         Ok(DwarfResolver {
             parser,
+            debug_parser,
             debug_line_cus,
             addr_to_dlcu,
             enable_debug_info_syms: debug_info_symbols,
-            debug_info_syms: RefCell::new(None),
+            debug_info_syms: Mutex::new(None),
         })
     }
 
@@ -79,6 +160,7 @@ impl DwarfResolver {
     /// from the given file.  If the instance will be used for long
     /// running, you would want to load all data into memory to have
     /// the ability of handling all possible addresses.
+    #[cfg(test)]
     fn open_for_addresses(
         filename: &Path,
         addresses: &[Addr],
@@ -87,7 +169,7 @@ impl DwarfResolver {
     ) -> Result<DwarfResolver, Error> {
         let parser = ElfParser::open(filename)?;
         Self::from_parser_for_addresses(
-            Rc::new(parser),
+            Arc::new(parser),
             addresses,
             line_number_info,
             debug_info_symbols,
@@ -98,6 +180,7 @@ impl DwarfResolver {
     ///
     /// `filename` is the name of an ELF binary/or shared object that
     /// has .debug_line section.
+    #[cfg(test)]
     pub fn open(
         filename: &Path,
         debug_line_info: bool,
@@ -108,10 +191,12 @@ impl DwarfResolver {
 
     fn find_dlcu_index(&self, addr: Addr) -> Option<usize> {
         let a2a = &self.addr_to_dlcu;
-        let a2a_idx = find_match_or_lower_bound_by(a2a, addr, |a2dlcu| a2dlcu.0)?;
-        let dlcu_idx = a2a[a2a_idx].1 as usize;
-
-        Some(dlcu_idx)
+        let a2a_idx = find_match_or_lower_bound_by(a2a, addr, |(range, _)| range.start)?;
+        a2a[a2a_idx..]
+            .iter()
+            .take_while(|(range, _)| range.start <= addr)
+            .find(|(range, _)| range.contains(&addr))
+            .map(|(_, idx)| *idx as usize)
     }
 
     /// Find line information of an address.
@@ -126,16 +211,60 @@ impl DwarfResolver {
         dlcu.find_line(addr)
     }
 
+    /// Retrieve the line number information for the address range `range`,
+    /// as `(addr, dir_name, file_name, line_no)` tuples sorted by address.
+    ///
+    /// The first row is adjusted to start at `range.start`, if necessary.
+    pub(crate) fn find_lines(&self, range: Range<Addr>) -> Vec<(Addr, &Path, &OsStr, usize)> {
+        let dlcu = if let Some(idx) = self.find_dlcu_index(range.start) {
+            &self.debug_line_cus[idx]
+        } else {
+            return Vec::new()
+        };
+
+        let start = if let Some(idx) =
+            find_match_or_lower_bound_by(&dlcu.matrix, range.start, |dls| dls.addr)
+        {
+            idx
+        } else {
+            return Vec::new()
+        };
+
+        dlcu.matrix[start..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, row)| row.addr < range.end)
+            .filter(|(_, row)| !row.end_sequence)
+            .filter_map(|(idx, row)| {
+                let (dir, file, line) = dlcu.stringify_row(start + idx)?;
+                Some((row.addr.max(range.start), dir, file, line))
+            })
+            .collect()
+    }
+
     /// Extract the symbol information from DWARf if having not done it before.
     fn ensure_debug_info_syms(&self) -> Result<(), Error> {
         if self.enable_debug_info_syms {
-            let mut dis_ref = self.debug_info_syms.borrow_mut();
+            let mut dis_ref = self.debug_info_syms.lock().unwrap();
             if dis_ref.is_some() {
                 return Ok(())
             }
-            let mut debug_info_syms = debug_info_parse_symbols(&self.parser, None)?;
-            debug_info_syms.sort_by_key(|v: &DWSymInfo| -> &str { v.name });
-            *dis_ref = Some(unsafe { mem::transmute(debug_info_syms) });
+            let mut by_name = debug_info_parse_symbols(&self.debug_parser, None)?;
+            by_name.sort_by_key(|v: &DWSymInfo| -> &str { v.name });
+            // Map every range of a symbol back to it, so that we find
+            // symbols not occupying a contiguous address range by any
+            // of their addresses.
+            let mut by_addr = by_name
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, sym)| sym.ranges.iter().map(move |range| (range.clone(), idx)))
+                .collect::<Vec<_>>();
+            by_addr.sort_by_key(|(range, _)| range.start);
+
+            *dis_ref = Some(DebugInfoSyms {
+                by_name: unsafe { mem::transmute(by_name) },
+                by_addr,
+            });
             Ok(())
         } else {
             Err(Error::new(
@@ -163,8 +292,8 @@ impl DwarfResolver {
         }
 
         self.ensure_debug_info_syms()?;
-        let dis_ref = self.debug_info_syms.borrow();
-        let debug_info_syms = dis_ref.as_ref().unwrap();
+        let dis_ref = self.debug_info_syms.lock().unwrap();
+        let debug_info_syms = &dis_ref.as_ref().unwrap().by_name;
         let mut idx =
             match debug_info_syms.binary_search_by_key(&name.to_string(), |v| v.name.to_string()) {
                 Ok(idx) => idx,
@@ -180,28 +309,96 @@ impl DwarfResolver {
         while debug_info_syms[idx].name.eq(name) {
             let DWSymInfo {
                 addr,
-                size,
+                ref ranges,
                 sym_type,
                 ..
             } = debug_info_syms[idx];
+            // The first range is the one containing the entry point.
+            let size = ranges
+                .first()
+                .map(|range| range.end - range.start)
+                .unwrap_or(0);
             found.push(SymInfo {
                 name: name.to_string(),
-                addr: addr as Addr,
+                addr,
                 size,
+                ranges: ranges.clone(),
                 sym_type,
                 file_offset: 0,
                 obj_file_name: None,
+                module: None,
             });
             idx += 1;
         }
         Ok(found)
     }
 
+    /// Find the symbol covering an address from DWARF.
+    ///
+    /// This function returns the name and the entry address of the
+    /// symbol, if any.
+    pub(crate) fn find_symbols(&self, addr: Addr) -> Result<Vec<(&str, Addr)>, Error> {
+        self.ensure_debug_info_syms()?;
+        let dis_ref = self.debug_info_syms.lock().unwrap();
+        let syms = dis_ref
+            .as_ref()
+            .unwrap()
+            .find_sym(addr)
+            .map(|sym| vec![(sym.name, sym.addr)])
+            .unwrap_or_default();
+        Ok(syms)
+    }
+
+    /// Find the functions inlined at an address.
+    ///
+    /// The returned list is ordered from the function inlined directly
+    /// into the symbol containing `addr` to the innermost one.
+    pub(crate) fn find_inlined_fns(&self, addr: Addr) -> Result<Vec<InlinedFn>, Error> {
+        self.ensure_debug_info_syms()?;
+        let dis_ref = self.debug_info_syms.lock().unwrap();
+        let sym = match dis_ref.as_ref().unwrap().find_sym(addr) {
+            Some(sym) => sym,
+            None => return Ok(vec![]),
+        };
+
+        // Call sites refer to files of the line number program of the
+        // symbol's compile unit. Line number information may not be
+        // available, though, in which case we report empty paths.
+        let dlcu = sym.stmt_list.and_then(|offset| {
+            let idx = self
+                .debug_line_cus
+                .binary_search_by_key(&offset, |dlcu| dlcu.offset)
+                .ok()?;
+            Some(&self.debug_line_cus[idx])
+        });
+
+        let inlined = sym
+            .inlined
+            .iter()
+            .filter(|inlined| inlined.ranges.iter().any(|range| range.contains(&addr)))
+            .map(|inlined| {
+                let call_path = dlcu
+                    .and_then(|dlcu| dlcu.find_file(inlined.call_file))
+                    .map(|(dir, file)| dir.join(file))
+                    .unwrap_or_default();
+                InlinedFn {
+                    name: inlined.name.to_string(),
+                    call_path,
+                    call_line: inlined.call_line,
+                    call_column: inlined.call_column,
+                }
+            })
+            .collect();
+        Ok(inlined)
+    }
+
     #[cfg(test)]
     fn pick_address_for_test(&self) -> (Addr, &Path, &OsStr, usize) {
-        let (addr, idx) = self.addr_to_dlcu[self.addr_to_dlcu.len() / 3];
-        let dlcu = &self.debug_line_cus[idx as usize];
-        let (dir, file, line) = dlcu.stringify_row(0).unwrap();
+        let (range, idx) = &self.addr_to_dlcu[self.addr_to_dlcu.len() / 3];
+        let addr = range.start;
+        let dlcu = &self.debug_line_cus[*idx as usize];
+        let row = dlcu.matrix.iter().position(|row| row.addr == addr).unwrap();
+        let (dir, file, line) = dlcu.stringify_row(row).unwrap();
         (addr, dir, file, line)
     }
 }
@@ -228,6 +425,109 @@ mod tests {
         assert_eq!(line, line_ret);
     }
 
+    /// Check that we can look up line information in DWARFv5 debug
+    /// information.
+    #[test]
+    fn test_dwarf_resolver_v5() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-dwarf-v5.bin");
+        let resolver = DwarfResolver::open(bin_name.as_ref(), true, false).unwrap();
+        let (addr, dir, file, line) = resolver.pick_address_for_test();
+
+        let (dir_ret, file_ret, line_ret) = resolver.find_line(addr).unwrap();
+        assert_eq!(dir, dir_ret);
+        assert_eq!(file, file_ret);
+        assert_eq!(file, OsStr::new("test-exe.c"));
+        assert_eq!(line, line_ret);
+    }
+
+    /// Check that we can look up line information of addresses in CUs
+    /// with multiple line number program sequences, whose address ranges
+    /// are interleaved with those of other CUs.
+    #[test]
+    fn test_dwarf_resolver_interleaved_cus() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses.bin");
+        let resolver = DwarfResolver::open(bin_name.as_ref(), true, false).unwrap();
+
+        // `main`, the first sequence of test-stable-addresses.c.
+        let (_dir, file, line) = resolver.find_line(0x2000000).unwrap();
+        assert_eq!(file, OsStr::new("test-stable-addresses.c"));
+        assert_eq!(line, 34);
+
+        // `factorial_wrapper` of test-stable-addresses-cu2.c, located
+        // in between sequences of test-stable-addresses.c.
+        let (_dir, file, line) = resolver.find_line(0x2000030).unwrap();
+        assert_eq!(file, OsStr::new("test-stable-addresses-cu2.c"));
+        assert_eq!(line, 5);
+
+        // `factorial_wrapper` of test-stable-addresses.c, located after
+        // the CU above.
+        let (_dir, file, line) = resolver.find_line(0x2000050).unwrap();
+        assert_eq!(file, OsStr::new("test-stable-addresses.c"));
+        assert_eq!(line, 16);
+
+        // `factorial`, starting a separate sequence at a fixed address.
+        let (_dir, file, line) = resolver.find_line(0x2000100).unwrap();
+        assert_eq!(file, OsStr::new("test-stable-addresses.c"));
+        assert_eq!(line, 8);
+
+        // The gap between sequences is not covered by any CU.
+        assert!(resolver.find_line(0x2000080).is_none());
+    }
+
+    /// Check that we can look up line information in compressed DWARF
+    /// debug information.
+    #[test]
+    fn test_dwarf_resolver_compressed() {
+        for bin in [
+            "test-dwarf-zlib.bin",
+            #[cfg(feature = "zstd")]
+            "test-dwarf-zstd.bin",
+            "test-dwarf-zlib-gnu.bin",
+        ] {
+            let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+                .join("data")
+                .join(bin);
+            let resolver = DwarfResolver::open(bin_name.as_ref(), true, true).unwrap();
+            let (addr, dir, file, line) = resolver.pick_address_for_test();
+
+            let (dir_ret, file_ret, line_ret) = resolver.find_line(addr).unwrap();
+            assert_eq!(dir, dir_ret);
+            assert_eq!(file, file_ret);
+            assert_eq!(file, OsStr::new("test-exe.c"));
+            assert_eq!(line, line_ret);
+
+            let symbols = resolver.find_symbols(addr).unwrap();
+            assert!(!symbols.is_empty());
+        }
+    }
+
+    /// Check that we can work with DWARF debug information of a 32 bit
+    /// ELF file.
+    #[test]
+    fn dwarf_resolver_elf32() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses-32.bin");
+        let resolver = DwarfResolver::open(bin_name.as_ref(), true, true).unwrap();
+
+        let (_dir, file, line) = resolver.find_line(0x2000100).unwrap();
+        assert_eq!(file, OsStr::new("test-stable-addresses.c"));
+        assert_ne!(line, 0);
+
+        let opts = FindAddrOpts {
+            offset_in_file: false,
+            obj_file_name: false,
+            sym_type: SymType::Function,
+        };
+        let symbols = resolver.find_addr("factorial", &opts).unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols.first().unwrap().addr, 0x2000100);
+    }
+
     /// Check that we can look up a symbol in DWARF debug information.
     #[test]
     fn lookup_symbol() {
@@ -249,6 +549,91 @@ mod tests {
         assert_eq!(symbol.addr, 0x2000100);
     }
 
+    /// Check that we can find the functions inlined at an address.
+    #[test]
+    fn lookup_inlined_fns() {
+        fn test(bin: &str) {
+            let test_inlined = Path::new(&env!("CARGO_MANIFEST_DIR"))
+                .join("data")
+                .join(bin);
+            let opts = FindAddrOpts {
+                offset_in_file: false,
+                obj_file_name: false,
+                sym_type: SymType::Function,
+            };
+            let resolver = DwarfResolver::open(test_inlined.as_ref(), true, true).unwrap();
+
+            let symbols = resolver.find_addr("main", &opts).unwrap();
+            assert_eq!(symbols.len(), 1);
+            let main = symbols.first().unwrap();
+
+            // Both functions are inlined into `main` and we should find
+            // an address that is part of the innermost one.
+            let inlined = (main.addr..main.addr + main.size)
+                .map(|addr| resolver.find_inlined_fns(addr).unwrap())
+                .find(|inlined| inlined.len() == 2)
+                .unwrap();
+            assert_eq!(inlined[0].name, "inlined_outer");
+            assert_eq!(inlined[0].call_line, 23);
+            assert_eq!(
+                inlined[0].call_path.file_name(),
+                Some(OsStr::new("test-inlined.c"))
+            );
+            assert_eq!(inlined[1].name, "inlined_call");
+            assert_eq!(inlined[1].call_line, 17);
+            assert_eq!(
+                inlined[1].call_path.file_name(),
+                Some(OsStr::new("test-inlined.c"))
+            );
+        }
+
+        test("test-inlined-dwarf-v4.bin");
+        test("test-inlined-dwarf-v5.bin");
+    }
+
+    /// Check that we map all address ranges of a function that is not
+    /// contiguous back to it.
+    #[test]
+    fn lookup_fn_ranges() {
+        fn test(bin: &str) {
+            let test_ranges = Path::new(&env!("CARGO_MANIFEST_DIR"))
+                .join("data")
+                .join(bin);
+            let opts = FindAddrOpts {
+                offset_in_file: false,
+                obj_file_name: false,
+                sym_type: SymType::Function,
+            };
+            let resolver = DwarfResolver::open(test_ranges.as_ref(), true, true).unwrap();
+
+            // The symbol table has separate symbols for the two parts
+            // of the function.
+            let symbols = resolver.find_addr("partitioned", &opts).unwrap();
+            assert_eq!(symbols.len(), 1);
+            let hot = symbols.first().unwrap();
+            let symbols = resolver.find_addr("partitioned.cold", &opts).unwrap();
+            assert_eq!(symbols.len(), 1);
+            let cold = symbols.first().unwrap();
+
+            for addr in [hot.addr, hot.addr + hot.size - 1, cold.addr] {
+                let symbols = resolver.find_symbols(addr).unwrap();
+                assert_eq!(symbols, vec![("partitioned", hot.addr)]);
+            }
+
+            let () = resolver.ensure_debug_info_syms().unwrap();
+            let dis_ref = resolver.debug_info_syms.lock().unwrap();
+            let syms = &dis_ref.as_ref().unwrap().by_name;
+            let sym = syms.iter().find(|sym| sym.name == "partitioned").unwrap();
+            assert_eq!(sym.addr, hot.addr);
+            assert_eq!(sym.ranges.len(), 2);
+            assert_eq!(sym.ranges[0], hot.addr..hot.addr + hot.size);
+            assert_eq!(sym.ranges[1].start, cold.addr);
+        }
+
+        test("test-ranges-dwarf-v4.bin");
+        test("test-ranges-dwarf-v5.bin");
+    }
+
     /// Check that we fail to look up variables.
     #[test]
     fn lookup_symbol_wrong_type() {
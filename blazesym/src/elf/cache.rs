@@ -1,19 +1,21 @@
-use std::cell::RefCell;
 use std::fs::File;
 use std::io::Error;
 use std::num::NonZeroUsize;
 use std::os::unix::io::AsRawFd;
 use std::path::Path;
-#[cfg(feature = "lru")]
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 #[cfg(feature = "lru")]
 use lru::LruCache;
 
 use crate::dwarf::DwarfResolver;
+use crate::mmap::Mmap;
 use crate::util::fstat;
 
+use super::find_debug_file;
+use super::relocate_parser;
 use super::ElfParser;
 
 // SAFETY: The provided value is non-zero.
@@ -21,15 +23,15 @@ const DFL_CACHE_MAX: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(1024) }
 
 #[derive(Clone, Debug)]
 pub(crate) enum ElfBackend {
-    Dwarf(Rc<DwarfResolver>), // ELF w/ DWARF
-    Elf(Rc<ElfParser>),       // ELF w/o DWARF
+    Dwarf(Arc<DwarfResolver>), // ELF w/ DWARF
+    Elf(Arc<ElfParser>),       // ELF w/o DWARF
 }
 
 #[cfg(test)]
 impl ElfBackend {
-    pub fn to_dwarf(&self) -> Option<Rc<DwarfResolver>> {
+    pub fn to_dwarf(&self) -> Option<Arc<DwarfResolver>> {
         if let Self::Dwarf(dwarf) = self {
-            Some(Rc::clone(dwarf))
+            Some(Arc::clone(dwarf))
         } else {
             None
         }
@@ -51,32 +53,56 @@ struct ElfCacheEntry {
 }
 
 impl ElfCacheEntry {
+    /// Create a new cache entry for the ELF file parsed by `parser`.
+    ///
+    /// `debug_path` is the path relative to which separate debug files
+    /// referenced via debug link are searched for.
     pub fn new(
-        file: File,
+        debug_path: &Path,
+        stat: &libc::stat,
+        parser: ElfParser,
         line_number_info: bool,
         debug_info_symbols: bool,
-    ) -> Result<ElfCacheEntry, Error> {
-        let stat = fstat(file.as_raw_fd())?;
-        let parser = Rc::new(ElfParser::open_file(file)?);
-        let backend = if let Ok(dwarf) = DwarfResolver::from_parser_for_addresses(
-            Rc::clone(&parser),
-            &[],
-            line_number_info,
-            debug_info_symbols,
-        ) {
-            ElfBackend::Dwarf(Rc::new(dwarf))
+        debug_dirs: &[PathBuf],
+    ) -> ElfCacheEntry {
+        let parser = Arc::new(parser);
+        let debug_parser = if line_number_info || debug_info_symbols {
+            // Separate debug files of relocatable ELF files (e.g.,
+            // kernel modules) have to be relocated the same way.
+            find_debug_file(debug_path, &parser, debug_dirs)
+                .and_then(|debug_parser| relocate_parser(debug_parser).ok())
+        } else {
+            None
+        };
+        let dwarf = if let Some(debug_parser) = debug_parser {
+            DwarfResolver::from_parsers(
+                Arc::clone(&parser),
+                Arc::new(debug_parser),
+                line_number_info,
+                debug_info_symbols,
+            )
+        } else {
+            DwarfResolver::from_parser_for_addresses(
+                Arc::clone(&parser),
+                &[],
+                line_number_info,
+                debug_info_symbols,
+            )
+        };
+        let backend = if let Ok(dwarf) = dwarf {
+            ElfBackend::Dwarf(Arc::new(dwarf))
         } else {
             ElfBackend::Elf(parser)
         };
 
-        Ok(ElfCacheEntry {
+        ElfCacheEntry {
             dev: stat.st_dev,
             inode: stat.st_ino,
             size: stat.st_size,
             mtime_sec: stat.st_mtime,
             mtime_nsec: stat.st_mtime_nsec,
             backend,
-        })
+        }
     }
 
     fn is_valid(&self, stat: &libc::stat) -> bool {
@@ -99,71 +125,198 @@ struct _ElfCache {
     cache: LruCache<PathBuf, ElfCacheEntry>,
     line_number_info: bool,
     debug_info_symbols: bool,
+    /// The directories to search for separate debug files.
+    debug_dirs: Vec<PathBuf>,
 }
 
 impl _ElfCache {
-    fn new(line_number_info: bool, debug_info_symbols: bool) -> _ElfCache {
+    fn new(
+        line_number_info: bool,
+        debug_info_symbols: bool,
+        debug_dirs: Vec<PathBuf>,
+    ) -> _ElfCache {
         _ElfCache {
             #[cfg(feature = "lru")]
             cache: LruCache::new(DFL_CACHE_MAX),
             line_number_info,
             debug_info_symbols,
+            debug_dirs,
         }
     }
 
+    /// Look up the backend for `key` in the cache, creating it using
+    /// `create` if it is not present or no longer valid.
+    ///
+    /// Separate debug files referenced via debug link are searched for
+    /// relative to `debug_path`.
+    ///
+    /// `stat` describes the file that ultimately backs the ELF data and is
+    /// used for checking whether a cached entry is still up-to-date.
     #[cfg(feature = "lru")]
-    fn find_or_create_backend(
+    fn find_or_create_backend<F>(
         &mut self,
-        file_name: &Path,
-        file: File,
-    ) -> Result<ElfBackend, Error> {
-        if let Some(entry) = self.cache.get(file_name) {
-            let stat = fstat(file.as_raw_fd())?;
-
-            if entry.is_valid(&stat) {
+        key: &Path,
+        debug_path: &Path,
+        stat: &libc::stat,
+        create: F,
+    ) -> Result<ElfBackend, Error>
+    where
+        F: FnOnce() -> Result<ElfParser, Error>,
+    {
+        if let Some(entry) = self.cache.get(key) {
+            if entry.is_valid(stat) {
                 return Ok(entry.get_backend())
             }
         }
 
-        let entry = ElfCacheEntry::new(file, self.line_number_info, self.debug_info_symbols)?;
+        let parser = create()?;
+        let entry = ElfCacheEntry::new(
+            debug_path,
+            stat,
+            parser,
+            self.line_number_info,
+            self.debug_info_symbols,
+            &self.debug_dirs,
+        );
         let backend = entry.get_backend();
-        let _previous = self.cache.put(file_name.to_path_buf(), entry);
+        let _previous = self.cache.put(key.to_path_buf(), entry);
         Ok(backend)
     }
 
     #[cfg(not(feature = "lru"))]
-    fn find_or_create_backend(
+    fn find_or_create_backend<F>(
         &mut self,
-        _file_name: &Path,
-        file: File,
-    ) -> Result<ElfBackend, Error> {
-        let entry = ElfCacheEntry::new(file, self.line_number_info, self.debug_info_symbols)?;
+        key: &Path,
+        debug_path: &Path,
+        stat: &libc::stat,
+        create: F,
+    ) -> Result<ElfBackend, Error>
+    where
+        F: FnOnce() -> Result<ElfParser, Error>,
+    {
+        let parser = create()?;
+        let entry = ElfCacheEntry::new(
+            debug_path,
+            stat,
+            parser,
+            self.line_number_info,
+            self.debug_info_symbols,
+            &self.debug_dirs,
+        );
         let backend = entry.get_backend();
         Ok(backend)
     }
 
     pub fn find(&mut self, path: &Path) -> Result<ElfBackend, Error> {
+        self.find_mapped(path, path)
+    }
+
+    /// Find the backend for an ELF file mapped into a process.
+    ///
+    /// The file is opened via `maps_file` (e.g.,
+    /// `/proc/<pid>/map_files/<range>`), whereas separate debug files
+    /// are searched for relative to `symbolic_path`, the path the file
+    /// was mapped from.
+    pub fn find_mapped(
+        &mut self,
+        maps_file: &Path,
+        symbolic_path: &Path,
+    ) -> Result<ElfBackend, Error> {
+        let file = File::open(maps_file)?;
+        let stat = fstat(file.as_raw_fd())?;
+        self.find_or_create_backend(maps_file, symbolic_path, &stat, || {
+            ElfParser::open_file(file).and_then(relocate_parser)
+        })
+    }
+
+    /// Find the backend for an ELF file stored (uncompressed) inside an
+    /// APK.
+    ///
+    /// `apk_stat` describes the APK file, `mmap` is expected to cover
+    /// exactly the ELF file's data, and `key` should uniquely identify
+    /// the entry, e.g., `<apk>!/<entry-path>`.
+    pub fn find_apk_entry(
+        &mut self,
+        apk_stat: &libc::stat,
+        key: &Path,
+        mmap: Mmap,
+    ) -> Result<ElfBackend, Error> {
+        self.find_or_create_backend(key, key, apk_stat, || Ok(ElfParser::from_mmap(mmap)))
+    }
+
+    /// Find the backend for a compressed ELF file, such as a compressed
+    /// kernel module.
+    ///
+    /// `decompress` is invoked with the file's contents and is expected
+    /// to produce the uncompressed ELF data.
+    pub fn find_compressed<F>(&mut self, path: &Path, decompress: F) -> Result<ElfBackend, Error>
+    where
+        F: FnOnce(&[u8]) -> Result<Vec<u8>, Error>,
+    {
         let file = File::open(path)?;
-        self.find_or_create_backend(path, file)
+        let stat = fstat(file.as_raw_fd())?;
+        self.find_or_create_backend(path, path, &stat, || {
+            let mmap = Mmap::map(&file)?;
+            let data = decompress(&mmap)?;
+            let parser = ElfParser::from_mmap(Mmap::from_bytes(&data)?);
+            relocate_parser(parser)
+        })
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct ElfCache {
-    cache: RefCell<_ElfCache>,
+    cache: Mutex<_ElfCache>,
 }
 
 impl ElfCache {
-    pub fn new(line_number_info: bool, debug_info_symbols: bool) -> ElfCache {
+    pub fn new(
+        line_number_info: bool,
+        debug_info_symbols: bool,
+        debug_dirs: Vec<PathBuf>,
+    ) -> ElfCache {
         ElfCache {
-            cache: RefCell::new(_ElfCache::new(line_number_info, debug_info_symbols)),
+            cache: Mutex::new(_ElfCache::new(
+                line_number_info,
+                debug_info_symbols,
+                debug_dirs,
+            )),
         }
     }
 
     pub fn find(&self, path: &Path) -> Result<ElfBackend, Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         cache.find(path)
     }
+
+    /// Find the backend for an ELF file mapped into a process. See
+    /// [`_ElfCache::find_mapped`].
+    pub fn find_mapped(&self, maps_file: &Path, symbolic_path: &Path) -> Result<ElfBackend, Error> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.find_mapped(maps_file, symbolic_path)
+    }
+
+    /// Find the backend for an ELF file embedded in an APK. See
+    /// [`_ElfCache::find_apk_entry`].
+    pub fn find_apk_entry(
+        &self,
+        apk_stat: &libc::stat,
+        key: &Path,
+        mmap: Mmap,
+    ) -> Result<ElfBackend, Error> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.find_apk_entry(apk_stat, key, mmap)
+    }
+
+    /// Find the backend for a compressed ELF file. See
+    /// [`_ElfCache::find_compressed`].
+    pub fn find_compressed<F>(&self, path: &Path, decompress: F) -> Result<ElfBackend, Error>
+    where
+        F: FnOnce(&[u8]) -> Result<Vec<u8>, Error>,
+    {
+        let mut cache = self.cache.lock().unwrap();
+        cache.find_compressed(path, decompress)
+    }
 }
 
 #[cfg(test)]
@@ -181,7 +334,7 @@ mod tests {
             .join("data")
             .join("test-no-debug.bin");
 
-        let cache = ElfCache::new(true, false);
+        let cache = ElfCache::new(true, false, Vec::new());
         let backend_first = cache.find(Path::new(&bin_name));
         let backend_second = cache.find(Path::new(&bin_name));
         assert!(backend_first.is_ok());
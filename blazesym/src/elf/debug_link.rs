@@ -0,0 +1,214 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::os::unix::ffi::OsStrExt as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::log::debug;
+use crate::mmap::Mmap;
+use crate::normalize::read_elf_build_id;
+use crate::util::crc32;
+use crate::util::to_hex;
+use crate::util::ReadRaw as _;
+
+use super::ElfParser;
+
+
+/// The directories searched for separate debug files by default.
+pub(crate) const DEFAULT_DEBUG_DIRS: &[&str] = &["/usr/lib/debug"];
+
+
+/// Read the file name of the debug file and its CRC-32 checksum from the
+/// `.gnu_debuglink` section of an ELF file, if present.
+fn read_debug_link(parser: &ElfParser) -> Result<Option<(&OsStr, u32)>> {
+    let idx = match parser.find_section(".gnu_debuglink") {
+        Ok(idx) => idx,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    let mut data = parser.section_data(idx)?;
+    let name = data.read_cstr().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "failed to read debug link file name",
+        )
+    })?;
+    // The checksum follows the (NUL terminated) name, four byte aligned.
+    let crc = data
+        .align(4)
+        .and_then(|()| data.read_u32())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read debug link checksum"))?;
+
+    Ok(Some((OsStr::from_bytes(name.to_bytes()), crc)))
+}
+
+/// Find a debug file in the `.build-id` sub-directory of one of
+/// `debug_dirs`, based on the build ID of the ELF file it belongs to.
+fn find_by_build_id(build_id: &[u8], debug_dirs: &[PathBuf]) -> Option<ElfParser> {
+    if build_id.len() < 2 {
+        return None
+    }
+
+    let hex = to_hex(build_id);
+    let (dir, file) = hex.split_at(2);
+
+    debug_dirs.iter().find_map(|debug_dir| {
+        let path = debug_dir
+            .join(".build-id")
+            .join(dir)
+            .join(format!("{file}.debug"));
+        let parser = ElfParser::open(&path).ok()?;
+        // Make sure that we don't pick up a stale file.
+        if read_elf_build_id(&parser).ok()?.as_deref() != Some(build_id) {
+            debug!("build ID of debug file {} does not match", path.display());
+            return None
+        }
+        Some(parser)
+    })
+}
+
+/// Find a debug file based on the name and checksum given by the debug
+/// link of the ELF file at `path`.
+///
+/// Similar to GDB, we search the directory containing the ELF file, its
+/// `.debug` sub-directory, and the directory mirroring that directory
+/// inside of each of `debug_dirs`.
+fn find_by_debug_link(
+    path: &Path,
+    name: &OsStr,
+    crc: u32,
+    debug_dirs: &[PathBuf],
+) -> Option<ElfParser> {
+    let dir = path.parent()?;
+    let mut candidates = vec![dir.join(name), dir.join(".debug").join(name)];
+    if let Ok(dir) = dir.canonicalize() {
+        // SANITY: A canonical path is always absolute.
+        let dir = dir.strip_prefix("/").unwrap();
+        let () = candidates.extend(
+            debug_dirs
+                .iter()
+                .map(|debug_dir| debug_dir.join(dir).join(name)),
+        );
+    }
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate != path)
+        .find_map(|candidate| {
+            let file = File::open(&candidate).ok()?;
+            let mmap = Mmap::map(&file).ok()?;
+            if crc32(&mmap) != crc {
+                debug!(
+                    "checksum of debug file {} does not match",
+                    candidate.display()
+                );
+                return None
+            }
+            Some(ElfParser::from_mmap(mmap))
+        })
+}
+
+/// Find the separate debug file belonging to the ELF file at `path`.
+///
+/// No debug file is searched for if the ELF file contains DWARF debug
+/// information itself. Otherwise it is looked up by the file's build ID
+/// first and via its `.gnu_debuglink` section afterwards.
+pub(crate) fn find_debug_file(
+    path: &Path,
+    parser: &ElfParser,
+    debug_dirs: &[PathBuf],
+) -> Option<ElfParser> {
+    if parser.find_section(".debug_info").is_ok() || parser.find_section(".debug_line").is_ok() {
+        return None
+    }
+
+    if let Ok(Some(build_id)) = read_elf_build_id(parser) {
+        if let Some(debug_parser) = find_by_build_id(&build_id, debug_dirs) {
+            return Some(debug_parser)
+        }
+    }
+
+    if let Ok(Some((name, crc))) = read_debug_link(parser) {
+        return find_by_debug_link(path, name, crc, debug_dirs)
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::copy;
+    use std::fs::create_dir_all;
+
+    use tempfile::tempdir;
+    use test_log::test;
+
+
+    /// Check that we can read the debug link of an ELF file.
+    #[test]
+    fn debug_link_reading() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-debug-link.bin");
+        let parser = ElfParser::open(&bin_name).unwrap();
+        let (name, crc) = read_debug_link(&parser).unwrap().unwrap();
+        assert_eq!(name, OsStr::new("test-debug-link.dbg"));
+
+        let dbg_name = bin_name.with_file_name(name);
+        let file = File::open(dbg_name).unwrap();
+        let mmap = Mmap::map(&file).unwrap();
+        assert_eq!(crc32(&mmap), crc);
+
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-dwarf-v4.bin");
+        let parser = ElfParser::open(&bin_name).unwrap();
+        assert_eq!(read_debug_link(&parser).unwrap(), None);
+    }
+
+    /// Check that we can find separate debug files via build ID and
+    /// debug link.
+    #[test]
+    fn debug_file_lookup() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-debug-link.bin");
+        let dbg_name = bin_name.with_file_name("test-debug-link.dbg");
+        let parser = ElfParser::open(&bin_name).unwrap();
+        let build_id = read_elf_build_id(&parser).unwrap().unwrap();
+        let hex = build_id
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        // The debug link refers to a file next to the binary.
+        let debug_parser = find_debug_file(&bin_name, &parser, &[]).unwrap();
+        assert!(debug_parser.find_section(".debug_info").is_ok());
+
+        // Without the debug file next to it, the binary's debug
+        // information can only be found via its build ID.
+        let dir = tempdir().unwrap();
+        let bin_copy = dir.path().join("test-debug-link.bin");
+        let _bytes = copy(&bin_name, &bin_copy).unwrap();
+        let debug_dir = dir.path().join("debug");
+        assert!(find_debug_file(&bin_copy, &parser, &[debug_dir.clone()]).is_none());
+
+        let build_id_dir = debug_dir.join(".build-id").join(&hex[..2]);
+        let () = create_dir_all(&build_id_dir).unwrap();
+        let _bytes = copy(&dbg_name, build_id_dir.join(format!("{}.debug", &hex[2..]))).unwrap();
+        let debug_parser = find_debug_file(&bin_copy, &parser, &[debug_dir]).unwrap();
+        assert!(debug_parser.find_section(".debug_info").is_ok());
+
+        // A file with debug information itself does not need a separate
+        // debug file.
+        let parser = ElfParser::open(&dbg_name).unwrap();
+        assert!(find_debug_file(&dbg_name, &parser, &[]).is_none());
+    }
+}
@@ -1,10 +1,20 @@
 mod cache;
+mod debug_link;
+mod notes;
 mod parser;
+mod reloc;
 mod resolver;
 #[allow(dead_code, non_camel_case_types)]
 pub(crate) mod types;
 
 pub(crate) use cache::ElfBackend;
 pub(crate) use cache::ElfCache;
+pub(crate) use debug_link::find_debug_file;
+pub(crate) use debug_link::DEFAULT_DEBUG_DIRS;
+pub(crate) use notes::NoteIter;
 pub(crate) use parser::ElfParser;
+pub(crate) use reloc::relocate_parser;
 pub(crate) use resolver::ElfResolver;
+
+#[cfg(test)]
+pub(crate) use notes::tests::make_note;
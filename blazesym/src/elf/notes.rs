@@ -0,0 +1,177 @@
+use crate::util::ReadRaw as _;
+
+use super::types::Elf64_Nhdr;
+
+
+/// An ELF note.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Note<'data> {
+    /// The note's name (or "owner"), without any trailing NUL bytes.
+    pub name: &'data [u8],
+    /// The note's type, the interpretation of which depends on `name`.
+    pub typ: u32,
+    /// The note's descriptor.
+    pub desc: &'data [u8],
+}
+
+
+/// An iterator over the notes contained in the data of a note section
+/// or segment.
+///
+/// Iteration stops at the first malformed note.
+#[derive(Clone, Debug)]
+pub(crate) struct NoteIter<'data> {
+    /// The note data.
+    data: &'data [u8],
+    /// The offset of the next note in `data`.
+    offset: usize,
+    /// The alignment of note names and descriptors.
+    align: usize,
+}
+
+impl<'data> NoteIter<'data> {
+    /// Create a new iterator over the notes in `data`, belonging to a
+    /// section or segment with the given alignment.
+    pub fn new(data: &'data [u8], align: u64) -> Self {
+        // Notes are four byte aligned as per the ELF specification, but
+        // eight byte alignment is used by some producers (e.g., for
+        // `.note.gnu.property`). Other values are best treated as the
+        // default.
+        let align = if align == 8 { 8 } else { 4 };
+        Self {
+            data,
+            offset: 0,
+            align,
+        }
+    }
+
+    /// Read `len` bytes at the current offset, advancing it by the
+    /// padded length.
+    fn read_padded(&mut self, len: u32) -> Option<&'data [u8]> {
+        let len = usize::try_from(len).ok()?;
+        let end = self.offset.checked_add(len)?;
+        let slice = self.data.get(self.offset..end)?;
+        let padded = end.checked_add(self.align - 1)? & !(self.align - 1);
+        self.offset = padded.min(self.data.len());
+        Some(slice)
+    }
+
+    fn next_impl(&mut self) -> Option<Note<'data>> {
+        let mut data = self.data.get(self.offset..)?;
+        let nhdr = data.read_pod::<Elf64_Nhdr>()?;
+        self.offset = self.data.len() - data.len();
+
+        let name = self.read_padded(nhdr.n_namesz)?;
+        let desc = self.read_padded(nhdr.n_descsz)?;
+
+        let end = name
+            .iter()
+            .rposition(|b| *b != 0)
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        let note = Note {
+            name: &name[..end],
+            typ: nhdr.n_type,
+            desc,
+        };
+        Some(note)
+    }
+}
+
+impl<'data> Iterator for NoteIter<'data> {
+    type Item = Note<'data>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let note = self.next_impl();
+        if note.is_none() {
+            // Make sure to not produce any more notes after
+            // encountering a malformed one.
+            self.offset = self.data.len();
+        }
+        note
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use test_log::test;
+
+
+    /// Create an ELF note with the given name, type, and descriptor,
+    /// using the provided alignment.
+    pub(crate) fn make_note(name: &[u8], typ: u32, desc: &[u8], align: usize) -> Vec<u8> {
+        let pad = |note: &mut Vec<u8>| note.resize((note.len() + align - 1) & !(align - 1), 0);
+
+        let mut note = Vec::new();
+        let () = note.extend_from_slice(&(name.len() as u32).to_ne_bytes());
+        let () = note.extend_from_slice(&(desc.len() as u32).to_ne_bytes());
+        let () = note.extend_from_slice(&typ.to_ne_bytes());
+        let () = note.extend_from_slice(name);
+        let () = pad(&mut note);
+        let () = note.extend_from_slice(desc);
+        let () = pad(&mut note);
+        note
+    }
+
+    /// Check that we can iterate over four byte aligned notes.
+    #[test]
+    fn note_iteration() {
+        let mut data = make_note(b"GNU\0", 3, &[1, 2, 3, 4, 5], 4);
+        let () = data.extend(make_note(b"Android\0", 1, &[6, 7, 8, 9], 4));
+        let () = data.extend(make_note(b"", 42, &[], 4));
+
+        let notes = NoteIter::new(&data, 4).collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![
+                Note {
+                    name: b"GNU",
+                    typ: 3,
+                    desc: &[1, 2, 3, 4, 5],
+                },
+                Note {
+                    name: b"Android",
+                    typ: 1,
+                    desc: &[6, 7, 8, 9],
+                },
+                Note {
+                    name: b"",
+                    typ: 42,
+                    desc: &[],
+                },
+            ]
+        );
+    }
+
+    /// Check that we honor an eight byte alignment of notes.
+    #[test]
+    fn aligned_note_iteration() {
+        let mut data = make_note(b"GNU\0", 5, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], 8);
+        let () = data.extend(make_note(b"Go\0\0", 4, b"abc", 8));
+
+        let notes = NoteIter::new(&data, 8).collect::<Vec<_>>();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].desc.len(), 12);
+        assert_eq!(notes[1].name, b"Go");
+        assert_eq!(notes[1].typ, 4);
+        assert_eq!(notes[1].desc, b"abc");
+    }
+
+    /// Make sure that iteration stops at malformed notes.
+    #[test]
+    fn malformed_note_iteration() {
+        let mut data = make_note(b"GNU\0", 3, &[1, 2, 3, 4], 4);
+        let () = data.extend(make_note(b"GNU\0", 3, &[5, 6, 7, 8], 4));
+        let data = &data[..data.len() - 2];
+
+        let mut iter = NoteIter::new(data, 4);
+        assert_eq!(iter.next().unwrap().desc, &[1, 2, 3, 4]);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(NoteIter::new(&[1, 2, 3], 4).next(), None);
+    }
+}
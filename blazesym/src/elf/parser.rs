@@ -1,4 +1,5 @@
-use std::cell::RefCell;
+use std::any::Any;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
@@ -8,22 +9,40 @@ use std::io::ErrorKind;
 use std::mem;
 use std::ops::Deref as _;
 use std::path::Path;
+use std::slice;
+use std::sync::Mutex;
 
+use crate::demangle::demangle;
+use crate::demangle::demangle_without_params;
 use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
 use crate::inspect::SymType;
 use crate::mmap::Mmap;
-use crate::util::find_match_or_lower_bound_by;
 use crate::util::search_address_opt_key;
+use crate::util::Pod;
 use crate::util::ReadRaw as _;
 use crate::Addr;
 
+use super::notes::Note;
+use super::notes::NoteIter;
+use super::types::Elf32_Chdr;
+use super::types::Elf32_Ehdr;
+use super::types::Elf32_Phdr;
+use super::types::Elf32_Shdr;
+use super::types::Elf32_Sym;
+use super::types::Elf64_Chdr;
 use super::types::Elf64_Ehdr;
 use super::types::Elf64_Phdr;
 use super::types::Elf64_Shdr;
 use super::types::Elf64_Sym;
+use super::types::EI_CLASS;
+use super::types::ELFCLASS32;
+use super::types::ELFCOMPRESS_ZLIB;
+use super::types::ELFCOMPRESS_ZSTD;
+use super::types::PT_NOTE;
+use super::types::SHF_COMPRESSED;
 use super::types::SHN_UNDEF;
-#[cfg(test)]
+use super::types::SHT_NOTE;
 use super::types::STT_FUNC;
 
 
@@ -41,6 +60,16 @@ struct Cache<'mmap> {
     /// The cached ELF string table.
     strtab: Option<&'mmap [u8]>,
     str2symtab: Option<Vec<(&'mmap str, usize)>>, // strtab offset to symtab in the dictionary order
+    /// Demangled symbol names along with the index of the respective
+    /// symbol in `symtab`, in dictionary order.
+    demangled2symtab: Option<Vec<(String, usize)>>,
+    /// The decompressed data of compressed sections, keyed by section
+    /// index.
+    decompressed: HashMap<usize, &'mmap [u8]>,
+    /// Data owned by the cache that we hand out references to, such as
+    /// decompressed sections or headers of 32 bit ELF files converted
+    /// to their 64 bit counterparts.
+    retained: Vec<Box<dyn Any + Send>>,
 }
 
 impl<'mmap> Cache<'mmap> {
@@ -55,12 +84,53 @@ impl<'mmap> Cache<'mmap> {
             symtab: None,
             strtab: None,
             str2symtab: None,
+            demangled2symtab: None,
+            decompressed: HashMap::new(),
+            retained: Vec::new(),
         }
     }
 
+    /// Keep `data` alive for as long as the cache, handing out a
+    /// reference to it with the lifetime of the ELF data.
+    fn retain<T: Send + 'static>(&mut self, data: Box<[T]>) -> &'mmap [T] {
+        // SAFETY: `data` is heap allocated and never removed from the
+        //         cache, so it lives as long as the cache itself, which
+        //         in turn never outlives the ELF data.
+        let slice = unsafe { slice::from_raw_parts(data.as_ptr(), data.len()) };
+        let () = self.retained.push(Box::new(data));
+        slice
+    }
+
+    /// Check whether we are dealing with a 32 bit ELF file.
+    fn is_32bit(&mut self) -> Result<bool, Error> {
+        let ehdr = self.ensure_ehdr()?;
+        Ok(ehdr.e_ident[EI_CLASS] == ELFCLASS32)
+    }
+
+    /// Read `count` objects of type `T64` from `data`. For 32 bit ELF
+    /// files objects of type `T32` are read instead and converted to
+    /// their 64 bit counterparts.
+    fn read_pod_slice<T32, T64>(
+        &mut self,
+        mut data: &'mmap [u8],
+        count: usize,
+    ) -> Result<Option<&'mmap [T64]>, Error>
+    where
+        T32: Pod + 'static,
+        T64: Pod + for<'a> From<&'a T32> + Send + 'static,
+    {
+        let slice = if self.is_32bit()? {
+            data.read_pod_slice_ref::<T32>(count)
+                .map(|slice| self.retain(slice.iter().map(T64::from).collect()))
+        } else {
+            data.read_pod_slice_ref::<T64>(count)
+        };
+        Ok(slice)
+    }
+
     /// Retrieve the raw section data for the ELF section at index
     /// `idx`.
-    fn section_data(&mut self, idx: usize) -> Result<&'mmap [u8], Error> {
+    fn raw_section_data(&mut self, idx: usize) -> Result<&'mmap [u8], Error> {
         let shdrs = self.ensure_shdrs()?;
         let section = shdrs.get(idx).ok_or_else(|| {
             Error::new(
@@ -88,15 +158,46 @@ impl<'mmap> Cache<'mmap> {
         Ok(data)
     }
 
+    /// Retrieve the data for the ELF section at index `idx`,
+    /// transparently decompressing it if it is compressed.
+    fn section_data(&mut self, idx: usize) -> Result<&'mmap [u8], Error> {
+        if let Some(data) = self.decompressed.get(&idx) {
+            return Ok(data)
+        }
+
+        let data = self.raw_section_data(idx)?;
+        // SANITY: `raw_section_data` checked that the index is valid.
+        let section = &self.ensure_shdrs()?[idx];
+        let decompressed = if section.sh_flags & SHF_COMPRESSED != 0 {
+            decompress_section(data, self.is_32bit()?)?
+        } else if self.section_name(idx)?.starts_with(".zdebug_") {
+            decompress_zdebug(data)?
+        } else {
+            return Ok(data)
+        };
+
+        let data = self.retain(decompressed);
+        let _prev = self.decompressed.insert(idx, data);
+        Ok(data)
+    }
+
     fn ensure_ehdr(&mut self) -> Result<&'mmap Elf64_Ehdr, Error> {
         if let Some(ehdr) = self.ehdr {
             return Ok(ehdr)
         }
 
         let mut elf_data = self.elf_data;
-        let ehdr = elf_data
-            .read_pod_ref::<Elf64_Ehdr>()
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read Elf64_Ehdr"))?;
+        let ehdr = if elf_data.get(EI_CLASS) == Some(&ELFCLASS32) {
+            let ehdr = elf_data
+                .read_pod_ref::<Elf32_Ehdr>()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read Elf32_Ehdr"))?;
+            let ehdr = self.retain(Box::new([Elf64_Ehdr::from(ehdr)]));
+            &ehdr[0]
+        } else {
+            elf_data
+                .read_pod_ref::<Elf64_Ehdr>()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read Elf64_Ehdr"))?
+        };
         if !(ehdr.e_ident[0] == 0x7f
             && ehdr.e_ident[1] == b'E'
             && ehdr.e_ident[2] == b'L'
@@ -117,12 +218,13 @@ impl<'mmap> Cache<'mmap> {
         }
 
         let ehdr = self.ensure_ehdr()?;
-        let shdrs = self
+        let data = self
             .elf_data
             .get(ehdr.e_shoff as usize..)
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Elf64_Ehdr::e_shoff is invalid"))?
-            .read_pod_slice_ref::<Elf64_Shdr>(ehdr.e_shnum.into())
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read Elf64_Shdr"))?;
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Elf64_Ehdr::e_shoff is invalid"))?;
+        let shdrs = self
+            .read_pod_slice::<Elf32_Shdr, Elf64_Shdr>(data, ehdr.e_shnum.into())?
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read section headers"))?;
         self.shdrs = Some(shdrs);
         Ok(shdrs)
     }
@@ -133,12 +235,13 @@ impl<'mmap> Cache<'mmap> {
         }
 
         let ehdr = self.ensure_ehdr()?;
-        let phdrs = self
+        let data = self
             .elf_data
             .get(ehdr.e_phoff as usize..)
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Elf64_Ehdr::e_phoff is invalid"))?
-            .read_pod_slice_ref::<Elf64_Phdr>(ehdr.e_phnum.into())
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read Elf64_Phdr"))?;
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Elf64_Ehdr::e_phoff is invalid"))?;
+        let phdrs = self
+            .read_pod_slice::<Elf32_Phdr, Elf64_Phdr>(data, ehdr.e_phnum.into())?
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read program headers"))?;
         self.phdrs = Some(phdrs);
         Ok(phdrs)
     }
@@ -150,7 +253,7 @@ impl<'mmap> Cache<'mmap> {
 
         let ehdr = self.ensure_ehdr()?;
         let shstrndx = ehdr.e_shstrndx;
-        let shstrtab = self.section_data(shstrndx as usize)?;
+        let shstrtab = self.raw_section_data(shstrndx as usize)?;
         self.shstrtab = Some(shstrtab);
         Ok(shstrtab)
     }
@@ -222,6 +325,17 @@ impl<'mmap> Cache<'mmap> {
                 return Ok(i)
             }
         }
+
+        // Debug sections may have been compressed with the legacy GNU
+        // scheme, in which case they are named `.zdebug_*`.
+        if let Some(suffix) = name.strip_prefix(".debug_") {
+            let zname = format!(".zdebug_{suffix}");
+            for i in 1..ehdr.e_shnum.into() {
+                if self.section_name(i)? == zname {
+                    return Ok(i)
+                }
+            }
+        }
         Err(Error::new(
             ErrorKind::NotFound,
             format!("unable to find ELF section: {name}"),
@@ -241,18 +355,23 @@ impl<'mmap> Cache<'mmap> {
         } else {
             self.find_section(".dynsym")?
         };
-        let mut symtab = self.section_data(idx)?;
+        let symtab = self.raw_section_data(idx)?;
+        let sym_size = if self.is_32bit()? {
+            mem::size_of::<Elf32_Sym>()
+        } else {
+            mem::size_of::<Elf64_Sym>()
+        };
 
-        if symtab.len() % mem::size_of::<Elf64_Sym>() != 0 {
+        if symtab.len() % sym_size != 0 {
             return Err(Error::new(
                 ErrorKind::InvalidData,
                 "size of symbol table section is invalid",
             ))
         }
 
-        let count = symtab.len() / mem::size_of::<Elf64_Sym>();
-        let mut symtab = symtab
-            .read_pod_slice_ref::<Elf64_Sym>(count)
+        let count = symtab.len() / sym_size;
+        let mut symtab = self
+            .read_pod_slice::<Elf32_Sym, Elf64_Sym>(symtab, count)?
             .ok_or_else(|| {
                 Error::new(
                     ErrorKind::InvalidData,
@@ -277,7 +396,7 @@ impl<'mmap> Cache<'mmap> {
         } else {
             self.find_section(".dynstr")?
         };
-        let strtab = self.section_data(idx)?;
+        let strtab = self.raw_section_data(idx)?;
         self.strtab = Some(strtab);
         Ok(strtab)
     }
@@ -323,6 +442,156 @@ impl<'mmap> Cache<'mmap> {
         self.str2symtab = Some(str2symtab);
         Ok(())
     }
+
+    fn ensure_demangled2symtab(&mut self) -> Result<(), Error> {
+        if self.demangled2symtab.is_some() {
+            return Ok(())
+        }
+
+        let () = self.ensure_str2symtab()?;
+        // SANITY: The above `ensure_str2symtab` ensures we have
+        //         `str2symtab` available.
+        let str2symtab = self.str2symtab.as_ref().unwrap();
+
+        // Index C++ functions by their fully demangled name as well as
+        // by their name sans parameter list, so that users can look
+        // them up by either.
+        let mut demangled2symtab = Vec::new();
+        for &(name, i) in str2symtab {
+            if let Some(demangled) = demangle(name) {
+                match demangle_without_params(name) {
+                    Some(short) if short != demangled => demangled2symtab.push((short, i)),
+                    _ => (),
+                }
+                let () = demangled2symtab.push((demangled, i));
+            }
+        }
+        let () = demangled2symtab.sort();
+
+        self.demangled2symtab = Some(demangled2symtab);
+        Ok(())
+    }
+}
+
+/// Find all symbols with the given name.
+///
+/// `names` is a list of symbol names in dictionary order, along with
+/// the index of the respective symbol in `symtab`.
+fn find_syms_by_name<'mmap, S>(
+    symtab: &[&'mmap Elf64_Sym],
+    names: &[(S, usize)],
+    name: &str,
+) -> Result<Vec<&'mmap Elf64_Sym>, Error>
+where
+    S: AsRef<str>,
+{
+    let idx = names.partition_point(|(name_visit, _i)| name_visit.as_ref() < name);
+    names[idx..]
+        .iter()
+        .take_while(|(name_visit, _i)| name_visit.as_ref() == name)
+        .map(|(_name, sym_i)| {
+            symtab.get(*sym_i).copied().ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("symbol table index ({sym_i}) out of bounds"),
+                )
+            })
+        })
+        .filter(|sym| !matches!(sym, Ok(sym) if sym.st_shndx == SHN_UNDEF))
+        .collect()
+}
+
+/// Decompress the data of a section with the `SHF_COMPRESSED` flag set.
+fn decompress_section(mut data: &[u8], is_32bit: bool) -> Result<Box<[u8]>, Error> {
+    let chdr = if is_32bit {
+        data.read_pod::<Elf32_Chdr>()
+            .map(|chdr| Elf64_Chdr::from(&chdr))
+    } else {
+        data.read_pod::<Elf64_Chdr>()
+    }
+    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "failed to read compression header"))?;
+    let size = chdr.ch_size as usize;
+
+    match chdr.ch_type {
+        ELFCOMPRESS_ZLIB => decompress_zlib(data, size),
+        ELFCOMPRESS_ZSTD => decompress_zstd(data, size),
+        ty => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("ELF section is compressed with unknown format {ty}"),
+        )),
+    }
+}
+
+/// Decompress the data of a section compressed with the legacy GNU
+/// scheme, as used by `.zdebug_*` sections.
+fn decompress_zdebug(mut data: &[u8]) -> Result<Box<[u8]>, Error> {
+    // The compressed data is preceded by the magic "ZLIB" and the size
+    // of the uncompressed data, as a 64 bit big endian value.
+    if data.read_slice(4) != Some(b"ZLIB".as_slice()) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "encountered invalid .zdebug section header",
+        ))
+    }
+    let size = data
+        .read_slice(8)
+        .and_then(|size| <[u8; 8]>::try_from(size).ok())
+        .map(u64::from_be_bytes)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "failed to read .zdebug section size",
+            )
+        })?;
+
+    decompress_zlib(data, size as usize)
+}
+
+#[cfg(feature = "zlib")]
+fn decompress_zlib(data: &[u8], size: usize) -> Result<Box<[u8]>, Error> {
+    use miniz_oxide::inflate::decompress_to_vec_zlib_with_limit;
+
+    let decompressed = decompress_to_vec_zlib_with_limit(data, size).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to decompress zlib data: {err}"),
+        )
+    })?;
+    if decompressed.len() != size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "decompressed zlib data has unexpected size",
+        ))
+    }
+    Ok(decompressed.into_boxed_slice())
+}
+
+#[cfg(not(feature = "zlib"))]
+fn decompress_zlib(_data: &[u8], _size: usize) -> Result<Box<[u8]>, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "zlib compressed sections are not supported; enable the `zlib` feature",
+    ))
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(data: &[u8], size: usize) -> Result<Box<[u8]>, Error> {
+    let decompressed = zstd::bulk::decompress(data, size)?;
+    if decompressed.len() != size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "decompressed zstd data has unexpected size",
+        ))
+    }
+    Ok(decompressed.into_boxed_slice())
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_data: &[u8], _size: usize) -> Result<Box<[u8]>, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "zstd compressed sections are not supported; enable the `zstd` feature",
+    ))
 }
 
 impl Debug for Cache<'_> {
@@ -332,7 +601,7 @@ impl Debug for Cache<'_> {
 }
 
 
-/// A parser for ELF64 files.
+/// A parser for ELF files, supporting both 32 bit and 64 bit ones.
 #[derive(Debug)]
 pub(crate) struct ElfParser {
     /// A cache for relevant parts of the ELF file.
@@ -340,9 +609,9 @@ pub(crate) struct ElfParser {
     ///         this member. Rather, they should never outlive `self`.
     ///         Furthermore, this member has to be listed before `mmap`
     ///         to make sure we never end up with a dangling reference.
-    cache: RefCell<Cache<'static>>,
+    cache: Mutex<Cache<'static>>,
     /// The memory mapped file.
-    _mmap: Mmap,
+    mmap: Mmap,
 }
 
 impl ElfParser {
@@ -360,8 +629,8 @@ impl ElfParser {
         let elf_data = unsafe { mem::transmute(mmap.deref()) };
 
         let parser = ElfParser {
-            _mmap: mmap,
-            cache: RefCell::new(Cache::new(elf_data)),
+            mmap,
+            cache: Mutex::new(Cache::new(elf_data)),
         };
         parser
     }
@@ -377,39 +646,49 @@ impl ElfParser {
         }
     }
 
+    /// Retrieve the raw data of the ELF file.
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.mmap
+    }
+
     /// Retrieve the data corresponding to the ELF section at index `idx`.
     pub fn section_data(&self, idx: usize) -> Result<&[u8], Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         cache.section_data(idx)
     }
 
     /// Read the raw data of the section of a given index.
     #[cfg(test)]
     pub fn read_section_raw(&self, sect_idx: usize) -> Result<&[u8], Error> {
-        let mut cache = self.cache.borrow_mut();
-        cache.section_data(sect_idx)
+        let mut cache = self.cache.lock().unwrap();
+        cache.raw_section_data(sect_idx)
     }
 
+    /// Retrieve the size of the (decompressed) data of the section of a
+    /// given index.
     pub fn get_section_size(&self, sect_idx: usize) -> Result<usize, Error> {
-        let mut cache = self.cache.borrow_mut();
-        let shdrs = cache.ensure_shdrs()?;
-        let sect = shdrs.get(sect_idx).ok_or_else(|| {
-            Error::new(ErrorKind::InvalidInput, "ELF section index out of bounds")
-        })?;
-        Ok(sect.sh_size as usize)
+        let mut cache = self.cache.lock().unwrap();
+        let data = cache.section_data(sect_idx)?;
+        Ok(data.len())
     }
 
     /// Find the section of a given name.
     ///
     /// This function return the index of the section if found.
     pub fn find_section(&self, name: &str) -> Result<usize, Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let index = cache.find_section(name)?;
         Ok(index)
     }
 
+    /// Retrieve the name of the section at index `idx`.
+    pub(crate) fn section_name(&self, idx: usize) -> Result<&str, Error> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.section_name(idx)
+    }
+
     pub fn find_symbol(&self, addr: Addr, st_type: u8) -> Result<(&str, Addr), Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let () = cache.ensure_symtab()?;
         // SANITY: The above `ensure_symtab` ensures we have `symtab`
         //         available.
@@ -440,7 +719,7 @@ impl ElfParser {
             return Err(Error::new(ErrorKind::Unsupported, "Not implemented"))
         }
 
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let () = cache.ensure_symtab()?;
         let () = cache.ensure_str2symtab()?;
         // SANITY: The above `ensure_symtab` ensures we have `symtab`
@@ -450,60 +729,131 @@ impl ElfParser {
         //         `str2symtab` available.
         let str2symtab = cache.str2symtab.as_ref().unwrap();
 
-        let r = find_match_or_lower_bound_by(str2symtab, name, |&(name, _i)| name);
-        match r {
-            Some(idx) => {
-                let mut found = vec![];
-                for (name_visit, sym_i) in str2symtab.iter().skip(idx) {
-                    if *name_visit != name {
-                        break
-                    }
-                    let sym_ref = &symtab.get(*sym_i).ok_or_else(|| {
-                        Error::new(
-                            ErrorKind::InvalidInput,
-                            format!("symbol table index ({sym_i}) out of bounds"),
-                        )
-                    })?;
-                    if sym_ref.st_shndx != SHN_UNDEF {
-                        found.push(SymInfo {
-                            name: name.to_string(),
-                            addr: sym_ref.st_value as Addr,
-                            size: sym_ref.st_size as usize,
-                            sym_type: SymType::Function,
-                            file_offset: 0,
-                            obj_file_name: None,
-                        });
-                    }
-                }
-                Ok(found)
-            }
-            None => Ok(vec![]),
+        let mut syms = find_syms_by_name(symtab, str2symtab, name)?;
+        if syms.is_empty() && cfg!(feature = "demangle") {
+            // The name may be given in demangled form. Only in that
+            // case we incur the cost of demangling all symbol names.
+            let () = cache.ensure_demangled2symtab()?;
+            let symtab = cache.symtab.as_ref().unwrap();
+            // SANITY: The above `ensure_demangled2symtab` ensures we
+            //         have `demangled2symtab` available.
+            let demangled2symtab = cache.demangled2symtab.as_ref().unwrap();
+            syms = find_syms_by_name(symtab, demangled2symtab, name)?;
         }
+
+        let found = syms
+            .into_iter()
+            .map(|sym| {
+                let addr = sym.st_value as Addr;
+                let size = sym.st_size as usize;
+                let range = addr..addr + size;
+                SymInfo {
+                    name: name.to_string(),
+                    addr,
+                    size,
+                    ranges: vec![range],
+                    sym_type: SymType::Function,
+                    file_offset: 0,
+                    obj_file_name: None,
+                    module: None,
+                }
+            })
+            .collect();
+        Ok(found)
+    }
+
+    /// Retrieve all defined function symbols, as `(name, address, size)`
+    /// tuples, sorted by address.
+    pub(crate) fn function_symbols(&self) -> Result<Vec<(&str, Addr, usize)>, Error> {
+        let mut cache = self.cache.lock().unwrap();
+        let () = cache.ensure_symtab()?;
+        // SANITY: The above `ensure_symtab` ensures we have `symtab`
+        //         available.
+        let symtab = cache.symtab.clone().unwrap();
+
+        symtab
+            .into_iter()
+            .filter(|sym| {
+                sym.st_info & 0xf == STT_FUNC && sym.st_shndx != SHN_UNDEF && sym.st_value != 0
+            })
+            .map(|sym| {
+                let name = cache.symbol_name(sym)?;
+                Ok((name, sym.st_value as Addr, sym.st_size as usize))
+            })
+            .collect()
     }
 
     #[cfg(test)]
     fn get_symbol_name(&self, idx: usize) -> Result<&str, Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let sym = cache.symbol(idx)?;
         let name = cache.symbol_name(sym)?;
         Ok(name)
     }
 
     pub(crate) fn section_headers(&self) -> Result<&[Elf64_Shdr], Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let phdrs = cache.ensure_shdrs()?;
         Ok(phdrs)
     }
 
     pub(crate) fn program_headers(&self) -> Result<&[Elf64_Phdr], Error> {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let phdrs = cache.ensure_phdrs()?;
         Ok(phdrs)
     }
 
+    /// Retrieve an iterator over all notes of the ELF file.
+    ///
+    /// Notes are looked up in `PT_NOTE` segments as well as in
+    /// `SHT_NOTE` sections, which makes it possible to find them in
+    /// files lacking either section or program headers. Notes in
+    /// sections covered by a `PT_NOTE` segment are only reported once.
+    pub(crate) fn notes(&self) -> Result<impl Iterator<Item = Note<'_>>, Error> {
+        let data = self.data();
+        let region = |offset: u64, size: u64| -> Option<&[u8]> {
+            let start = usize::try_from(offset).ok()?;
+            let end = start.checked_add(usize::try_from(size).ok()?)?;
+            data.get(start..end)
+        };
+
+        let phdrs = self.program_headers()?;
+        let shdrs = self.section_headers()?;
+        let segments = phdrs
+            .iter()
+            .filter(|phdr| phdr.p_type == PT_NOTE)
+            .filter_map(|phdr| {
+                let data = region(phdr.p_offset, phdr.p_filesz)?;
+                Some(NoteIter::new(data, phdr.p_align))
+            });
+        let sections = shdrs
+            .iter()
+            .filter(|shdr| shdr.sh_type == SHT_NOTE)
+            .filter(|shdr| {
+                let start = shdr.sh_offset;
+                let end = shdr.sh_offset.saturating_add(shdr.sh_size);
+                !phdrs.iter().any(|phdr| {
+                    phdr.p_type == PT_NOTE
+                        && phdr.p_offset <= start
+                        && end <= phdr.p_offset.saturating_add(phdr.p_filesz)
+                })
+            })
+            .filter_map(|shdr| {
+                let data = region(shdr.sh_offset, shdr.sh_size)?;
+                Some(NoteIter::new(data, shdr.sh_addralign))
+            });
+
+        let notes = segments
+            .chain(sections)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten();
+        Ok(notes)
+    }
+
     #[cfg(test)]
     fn pick_symtab_addr(&self) -> (&str, Addr) {
-        let mut cache = self.cache.borrow_mut();
+        let mut cache = self.cache.lock().unwrap();
         let () = cache.ensure_symtab().unwrap();
         let symtab = cache.symtab.as_ref().unwrap();
 
@@ -597,4 +947,52 @@ mod tests {
         assert_eq!(syms[1].name, "factorial_wrapper");
         assert_ne!(syms[0].addr, syms[1].addr);
     }
+
+    /// Check that we can parse 32 bit ELF files.
+    #[test]
+    fn elf32_parsing() {
+        let bin_name = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses-32.bin");
+
+        let parser = ElfParser::open(bin_name.as_ref()).unwrap();
+        assert!(parser.find_section(".debug_info").is_ok());
+
+        let (name, addr) = parser.find_symbol(0x2000100, STT_FUNC).unwrap();
+        assert_eq!(name, "factorial");
+        assert_eq!(addr, 0x2000100);
+
+        let opts = FindAddrOpts::default();
+        let syms = parser.find_addr("factorial", &opts).unwrap();
+        assert_eq!(syms.len(), 1);
+        let sym = syms.first().unwrap();
+        assert_eq!(sym.addr, 0x2000100);
+        assert_ne!(sym.size, 0);
+    }
+
+    /// Check that we transparently decompress compressed debug
+    /// sections.
+    #[test]
+    fn compressed_section_reading() {
+        let data_dir = Path::new(&env!("CARGO_MANIFEST_DIR")).join("data");
+        let parser = ElfParser::open(&data_dir.join("test-dwarf-v4.bin")).unwrap();
+        let idx = parser.find_section(".debug_info").unwrap();
+        let expected = parser.section_data(idx).unwrap();
+
+        for bin in [
+            "test-dwarf-zlib.bin",
+            #[cfg(feature = "zstd")]
+            "test-dwarf-zstd.bin",
+            "test-dwarf-zlib-gnu.bin",
+        ] {
+            let parser = ElfParser::open(&data_dir.join(bin)).unwrap();
+            let idx = parser.find_section(".debug_info").unwrap();
+            assert_ne!(parser.read_section_raw(idx).unwrap(), expected);
+            assert_eq!(parser.section_data(idx).unwrap(), expected);
+            assert_eq!(parser.get_section_size(idx).unwrap(), expected.len());
+            // Data should be decompressed only once.
+            let ptr = parser.section_data(idx).unwrap().as_ptr();
+            assert_eq!(parser.section_data(idx).unwrap().as_ptr(), ptr);
+        }
+    }
 }
@@ -0,0 +1,296 @@
+//! Support for relocatable ELF files (e.g., kernel modules).
+//!
+//! All sections of a relocatable ELF file have an address of zero and
+//! symbol values are relative to the section they are defined in.
+//! Similarly, references from DWARF debug information to code or to
+//! other debug sections are only resolved by means of relocations.
+//! To make such files amenable to symbolization, we assign each
+//! allocated section a distinct synthetic address and apply the
+//! relocations targeting non-allocated (i.e., debug) sections.
+
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::mem::size_of;
+
+use crate::log::warn;
+use crate::mmap::Mmap;
+use crate::util::Pod;
+use crate::util::ReadRaw as _;
+
+use super::types::Elf64_Ehdr;
+use super::types::Elf64_Rela;
+use super::types::Elf64_Shdr;
+use super::types::Elf64_Sym;
+use super::types::EI_CLASS;
+use super::types::ELFCLASS64;
+use super::types::EM_AARCH64;
+use super::types::EM_X86_64;
+use super::types::ET_REL;
+use super::types::R_AARCH64_ABS32;
+use super::types::R_AARCH64_ABS64;
+use super::types::R_AARCH64_NONE;
+use super::types::R_X86_64_32;
+use super::types::R_X86_64_32S;
+use super::types::R_X86_64_64;
+use super::types::R_X86_64_NONE;
+use super::types::SHF_ALLOC;
+use super::types::SHF_COMPRESSED;
+use super::types::SHN_LORESERVE;
+use super::types::SHN_UNDEF;
+use super::types::SHT_NOBITS;
+use super::types::SHT_RELA;
+use super::types::SHT_SYMTAB;
+use super::ElfParser;
+
+/// The synthetic address at which we place the first allocated
+/// section.
+const LAYOUT_BASE: u64 = 0x1000;
+/// The offset of `sh_addr` inside of `Elf64_Shdr`.
+const SH_ADDR_OFFSET: usize = 16;
+/// The offset of `st_value` inside of `Elf64_Sym`.
+const ST_VALUE_OFFSET: usize = 8;
+
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+fn read_pod_at<T>(data: &[u8], offset: u64) -> Option<T>
+where
+    T: Pod,
+{
+    let mut data = data.get(usize::try_from(offset).ok()?..)?;
+    data.read_pod::<T>()
+}
+
+fn write_bytes(data: &mut [u8], offset: u64, bytes: &[u8]) -> Result<()> {
+    let offset = usize::try_from(offset).map_err(|_| invalid_data("offset out of bounds"))?;
+    let dst = data
+        .get_mut(offset..offset.saturating_add(bytes.len()))
+        .ok_or_else(|| invalid_data("relocation target out of bounds"))?;
+    let () = dst.copy_from_slice(bytes);
+    Ok(())
+}
+
+/// Determine the number of bytes to write for a relocation of type
+/// `typ`, or `None` if it is not supported.
+fn reloc_size(machine: u16, typ: u32) -> Option<usize> {
+    match (machine, typ) {
+        (EM_X86_64, R_X86_64_NONE) | (EM_AARCH64, R_AARCH64_NONE) => Some(0),
+        (EM_X86_64, R_X86_64_64) | (EM_AARCH64, R_AARCH64_ABS64) => Some(8),
+        (EM_X86_64, R_X86_64_32 | R_X86_64_32S) | (EM_AARCH64, R_AARCH64_ABS32) => Some(4),
+        _ => None,
+    }
+}
+
+/// Assign synthetic addresses to allocated sections of the relocatable
+/// ELF file contained in `data` and apply relocations to its debug
+/// sections.
+///
+/// `None` is returned if `data` does not represent a relocatable 64 bit
+/// ELF file. Otherwise the result is a copy of `data` with all changes
+/// applied.
+pub(crate) fn relocate(data: &[u8]) -> Result<Option<Vec<u8>>> {
+    if data.get(EI_CLASS) != Some(&ELFCLASS64) {
+        return Ok(None)
+    }
+
+    let ehdr = read_pod_at::<Elf64_Ehdr>(data, 0)
+        .ok_or_else(|| invalid_data("failed to read ELF header"))?;
+    if ehdr.e_type != ET_REL {
+        return Ok(None)
+    }
+
+    let shdr_size = usize::from(ehdr.e_shentsize).max(size_of::<Elf64_Shdr>());
+    let shdrs = (0..usize::from(ehdr.e_shnum))
+        .map(|idx| {
+            read_pod_at::<Elf64_Shdr>(data, ehdr.e_shoff + (idx * shdr_size) as u64)
+                .ok_or_else(|| invalid_data("failed to read section header"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut out = data.to_vec();
+
+    // Lay out allocated sections one after the other, honoring their
+    // alignment requirements.
+    let mut addrs = vec![0u64; shdrs.len()];
+    let mut next = LAYOUT_BASE;
+    for (idx, shdr) in shdrs.iter().enumerate() {
+        if shdr.sh_flags & SHF_ALLOC == 0 {
+            continue
+        }
+
+        let align = shdr.sh_addralign.max(1);
+        let addr = (next + align - 1) / align * align;
+        addrs[idx] = addr;
+        next = addr + shdr.sh_size;
+
+        let offset = ehdr.e_shoff + (idx * shdr_size + SH_ADDR_OFFSET) as u64;
+        let () = write_bytes(&mut out, offset, &addr.to_ne_bytes())?;
+    }
+
+    // Adjust symbol values to be absolute, keeping track of them as
+    // they are needed for resolving relocations.
+    let mut symbols = vec![Vec::new(); shdrs.len()];
+    for (idx, shdr) in shdrs.iter().enumerate() {
+        if shdr.sh_type != SHT_SYMTAB {
+            continue
+        }
+
+        let count = shdr.sh_size as usize / size_of::<Elf64_Sym>();
+        let mut values = Vec::with_capacity(count);
+        for sym_idx in 0..count {
+            let offset = shdr.sh_offset + (sym_idx * size_of::<Elf64_Sym>()) as u64;
+            let sym = read_pod_at::<Elf64_Sym>(data, offset)
+                .ok_or_else(|| invalid_data("failed to read symbol"))?;
+            let value = if sym.st_shndx != SHN_UNDEF && sym.st_shndx < SHN_LORESERVE {
+                let base = addrs.get(usize::from(sym.st_shndx)).copied().unwrap_or(0);
+                let value = sym.st_value.wrapping_add(base);
+                let () = write_bytes(
+                    &mut out,
+                    offset + ST_VALUE_OFFSET as u64,
+                    &value.to_ne_bytes(),
+                )?;
+                value
+            } else {
+                sym.st_value
+            };
+            let () = values.push(value);
+        }
+        symbols[idx] = values;
+    }
+
+    let mut unsupported = 0usize;
+    for shdr in shdrs.iter().filter(|shdr| shdr.sh_type == SHT_RELA) {
+        let target = shdrs
+            .get(shdr.sh_info as usize)
+            .ok_or_else(|| invalid_data("relocation section has invalid target"))?;
+        // We only care about debug information. Code is never looked at
+        // and so we do not bother relocating it.
+        if target.sh_flags & SHF_ALLOC != 0 || target.sh_type == SHT_NOBITS {
+            continue
+        }
+        if target.sh_flags & SHF_COMPRESSED != 0 {
+            warn!("unable to apply relocations to compressed section; ignoring...");
+            continue
+        }
+
+        let values = symbols
+            .get(shdr.sh_link as usize)
+            .ok_or_else(|| invalid_data("relocation section has invalid symbol table"))?;
+        let count = shdr.sh_size as usize / size_of::<Elf64_Rela>();
+        for rela_idx in 0..count {
+            let offset = shdr.sh_offset + (rela_idx * size_of::<Elf64_Rela>()) as u64;
+            let rela = read_pod_at::<Elf64_Rela>(data, offset)
+                .ok_or_else(|| invalid_data("failed to read relocation"))?;
+            let sym_idx = (rela.r_info >> 32) as usize;
+            let typ = rela.r_info as u32;
+
+            let size = if let Some(size) = reloc_size(ehdr.e_machine, typ) {
+                size
+            } else {
+                unsupported += 1;
+                continue
+            };
+            let value = values
+                .get(sym_idx)
+                .copied()
+                .unwrap_or(0)
+                .wrapping_add(rela.r_addend as u64);
+            let offset = target.sh_offset + rela.r_offset;
+            let () = write_bytes(&mut out, offset, &value.to_ne_bytes()[..size])?;
+        }
+    }
+
+    if unsupported > 0 {
+        warn!(
+            "encountered {unsupported} unsupported relocations for machine {}; debug information may be inaccurate",
+            ehdr.e_machine
+        );
+    }
+    Ok(Some(out))
+}
+
+/// Relocate the ELF file represented by `parser` if it is a relocatable
+/// one, returning `parser` itself otherwise. See [`relocate`].
+pub(crate) fn relocate_parser(parser: ElfParser) -> Result<ElfParser> {
+    if let Some(data) = relocate(parser.data())? {
+        let mmap = Mmap::from_bytes(&data)?;
+        Ok(ElfParser::from_mmap(mmap))
+    } else {
+        Ok(parser)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::read as read_file;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use test_log::test;
+
+    use crate::dwarf::DwarfResolver;
+    use crate::elf::types::STT_FUNC;
+    use crate::inspect::FindAddrOpts;
+
+
+    /// Check that non-relocatable ELF files are left alone.
+    #[test]
+    fn relocate_non_relocatable() {
+        let path = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses.bin");
+        let data = read_file(path).unwrap();
+        assert_eq!(relocate(&data).unwrap(), None);
+    }
+
+    /// Check that we can symbolize addresses in a relocated object
+    /// file.
+    #[test]
+    fn relocate_object() {
+        let path = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-kmod.ko");
+        let data = read_file(path).unwrap();
+        assert!(relocate(&data).unwrap().is_some());
+        let mmap = Mmap::from_bytes(&data).unwrap();
+        let parser = relocate_parser(ElfParser::from_mmap(mmap)).unwrap();
+        let parser = Arc::new(parser);
+
+        let addr_of = |name| {
+            let syms = parser.find_addr(name, &FindAddrOpts::default()).unwrap();
+            assert_eq!(syms.len(), 1);
+            syms[0].addr
+        };
+        let add = addr_of("kmod_add");
+        let work = addr_of("kmod_work");
+        let init = addr_of("kmod_init");
+        assert!(add >= LAYOUT_BASE as usize);
+        assert!(add < work);
+        assert_ne!(init, add);
+        assert_ne!(init, work);
+
+        assert_eq!(
+            parser.find_symbol(init + 1, STT_FUNC).unwrap(),
+            ("kmod_init", init)
+        );
+        assert_eq!(
+            parser.find_symbol(work + 1, STT_FUNC).unwrap(),
+            ("kmod_work", work)
+        );
+
+        let dwarf = DwarfResolver::from_parser_for_addresses(parser, &[], true, false).unwrap();
+        let (_dir, file, line) = dwarf.find_line(add).unwrap();
+        assert_eq!(file, "test-kmod.c");
+        assert_eq!(line, 10);
+        let (_dir, file, line) = dwarf.find_line(init).unwrap();
+        assert_eq!(file, "test-kmod.c");
+        assert_eq!(line, 21);
+    }
+}
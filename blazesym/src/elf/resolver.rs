@@ -9,6 +9,7 @@ use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
 use crate::log::warn;
 use crate::symbolize::AddrLineInfo;
+use crate::symbolize::InlinedFn;
 use crate::Addr;
 use crate::SymResolver;
 
@@ -39,7 +40,7 @@ impl ElfResolver {
         })
     }
 
-    fn get_parser(&self) -> &ElfParser {
+    pub(crate) fn get_parser(&self) -> &ElfParser {
         match &self.backend {
             ElfBackend::Dwarf(dwarf) => dwarf.get_parser(),
             ElfBackend::Elf(parser) => parser,
@@ -56,6 +57,15 @@ impl SymResolver for ElfResolver {
                 vec![(name, start_addr)]
             }
             Err(err) => {
+                // The symbol table may be incomplete or have been
+                // stripped. Fall back to the symbols described by DWARF
+                // debug information, if available.
+                if let ElfBackend::Dwarf(dwarf) = &self.backend {
+                    match dwarf.find_symbols(addr) {
+                        Ok(syms) if !syms.is_empty() => return syms,
+                        _ => (),
+                    }
+                }
                 warn!("no symbol found for address 0x{addr:x}: {err}");
                 vec![]
             }
@@ -83,6 +93,20 @@ impl SymResolver for ElfResolver {
         }
     }
 
+    fn find_inlined_fns(&self, addr: Addr) -> Vec<InlinedFn> {
+        if let ElfBackend::Dwarf(dwarf) = &self.backend {
+            match dwarf.find_inlined_fns(addr) {
+                Ok(inlined) => inlined,
+                Err(err) => {
+                    warn!("failed to find inlined functions for address 0x{addr:x}: {err}");
+                    vec![]
+                }
+            }
+        } else {
+            vec![]
+        }
+    }
+
     /// Find the file offset of the symbol at address `addr`.
     // TODO: See if we could make this a constant time calculation by supplying
     //       the ELF symbol index (and potentially an offset from it) [this will
@@ -122,7 +146,7 @@ mod tests {
     use super::*;
 
     use std::path::Path;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
 
     /// Check that we fail finding an offset for an address not
@@ -133,7 +157,7 @@ mod tests {
             .join("data")
             .join("test-dwarf.bin");
         let elf = ElfParser::open(&path).unwrap();
-        let backend = ElfBackend::Elf(Rc::new(elf));
+        let backend = ElfBackend::Elf(Arc::new(elf));
         let resolver = ElfResolver::with_backend(&path, backend).unwrap();
 
         assert_eq!(resolver.addr_file_off(0x0), None);
@@ -1,14 +1,28 @@
 const EI_NIDENT: usize = 16;
 
+pub(crate) const EI_CLASS: usize = 4;
+
+pub(crate) const ELFCLASS32: u8 = 1;
+pub(crate) const ELFCLASS64: u8 = 2;
+
+type Elf32_Addr = u32;
+type Elf32_Half = u16;
+type Elf32_Off = u32;
+type Elf32_Word = u32;
+
 type Elf64_Addr = u64;
 type Elf64_Half = u16;
 type Elf64_Off = u64;
 type Elf64_Word = u32;
 type Elf64_Xword = u64;
 
+pub(crate) const ET_REL: u16 = 1;
 pub(crate) const ET_EXEC: u16 = 2;
 pub(crate) const ET_DYN: u16 = 3;
 
+pub(crate) const EM_X86_64: u16 = 62;
+pub(crate) const EM_AARCH64: u16 = 183;
+
 #[repr(C)]
 pub(crate) struct Elf64_Ehdr {
     pub e_ident: [u8; EI_NIDENT], /* ELF "magic number" */
@@ -30,7 +44,50 @@ pub(crate) struct Elf64_Ehdr {
 // SAFETY: `Elf64_Ehdr` is valid for any bit pattern.
 unsafe impl crate::util::Pod for Elf64_Ehdr {}
 
+#[repr(C)]
+pub(crate) struct Elf32_Ehdr {
+    pub e_ident: [u8; EI_NIDENT],
+    pub e_type: Elf32_Half,
+    pub e_machine: Elf32_Half,
+    pub e_version: Elf32_Word,
+    pub e_entry: Elf32_Addr,
+    pub e_phoff: Elf32_Off,
+    pub e_shoff: Elf32_Off,
+    pub e_flags: Elf32_Word,
+    pub e_ehsize: Elf32_Half,
+    pub e_phentsize: Elf32_Half,
+    pub e_phnum: Elf32_Half,
+    pub e_shentsize: Elf32_Half,
+    pub e_shnum: Elf32_Half,
+    pub e_shstrndx: Elf32_Half,
+}
+
+// SAFETY: `Elf32_Ehdr` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf32_Ehdr {}
+
+impl From<&Elf32_Ehdr> for Elf64_Ehdr {
+    fn from(ehdr: &Elf32_Ehdr) -> Self {
+        Self {
+            e_ident: ehdr.e_ident,
+            e_type: ehdr.e_type,
+            e_machine: ehdr.e_machine,
+            e_version: ehdr.e_version,
+            e_entry: ehdr.e_entry.into(),
+            e_phoff: ehdr.e_phoff.into(),
+            e_shoff: ehdr.e_shoff.into(),
+            e_flags: ehdr.e_flags,
+            e_ehsize: ehdr.e_ehsize,
+            e_phentsize: ehdr.e_phentsize,
+            e_phnum: ehdr.e_phnum,
+            e_shentsize: ehdr.e_shentsize,
+            e_shnum: ehdr.e_shnum,
+            e_shstrndx: ehdr.e_shstrndx,
+        }
+    }
+}
+
 pub(crate) const PT_LOAD: u32 = 1;
+pub(crate) const PT_NOTE: u32 = 4;
 
 #[repr(C)]
 pub(crate) struct Elf64_Phdr {
@@ -47,6 +104,36 @@ pub(crate) struct Elf64_Phdr {
 // SAFETY: `Elf64_Phdr` is valid for any bit pattern.
 unsafe impl crate::util::Pod for Elf64_Phdr {}
 
+#[repr(C)]
+pub(crate) struct Elf32_Phdr {
+    pub p_type: Elf32_Word,
+    pub p_offset: Elf32_Off,
+    pub p_vaddr: Elf32_Addr,
+    pub p_paddr: Elf32_Addr,
+    pub p_filesz: Elf32_Word,
+    pub p_memsz: Elf32_Word,
+    pub p_flags: Elf32_Word,
+    pub p_align: Elf32_Word,
+}
+
+// SAFETY: `Elf32_Phdr` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf32_Phdr {}
+
+impl From<&Elf32_Phdr> for Elf64_Phdr {
+    fn from(phdr: &Elf32_Phdr) -> Self {
+        Self {
+            p_type: phdr.p_type,
+            p_flags: phdr.p_flags,
+            p_offset: phdr.p_offset.into(),
+            p_vaddr: phdr.p_vaddr.into(),
+            p_paddr: phdr.p_paddr.into(),
+            p_filesz: phdr.p_filesz.into(),
+            p_memsz: phdr.p_memsz.into(),
+            p_align: phdr.p_align.into(),
+        }
+    }
+}
+
 pub(crate) const PF_X: Elf64_Word = 1;
 
 #[repr(C)]
@@ -66,9 +153,86 @@ pub(crate) struct Elf64_Shdr {
 // SAFETY: `Elf64_Shdr` is valid for any bit pattern.
 unsafe impl crate::util::Pod for Elf64_Shdr {}
 
+#[repr(C)]
+pub(crate) struct Elf32_Shdr {
+    pub sh_name: Elf32_Word,
+    pub sh_type: Elf32_Word,
+    pub sh_flags: Elf32_Word,
+    pub sh_addr: Elf32_Addr,
+    pub sh_offset: Elf32_Off,
+    pub sh_size: Elf32_Word,
+    pub sh_link: Elf32_Word,
+    pub sh_info: Elf32_Word,
+    pub sh_addralign: Elf32_Word,
+    pub sh_entsize: Elf32_Word,
+}
+
+// SAFETY: `Elf32_Shdr` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf32_Shdr {}
+
+impl From<&Elf32_Shdr> for Elf64_Shdr {
+    fn from(shdr: &Elf32_Shdr) -> Self {
+        Self {
+            sh_name: shdr.sh_name,
+            sh_type: shdr.sh_type,
+            sh_flags: shdr.sh_flags.into(),
+            sh_addr: shdr.sh_addr.into(),
+            sh_offset: shdr.sh_offset.into(),
+            sh_size: shdr.sh_size.into(),
+            sh_link: shdr.sh_link,
+            sh_info: shdr.sh_info,
+            sh_addralign: shdr.sh_addralign.into(),
+            sh_entsize: shdr.sh_entsize.into(),
+        }
+    }
+}
+
+pub(crate) const SHF_ALLOC: Elf64_Xword = 0x2;
+pub(crate) const SHF_EXECINSTR: Elf64_Xword = 0x4;
+pub(crate) const SHF_COMPRESSED: Elf64_Xword = 0x800;
+
+pub(crate) const ELFCOMPRESS_ZLIB: Elf64_Word = 1;
+pub(crate) const ELFCOMPRESS_ZSTD: Elf64_Word = 2;
+
+#[repr(C)]
+pub(crate) struct Elf64_Chdr {
+    pub ch_type: Elf64_Word, /* Compression format */
+    pub ch_reserved: Elf64_Word,
+    pub ch_size: Elf64_Xword,      /* Uncompressed data size */
+    pub ch_addralign: Elf64_Xword, /* Uncompressed data alignment */
+}
+
+// SAFETY: `Elf64_Chdr` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf64_Chdr {}
+
+#[repr(C)]
+pub(crate) struct Elf32_Chdr {
+    pub ch_type: Elf32_Word,
+    pub ch_size: Elf32_Word,
+    pub ch_addralign: Elf32_Word,
+}
+
+// SAFETY: `Elf32_Chdr` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf32_Chdr {}
+
+impl From<&Elf32_Chdr> for Elf64_Chdr {
+    fn from(chdr: &Elf32_Chdr) -> Self {
+        Self {
+            ch_type: chdr.ch_type,
+            ch_reserved: 0,
+            ch_size: chdr.ch_size.into(),
+            ch_addralign: chdr.ch_addralign.into(),
+        }
+    }
+}
+
 pub(crate) const SHN_UNDEF: u16 = 0;
+pub(crate) const SHN_LORESERVE: u16 = 0xff00;
 
+pub(crate) const SHT_SYMTAB: Elf64_Word = 2;
+pub(crate) const SHT_RELA: Elf64_Word = 4;
 pub(crate) const SHT_NOTE: Elf64_Word = 7;
+pub(crate) const SHT_NOBITS: Elf64_Word = 8;
 
 pub(crate) const STT_FUNC: u8 = 2;
 
@@ -86,6 +250,53 @@ pub(crate) struct Elf64_Sym {
 // SAFETY: `Elf64_Sym` is valid for any bit pattern.
 unsafe impl crate::util::Pod for Elf64_Sym {}
 
+#[repr(C)]
+pub(crate) struct Elf32_Sym {
+    pub st_name: Elf32_Word,
+    pub st_value: Elf32_Addr,
+    pub st_size: Elf32_Word,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: Elf32_Half,
+}
+
+// SAFETY: `Elf32_Sym` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf32_Sym {}
+
+impl From<&Elf32_Sym> for Elf64_Sym {
+    fn from(sym: &Elf32_Sym) -> Self {
+        Self {
+            st_name: sym.st_name,
+            st_info: sym.st_info,
+            st_other: sym.st_other,
+            st_shndx: sym.st_shndx,
+            st_value: sym.st_value.into(),
+            st_size: sym.st_size.into(),
+        }
+    }
+}
+
+#[repr(C)]
+pub(crate) struct Elf64_Rela {
+    pub r_offset: Elf64_Addr, /* Location at which to apply the action */
+    pub r_info: Elf64_Xword,  /* Index and type of relocation */
+    pub r_addend: i64,        /* Constant addend used to compute value */
+}
+
+// SAFETY: `Elf64_Rela` is valid for any bit pattern.
+unsafe impl crate::util::Pod for Elf64_Rela {}
+
+pub(crate) const R_X86_64_NONE: u32 = 0;
+pub(crate) const R_X86_64_64: u32 = 1;
+pub(crate) const R_X86_64_32: u32 = 10;
+pub(crate) const R_X86_64_32S: u32 = 11;
+pub(crate) const R_AARCH64_NONE: u32 = 0;
+pub(crate) const R_AARCH64_ABS64: u32 = 257;
+pub(crate) const R_AARCH64_ABS32: u32 = 258;
+
+/// The header of an ELF note.
+///
+/// Note headers have the same layout in 32 bit and 64 bit ELF files.
 #[repr(C)]
 pub(crate) struct Elf64_Nhdr {
     pub n_namesz: Elf64_Word,
@@ -95,3 +306,6 @@ pub(crate) struct Elf64_Nhdr {
 
 // SAFETY: `Elf64_Nhdr` is valid for any bit pattern.
 unsafe impl crate::util::Pod for Elf64_Nhdr {}
+
+/// The note type of a GNU build ID note.
+pub(crate) const NT_GNU_BUILD_ID: Elf64_Word = 3;
@@ -0,0 +1,170 @@
+//! Decoding of GSYM inline information.
+//!
+//! An [`AddrData`][super::types::AddrData] entry of type
+//! [`InfoTypeInlineInfo`][super::types::InfoTypeInlineInfo] encodes a
+//! tree of [`InlineInfo`] objects. The root object represents the
+//! concrete function itself, while its (transitive) children describe
+//! functions that got inlined into it.
+//!
+//! Each object is encoded as follows:
+//! - the number of address ranges (ULEB128); zero terminates a list of
+//!   children
+//! - for each range, its offset relative to the parent's first range
+//!   start address (or to the function's address, for the root) and its
+//!   size (both ULEB128)
+//! - a flag indicating whether children follow (u8)
+//! - the name, as an offset into the string table (u32)
+//! - the call file, as an index into the file table (ULEB128)
+//! - the call line (ULEB128)
+//! - the children, if any, followed by a terminating object without
+//!   any ranges
+
+use std::ops::Range;
+
+use crate::util::ReadRaw as _;
+use crate::Addr;
+
+
+/// A single (potentially inlined) function in a GSYM inline info tree.
+#[derive(Debug)]
+pub struct InlineInfo {
+    /// The address ranges covered by the function.
+    pub ranges: Vec<Range<Addr>>,
+    /// The function's name, as an offset into the string table.
+    pub name: u32,
+    /// The index of the file the function was called from, as an index
+    /// into the file table.
+    pub call_file: u32,
+    /// The line number the function was called from.
+    pub call_line: u32,
+    /// Functions inlined into this one.
+    pub children: Vec<InlineInfo>,
+}
+
+impl InlineInfo {
+    /// Parse an [`InlineInfo`] tree from `data`.
+    ///
+    /// `base_addr` is the address that range offsets are relative to. For
+    /// the root of the tree it is the start address of the function.
+    ///
+    /// `None` is returned if the data are malformed. `Some(None)`
+    /// indicates the terminator of a list of children.
+    pub fn parse(data: &mut &[u8], base_addr: Addr) -> Option<Option<InlineInfo>> {
+        let (range_cnt, _bytes) = data.read_u128_leb128()?;
+        if range_cnt == 0 {
+            return Some(None)
+        }
+
+        let mut ranges = Vec::with_capacity(range_cnt as usize);
+        for _ in 0..range_cnt {
+            let (offset, _bytes) = data.read_u128_leb128()?;
+            let (size, _bytes) = data.read_u128_leb128()?;
+            let start = base_addr.checked_add(Addr::try_from(offset).ok()?)?;
+            let end = start.checked_add(Addr::try_from(size).ok()?)?;
+            let () = ranges.push(start..end);
+        }
+
+        let has_children = data.read_u8()? != 0;
+        let name = data.read_u32()?;
+        let (call_file, _bytes) = data.read_u128_leb128()?;
+        let (call_line, _bytes) = data.read_u128_leb128()?;
+
+        let mut children = Vec::new();
+        if has_children {
+            // Children's ranges are relative to the start of our first
+            // range.
+            let child_base_addr = ranges[0].start;
+            while let Some(child) = Self::parse(data, child_base_addr)? {
+                let () = children.push(child);
+            }
+        }
+
+        let info = InlineInfo {
+            ranges,
+            name,
+            call_file: call_file as u32,
+            call_line: call_line as u32,
+            children,
+        };
+        Some(Some(info))
+    }
+
+    /// Find the chain of functions inlined at `addr`.
+    ///
+    /// The function represented by `self` is considered the concrete
+    /// function and not included. Inlined functions are reported from the
+    /// outermost to the innermost one.
+    pub fn inline_stack(&self, addr: Addr) -> Vec<&InlineInfo> {
+        let mut stack = Vec::new();
+        if !self.ranges.iter().any(|range| range.contains(&addr)) {
+            return stack
+        }
+
+        let mut info = self;
+        while let Some(child) = info
+            .children
+            .iter()
+            .find(|child| child.ranges.iter().any(|range| range.contains(&addr)))
+        {
+            let () = stack.push(child);
+            info = child;
+        }
+        stack
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+
+    /// Check that we can parse and query a manually encoded inline info
+    /// tree.
+    #[test]
+    fn parse_inline_info() {
+        #[rustfmt::skip]
+        let data = [
+            // root: one range [0x1000, 0x1040)
+            0x01, 0x00, 0x40,
+            // has children, name, call file, call line
+            0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // child: one range [0x1010, 0x1030)
+            0x01, 0x10, 0x20,
+            0x01, 0x02, 0x00, 0x00, 0x00, 0x01, 0x17,
+            // grandchild: one range [0x1018, 0x1020)
+            0x01, 0x08, 0x08,
+            0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x11,
+            // end of child's children
+            0x00,
+            // end of root's children
+            0x00,
+        ];
+        let mut slice = data.as_slice();
+        let info = InlineInfo::parse(&mut slice, 0x1000).unwrap().unwrap();
+        assert!(slice.is_empty());
+        assert_eq!(info.ranges, vec![0x1000..0x1040]);
+        assert_eq!(info.name, 1);
+        assert_eq!(info.children.len(), 1);
+
+        let child = &info.children[0];
+        assert_eq!(child.ranges, vec![0x1010..0x1030]);
+        assert_eq!(child.call_line, 0x17);
+        assert_eq!(child.children[0].ranges, vec![0x1018..0x1020]);
+
+        assert!(info.inline_stack(0x1000).is_empty());
+        assert!(info.inline_stack(0x1040).is_empty());
+
+        let names = |addr| {
+            info.inline_stack(addr)
+                .iter()
+                .map(|info| info.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(0x1010), vec![2]);
+        assert_eq!(names(0x1018), vec![2, 3]);
+        assert_eq!(names(0x1020), vec![2]);
+    }
+}
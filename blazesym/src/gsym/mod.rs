@@ -1,6 +1,16 @@
+//! Functionality for working with Gsym files.
+//!
+//! Gsym files can be used for symbolization via
+//! [`symbolize::Source::Gsym`][crate::symbolize::Source::Gsym] and
+//! inspection via [`inspect::Source::Gsym`][crate::inspect::Source::Gsym].
+//! This module provides means for creating them.
+
+mod inline;
 mod linetab;
 mod parser;
 mod resolver;
 mod types;
+mod writer;
 
-pub use resolver::GsymResolver;
+pub(crate) use resolver::GsymResolver;
+pub use writer::Writer;
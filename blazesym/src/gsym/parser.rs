@@ -178,6 +178,12 @@ impl GsymContext<'_> {
         }
     }
 
+    /// Get the number of entries in the Address Table.
+    #[inline]
+    pub fn num_addrs(&self) -> usize {
+        self.header.num_addrs as usize
+    }
+
     /// Get the address of an entry in the Address Table.
     pub fn addr_at(&self, idx: usize) -> Option<Addr> {
         let addr_off_size = self.header.addr_off_size as usize;
@@ -7,25 +7,34 @@ use std::io::Read as _;
 use std::mem;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
+use crate::inspect::SymType;
 use crate::symbolize::AddrLineInfo;
+use crate::symbolize::InlinedFn;
 use crate::Addr;
 use crate::SymResolver;
 
+use super::inline::InlineInfo;
 use super::linetab::run_op;
 use super::linetab::LineTableRow;
 use super::linetab::RunResult;
 use super::parser::parse_address_data;
 use super::parser::parse_line_table_header;
 use super::parser::GsymContext;
+use super::types::AddrInfo;
+use super::types::InfoTypeInlineInfo;
 use super::types::InfoTypeLineTableInfo;
 
 /// The symbol resolver for the GSYM format.
 pub struct GsymResolver {
     file_name: PathBuf,
     ctx: GsymContext<'static>,
+    /// Indices into the Address Table, sorted by the name of the
+    /// respective symbol. Computed lazily on the first lookup by name.
+    by_name: Mutex<Vec<usize>>,
     _data: Vec<u8>,
 }
 
@@ -42,9 +51,60 @@ impl GsymResolver {
             // owned by the object.  So, it is safe to strip the
             // lifetime of ctx.
             ctx: unsafe { mem::transmute(ctx) },
+            by_name: Mutex::default(),
             _data: data,
         })
     }
+
+    /// Retrieve the name of the symbol at the given index in the Address
+    /// Table.
+    fn name_at(&self, idx: usize) -> Option<&str> {
+        let info = self.ctx.addr_info(idx)?;
+        self.ctx.get_str(info.name as usize)
+    }
+
+    /// Find the indices of all Address Table entries for symbols with the
+    /// given name.
+    fn find_indices_by_name(&self, name: &str) -> Vec<usize> {
+        let mut by_name = self.by_name.lock().unwrap();
+        if by_name.is_empty() {
+            let mut indices = (0..self.ctx.num_addrs())
+                .filter(|idx| self.name_at(*idx).is_some())
+                .collect::<Vec<_>>();
+            let () = indices.sort_by_key(|idx| self.name_at(*idx));
+            *by_name = indices;
+        }
+
+        let start = by_name.partition_point(|idx| self.name_at(*idx) < Some(name));
+        by_name[start..]
+            .iter()
+            .take_while(|idx| self.name_at(**idx) == Some(name))
+            .copied()
+            .collect()
+    }
+
+    /// Find the [`AddrInfo`] of the function containing `addr`, along with
+    /// the function's start address.
+    fn find_addr_info(&self, addr: Addr) -> Option<(Addr, AddrInfo<'_>)> {
+        let idx = self.ctx.find_addr(addr)?;
+        let symaddr = self.ctx.addr_at(idx)?;
+        if addr < symaddr {
+            return None
+        }
+        let addrinfo = self.ctx.addr_info(idx)?;
+        if addr >= (symaddr + addrinfo.size as Addr) {
+            return None
+        }
+        Some((symaddr, addrinfo))
+    }
+
+    /// Construct the path of the file identified by `file_idx`.
+    fn file_path(&self, file_idx: u32) -> Option<PathBuf> {
+        let finfo = self.ctx.file_info(file_idx as usize)?;
+        let dirname = self.ctx.get_str(finfo.directory as usize)?;
+        let filename = self.ctx.get_str(finfo.filename as usize)?;
+        Some(Path::new(dirname).join(filename))
+    }
 }
 
 impl SymResolver for GsymResolver {
@@ -66,10 +126,34 @@ impl SymResolver for GsymResolver {
         find_addr_impl(self, addr).unwrap_or_default()
     }
 
-    fn find_addr(&self, _name: &str, _opts: &FindAddrOpts) -> Option<Vec<SymInfo>> {
-        // It is inefficient to find the address of a symbol with
-        // GSYM.  We may support it in the future if needed.
-        None
+    fn find_addr(&self, name: &str, opts: &FindAddrOpts) -> Option<Vec<SymInfo>> {
+        // Gsym only contains information about functions.
+        if let SymType::Variable = opts.sym_type {
+            return None
+        }
+
+        let syms = self
+            .find_indices_by_name(name)
+            .into_iter()
+            .filter_map(|idx| {
+                let addr = self.ctx.addr_at(idx)?;
+                let info = self.ctx.addr_info(idx)?;
+                let size = info.size as usize;
+                let range = addr..addr + size;
+                let sym = SymInfo {
+                    name: name.to_string(),
+                    addr,
+                    size,
+                    ranges: vec![range],
+                    sym_type: SymType::Function,
+                    file_offset: 0,
+                    obj_file_name: None,
+                    module: None,
+                };
+                Some(sym)
+            })
+            .collect();
+        Some(syms)
     }
 
     /// Finds the source code location for a given address.
@@ -87,16 +171,7 @@ impl SymResolver for GsymResolver {
     ///
     /// The `AddrLineInfo` corresponding to the address or `None`.
     fn find_line_info(&self, addr: Addr) -> Option<AddrLineInfo> {
-        let idx = self.ctx.find_addr(addr)?;
-        let symaddr = self.ctx.addr_at(idx)?;
-        if addr < symaddr {
-            return None
-        }
-        let addrinfo = self.ctx.addr_info(idx)?;
-        if addr >= (symaddr + addrinfo.size as Addr) {
-            return None
-        }
-
+        let (symaddr, addrinfo) = self.find_addr_info(addr)?;
         let addrdatas = parse_address_data(addrinfo.data)?;
         for adr_ent in addrdatas {
             if adr_ent.typ != InfoTypeLineTableInfo {
@@ -134,10 +209,7 @@ impl SymResolver for GsymResolver {
                 continue
             }
 
-            let finfo = self.ctx.file_info(lntab_row.file_idx as usize)?;
-            let dirname = self.ctx.get_str(finfo.directory as usize)?;
-            let filename = self.ctx.get_str(finfo.filename as usize)?;
-            let path = Path::new(dirname).join(filename);
+            let path = self.file_path(lntab_row.file_idx)?;
             return Some(AddrLineInfo {
                 path,
                 line: lntab_row.file_line as usize,
@@ -147,6 +219,32 @@ impl SymResolver for GsymResolver {
         None
     }
 
+    fn find_inlined_fns(&self, addr: Addr) -> Vec<InlinedFn> {
+        fn find_inlined_fns_impl(gsym: &GsymResolver, addr: Addr) -> Option<Vec<InlinedFn>> {
+            let (symaddr, addrinfo) = gsym.find_addr_info(addr)?;
+            let addrdata = parse_address_data(addrinfo.data)?
+                .into_iter()
+                .find(|addrdata| addrdata.typ == InfoTypeInlineInfo)?;
+
+            let mut data = addrdata.data;
+            let root = InlineInfo::parse(&mut data, symaddr)??;
+            root.inline_stack(addr)
+                .into_iter()
+                .map(|info| {
+                    let inlined = InlinedFn {
+                        name: gsym.ctx.get_str(info.name as usize)?.to_string(),
+                        call_path: gsym.file_path(info.call_file).unwrap_or_default(),
+                        call_line: info.call_line as usize,
+                        call_column: 0,
+                    };
+                    Some(inlined)
+                })
+                .collect()
+        }
+
+        find_inlined_fns_impl(self, addr).unwrap_or_default()
+    }
+
     fn addr_file_off(&self, _addr: Addr) -> Option<u64> {
         // Unavailable
         None
@@ -172,6 +270,8 @@ mod tests {
 
     use test_log::test;
 
+    use crate::elf::ElfParser;
+
     /// Make sure that we can find file line information for a function, if available.
     #[test]
     fn test_find_line_info() {
@@ -192,4 +292,67 @@ mod tests {
         assert_eq!(info.line, 8);
         assert!(info.path.ends_with("test-stable-addresses.c"));
     }
+
+    /// Check that we can look up symbols by name.
+    #[test]
+    fn find_addr_by_name() {
+        let test_gsym = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test.gsym");
+        let resolver = GsymResolver::new(test_gsym).unwrap();
+        let opts = FindAddrOpts::default();
+
+        let syms = resolver.find_addr("factorial", &opts).unwrap();
+        assert_eq!(syms.len(), 1);
+        let sym = &syms[0];
+        assert_eq!(sym.name, "factorial");
+        assert_eq!(sym.addr, 0x2000100);
+        assert_ne!(sym.size, 0);
+
+        let syms = resolver.find_addr("main", &opts).unwrap();
+        assert_eq!(syms.len(), 1);
+        assert_eq!(syms[0].addr, 0x2000000);
+
+        let syms = resolver.find_addr("does_not_exist", &opts).unwrap();
+        assert!(syms.is_empty());
+
+        let opts = FindAddrOpts {
+            sym_type: SymType::Variable,
+            ..Default::default()
+        };
+        assert!(resolver.find_addr("factorial", &opts).is_none());
+    }
+
+    /// Check that we report functions inlined at an address.
+    #[test]
+    fn find_inlined_fns() {
+        let data_dir = Path::new(&env!("CARGO_MANIFEST_DIR")).join("data");
+        let parser = ElfParser::open(&data_dir.join("test-inlined-dwarf-v4.bin")).unwrap();
+        let opts = FindAddrOpts {
+            sym_type: SymType::Function,
+            ..Default::default()
+        };
+        let main = parser.find_addr("main", &opts).unwrap().remove(0);
+
+        let resolver = GsymResolver::new(data_dir.join("test-inlined.gsym")).unwrap();
+        assert_eq!(resolver.find_symbols(main.addr), vec![("main", main.addr)]);
+
+        // Check all addresses of `main` and make sure that we find the
+        // deepest chain of inlined functions somewhere.
+        let inlined = (main.addr..main.addr + main.size)
+            .map(|addr| resolver.find_inlined_fns(addr))
+            .max_by_key(|inlined| inlined.len())
+            .unwrap();
+        let names = inlined
+            .iter()
+            .map(|inlined| inlined.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["inlined_outer", "inlined_call"]);
+        let lines = inlined
+            .iter()
+            .map(|inlined| inlined.call_line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [23, 17]);
+        assert!(inlined[0].call_path.ends_with("test-inlined.c"));
+    }
 }
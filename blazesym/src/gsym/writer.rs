@@ -0,0 +1,539 @@
+//! Creation of standalone GSYM files.
+//!
+//! The generated files have the layout described in
+//! [`parser`][super::parser]:
+//!
+//! * Header
+//! * Address Table
+//! * Address Data Offset Table
+//! * File Table
+//! * String Table
+//! * Address Data
+//!
+//! Each item of the Address Data is aligned to four bytes and contains
+//! the size and name of a function, optionally followed by an
+//! [`InfoTypeLineTableInfo`] entry describing its line table.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::io::Write;
+use std::mem::size_of;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::dwarf::DwarfResolver;
+use crate::elf::find_debug_file;
+use crate::elf::ElfParser;
+use crate::elf::DEFAULT_DEBUG_DIRS;
+use crate::normalize::read_elf_build_id;
+use crate::Addr;
+
+use super::types::InfoTypeEndOfList;
+use super::types::InfoTypeLineTableInfo;
+use super::types::GSYM_MAGIC;
+use super::types::GSYM_VERSION;
+
+/// The size of the GSYM header, in bytes.
+const HEADER_SIZE: usize = 48;
+/// The maximum size of the UUID stored in the header.
+const MAX_UUID_SIZE: usize = 20;
+/// The maximum difference between the smallest and the largest line
+/// delta that can be encoded using special opcodes.
+const MAX_LINE_RANGE: i64 = 14;
+
+/// Line table opcodes; see [`linetab`][super::linetab].
+const END_SEQUENCE: u8 = 0x00;
+const SET_FILE: u8 = 0x01;
+const ADVANCE_PC: u8 = 0x02;
+const ADVANCE_LINE: u8 = 0x03;
+const FIRST_SPECIAL: u8 = 0x04;
+
+
+fn write_uleb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            let () = out.push(byte);
+            break
+        }
+        let () = out.push(byte | 0x80);
+    }
+}
+
+fn write_sleb128(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            let () = out.push(byte);
+            break
+        }
+        let () = out.push(byte | 0x80);
+    }
+}
+
+fn align(out: &mut Vec<u8>, align_to: usize) {
+    let len = (out.len() + align_to - 1) / align_to * align_to;
+    let () = out.resize(len, 0);
+}
+
+
+/// A row of a line table: an address along with its file index and
+/// line number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LineRow {
+    addr: Addr,
+    file_idx: u32,
+    line: u32,
+}
+
+/// Encode the line table of a function starting at `base_addr`.
+///
+/// `rows` is expected to be sorted by address, with no address being
+/// smaller than `base_addr`.
+fn encode_line_table(rows: &[LineRow], base_addr: Addr) -> Vec<u8> {
+    let deltas = rows
+        .windows(2)
+        .map(|rows| i64::from(rows[1].line) - i64::from(rows[0].line));
+    let mut min_delta = deltas.clone().min().unwrap_or(0);
+    let mut max_delta = deltas.max().unwrap_or(0);
+    // Restrict the range of line deltas, so that special opcodes remain
+    // usable for small address advances. Deltas outside of the range are
+    // encoded using dedicated opcodes.
+    if max_delta - min_delta > MAX_LINE_RANGE {
+        min_delta = min_delta.max(-4);
+        max_delta = max_delta.min(min_delta + MAX_LINE_RANGE);
+    }
+    let line_range = max_delta - min_delta + 1;
+
+    let mut out = Vec::new();
+    let first_line = rows.first().map(|row| row.line).unwrap_or(0);
+    let () = write_sleb128(&mut out, min_delta);
+    let () = write_sleb128(&mut out, max_delta);
+    let () = write_uleb128(&mut out, first_line.into());
+
+    let mut prev = LineRow {
+        addr: base_addr,
+        file_idx: 1,
+        line: first_line,
+    };
+    for row in rows {
+        if row.file_idx != prev.file_idx {
+            let () = out.push(SET_FILE);
+            let () = write_uleb128(&mut out, row.file_idx.into());
+        }
+
+        let line_delta = i64::from(row.line) - i64::from(prev.line);
+        let addr_delta = (row.addr - prev.addr) as i64;
+        let special = (line_delta - min_delta) + addr_delta * line_range + i64::from(FIRST_SPECIAL);
+        if (min_delta..=max_delta).contains(&line_delta) && special <= i64::from(u8::MAX) {
+            let () = out.push(special as u8);
+        } else {
+            if line_delta != 0 {
+                let () = out.push(ADVANCE_LINE);
+                let () = write_sleb128(&mut out, line_delta);
+            }
+            let () = out.push(ADVANCE_PC);
+            let () = write_uleb128(&mut out, addr_delta as u64);
+        }
+        prev = *row;
+    }
+    let () = out.push(END_SEQUENCE);
+    out
+}
+
+
+/// The string table being built up, with the empty string at offset
+/// zero.
+#[derive(Debug)]
+struct StrTab {
+    data: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StrTab {
+    fn new() -> Self {
+        Self {
+            data: vec![0],
+            offsets: HashMap::from([(String::new(), 0)]),
+        }
+    }
+
+    fn insert(&mut self, s: &str) -> Result<u32> {
+        if let Some(offset) = self.offsets.get(s) {
+            return Ok(*offset)
+        }
+
+        if s.contains('\0') {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("string {s:?} contains NUL byte"),
+            ))
+        }
+
+        let offset = u32::try_from(self.data.len())
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "string table is too large"))?;
+        let () = self.data.extend_from_slice(s.as_bytes());
+        let () = self.data.push(0);
+        let _prev = self.offsets.insert(s.to_string(), offset);
+        Ok(offset)
+    }
+}
+
+
+/// A function to be written to the GSYM file.
+#[derive(Debug)]
+struct Function {
+    addr: Addr,
+    size: u32,
+    name: u32,
+    line_table: Option<Vec<u8>>,
+}
+
+
+/// A writer of GSYM files.
+///
+/// A `Writer` converts the symbols and line number information of an ELF
+/// file into the GSYM format, which can subsequently be used for
+/// symbolization via [`symbolize::Source::Gsym`][crate::symbolize::Source::Gsym].
+///
+/// Symbols are taken from the ELF symbol table, while line number
+/// information is read from DWARF debug information, if available
+/// (potentially stored in a separate debug file).
+#[derive(Debug, Default)]
+pub struct Writer {
+    _private: (),
+}
+
+impl Writer {
+    /// Create a new `Writer`.
+    pub fn new() -> Self {
+        Self { _private: () }
+    }
+
+    /// Convert the ELF file at `path` into GSYM format, writing the
+    /// result to `out`.
+    pub fn write_elf<W>(&self, path: &Path, mut out: W) -> Result<()>
+    where
+        W: Write,
+    {
+        let parser = Arc::new(ElfParser::open(path)?);
+        let uuid = read_elf_build_id(&parser)?.unwrap_or_default();
+        let debug_dirs = DEFAULT_DEBUG_DIRS
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        let debug_parser = find_debug_file(path, &parser, &debug_dirs)
+            .map(Arc::new)
+            .unwrap_or_else(|| Arc::clone(&parser));
+        // Line number information is optional.
+        let dwarf =
+            DwarfResolver::from_parsers(Arc::clone(&parser), debug_parser, true, false).ok();
+
+        let mut syms = parser.function_symbols()?;
+        // There may be multiple symbols (aliases) for the same
+        // function. Only keep one of them, preferring the ones with a
+        // size.
+        let () = syms.sort_by_key(|(_name, addr, size)| (*addr, *size == 0));
+        let () = syms.dedup_by_key(|(_name, addr, _size)| *addr);
+
+        let mut strtab = StrTab::new();
+        // The file at index zero is invalid.
+        let mut files = vec![(0u32, 0u32)];
+        let mut file_indices = HashMap::<(&Path, &OsStr), u32>::new();
+
+        let mut functions = Vec::with_capacity(syms.len());
+        for (name, addr, size) in syms {
+            let line_table = if let Some(dwarf) = &dwarf {
+                let lines = dwarf.find_lines(addr..addr + size.max(1));
+                let mut rows = Vec::<LineRow>::with_capacity(lines.len());
+                for (addr, dir, file, line) in lines {
+                    let file_idx = if let Some(idx) = file_indices.get(&(dir, file)) {
+                        *idx
+                    } else {
+                        let dir_off = strtab.insert(&dir.to_string_lossy())?;
+                        let file_off = strtab.insert(&file.to_string_lossy())?;
+                        let idx = files.len() as u32;
+                        let () = files.push((dir_off, file_off));
+                        let _prev = file_indices.insert((dir, file), idx);
+                        idx
+                    };
+                    let row = LineRow {
+                        addr,
+                        file_idx,
+                        line: line as u32,
+                    };
+                    // Skip rows not adding any information.
+                    if rows
+                        .last()
+                        .map(|last| (last.file_idx, last.line) != (row.file_idx, row.line))
+                        .unwrap_or(true)
+                    {
+                        let () = rows.push(row);
+                    }
+                }
+                (!rows.is_empty()).then(|| encode_line_table(&rows, addr))
+            } else {
+                None
+            };
+
+            let function = Function {
+                addr,
+                size: u32::try_from(size).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("function {name} is too large"),
+                    )
+                })?,
+                name: strtab.insert(name)?,
+                line_table,
+            };
+            let () = functions.push(function);
+        }
+
+        let data = Self::layout(&functions, &files, &strtab, &uuid)?;
+        let () = out.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Lay out the GSYM file for the provided functions, files, and
+    /// strings.
+    fn layout(
+        functions: &[Function],
+        files: &[(u32, u32)],
+        strtab: &StrTab,
+        uuid: &[u8],
+    ) -> Result<Vec<u8>> {
+        let base_addr = functions.first().map(|function| function.addr).unwrap_or(0);
+        let max_offset = functions
+            .last()
+            .map(|function| function.addr - base_addr)
+            .unwrap_or(0) as u64;
+        let addr_off_size = if max_offset <= u64::from(u8::MAX) {
+            1
+        } else if max_offset <= u64::from(u16::MAX) {
+            2
+        } else if max_offset <= u64::from(u32::MAX) {
+            4
+        } else {
+            8
+        };
+        let num_addrs = u32::try_from(functions.len())
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "too many functions"))?;
+        let uuid = &uuid[..uuid.len().min(MAX_UUID_SIZE)];
+
+        let mut out = Vec::new();
+        let () = out.resize(HEADER_SIZE, 0);
+
+        for function in functions {
+            let offset = (function.addr - base_addr) as u64;
+            let () = out.extend_from_slice(&offset.to_ne_bytes()[..addr_off_size]);
+        }
+
+        let () = align(&mut out, size_of::<u32>());
+        let addr_data_off_tab = out.len();
+        let () = out.resize(addr_data_off_tab + functions.len() * size_of::<u32>(), 0);
+
+        let () = align(&mut out, size_of::<u32>());
+        let () = out.extend_from_slice(&(files.len() as u32).to_ne_bytes());
+        for (dir, file) in files {
+            let () = out.extend_from_slice(&dir.to_ne_bytes());
+            let () = out.extend_from_slice(&file.to_ne_bytes());
+        }
+
+        let strtab_offset = out.len();
+        let () = out.extend_from_slice(&strtab.data);
+
+        for (idx, function) in functions.iter().enumerate() {
+            let () = align(&mut out, size_of::<u32>());
+            let offset = u32::try_from(out.len())
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "GSYM data is too large"))?;
+            let entry = addr_data_off_tab + idx * size_of::<u32>();
+            let () = out[entry..entry + size_of::<u32>()].copy_from_slice(&offset.to_ne_bytes());
+
+            let () = out.extend_from_slice(&function.size.to_ne_bytes());
+            let () = out.extend_from_slice(&function.name.to_ne_bytes());
+            if let Some(line_table) = &function.line_table {
+                let () = out.extend_from_slice(&InfoTypeLineTableInfo.to_ne_bytes());
+                let () = out.extend_from_slice(&(line_table.len() as u32).to_ne_bytes());
+                let () = out.extend_from_slice(line_table);
+            }
+            let () = out.extend_from_slice(&InfoTypeEndOfList.to_ne_bytes());
+            let () = out.extend_from_slice(&0u32.to_ne_bytes());
+        }
+
+        let strtab_offset = u32::try_from(strtab_offset)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "GSYM data is too large"))?;
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        let () = header.extend_from_slice(&GSYM_MAGIC.to_ne_bytes());
+        let () = header.extend_from_slice(&GSYM_VERSION.to_ne_bytes());
+        let () = header.push(addr_off_size as u8);
+        let () = header.push(uuid.len() as u8);
+        let () = header.extend_from_slice(&(base_addr as u64).to_ne_bytes());
+        let () = header.extend_from_slice(&num_addrs.to_ne_bytes());
+        let () = header.extend_from_slice(&strtab_offset.to_ne_bytes());
+        let () = header.extend_from_slice(&(strtab.data.len() as u32).to_ne_bytes());
+        let () = header.extend_from_slice(uuid);
+        let () = header.resize(HEADER_SIZE, 0);
+        let () = out[..HEADER_SIZE].copy_from_slice(&header);
+
+        Ok(out)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::read as read_file;
+
+    use test_log::test;
+
+    use crate::gsym::linetab::run_op;
+    use crate::gsym::linetab::LineTableRow;
+    use crate::gsym::linetab::RunResult;
+    use crate::gsym::parser::parse_line_table_header;
+    use crate::gsym::parser::GsymContext;
+    use crate::gsym::GsymResolver;
+    use crate::inspect::FindAddrOpts;
+    use crate::util::ReadRaw as _;
+    use crate::SymResolver;
+
+
+    /// Check that our LEB128 encoders produce data that our decoders
+    /// understand.
+    #[test]
+    fn leb128_round_trip() {
+        for value in [0, 1, 63, 64, 127, 128, 0x3fff, 0x4000, u64::MAX] {
+            let mut data = Vec::new();
+            let () = write_uleb128(&mut data, value);
+            let (decoded, _bytes) = data.as_slice().read_u128_leb128().unwrap();
+            assert_eq!(decoded, u128::from(value));
+        }
+
+        for value in [
+            0,
+            1,
+            -1,
+            63,
+            64,
+            -64,
+            -65,
+            0x3fff,
+            -0x4000,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            let mut data = Vec::new();
+            let () = write_sleb128(&mut data, value);
+            let (decoded, _bytes) = data.as_slice().read_i128_leb128().unwrap();
+            assert_eq!(decoded, i128::from(value));
+        }
+    }
+
+    /// Check that line tables we encode decode to the original rows.
+    #[test]
+    fn line_table_round_trip() {
+        let base_addr = 0x1000;
+        let rows = [
+            LineRow {
+                addr: 0x1000,
+                file_idx: 1,
+                line: 10,
+            },
+            LineRow {
+                addr: 0x1004,
+                file_idx: 1,
+                line: 11,
+            },
+            LineRow {
+                addr: 0x1004,
+                file_idx: 2,
+                line: 3,
+            },
+            LineRow {
+                addr: 0x1100,
+                file_idx: 2,
+                line: 250,
+            },
+            LineRow {
+                addr: 0x1101,
+                file_idx: 1,
+                line: 9,
+            },
+        ];
+        let data = encode_line_table(&rows, base_addr);
+
+        let mut data = data.as_slice();
+        let header = parse_line_table_header(&mut data).unwrap();
+        let mut row = LineTableRow::line_table_row_from(&header, base_addr);
+        let mut decoded = Vec::new();
+        loop {
+            match run_op(&mut row, &header, &mut data).unwrap() {
+                RunResult::Ok => (),
+                RunResult::NewRow => decoded.push(LineRow {
+                    addr: row.address,
+                    file_idx: row.file_idx,
+                    line: row.file_line,
+                }),
+                RunResult::End => break,
+            }
+        }
+        assert!(data.is_empty());
+        assert_eq!(decoded, rows);
+    }
+
+    /// Check that we can convert an ELF file into GSYM and read the
+    /// result back.
+    #[test]
+    fn elf_round_trip() {
+        let test_elf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-stable-addresses.bin");
+
+        let mut data = Vec::new();
+        let () = Writer::new().write_elf(&test_elf, &mut data).unwrap();
+
+        let ctx = GsymContext::parse_header(&data).unwrap();
+        let idx = ctx.find_addr(0x2000100).unwrap();
+        assert_eq!(ctx.addr_at(idx), Some(0x2000100));
+        let info = ctx.addr_info(idx).unwrap();
+        assert_eq!(ctx.get_str(info.name as usize), Some("factorial"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let gsym = dir.path().join("test.gsym");
+        let () = Writer::new()
+            .write_elf(&test_elf, std::fs::File::create(&gsym).unwrap())
+            .unwrap();
+        assert_eq!(read_file(&gsym).unwrap(), data);
+
+        let resolver = GsymResolver::new(gsym).unwrap();
+        assert_eq!(
+            resolver.find_symbols(0x2000100),
+            vec![("factorial", 0x2000100)]
+        );
+        assert_eq!(resolver.find_symbols(0x2000000), vec![("main", 0x2000000)]);
+
+        let info = resolver.find_line_info(0x2000100).unwrap();
+        assert_eq!(info.line, 8);
+        assert!(info.path.ends_with("test-stable-addresses.c"));
+
+        let info = resolver.find_line_info(0x2000000).unwrap();
+        assert_eq!(info.line, 34);
+        assert!(info.path.ends_with("test-stable-addresses.c"));
+
+        let syms = resolver
+            .find_addr("factorial", &FindAddrOpts::default())
+            .unwrap();
+        assert_eq!(syms.len(), 1);
+        assert_eq!(syms[0].addr, 0x2000100);
+        assert_ne!(syms[0].size, 0);
+    }
+}
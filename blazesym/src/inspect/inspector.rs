@@ -1,13 +1,24 @@
+use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Result;
-use std::rc::Rc;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::dwarf::DwarfResolver;
+use crate::elf::find_debug_file;
 use crate::elf::ElfBackend;
 use crate::elf::ElfParser;
 use crate::elf::ElfResolver;
+use crate::elf::DEFAULT_DEBUG_DIRS;
+use crate::gsym::GsymResolver;
+use crate::ksym::KSymResolver;
+use crate::ksym::KALLSYMS;
+use crate::util::glob_match;
 use crate::SymResolver;
 
 use super::source::Elf;
+use super::source::Gsym;
+use super::source::Kernel;
 use super::source::Source;
 use super::FindAddrOpts;
 use super::SymInfo;
@@ -30,6 +41,124 @@ impl Inspector {
         Self { _private: () }
     }
 
+    /// Fill in the information requested by `opts` that `resolver` does
+    /// not report as part of a lookup.
+    fn complete_syms(syms: &mut [SymInfo], resolver: &dyn SymResolver, opts: &FindAddrOpts) {
+        let () = syms.iter_mut().for_each(|sym| {
+            if opts.offset_in_file {
+                if let Some(off) = resolver.addr_file_off(sym.addr) {
+                    sym.file_offset = off;
+                }
+            }
+            if opts.obj_file_name {
+                sym.obj_file_name = Some(resolver.get_obj_file_name().to_path_buf());
+            }
+        });
+    }
+
+    /// Look up a list of symbols by name using the provided
+    /// [`SymResolver`].
+    fn lookup_with_resolver(
+        names: &[&str],
+        resolver: &dyn SymResolver,
+        opts: &FindAddrOpts,
+    ) -> Vec<Vec<SymInfo>> {
+        names
+            .iter()
+            .map(|name| {
+                let mut syms = resolver.find_addr(name, opts).unwrap_or_default();
+                let () = Self::complete_syms(&mut syms, resolver, opts);
+                syms
+            })
+            .collect()
+    }
+
+    /// Look up a list of kernel symbols, given their names or, if
+    /// `glob` is `true`, glob patterns matching them.
+    fn lookup_kernel(
+        names: &[&str],
+        kernel: &Kernel,
+        glob: bool,
+        opts: &FindAddrOpts,
+    ) -> Result<Vec<Vec<SymInfo>>> {
+        let Kernel {
+            kallsyms,
+            kernel_image,
+            _non_exhaustive: (),
+        } = kernel;
+
+        let kallsyms = match (kallsyms, kernel_image) {
+            (Some(kallsyms), _) => Some(kallsyms.clone()),
+            (None, None) => Some(PathBuf::from(KALLSYMS)),
+            (None, Some(..)) => None,
+        };
+        let ksym_resolver = kallsyms.map(KSymResolver::load_file_name).transpose()?;
+        let elf_resolver = kernel_image
+            .as_ref()
+            .map(|image| {
+                let parser = ElfParser::open(image)?;
+                let backend = ElfBackend::Elf(Arc::new(parser));
+                ElfResolver::with_backend(image, backend)
+            })
+            .transpose()?;
+
+        let find_elf_syms = |resolver: &ElfResolver, name: &str| -> Result<Vec<SymInfo>> {
+            if !glob {
+                return Ok(resolver.find_addr(name, opts).unwrap_or_default())
+            }
+            // Only function symbols are reported for ELF files.
+            if opts.sym_type == SymType::Variable {
+                return Ok(Vec::new())
+            }
+
+            let syms = resolver
+                .get_parser()
+                .function_symbols()?
+                .into_iter()
+                .filter(|(sym_name, _addr, _size)| glob_match(name, sym_name))
+                .map(|(sym_name, addr, size)| {
+                    let range = addr..addr + size;
+                    SymInfo {
+                        name: sym_name.to_string(),
+                        addr,
+                        size,
+                        ranges: vec![range],
+                        sym_type: SymType::Function,
+                        file_offset: 0,
+                        obj_file_name: None,
+                        module: None,
+                    }
+                })
+                .collect();
+            Ok(syms)
+        };
+
+        names
+            .iter()
+            .map(|name| {
+                if let Some(resolver) = &ksym_resolver {
+                    let mut syms = if glob {
+                        resolver.find_addr_glob(name, opts)
+                    } else {
+                        resolver.find_addr(name, opts).unwrap_or_default()
+                    };
+                    if !syms.is_empty() {
+                        let () = Self::complete_syms(&mut syms, resolver, opts);
+                        return Ok(syms)
+                    }
+                }
+
+                if let Some(resolver) = &elf_resolver {
+                    let mut syms = find_elf_syms(resolver, name)?;
+                    let () = Self::complete_syms(&mut syms, resolver, opts);
+                    Ok(syms)
+                } else {
+                    Ok(Vec::new())
+                }
+            })
+            .collect()
+    }
+
     /// Look up information (address etc.) about a list of symbols,
     /// given their names.
     pub fn lookup(&self, names: &[&str], src: &Source) -> Result<Vec<Vec<SymInfo>>> {
@@ -48,38 +177,73 @@ impl Inspector {
                 let backend = if *debug_info {
                     let debug_line_info = true;
                     let debug_info_symbols = true;
-                    let dwarf = DwarfResolver::open(path, debug_line_info, debug_info_symbols)?;
-                    let backend = ElfBackend::Dwarf(Rc::new(dwarf));
+                    let parser = Arc::new(ElfParser::open(path)?);
+                    let debug_dirs = DEFAULT_DEBUG_DIRS
+                        .iter()
+                        .map(PathBuf::from)
+                        .collect::<Vec<_>>();
+                    // Debug information may be stored in a separate file.
+                    let dwarf =
+                        if let Some(debug_parser) = find_debug_file(path, &parser, &debug_dirs) {
+                            DwarfResolver::from_parsers(
+                                parser,
+                                Arc::new(debug_parser),
+                                debug_line_info,
+                                debug_info_symbols,
+                            )?
+                        } else {
+                            DwarfResolver::from_parser_for_addresses(
+                                parser,
+                                &[],
+                                debug_line_info,
+                                debug_info_symbols,
+                            )?
+                        };
+                    let backend = ElfBackend::Dwarf(Arc::new(dwarf));
                     backend
                 } else {
                     let elf = ElfParser::open(path)?;
-                    let backend = ElfBackend::Elf(Rc::new(elf));
+                    let backend = ElfBackend::Elf(Arc::new(elf));
                     backend
                 };
 
                 let resolver = ElfResolver::with_backend(path, backend)?;
-                let syms = names
-                    .iter()
-                    .map(|name| {
-                        let mut syms = resolver.find_addr(name, &opts).unwrap_or_default();
-                        let () = syms.iter_mut().for_each(|sym| {
-                            if opts.offset_in_file {
-                                if let Some(off) = resolver.addr_file_off(sym.addr) {
-                                    sym.file_offset = off;
-                                }
-                            }
-                            if opts.obj_file_name {
-                                sym.obj_file_name =
-                                    Some(resolver.get_obj_file_name().to_path_buf());
-                            }
-                        });
-
-                        syms
-                    })
-                    .collect();
-
+                let syms = Self::lookup_with_resolver(names, &resolver, &opts);
                 Ok(syms)
             }
+            Source::Gsym(Gsym {
+                path,
+                _non_exhaustive: (),
+            }) => {
+                let resolver = GsymResolver::new(path.clone())?;
+                let syms = Self::lookup_with_resolver(names, &resolver, &opts);
+                Ok(syms)
+            }
+            Source::Kernel(kernel) => Self::lookup_kernel(names, kernel, false, &opts),
+        }
+    }
+
+    /// Look up information (address etc.) about all symbols with names
+    /// matching any of the provided glob patterns.
+    ///
+    /// Patterns support `*`, `?`, and bracket expressions such as
+    /// `[a-z]`. The result contains one list of symbols per pattern.
+    ///
+    /// Glob based lookup is currently only supported for
+    /// [`Source::Kernel`].
+    pub fn lookup_glob(&self, patterns: &[&str], src: &Source) -> Result<Vec<Vec<SymInfo>>> {
+        let opts = FindAddrOpts {
+            offset_in_file: false,
+            obj_file_name: true,
+            sym_type: SymType::Unknown,
+        };
+
+        match src {
+            Source::Kernel(kernel) => Self::lookup_kernel(patterns, kernel, true, &opts),
+            Source::Elf(..) | Source::Gsym(..) => Err(Error::new(
+                ErrorKind::Unsupported,
+                "glob based symbol lookup is only supported for the kernel",
+            )),
         }
     }
 }
@@ -109,9 +273,12 @@ mod tests {
         let src = Source::Elf(Elf::new(&file));
         let () = test(&src);
 
-        let mut elf = Elf::new(file);
+        let mut elf = Elf::new(&file);
         elf.debug_info = !elf.debug_info;
         let src = Source::Elf(elf);
         let () = test(&src);
+
+        let src = Source::Gsym(Gsym::new(file));
+        let () = test(&src);
     }
 }
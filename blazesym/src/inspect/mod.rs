@@ -1,4 +1,5 @@
-//! Functionality for inspecting files such as ELF or Gsym.
+//! Functionality for inspecting files such as ELF or Gsym as well as the
+//! kernel.
 //!
 //! ```no_run
 //! use blazesym::inspect;
@@ -17,17 +18,20 @@
 mod inspector;
 mod source;
 
+use std::ops::Range;
 use std::path::PathBuf;
 
 use crate::Addr;
 
 pub use inspector::Inspector;
 pub use source::Elf;
+pub use source::Gsym;
+pub use source::Kernel;
 pub use source::Source;
 
 
 /// The type of a symbol.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum SymType {
     /// The symbol type is unknown.
     #[default]
@@ -48,12 +52,20 @@ pub struct SymInfo {
     pub addr: Addr,
     /// The size of the symbol. The size of a function for example.
     pub size: usize,
+    /// All address ranges covered by the symbol.
+    ///
+    /// For most symbols this is just `addr..addr + size`, but functions
+    /// may be split into multiple non-contiguous ranges, in which case
+    /// `addr` and `size` describe the one containing the entry point.
+    pub ranges: Vec<Range<Addr>>,
     /// A function or a variable.
     pub sym_type: SymType,
     /// The offset in the object file.
     pub file_offset: u64,
     /// The file name of the shared object.
     pub obj_file_name: Option<PathBuf>,
+    /// The name of the kernel module the symbol belongs to, if any.
+    pub module: Option<String>,
 }
 
 
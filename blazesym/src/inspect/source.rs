@@ -33,12 +33,69 @@ impl From<Elf> for Source {
 }
 
 
+/// A Gsym file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gsym {
+    /// The path to the Gsym file.
+    pub path: PathBuf,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl Gsym {
+    /// Create a new [`Gsym`] object, referencing the provided path.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl From<Gsym> for Source {
+    fn from(gsym: Gsym) -> Self {
+        Source::Gsym(gsym)
+    }
+}
+
+
+/// A kernel, as represented by kallsyms and/or its image.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Kernel {
+    /// The path of a kallsyms copy.
+    ///
+    /// A `None` value will use `"/proc/kallsyms"`, unless a kernel
+    /// image is provided.
+    pub kallsyms: Option<PathBuf>,
+    /// The path of a kernel image (e.g., `"/boot/vmlinux-xxxx"`).
+    ///
+    /// The kernel image is consulted for symbols not found via kallsyms
+    /// (or if kallsyms is not used). Addresses reported from it do not
+    /// reflect kernel address space layout randomization.
+    pub kernel_image: Option<PathBuf>,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl From<Kernel> for Source {
+    fn from(kernel: Kernel) -> Self {
+        Source::Kernel(kernel)
+    }
+}
+
+
 /// The source to use for the inspection request.
 #[derive(Clone, Debug, PartialEq)]
 #[non_exhaustive]
 pub enum Source {
     /// The source is an ELF file.
     Elf(Elf),
+    /// The source is a Gsym file.
+    Gsym(Gsym),
+    /// The source is the kernel.
+    Kernel(Kernel),
 }
 
 impl Source {
@@ -46,6 +103,8 @@ impl Source {
     pub fn path(&self) -> Option<&Path> {
         match self {
             Self::Elf(elf) => Some(&elf.path),
+            Self::Gsym(gsym) => Some(&gsym.path),
+            Self::Kernel(..) => None,
         }
     }
 }
@@ -1,31 +1,431 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::fs::read_dir;
+use std::fs::File;
+use std::io::BufRead as _;
+use std::io::BufReader;
 use std::io::Error;
 use std::io::ErrorKind;
 use std::io::Result;
 use std::ops::Deref as _;
+use std::ops::Range;
 use std::path::Path;
-use std::rc::Rc;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
 
+use crate::bpf::BpfResolver;
+use crate::elf::types::SHF_ALLOC;
+use crate::elf::types::SHF_EXECINSTR;
+use crate::elf::ElfParser;
 use crate::elf::ElfResolver;
 use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
 use crate::ksym::KSymResolver;
+use crate::ksym::Ksym;
+use crate::log::debug;
+use crate::log::warn;
 use crate::symbolize::AddrLineInfo;
+use crate::symbolize::InlinedFn;
 use crate::Addr;
 use crate::SymResolver;
 
 
+/// The path to the list of loaded kernel modules.
+pub const PROC_MODULES: &str = "/proc/modules";
+
+
+/// A kernel module loaded into memory.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LoadedModule {
+    /// The name of the module.
+    pub name: String,
+    /// The address range the module's core (its non-init sections) got
+    /// loaded to.
+    pub range: Range<Addr>,
+}
+
+/// Parse the list of loaded kernel modules from a file in the format of
+/// `/proc/modules`.
+///
+/// Modules for which no address is reported (which is the case for
+/// unprivileged readers) are skipped.
+pub(crate) fn parse_modules(path: &Path) -> Result<Vec<LoadedModule>> {
+    let file = File::open(path)?;
+    let mut modules = Vec::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        // Each line has the form:
+        // <name> <size> <refcount> <dependencies> <state> <address> [<taint>]
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let (name, size, addr) = match tokens.as_slice() {
+            [name, size, _refcnt, _deps, _state, addr, ..] => (name, size, addr),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("encountered malformed kernel module line: {line}"),
+                ))
+            }
+        };
+
+        let size = size.parse::<Addr>().map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("encountered invalid kernel module size `{size}`: {err}"),
+            )
+        })?;
+        let addr = Addr::from_str_radix(addr.trim_start_matches("0x"), 16).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("encountered invalid kernel module address `{addr}`: {err}"),
+            )
+        })?;
+        if addr == 0 {
+            continue
+        }
+
+        let module = LoadedModule {
+            name: name.to_string(),
+            range: addr..addr + size,
+        };
+        let () = modules.push(module);
+    }
+    Ok(modules)
+}
+
+/// The suffixes of compressed kernel module files.
+const COMPRESSED_MODULE_SUFFIXES: [&str; 3] = [".ko.xz", ".ko.zst", ".ko.gz"];
+
+/// Normalize the name of a kernel module as derived from its file name.
+///
+/// The kernel reports module names with underscores in place of dashes.
+/// Compressed modules are recognized as well.
+fn module_name(file_name: &OsStr) -> Option<String> {
+    let file_name = file_name.to_str()?;
+    let name = file_name.strip_suffix(".ko").or_else(|| {
+        COMPRESSED_MODULE_SUFFIXES
+            .iter()
+            .find_map(|suffix| file_name.strip_suffix(suffix))
+    })?;
+    Some(name.replace('-', "_"))
+}
+
+/// Check whether the kernel module file at `path` is compressed.
+pub(crate) fn is_compressed_module(path: &Path) -> bool {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .map_or(false, |name| {
+            COMPRESSED_MODULE_SUFFIXES
+                .iter()
+                .any(|suffix| name.ends_with(suffix))
+        })
+}
+
+/// Decompress the data of the compressed kernel module file at `path`.
+///
+/// gzip (`.ko.gz`) and zstd (`.ko.zst`) compressed modules are
+/// supported, given that the `zlib` and `zstd` features, respectively,
+/// are enabled. xz (`.ko.xz`) compressed modules are not supported.
+pub(crate) fn decompress_module(path: &Path, data: &[u8]) -> Result<Vec<u8>> {
+    let name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+    if name.ends_with(".ko.gz") {
+        decompress_gzip(data)
+    } else if name.ends_with(".ko.zst") {
+        decompress_zstd(data)
+    } else {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "kernel module {} uses an unsupported compression format",
+                path.display()
+            ),
+        ))
+    }
+}
+
+/// Decompress gzip (RFC 1952) compressed data.
+#[cfg(feature = "zlib")]
+fn decompress_gzip(data: &[u8]) -> Result<Vec<u8>> {
+    use miniz_oxide::inflate::decompress_to_vec_with_limit;
+
+    const FHCRC: u8 = 1 << 1;
+    const FEXTRA: u8 = 1 << 2;
+    const FNAME: u8 = 1 << 3;
+    const FCOMMENT: u8 = 1 << 4;
+
+    let invalid = || Error::new(ErrorKind::InvalidData, "encountered invalid gzip data");
+
+    // The header is at least ten bytes in size and the data is followed
+    // by an eight byte trailer.
+    if data.len() < 18 || data[0..3] != [0x1f, 0x8b, 8] {
+        return Err(invalid())
+    }
+    let flags = data[3];
+    let mut offset = 10;
+
+    if flags & FEXTRA != 0 {
+        let xlen = data
+            .get(offset..offset + 2)
+            .map(|xlen| u16::from_le_bytes([xlen[0], xlen[1]]))
+            .ok_or_else(invalid)?;
+        offset += 2 + usize::from(xlen);
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            // Both the file name and the comment are NUL terminated.
+            let len = data
+                .get(offset..)
+                .and_then(|rest| rest.iter().position(|b| *b == 0))
+                .ok_or_else(invalid)?;
+            offset += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        offset += 2;
+    }
+
+    let trailer = data.len() - 8;
+    let deflated = data.get(offset..trailer).ok_or_else(invalid)?;
+    // The trailer contains the size of the uncompressed data (modulo
+    // 2^32) in its last four bytes.
+    let size = u32::from_le_bytes([
+        data[trailer + 4],
+        data[trailer + 5],
+        data[trailer + 6],
+        data[trailer + 7],
+    ]) as usize;
+
+    let decompressed = decompress_to_vec_with_limit(deflated, size).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to decompress gzip data: {err}"),
+        )
+    })?;
+    if decompressed.len() != size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "decompressed gzip data has unexpected size",
+        ))
+    }
+    Ok(decompressed)
+}
+
+#[cfg(not(feature = "zlib"))]
+fn decompress_gzip(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "gzip compressed kernel modules are not supported; enable the `zlib` feature",
+    ))
+}
+
+/// Decompress zstd compressed data.
+#[cfg(feature = "zstd")]
+fn decompress_zstd(data: &[u8]) -> Result<Vec<u8>> {
+    zstd::stream::decode_all(data)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "zstd compressed kernel modules are not supported; enable the `zstd` feature",
+    ))
+}
+
+/// Record the module file at `path`, preferring uncompressed files over
+/// compressed ones for the same module.
+fn add_module_file(files: &mut HashMap<String, PathBuf>, path: PathBuf) {
+    let name = match path.file_name().and_then(module_name) {
+        Some(name) => name,
+        None => return,
+    };
+
+    match files.entry(name) {
+        Entry::Occupied(mut occupied) => {
+            if is_compressed_module(occupied.get()) && !is_compressed_module(&path) {
+                let _prev = occupied.insert(path);
+            }
+        }
+        Entry::Vacant(vacant) => {
+            let _path = vacant.insert(path);
+        }
+    }
+}
+
+/// Parse the `modules.dep` file in `dir`, listing all module files of
+/// a kernel release.
+fn parse_modules_dep(dir: &Path) -> Result<HashMap<String, PathBuf>> {
+    let file = File::open(dir.join("modules.dep"))?;
+    let mut files = HashMap::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        // Each line has the form:
+        // <module path>: [<dependency path> ...]
+        let path = match line.split_once(':') {
+            Some((path, _deps)) => path.trim(),
+            None => {
+                warn!("encountered malformed modules.dep line: {line}; ignoring...");
+                continue
+            }
+        };
+        // Paths are relative to `dir`, unless they are absolute.
+        let () = add_module_file(&mut files, dir.join(path));
+    }
+    Ok(files)
+}
+
+/// Find the module files in `dir` by searching it recursively.
+fn walk_module_files(dir: &Path, files: &mut HashMap<String, PathBuf>) -> Result<()> {
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            // Errors in sub-directories should not prevent us from
+            // finding modules elsewhere.
+            let _result = walk_module_files(&path, files);
+        } else {
+            let () = add_module_file(files, path);
+        }
+    }
+    Ok(())
+}
+
+/// Find the files of all kernel modules available in `dir`, keyed by
+/// module name.
+///
+/// Modules are looked up in the `modules.dep` file inside of `dir`, if
+/// present. Otherwise `dir` is searched recursively.
+pub(crate) fn find_module_files(dir: &Path) -> HashMap<String, PathBuf> {
+    match parse_modules_dep(dir) {
+        Ok(files) => return files,
+        Err(err) => debug!(
+            "failed to read modules.dep in {}: {err}; searching directory...",
+            dir.display()
+        ),
+    }
+
+    let mut files = HashMap::new();
+    if let Err(err) = walk_module_files(dir, &mut files) {
+        warn!(
+            "failed to search for kernel modules in {}: {err}",
+            dir.display()
+        );
+    }
+    files
+}
+
+
+/// Cache of the kernel module files available in module directories.
+#[derive(Debug)]
+pub(crate) struct ModuleFilesCache {
+    files: Mutex<HashMap<PathBuf, Arc<HashMap<String, PathBuf>>>>,
+}
+
+impl ModuleFilesCache {
+    pub fn new() -> Self {
+        Self {
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Retrieve the module files available in `dir`, keyed by module
+    /// name, from the cache or look them up.
+    pub fn get_files(&self, dir: &Path) -> Arc<HashMap<String, PathBuf>> {
+        let mut files = self.files.lock().unwrap();
+        if let Some(files) = files.get(dir) {
+            return Arc::clone(files)
+        }
+
+        let module_files = Arc::new(find_module_files(dir));
+        let _prev = files.insert(dir.to_path_buf(), Arc::clone(&module_files));
+        module_files
+    }
+}
+
+/// Cache of the lists of loaded kernel modules, as parsed from files in
+/// the format of `/proc/modules`.
+#[derive(Debug)]
+pub(crate) struct LoadedModulesCache {
+    modules: Mutex<HashMap<PathBuf, Arc<Vec<LoadedModule>>>>,
+}
+
+impl LoadedModulesCache {
+    pub fn new() -> Self {
+        Self {
+            modules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Retrieve the list of loaded kernel modules stored in `path` from
+    /// the cache or parse it.
+    pub fn get_modules(&self, path: &Path) -> Result<Arc<Vec<LoadedModule>>> {
+        let mut modules = self.modules.lock().unwrap();
+        if let Some(modules) = modules.get(path) {
+            return Ok(Arc::clone(modules))
+        }
+
+        let loaded = Arc::new(parse_modules(path)?);
+        let _prev = modules.insert(path.to_path_buf(), Arc::clone(&loaded));
+        Ok(loaded)
+    }
+}
+
+/// Find the address of the first executable non-init section of a
+/// kernel module.
+///
+/// The kernel places this section at the start of the module's core.
+fn core_text_addr(parser: &ElfParser) -> Option<Addr> {
+    let shdrs = parser.section_headers().ok()?;
+    shdrs.iter().enumerate().find_map(|(idx, shdr)| {
+        let flags = SHF_ALLOC | SHF_EXECINSTR;
+        if shdr.sh_flags & flags != flags {
+            return None
+        }
+        let name = parser.section_name(idx).ok()?;
+        (!name.starts_with(".init")).then_some(shdr.sh_addr as Addr)
+    })
+}
+
+
+/// An address inside of a kernel module.
+struct ModuleAddr<'res> {
+    /// The name of the module.
+    name: &'res str,
+    /// The resolver for the module's ELF file along with the address
+    /// normalized to it, if available.
+    resolver: Option<(&'res ElfResolver, Addr)>,
+}
+
+
 pub(crate) struct KernelResolver {
-    pub ksym_resolver: Option<Rc<KSymResolver>>,
+    pub ksym_resolver: Option<Arc<KSymResolver>>,
     pub elf_resolver: Option<ElfResolver>,
+    /// Kernel modules loaded into memory. Only used for attributing
+    /// addresses to modules when kallsyms does not provide the
+    /// necessary information.
+    pub modules: Arc<Vec<LoadedModule>>,
+    /// Resolvers for the ELF files of kernel modules, keyed by module
+    /// name.
+    pub module_resolvers: HashMap<String, ElfResolver>,
+    /// The resolver for JIT-ed BPF programs, if available.
+    pub bpf_resolver: Option<BpfResolver>,
 }
 
 impl KernelResolver {
     pub fn new(
-        ksym_resolver: Option<Rc<KSymResolver>>,
+        ksym_resolver: Option<Arc<KSymResolver>>,
         elf_resolver: Option<ElfResolver>,
+        modules: Arc<Vec<LoadedModule>>,
+        module_resolvers: HashMap<String, ElfResolver>,
+        bpf_resolver: Option<BpfResolver>,
     ) -> Result<KernelResolver> {
         if ksym_resolver.is_none() && elf_resolver.is_none() {
             return Err(Error::new(
@@ -37,12 +437,87 @@ impl KernelResolver {
         Ok(KernelResolver {
             ksym_resolver,
             elf_resolver,
+            modules,
+            module_resolvers,
+            bpf_resolver,
+        })
+    }
+
+    /// Find the name of the kernel module containing `addr`, if any.
+    pub fn find_module(&self, addr: Addr) -> Option<&str> {
+        self.module_addr(addr).map(|module| module.name)
+    }
+
+    /// Check whether `addr` belongs to a kernel module and, if so,
+    /// normalize it to the module's ELF file.
+    ///
+    /// Relocatable ELF files, such as kernel modules, are assigned
+    /// synthetic addresses for each of their sections when loaded. We
+    /// anchor an address to the kallsyms symbol containing it, whose
+    /// address is also known in the ELF file. Lacking such a symbol, we
+    /// fall back to the module's load address, which corresponds to its
+    /// first executable section.
+    fn module_addr(&self, addr: Addr) -> Option<ModuleAddr<'_>> {
+        let ksym = self
+            .ksym_resolver
+            .as_ref()
+            .and_then(|resolver| resolver.find_addresses_ksym(addr).next());
+
+        if let Some(Ksym {
+            name,
+            addr: sym_addr,
+            module: Some(module),
+            ..
+        }) = ksym
+        {
+            let resolver = self.module_resolvers.get(module).and_then(|resolver| {
+                let syms = resolver
+                    .get_parser()
+                    .find_addr(name, &FindAddrOpts::default())
+                    .ok()?;
+                let sym = syms.first()?;
+                Some((resolver, sym.addr + (addr - sym_addr)))
+            });
+            return Some(ModuleAddr {
+                name: module,
+                resolver,
+            })
+        }
+
+        let module = self
+            .modules
+            .iter()
+            .find(|module| module.range.contains(&addr))?;
+        let resolver = self
+            .module_resolvers
+            .get(&module.name)
+            .and_then(|resolver| {
+                let text_addr = core_text_addr(resolver.get_parser())?;
+                Some((resolver, text_addr + (addr - module.range.start)))
+            });
+        Some(ModuleAddr {
+            name: &module.name,
+            resolver,
         })
     }
 }
 
 impl SymResolver for KernelResolver {
     fn find_symbols(&self, addr: Addr) -> Vec<(&str, Addr)> {
+        if let Some(ModuleAddr {
+            resolver: Some((resolver, norm_addr)),
+            ..
+        }) = self.module_addr(addr)
+        {
+            let syms = resolver.find_symbols(norm_addr);
+            if !syms.is_empty() {
+                return syms
+                    .into_iter()
+                    .map(|(name, start)| (name, addr - (norm_addr - start)))
+                    .collect()
+            }
+        }
+
         if let Some(ksym_resolver) = self.ksym_resolver.as_ref() {
             ksym_resolver.find_symbols(addr)
         } else {
@@ -55,11 +530,38 @@ impl SymResolver for KernelResolver {
     }
 
     fn find_line_info(&self, addr: Addr) -> Option<AddrLineInfo> {
+        if let Some(info) = self
+            .bpf_resolver
+            .as_ref()
+            .and_then(|resolver| resolver.find_line_info(addr))
+        {
+            return Some(info)
+        }
+
+        if let Some(module) = self.module_addr(addr) {
+            let (resolver, norm_addr) = module.resolver?;
+            return resolver.find_line_info(norm_addr)
+        }
+
         self.elf_resolver
             .as_ref()
             .and_then(|resolver| resolver.find_line_info(addr))
     }
 
+    fn find_inlined_fns(&self, addr: Addr) -> Vec<InlinedFn> {
+        if let Some(module) = self.module_addr(addr) {
+            return module
+                .resolver
+                .map(|(resolver, norm_addr)| resolver.find_inlined_fns(norm_addr))
+                .unwrap_or_default()
+        }
+
+        self.elf_resolver
+            .as_ref()
+            .map(|resolver| resolver.find_inlined_fns(addr))
+            .unwrap_or_default()
+    }
+
     fn addr_file_off(&self, _addr: Addr) -> Option<u64> {
         None
     }
@@ -99,3 +601,208 @@ impl Debug for KernelResolver {
         )
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::create_dir;
+    use std::fs::read as read_file;
+    use std::fs::remove_file;
+    use std::io::Write as _;
+
+    use tempfile::tempdir;
+    use tempfile::NamedTempFile;
+
+    use test_log::test;
+
+    use crate::bpf::make_prog;
+    use crate::bpf::PROG_ADDR;
+
+
+    /// Check that we can parse the list of loaded kernel modules.
+    #[test]
+    fn modules_parsing() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(
+            file,
+            "nf_tables 372736 0 - Live 0xffffffffc0a2e000
+test_kmod 16384 1 nf_tables, Live 0xffffffffc0a01000 (OE)
+hidden 8192 0 - Live 0x0000000000000000"
+        )
+        .unwrap();
+
+        let modules = parse_modules(file.path()).unwrap();
+        assert_eq!(
+            modules,
+            vec![
+                LoadedModule {
+                    name: "nf_tables".to_string(),
+                    range: 0xffffffffc0a2e000..0xffffffffc0a2e000 + 372736,
+                },
+                LoadedModule {
+                    name: "test_kmod".to_string(),
+                    range: 0xffffffffc0a01000..0xffffffffc0a01000 + 16384,
+                },
+            ]
+        );
+
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(file, "nf_tables 372736").unwrap();
+        let err = parse_modules(file.path()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// Check that the list of loaded kernel modules is cached.
+    #[test]
+    fn loaded_modules_caching() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(file, "nf_tables 372736 0 - Live 0xffffffffc0a2e000").unwrap();
+
+        let cache = LoadedModulesCache::new();
+        let modules = cache.get_modules(file.path()).unwrap();
+        assert_eq!(modules.len(), 1);
+
+        let () = writeln!(file, "test_kmod 16384 0 - Live 0xffffffffc0a01000").unwrap();
+        let cached = cache.get_modules(file.path()).unwrap();
+        assert!(Arc::ptr_eq(&modules, &cached));
+    }
+
+    /// Check that we can find the files of kernel modules by searching
+    /// a directory.
+    #[test]
+    fn module_file_lookup() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("kernel").join("net");
+        let () = create_dir(dir.path().join("kernel")).unwrap();
+        let () = create_dir(&sub).unwrap();
+        let _file = File::create(sub.join("nf-tables.ko")).unwrap();
+        let _file = File::create(sub.join("nf-tables.ko.xz")).unwrap();
+        let _file = File::create(sub.join("other.ko.zst")).unwrap();
+        let _file = File::create(dir.path().join("modules.order")).unwrap();
+
+        let files = find_module_files(dir.path());
+        assert_eq!(files.len(), 2);
+        assert_eq!(files["nf_tables"], sub.join("nf-tables.ko"));
+        assert_eq!(files["other"], sub.join("other.ko.zst"));
+        assert!(!is_compressed_module(&files["nf_tables"]));
+        assert!(is_compressed_module(&files["other"]));
+    }
+
+    /// Check that we can decompress kernel module files.
+    #[test]
+    fn module_decompression() {
+        let ko = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-kmod.ko");
+        let expected = read_file(&ko).unwrap();
+
+        let gz = ko.with_file_name("test-kmod.ko.gz");
+        let result = decompress_module(&gz, &read_file(&gz).unwrap());
+        if cfg!(feature = "zlib") {
+            assert_eq!(result.unwrap(), expected);
+        } else {
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::Unsupported);
+        }
+
+        let zst = ko.with_file_name("test-kmod.ko.zst");
+        let result = decompress_module(&zst, &read_file(&zst).unwrap());
+        if cfg!(feature = "zstd") {
+            assert_eq!(result.unwrap(), expected);
+        } else {
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::Unsupported);
+        }
+
+        let err = decompress_module(Path::new("test-kmod.ko.xz"), &[]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
+    /// Check that we fail to decompress invalid gzip data.
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn invalid_gzip_decompression() {
+        let err = decompress_gzip(&[0x1f, 0x8b, 8, 0]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // The header claims the presence of a file name, but it is not
+        // NUL terminated.
+        let mut data = vec![0x1f, 0x8b, 8, 1 << 3, 0, 0, 0, 0, 0, 0];
+        let () = data.extend_from_slice(b"file-name-without-terminator");
+        let err = decompress_gzip(&data).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// Check that we can find the files of kernel modules via
+    /// `modules.dep`.
+    #[test]
+    fn module_file_lookup_modules_dep() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("modules.dep")).unwrap();
+        let () = writeln!(
+            file,
+            "kernel/net/netfilter/nf_tables.ko.zst: kernel/lib/libcrc32c.ko.zst
+kernel/lib/libcrc32c.ko.zst:
+malformed
+/abs/test-kmod.ko:"
+        )
+        .unwrap();
+
+        let files = find_module_files(dir.path());
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files["nf_tables"],
+            dir.path().join("kernel/net/netfilter/nf_tables.ko.zst")
+        );
+        assert_eq!(
+            files["libcrc32c"],
+            dir.path().join("kernel/lib/libcrc32c.ko.zst")
+        );
+        assert_eq!(files["test_kmod"], Path::new("/abs/test-kmod.ko"));
+
+        let cache = ModuleFilesCache::new();
+        let files = cache.get_files(dir.path());
+        let () = remove_file(dir.path().join("modules.dep")).unwrap();
+        // The cache should not look at the directory again.
+        assert!(Arc::ptr_eq(&files, &cache.get_files(dir.path())));
+        assert_eq!(files.len(), 3);
+    }
+
+    /// Check that addresses in JIT-ed BPF programs are attributed to
+    /// the program's source code.
+    #[test]
+    fn bpf_prog_symbolization() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(
+            file,
+            "ffffffff81000000 T _stext
+{PROG_ADDR:x} t bpf_prog_d35c0e439a122564_handle_exec\t[bpf]"
+        )
+        .unwrap();
+
+        let ksym_resolver = KSymResolver::load_file_name(file.path().to_path_buf()).unwrap();
+        let bpf_resolver = BpfResolver::from_progs([Arc::new(make_prog())]);
+        let resolver = KernelResolver::new(
+            Some(Arc::new(ksym_resolver)),
+            None,
+            Arc::new(Vec::new()),
+            HashMap::new(),
+            Some(bpf_resolver),
+        )
+        .unwrap();
+
+        let addr = PROG_ADDR + 0x20;
+        assert_eq!(
+            resolver.find_symbols(addr),
+            vec![("bpf_prog_d35c0e439a122564_handle_exec", PROG_ADDR)]
+        );
+        assert_eq!(resolver.find_module(addr), None);
+
+        let info = resolver.find_line_info(addr).unwrap();
+        assert_eq!(info.path, Path::new("/src/prog.bpf.c"));
+        assert_eq!(info.line, 22);
+        assert_eq!(info.column, 5);
+
+        assert!(resolver.find_line_info(0xffffffff81000010).is_none());
+    }
+}
@@ -1,4 +1,3 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Formatter;
@@ -9,12 +8,15 @@ use std::io::BufReader;
 use std::io::Result;
 use std::path::Path;
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
 use crate::inspect::SymType;
 use crate::symbolize::AddrLineInfo;
+use crate::symbolize::InlinedFn;
+use crate::util::glob_match;
 use crate::Addr;
 use crate::SymResolver;
 
@@ -25,6 +27,36 @@ const DFL_KSYM_CAP: usize = 200000;
 pub struct Ksym {
     pub addr: Addr,
     pub name: String,
+    /// The symbol type, as reported by kallsyms (e.g., `T` for a global
+    /// symbol in the text section).
+    pub typ: char,
+    /// The name of the kernel module the symbol belongs to, if any.
+    pub module: Option<String>,
+}
+
+impl Ksym {
+    /// Determine the type of the symbol based on its kallsyms type
+    /// letter.
+    pub fn sym_type(&self) -> SymType {
+        match self.typ.to_ascii_lowercase() {
+            't' => SymType::Function,
+            'b' | 'd' | 'g' | 'r' | 's' | 'v' => SymType::Variable,
+            _ => SymType::Unknown,
+        }
+    }
+
+    /// Check whether the symbol may refer to code, i.e., whether it is
+    /// not known to be a data symbol.
+    #[inline]
+    fn is_code(&self) -> bool {
+        self.sym_type() != SymType::Variable
+    }
+}
+
+/// Check whether `sym` is of the requested type, with
+/// [`SymType::Unknown`] matching all symbols.
+fn type_matches(sym_type: SymType, sym: &Ksym) -> bool {
+    sym_type == SymType::Unknown || sym.sym_type() == sym_type
 }
 
 /// The symbol resolver for /proc/kallsyms.
@@ -33,7 +65,9 @@ pub struct Ksym {
 /// a copy from other devices.
 pub struct KSymResolver {
     syms: Vec<Ksym>,
-    sym_to_addr: RefCell<HashMap<&'static str, Addr>>,
+    /// A mapping from symbol names to the indices of all symbols with
+    /// that name in `syms`, created lazily.
+    sym_to_idx: Mutex<HashMap<&'static str, Vec<usize>>>,
     file_name: PathBuf,
 }
 
@@ -51,16 +85,36 @@ impl KSymResolver {
             }
             let tokens = line.split_whitespace().collect::<Vec<_>>();
             if tokens.len() < 3 {
-                break
+                // Skip over malformed (or empty) lines instead of
+                // discarding all the symbols following them.
+                line.truncate(0);
+                continue
             }
-            let (addr, _symbol, func) = (tokens[0], tokens[1], tokens[2]);
+            let (addr, typ, func) = (tokens[0], tokens[1], tokens[2]);
             if let Ok(addr) = Addr::from_str_radix(addr, 16) {
                 if addr == 0 {
                     line.truncate(0);
                     continue
                 }
                 let name = String::from(func);
-                syms.push(Ksym { addr, name });
+                // Symbols of kernel modules are suffixed with the module
+                // name in square brackets. JIT-ed BPF programs and other
+                // dynamically generated code are reported with pseudo
+                // module names (e.g., `[bpf]`), which do not correspond
+                // to actual modules.
+                let module = tokens
+                    .get(3)
+                    .and_then(|module| module.strip_prefix('['))
+                    .and_then(|module| module.strip_suffix(']'))
+                    .filter(|module| *module != "bpf" && !module.starts_with("__builtin__"))
+                    .map(String::from);
+                let typ = typ.chars().next().unwrap_or('?');
+                syms.push(Ksym {
+                    addr,
+                    name,
+                    typ,
+                    module,
+                });
             }
 
             line.truncate(0);
@@ -70,24 +124,61 @@ impl KSymResolver {
 
         let slf = Self {
             syms,
-            sym_to_addr: RefCell::default(),
+            sym_to_idx: Mutex::default(),
             file_name: filename,
         };
         Ok(slf)
     }
 
-    fn ensure_sym_to_addr(&self) {
-        if self.sym_to_addr.borrow().len() > 0 {
+    fn ensure_sym_to_idx(&self) {
+        let mut sym_to_idx = self.sym_to_idx.lock().unwrap();
+        if !sym_to_idx.is_empty() {
             return
         }
-        let mut sym_to_addr = self.sym_to_addr.borrow_mut();
-        for Ksym { name, addr } in self.syms.iter() {
+        for (idx, Ksym { name, .. }) in self.syms.iter().enumerate() {
             // Performance & lifetime hacking
             let name_static = unsafe { &*(name as *const String) };
-            sym_to_addr.insert(name_static, *addr);
+            sym_to_idx.entry(name_static).or_default().push(idx);
+        }
+    }
+
+    /// Create a [`SymInfo`] object for the symbol at index `idx`.
+    ///
+    /// kallsyms does not report symbol sizes, so we approximate them by
+    /// the distance to the next symbol, just like the kernel does.
+    fn sym_info(&self, idx: usize) -> SymInfo {
+        let sym = &self.syms[idx];
+        let size = self.syms[idx + 1..]
+            .iter()
+            .find(|next| next.addr > sym.addr)
+            .map(|next| next.addr - sym.addr)
+            .unwrap_or(0);
+        let range = sym.addr..sym.addr + size;
+
+        SymInfo {
+            name: sym.name.clone(),
+            addr: sym.addr,
+            size,
+            ranges: vec![range],
+            sym_type: sym.sym_type(),
+            file_offset: 0,
+            obj_file_name: None,
+            module: sym.module.clone(),
         }
     }
 
+    /// Find all symbols whose names match the glob `pattern`.
+    pub fn find_addr_glob(&self, pattern: &str, opts: &FindAddrOpts) -> Vec<SymInfo> {
+        self.syms
+            .iter()
+            .enumerate()
+            .filter(|(_idx, sym)| {
+                type_matches(opts.sym_type, sym) && glob_match(pattern, &sym.name)
+            })
+            .map(|(idx, _sym)| self.sym_info(idx))
+            .collect()
+    }
+
     pub fn find_addresses_ksym(&self, addr: Addr) -> impl Iterator<Item = &Ksym> {
         let mut l = 0;
         let mut r = self.syms.len();
@@ -106,22 +197,31 @@ impl KSymResolver {
             (l == 0 || l >= self.syms.len())
                 || (self.syms[l - 1].addr <= addr && addr < self.syms[l].addr)
         );
+        let sym_addr = l.checked_sub(1).map(|idx| self.syms[idx].addr);
+        // Addresses we are asked to symbolize are code addresses (e.g.,
+        // from a stack trace). If the closest symbol is a data symbol, the
+        // address is not inside a function we know about.
         self.syms[0..l]
             .iter()
             .rev()
-            .take_while(move |sym| sym.addr == self.syms[l - 1].addr)
+            .take_while(move |sym| Some(sym.addr) == sym_addr)
+            .filter(|sym| sym.is_code())
     }
 
+    /// A straightforward reference implementation of
+    /// [`find_addresses_ksym`][Self::find_addresses_ksym].
     #[cfg(test)]
     pub fn find_addresses_ksym_simple(&self, addr: Addr) -> impl Iterator<Item = &Ksym> {
-        let mut i = 0;
-        while i < self.syms.len() && addr >= self.syms[i].addr {
-            i += 1;
-        }
-        self.syms[..i]
+        let sym_addr = self
+            .syms
+            .iter()
+            .map(|sym| sym.addr)
+            .filter(|sym_addr| *sym_addr <= addr)
+            .max();
+        self.syms
             .iter()
             .rev()
-            .take_while(move |x| x.addr == self.syms[i - 1].addr)
+            .filter(move |sym| Some(sym.addr) == sym_addr && sym.sym_type() != SymType::Variable)
     }
 }
 
@@ -133,27 +233,26 @@ impl SymResolver for KSymResolver {
     }
 
     fn find_addr(&self, name: &str, opts: &FindAddrOpts) -> Option<Vec<SymInfo>> {
-        if let SymType::Variable = opts.sym_type {
-            return None
-        }
-        self.ensure_sym_to_addr();
-
-        self.sym_to_addr.borrow().get(name).map(|addr| {
-            vec![SymInfo {
-                name: name.to_string(),
-                addr: *addr,
-                size: 0,
-                sym_type: SymType::Function,
-                file_offset: 0,
-                obj_file_name: None,
-            }]
-        })
+        self.ensure_sym_to_idx();
+
+        let sym_to_idx = self.sym_to_idx.lock().unwrap();
+        let syms = sym_to_idx
+            .get(name)?
+            .iter()
+            .filter(|idx| type_matches(opts.sym_type, &self.syms[**idx]))
+            .map(|idx| self.sym_info(*idx))
+            .collect::<Vec<_>>();
+        (!syms.is_empty()).then_some(syms)
     }
 
     fn find_line_info(&self, _addr: Addr) -> Option<AddrLineInfo> {
         None
     }
 
+    fn find_inlined_fns(&self, _addr: Addr) -> Vec<InlinedFn> {
+        Vec::new()
+    }
+
     fn addr_file_off(&self, _addr: Addr) -> Option<u64> {
         None
     }
@@ -175,25 +274,25 @@ impl Debug for KSymResolver {
 /// It returns the same instance if path is the same.
 #[derive(Debug)]
 pub struct KSymCache {
-    resolvers: RefCell<HashMap<PathBuf, Rc<KSymResolver>>>,
+    resolvers: Mutex<HashMap<PathBuf, Arc<KSymResolver>>>,
 }
 
 impl KSymCache {
     pub fn new() -> KSymCache {
         KSymCache {
-            resolvers: RefCell::new(HashMap::new()),
+            resolvers: Mutex::new(HashMap::new()),
         }
     }
 
     /// Find an instance of KSymResolver from the cache or create a new one.
-    pub fn get_resolver(&self, path: &Path) -> Result<Rc<KSymResolver>> {
-        let mut resolvers = self.resolvers.borrow_mut();
+    pub fn get_resolver(&self, path: &Path) -> Result<Arc<KSymResolver>> {
+        let mut resolvers = self.resolvers.lock().unwrap();
         if let Some(resolver) = resolvers.get(path) {
             return Ok(resolver.clone())
         }
 
         let resolver = KSymResolver::load_file_name(path.to_path_buf())?;
-        let resolver = Rc::new(resolver);
+        let resolver = Arc::new(resolver);
         resolvers.insert(path.to_path_buf(), resolver.clone());
         Ok(resolver)
     }
@@ -204,6 +303,9 @@ mod tests {
     use super::*;
 
     use std::cmp::Ordering;
+    use std::io::Write as _;
+
+    use tempfile::NamedTempFile;
 
     use test_log::test;
 
@@ -274,6 +376,97 @@ mod tests {
         assert!(resolver1.is_ok());
     }
 
+    /// Check that we correctly parse the module a symbol belongs to.
+    #[test]
+    fn module_parsing() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(
+            file,
+            "ffffffff81000000 T _stext
+ffffffffc0a01000 t kmod_add\t[test_kmod]
+ffffffffc0a01020 T kmod_work\t[test_kmod]
+ffffffffc0a02000 t bpf_prog_d35c0e439a122564_handle_exec\t[bpf]
+ffffffffc0a03000 t __bpf_trampoline\t[__builtin__ftrace]"
+        )
+        .unwrap();
+
+        let resolver = KSymResolver::load_file_name(file.path().to_path_buf()).unwrap();
+        let sym = resolver
+            .find_addresses_ksym(0xffffffff81000010)
+            .next()
+            .unwrap();
+        assert_eq!(sym.name, "_stext");
+        assert_eq!(sym.module, None);
+
+        let sym = resolver
+            .find_addresses_ksym(0xffffffffc0a01004)
+            .next()
+            .unwrap();
+        assert_eq!(sym.name, "kmod_add");
+        assert_eq!(sym.module.as_deref(), Some("test_kmod"));
+
+        let sym = resolver
+            .find_addresses_ksym(0xffffffffc0a02004)
+            .next()
+            .unwrap();
+        assert_eq!(sym.name, "bpf_prog_d35c0e439a122564_handle_exec");
+        assert_eq!(sym.module, None);
+
+        let sym = resolver
+            .find_addresses_ksym(0xffffffffc0a03004)
+            .next()
+            .unwrap();
+        assert_eq!(sym.module, None);
+    }
+
+    /// Check that malformed lines are skipped and that data symbols are
+    /// not used for symbolizing addresses.
+    #[test]
+    fn data_symbol_skipping() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(
+            file,
+            "ffffffff81000000 T _stext
+ffffffff81000100 t vfs_read
+
+ffffffff81000180 D
+ffffffff81000180 d read_lock
+ffffffff81000200 B read_count
+ffffffff81000300 T vfs_write	[ext4]"
+        )
+        .unwrap();
+
+        let resolver = KSymResolver::load_file_name(file.path().to_path_buf()).unwrap();
+        assert_eq!(resolver.syms.len(), 5);
+
+        let sym = resolver
+            .find_addresses_ksym(0xffffffff81000110)
+            .next()
+            .unwrap();
+        assert_eq!(sym.name, "vfs_read");
+        assert_eq!(sym.sym_type(), SymType::Function);
+
+        // The address is inside of a data object and must not be
+        // attributed to the function preceding it.
+        let syms = resolver.find_symbols(0xffffffff81000240);
+        assert_eq!(syms, Vec::new());
+        assert_eq!(resolver.find_addresses_ksym(0xffffffff81000180).count(), 0);
+
+        let sym = resolver
+            .find_addresses_ksym(0xffffffff81000304)
+            .next()
+            .unwrap();
+        assert_eq!(sym.name, "vfs_write");
+        assert_eq!(sym.module.as_deref(), Some("ext4"));
+
+        let sym = &resolver.syms[2];
+        assert_eq!(sym.name, "read_lock");
+        assert_eq!(sym.sym_type(), SymType::Variable);
+        let sym = &resolver.syms[3];
+        assert_eq!(sym.name, "read_count");
+        assert_eq!(sym.sym_type(), SymType::Variable);
+    }
+
     #[test]
     fn find_addresses_ksym() {
         let resolver = KSymResolver {
@@ -281,21 +474,29 @@ mod tests {
                 Ksym {
                     addr: 0x123,
                     name: "1".to_string(),
+                    typ: 'T',
+                    module: None,
                 },
                 Ksym {
                     addr: 0x123,
                     name: "1.5".to_string(),
+                    typ: 'T',
+                    module: None,
                 },
                 Ksym {
                     addr: 0x1234,
                     name: "2".to_string(),
+                    typ: 'T',
+                    module: None,
                 },
                 Ksym {
                     addr: 0x12345,
                     name: "3".to_string(),
+                    typ: 'T',
+                    module: None,
                 },
             ],
-            sym_to_addr: RefCell::default(),
+            sym_to_idx: Mutex::default(),
             file_name: PathBuf::new(),
         };
 
@@ -351,9 +552,13 @@ mod tests {
                 .map(|x| Ksym {
                     addr: 1,
                     name: x.to_string(),
+                    // Mix in some data symbols, which should never be
+                    // reported.
+                    typ: if x % 3 == 0 { 'd' } else { 'T' },
+                    module: None,
                 })
                 .collect(),
-            sym_to_addr: RefCell::default(),
+            sym_to_idx: Mutex::default(),
             file_name: PathBuf::new(),
         };
 
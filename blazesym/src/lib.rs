@@ -15,6 +15,9 @@
 //! - [`inspect`] contains APIs for inspecting files such as ELF and Gsym to
 //!   lookup addresses to symbol names, for example
 //! - [`normalize`] exposes address normalization functionality
+//! - [`snippet`] provides means for retrieving source code surrounding
+//!   symbolized locations
+//! - [`gsym`] provides means for creating Gsym files
 //!
 //! C API bindings are defined in a cross-cutting manner as part of the
 //! [`c_api`] module (note that Rust code should not have to consume these
@@ -30,21 +33,24 @@
 #[cfg(feature = "nightly")]
 extern crate test;
 
+mod bpf;
 pub mod c_api;
+mod demangle;
 mod dwarf;
 mod elf;
-mod gsym;
+pub mod gsym;
 pub mod inspect;
 mod kernel;
 mod ksym;
 mod maps;
 mod mmap;
 pub mod normalize;
+mod perf_map;
 mod resolver;
+pub mod snippet;
 pub mod symbolize;
 mod util;
-// TODO: Remove `allow`.
-#[allow(unused)]
+mod vdso;
 mod zip;
 
 use std::fmt::Display;
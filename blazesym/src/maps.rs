@@ -6,8 +6,11 @@ use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Result;
 use std::ops::Range;
+use std::os::unix::ffi::OsStrExt as _;
+use std::path::Path;
 use std::path::PathBuf;
 
+use crate::vdso::VDSO_MAPS_COMPONENT;
 use crate::Addr;
 use crate::Pid;
 
@@ -225,6 +228,40 @@ pub(crate) fn parse(pid: Pid) -> Result<impl Iterator<Item = Result<MapsEntry>>>
     Ok(iter)
 }
 
+/// Check whether a `MapsEntry` represents executable memory not backed
+/// by a regular file, as is typically used for JIT-ed code.
+pub(crate) fn is_anon_exec(entry: &MapsEntry) -> bool {
+    if (entry.mode & 0b0010) != 0b0010 {
+        return false
+    }
+
+    match &entry.path_name {
+        None => true,
+        // Android names anonymous mappings, e.g.,
+        // `[anon:dalvik-jit-code-cache]`.
+        Some(PathName::Component(comp)) => comp.starts_with("[anon:"),
+        Some(PathName::Path(path)) => is_memfd(&path.symbolic_path),
+    }
+}
+
+/// Check whether a `MapsEntry` represents the process' vDSO.
+pub(crate) fn is_vdso(entry: &MapsEntry) -> bool {
+    if (entry.mode & 0b0010) != 0b0010 {
+        return false
+    }
+
+    matches!(
+        &entry.path_name,
+        Some(PathName::Component(comp)) if comp == VDSO_MAPS_COMPONENT
+    )
+}
+
+/// Check whether `path` refers to a `memfd_create(2)` file, which some
+/// runtimes use for their JIT code caches.
+pub(crate) fn is_memfd(path: &Path) -> bool {
+    path.as_os_str().as_bytes().starts_with(b"/memfd:")
+}
+
 /// A helper function checking whether a `MapsEntry` has relevance to
 /// symbolization efforts and converting it accordingly.
 pub(crate) fn filter_map_relevant(entry: MapsEntry) -> Option<PathMapsEntry> {
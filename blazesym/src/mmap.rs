@@ -5,10 +5,12 @@ use std::io::Result;
 use std::ops::Deref;
 use std::ops::Range;
 use std::os::unix::io::AsRawFd;
+#[cfg(test)]
 use std::path::Path;
+use std::ptr;
 use std::ptr::null_mut;
-use std::rc::Rc;
 use std::slice;
+use std::sync::Arc;
 
 
 #[derive(Debug)]
@@ -32,6 +34,7 @@ impl Builder {
     }
 
     /// Memory map the file at the provided `path`.
+    #[cfg(test)]
     pub fn open<P>(self, path: P) -> Result<Mmap>
     where
         P: AsRef<Path>,
@@ -64,7 +67,7 @@ impl Builder {
 
         let mapping = Mapping { ptr, len };
         let mmap = Mmap {
-            mapping: Rc::new(mapping),
+            mapping: Arc::new(mapping),
             view: 0..len,
         };
         Ok(mmap)
@@ -78,6 +81,14 @@ pub(crate) struct Mapping {
     len: usize,
 }
 
+// SAFETY: The mapping is read-only (or at least never written to by us)
+//         and its address does not change for as long as it exists.
+//         It can hence safely be shared between and sent to other
+//         threads.
+unsafe impl Send for Mapping {}
+// SAFETY: See above.
+unsafe impl Sync for Mapping {}
+
 impl Deref for Mapping {
     type Target = [u8];
 
@@ -100,7 +111,7 @@ impl Drop for Mapping {
 #[derive(Clone, Debug)]
 pub(crate) struct Mmap {
     /// The actual memory mapping.
-    mapping: Rc<Mapping>,
+    mapping: Arc<Mapping>,
     /// The view on the memory mapping that this object represents.
     view: Range<usize>,
 }
@@ -116,10 +127,49 @@ impl Mmap {
         Self::builder().map(file)
     }
 
+    /// Create an anonymous, read-only memory mapping containing a copy
+    /// of `data`.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        // Zero sized mappings are not supported, so always map at least
+        // one byte.
+        let len = data.len().max(1);
+        // SAFETY: `mmap` with the provided arguments is always safe to call.
+        let ptr = unsafe {
+            libc::mmap(
+                null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::last_os_error())
+        }
+
+        let mapping = Mapping { ptr, len };
+        // SAFETY: The mapping is valid for `len` bytes, which is at least
+        //         `data.len()`, and it does not overlap with `data`.
+        let () = unsafe { ptr::copy_nonoverlapping(data.as_ptr(), ptr.cast::<u8>(), data.len()) };
+        // SAFETY: `mprotect` with the provided arguments is always safe to
+        //         call.
+        let rc = unsafe { libc::mprotect(ptr, len, libc::PROT_READ) };
+        if rc != 0 {
+            return Err(Error::last_os_error())
+        }
+
+        let mmap = Mmap {
+            mapping: Arc::new(mapping),
+            view: 0..data.len(),
+        };
+        Ok(mmap)
+    }
+
     /// Create a new `Mmap` object (sharing the same underlying memory mapping
     /// as the current one) that restricts its view to the provided `range`.
     /// Adjustment happens relative to the current view.
-    #[cfg(test)]
     pub fn constrain(&self, range: Range<usize>) -> Option<Self> {
         if self.view.start + range.end > self.view.end {
             return None
@@ -171,6 +221,16 @@ mod tests {
         );
     }
 
+    /// Check that we can create a `Mmap` from in-memory data.
+    #[test]
+    fn mmap_from_bytes() {
+        let mmap = Mmap::from_bytes(b"abcdefghijklmnopqrstuvwxyz").unwrap();
+        assert_eq!(mmap.deref(), b"abcdefghijklmnopqrstuvwxyz");
+
+        let mmap = Mmap::from_bytes(&[]).unwrap();
+        assert_eq!(mmap.deref(), b"");
+    }
+
     /// Check that we can properly restrict the view of a `Mmap`.
     #[test]
     fn view_constraining() {
@@ -0,0 +1,513 @@
+//! A compact and versioned binary encoding of normalized user addresses.
+//!
+//! The encoding is meant for transferring [`NormalizedUserAddrs`] from
+//! the system on which addresses were captured to one where they get
+//! symbolized. All integers are stored in little endian byte order. An
+//! encoded object has the following layout:
+//!
+//! ```text
+//! header:
+//!   magic       [u8; 4]   "BZNA"
+//!   version     u16       currently 1
+//!   reserved    u16       zero
+//!   addr_count  u64
+//!   meta_count  u64
+//! addrs (addr_count fixed size records):
+//!   addr        u64
+//!   meta_idx    u32
+//! metas (meta_count variable size records):
+//!   kind        u8        0: unknown, 1: binary, 2: vDSO
+//!   [binary]    path_len u32, path [u8; path_len], build ID
+//!   [vDSO]      build ID
+//! build ID:
+//!   len         u32       u32::MAX if not present
+//!   data        [u8; len]
+//! ```
+//!
+//! Because address records have a fixed size, they can be accessed
+//! without decoding the full object first, by means of a
+//! [`NormalizedUserAddrsReader`].
+
+use std::ffi::OsStr;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::mem::size_of;
+use std::os::unix::ffi::OsStrExt as _;
+use std::path::Path;
+
+use crate::util::ReadRaw as _;
+use crate::Addr;
+
+use super::meta::Binary;
+use super::meta::Unknown;
+use super::meta::UserAddrMeta;
+use super::meta::Vdso;
+use super::normalizer::NormalizedUserAddrs;
+
+
+/// The magic bytes identifying an encoded object.
+const MAGIC: [u8; 4] = *b"BZNA";
+/// The version of the encoding produced.
+const VERSION: u16 = 1;
+/// The size of the header, in bytes.
+const HEADER_SIZE: usize = 4 + 2 + 2 + 8 + 8;
+/// The size of a single address record, in bytes.
+const ADDR_RECORD_SIZE: usize = size_of::<u64>() + size_of::<u32>();
+
+/// Meta kind identifier for [`Unknown`].
+const KIND_UNKNOWN: u8 = 0;
+/// Meta kind identifier for [`Binary`].
+const KIND_BINARY: u8 = 1;
+/// Meta kind identifier for [`Vdso`].
+const KIND_VDSO: u8 = 2;
+/// The length marker used for an absent build ID.
+const NO_BUILD_ID: u32 = u32::MAX;
+
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid normalized address data: {msg}"),
+    )
+}
+
+fn usize_from_u64(value: u64) -> Result<usize> {
+    usize::try_from(value).map_err(|_| invalid_data("count does not fit into usize"))
+}
+
+fn u32_from_usize(value: usize) -> Result<u32> {
+    // `u32::MAX` is reserved as a marker for absent build IDs.
+    u32::try_from(value)
+        .ok()
+        .filter(|value| *value != NO_BUILD_ID)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "value too large to be encoded"))
+}
+
+
+fn write_build_id(buf: &mut Vec<u8>, build_id: Option<&[u8]>) -> Result<()> {
+    match build_id {
+        Some(build_id) => {
+            let () = buf.extend_from_slice(&u32_from_usize(build_id.len())?.to_le_bytes());
+            let () = buf.extend_from_slice(build_id);
+        }
+        None => buf.extend_from_slice(&NO_BUILD_ID.to_le_bytes()),
+    }
+    Ok(())
+}
+
+fn write_meta(buf: &mut Vec<u8>, meta: &UserAddrMeta) -> Result<()> {
+    match meta {
+        UserAddrMeta::Binary(Binary { path, build_id, .. }) => {
+            let path = path.as_os_str().as_bytes();
+            let () = buf.push(KIND_BINARY);
+            let () = buf.extend_from_slice(&u32_from_usize(path.len())?.to_le_bytes());
+            let () = buf.extend_from_slice(path);
+            write_build_id(buf, build_id.as_deref())
+        }
+        UserAddrMeta::Vdso(Vdso { build_id, .. }) => {
+            let () = buf.push(KIND_VDSO);
+            write_build_id(buf, build_id.as_deref())
+        }
+        UserAddrMeta::Unknown(..) => {
+            let () = buf.push(KIND_UNKNOWN);
+            Ok(())
+        }
+    }
+}
+
+
+fn read_u32_le(data: &mut &[u8]) -> Result<u32> {
+    data.read_u32()
+        .map(u32::from_le)
+        .ok_or_else(|| invalid_data("unexpected end of data"))
+}
+
+fn read_u64_le(data: &mut &[u8]) -> Result<u64> {
+    data.read_u64()
+        .map(u64::from_le)
+        .ok_or_else(|| invalid_data("unexpected end of data"))
+}
+
+fn read_slice<'data>(data: &mut &'data [u8], len: usize) -> Result<&'data [u8]> {
+    data.read_slice(len)
+        .ok_or_else(|| invalid_data("unexpected end of data"))
+}
+
+fn read_build_id<'data>(data: &mut &'data [u8]) -> Result<Option<&'data [u8]>> {
+    let len = read_u32_le(data)?;
+    if len == NO_BUILD_ID {
+        Ok(None)
+    } else {
+        read_slice(data, len as usize).map(Some)
+    }
+}
+
+fn read_meta<'data>(data: &mut &'data [u8]) -> Result<UserAddrMetaRef<'data>> {
+    let kind = data
+        .read_u8()
+        .ok_or_else(|| invalid_data("unexpected end of data"))?;
+    let meta = match kind {
+        KIND_UNKNOWN => UserAddrMetaRef::Unknown,
+        KIND_BINARY => {
+            let len = read_u32_le(data)? as usize;
+            let path = Path::new(OsStr::from_bytes(read_slice(data, len)?));
+            let build_id = read_build_id(data)?;
+            UserAddrMetaRef::Binary { path, build_id }
+        }
+        KIND_VDSO => UserAddrMetaRef::Vdso {
+            build_id: read_build_id(data)?,
+        },
+        _ => return Err(invalid_data(&format!("unknown meta kind {kind}"))),
+    };
+    Ok(meta)
+}
+
+
+/// A borrowed view of a [`UserAddrMeta`] object, as provided by a
+/// [`NormalizedUserAddrsReader`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum UserAddrMetaRef<'data> {
+    /// A reference to a [`Binary`].
+    Binary {
+        /// The path to the binary.
+        path: &'data Path,
+        /// The binary's build ID, if available.
+        build_id: Option<&'data [u8]>,
+    },
+    /// A reference to a [`Vdso`].
+    Vdso {
+        /// The vDSO's build ID, if available.
+        build_id: Option<&'data [u8]>,
+    },
+    /// A reference to an [`Unknown`].
+    Unknown,
+}
+
+impl UserAddrMetaRef<'_> {
+    /// Convert this object into an owned [`UserAddrMeta`].
+    pub fn to_meta(&self) -> UserAddrMeta {
+        match self {
+            Self::Binary { path, build_id } => UserAddrMeta::Binary(Binary {
+                path: path.to_path_buf(),
+                build_id: build_id.map(<[u8]>::to_vec),
+                _non_exhaustive: (),
+            }),
+            Self::Vdso { build_id } => UserAddrMeta::Vdso(Vdso {
+                build_id: build_id.map(<[u8]>::to_vec),
+                _non_exhaustive: (),
+            }),
+            Self::Unknown => UserAddrMeta::Unknown(Unknown::default()),
+        }
+    }
+}
+
+
+/// A zero-copy reader for encoded [`NormalizedUserAddrs`].
+///
+/// Meta information is parsed eagerly on construction, while addresses
+/// are only read when accessed. Hence, this type is suitable for
+/// processing large batches of addresses without materializing them
+/// all in memory.
+#[derive(Clone, Debug)]
+pub struct NormalizedUserAddrsReader<'data> {
+    /// The raw address records.
+    addrs: &'data [u8],
+    /// The parsed meta information.
+    metas: Vec<UserAddrMetaRef<'data>>,
+}
+
+impl<'data> NormalizedUserAddrsReader<'data> {
+    /// Create a new reader for the provided encoded data, as produced by
+    /// [`NormalizedUserAddrs::encode`].
+    pub fn new(mut data: &'data [u8]) -> Result<Self> {
+        let header = read_slice(&mut data, HEADER_SIZE)
+            .map_err(|_| invalid_data("data too small to contain header"))?;
+        let (magic, mut header) = header.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(invalid_data("magic mismatch"))
+        }
+
+        let version = u16::from_le(header.read_u16().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(&format!("unsupported version {version}")))
+        }
+        let _reserved = header.read_u16().unwrap();
+        let addr_count = usize_from_u64(read_u64_le(&mut header)?)?;
+        let meta_count = usize_from_u64(read_u64_le(&mut header)?)?;
+
+        let addrs_size = addr_count
+            .checked_mul(ADDR_RECORD_SIZE)
+            .ok_or_else(|| invalid_data("address count too large"))?;
+        let addrs = read_slice(&mut data, addrs_size)?;
+
+        // Each meta record occupies at least one byte, so bound the
+        // allocation by the remaining data size.
+        let mut metas = Vec::with_capacity(meta_count.min(data.len()));
+        for _ in 0..meta_count {
+            let () = metas.push(read_meta(&mut data)?);
+        }
+        if !data.is_empty() {
+            return Err(invalid_data("trailing data"))
+        }
+
+        let slf = Self { addrs, metas };
+        // Validate all meta indices up front, so that accessors do not
+        // have to report errors.
+        if slf
+            .addrs()
+            .any(|(_addr, meta_idx)| meta_idx >= slf.metas.len())
+        {
+            return Err(invalid_data("meta index out of bounds"))
+        }
+        Ok(slf)
+    }
+
+    /// Retrieve the number of addresses.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.addrs.len() / ADDR_RECORD_SIZE
+    }
+
+    /// Check whether there are no addresses.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+
+    /// Retrieve the normalized address at index `idx` along with the
+    /// index of its meta information.
+    pub fn addr(&self, idx: usize) -> Option<(Addr, usize)> {
+        let start = idx.checked_mul(ADDR_RECORD_SIZE)?;
+        let mut record = self.addrs.get(start..start + ADDR_RECORD_SIZE)?;
+        let addr = u64::from_le(record.read_u64()?);
+        let meta_idx = u32::from_le(record.read_u32()?);
+        Some((addr as Addr, meta_idx as usize))
+    }
+
+    /// Iterate over all normalized addresses along with the indices of
+    /// their meta information.
+    pub fn addrs(&self) -> impl ExactSizeIterator<Item = (Addr, usize)> + '_ {
+        (0..self.len()).map(|idx| self.addr(idx).unwrap())
+    }
+
+    /// Retrieve the meta information at index `idx`.
+    #[inline]
+    pub fn meta(&self, idx: usize) -> Option<&UserAddrMetaRef<'data>> {
+        self.metas.get(idx)
+    }
+
+    /// Retrieve all meta information.
+    #[inline]
+    pub fn metas(&self) -> &[UserAddrMetaRef<'data>] {
+        &self.metas
+    }
+
+    /// Convert the encoded data into an owned [`NormalizedUserAddrs`]
+    /// object.
+    pub fn to_normalized(&self) -> NormalizedUserAddrs {
+        NormalizedUserAddrs {
+            addrs: self.addrs().collect(),
+            meta: self.metas.iter().map(UserAddrMetaRef::to_meta).collect(),
+        }
+    }
+}
+
+
+impl NormalizedUserAddrs {
+    /// Encode this object in a compact binary format.
+    ///
+    /// The result can be decoded using [`NormalizedUserAddrs::decode`]
+    /// or accessed directly via a [`NormalizedUserAddrsReader`], on
+    /// any system.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let meta_count = self.meta.len();
+        // Meta indices are encoded as `u32`, so we can't reference more
+        // entries than that.
+        if u32::try_from(meta_count).is_err() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("meta count {meta_count} too large to be encoded"),
+            ))
+        }
+
+        let mut buf = Vec::with_capacity(HEADER_SIZE + self.addrs.len() * ADDR_RECORD_SIZE);
+        let () = buf.extend_from_slice(&MAGIC);
+        let () = buf.extend_from_slice(&VERSION.to_le_bytes());
+        let () = buf.extend_from_slice(&0u16.to_le_bytes());
+        let () = buf.extend_from_slice(&(self.addrs.len() as u64).to_le_bytes());
+        let () = buf.extend_from_slice(&(meta_count as u64).to_le_bytes());
+
+        for (addr, meta_idx) in &self.addrs {
+            if *meta_idx >= meta_count {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("meta index {meta_idx} out of bounds"),
+                ))
+            }
+            let meta_idx = u32::try_from(*meta_idx).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("meta index {meta_idx} too large to be encoded"),
+                )
+            })?;
+            let () = buf.extend_from_slice(&(*addr as u64).to_le_bytes());
+            let () = buf.extend_from_slice(&meta_idx.to_le_bytes());
+        }
+
+        for meta in &self.meta {
+            let () = write_meta(&mut buf, meta)?;
+        }
+        Ok(buf)
+    }
+
+    /// Decode an object previously encoded using
+    /// [`NormalizedUserAddrs::encode`].
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        NormalizedUserAddrsReader::new(data).map(|reader| reader.to_normalized())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use test_log::test;
+
+
+    fn make_addrs() -> NormalizedUserAddrs {
+        NormalizedUserAddrs {
+            addrs: vec![(0x1000, 0), (0x2345, 1), (0xffff_ffff, 2), (0x10, 0)],
+            meta: vec![
+                UserAddrMeta::Binary(Binary {
+                    path: PathBuf::from("/usr/lib/libc.so.6"),
+                    build_id: Some(vec![0xde, 0xad, 0xbe, 0xef]),
+                    _non_exhaustive: (),
+                }),
+                UserAddrMeta::Vdso(Vdso {
+                    build_id: None,
+                    _non_exhaustive: (),
+                }),
+                UserAddrMeta::Unknown(Unknown::default()),
+            ],
+        }
+    }
+
+    /// Check that we can encode and decode normalized addresses.
+    #[test]
+    fn encode_decode() {
+        let addrs = make_addrs();
+        let data = addrs.encode().unwrap();
+        let decoded = NormalizedUserAddrs::decode(&data).unwrap();
+        assert_eq!(decoded.addrs, addrs.addrs);
+        assert_eq!(decoded.meta, addrs.meta);
+
+        let empty = NormalizedUserAddrs {
+            addrs: Vec::new(),
+            meta: Vec::new(),
+        };
+        let data = empty.encode().unwrap();
+        assert_eq!(data.len(), HEADER_SIZE);
+        let decoded = NormalizedUserAddrs::decode(&data).unwrap();
+        assert!(decoded.addrs.is_empty());
+        assert!(decoded.meta.is_empty());
+    }
+
+    /// Check that the [`NormalizedUserAddrsReader`] provides access to
+    /// encoded data.
+    #[test]
+    fn reader_access() {
+        let addrs = make_addrs();
+        let data = addrs.encode().unwrap();
+        let reader = NormalizedUserAddrsReader::new(&data).unwrap();
+        assert_eq!(reader.len(), 4);
+        assert!(!reader.is_empty());
+        assert_eq!(reader.addr(1), Some((0x2345, 1)));
+        assert_eq!(reader.addr(4), None);
+        assert_eq!(reader.addrs().collect::<Vec<_>>(), addrs.addrs);
+
+        assert_eq!(reader.metas().len(), 3);
+        assert_eq!(
+            reader.meta(0),
+            Some(&UserAddrMetaRef::Binary {
+                path: Path::new("/usr/lib/libc.so.6"),
+                build_id: Some(&[0xde, 0xad, 0xbe, 0xef]),
+            })
+        );
+        assert_eq!(
+            reader.meta(1),
+            Some(&UserAddrMetaRef::Vdso { build_id: None })
+        );
+        assert_eq!(reader.meta(2), Some(&UserAddrMetaRef::Unknown));
+        assert_eq!(reader.meta(3), None);
+
+        assert_eq!(reader.meta(0).unwrap().to_meta(), addrs.meta[0]);
+        let owned = reader.to_normalized();
+        assert_eq!(owned.addrs, addrs.addrs);
+        assert_eq!(owned.meta, addrs.meta);
+    }
+
+    /// Make sure that we reject malformed data.
+    #[test]
+    fn invalid_data_decoding() {
+        let data = make_addrs().encode().unwrap();
+
+        let mut bad_magic = data.clone();
+        bad_magic[0] = b'X';
+        let err = NormalizedUserAddrs::decode(&bad_magic).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut bad_version = data.clone();
+        bad_version[4] = 2;
+        let err = NormalizedUserAddrs::decode(&bad_version).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        for len in 0..data.len() {
+            let err = NormalizedUserAddrs::decode(&data[..len]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+
+        let mut trailing = data.clone();
+        let () = trailing.push(0);
+        let err = NormalizedUserAddrs::decode(&trailing).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Point the first address at a non-existent meta entry.
+        let mut bad_idx = data;
+        bad_idx[HEADER_SIZE + 8] = 42;
+        let err = NormalizedUserAddrs::decode(&bad_idx).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// Check that we refuse to encode addresses referencing
+    /// non-existent meta information.
+    #[test]
+    fn invalid_meta_idx_encoding() {
+        let mut addrs = make_addrs();
+        addrs.addrs[0].1 = 3;
+        let err = addrs.encode().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // An index that would be truncated to a valid one when encoded
+        // as `u32` must be rejected as well.
+        addrs.addrs[0].1 = u32::MAX as usize + 1;
+        let err = addrs.encode().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    /// Check that normalized addresses can be serialized and
+    /// deserialized using serde.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let addrs = make_addrs();
+        let json = serde_json::to_string(&addrs).unwrap();
+        let decoded = serde_json::from_str::<NormalizedUserAddrs>(&json).unwrap();
+        assert_eq!(decoded.addrs, addrs.addrs);
+        assert_eq!(decoded.meta, addrs.meta);
+    }
+}
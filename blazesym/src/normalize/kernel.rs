@@ -0,0 +1,410 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::read as read_file;
+use std::fs::read_dir;
+use std::fs::read_to_string;
+use std::fs::File;
+use std::io::BufRead as _;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::path::Path;
+
+use crate::elf::types::NT_GNU_BUILD_ID;
+use crate::elf::NoteIter;
+use crate::kernel::parse_modules;
+use crate::kernel::LoadedModule;
+use crate::kernel::PROC_MODULES;
+use crate::ksym::KALLSYMS;
+use crate::log::warn;
+use crate::Addr;
+
+use super::meta::Kernel;
+use super::meta::KernelAddrMeta;
+use super::meta::KernelModule;
+use super::normalizer::NormalizedAddrs;
+
+
+/// A type representing normalized kernel addresses.
+pub type NormalizedKernelAddrs = NormalizedAddrs<KernelAddrMeta>;
+
+
+/// The locations of the files describing the running kernel.
+#[derive(Debug)]
+pub(crate) struct KernelPaths<'path> {
+    /// The path to the kernel's symbols, in the format of
+    /// `/proc/kallsyms`.
+    pub kallsyms: &'path Path,
+    /// The path to the list of loaded kernel modules, in the format of
+    /// `/proc/modules`.
+    pub modules: &'path Path,
+    /// The path at which sysfs is mounted.
+    pub sysfs: &'path Path,
+}
+
+impl Default for KernelPaths<'_> {
+    fn default() -> Self {
+        Self {
+            kallsyms: Path::new(KALLSYMS),
+            modules: Path::new(PROC_MODULES),
+            sysfs: Path::new("/sys"),
+        }
+    }
+}
+
+
+/// Find the run time address range of the kernel image, as described
+/// by the `_text` (or, lacking that, `_stext`) and `_end` symbols in
+/// `kallsyms`.
+///
+/// `None` is returned if the start of the image is unknown, which is
+/// also the case if kallsyms addresses are hidden from the caller.
+fn find_kernel_image(kallsyms: &Path) -> Result<Option<(Addr, Option<Addr>)>> {
+    let file = File::open(kallsyms)?;
+    let mut text = None;
+    let mut stext = None;
+    let mut end = None;
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        let (addr, name) = match (tokens.next(), tokens.next(), tokens.next()) {
+            (Some(addr), Some(_typ), Some(name)) => (addr, name),
+            _ => continue,
+        };
+        let slot = match name {
+            "_text" => &mut text,
+            "_stext" => &mut stext,
+            "_end" => &mut end,
+            _ => continue,
+        };
+        *slot = Addr::from_str_radix(addr, 16)
+            .ok()
+            .filter(|addr| *addr != 0);
+
+        if text.is_some() && end.is_some() {
+            break
+        }
+    }
+
+    Ok(text.or(stext).map(|start| (start, end)))
+}
+
+/// Read the GNU build ID from a sysfs file containing ELF notes.
+fn read_build_id_notes(path: &Path) -> Result<Option<Vec<u8>>> {
+    match read_file(path) {
+        Ok(data) => {
+            // Notes exposed by the kernel are four byte aligned.
+            let build_id = NoteIter::new(&data, 4)
+                .find(|note| note.typ == NT_GNU_BUILD_ID && note.name == b"GNU")
+                .map(|note| note.desc.to_vec());
+            Ok(build_id)
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Read the load addresses of the sections of a kernel module from its
+/// sysfs `sections` directory.
+///
+/// Sections are returned sorted by address. Sections that cannot be
+/// read or that are reported at address zero, both of which may be the
+/// case for unprivileged readers, are skipped.
+fn read_module_sections(dir: &Path) -> Result<Vec<(Addr, String)>> {
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut sections = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        // Section files are only readable by privileged users.
+        let content = match read_to_string(entry.path()) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::PermissionDenied => continue,
+            Err(err) => return Err(err),
+        };
+        let addr = Addr::from_str_radix(content.trim().trim_start_matches("0x"), 16);
+        match addr {
+            Ok(addr) if addr != 0 => sections.push((addr, name)),
+            _ => continue,
+        }
+    }
+    let () = sections.sort();
+    Ok(sections)
+}
+
+
+/// Information about a loaded kernel module, as necessary for address
+/// normalization.
+#[derive(Debug)]
+struct ModuleInfo {
+    /// The module's build ID, if available.
+    build_id: Option<Vec<u8>>,
+    /// The module's sections, sorted by load address.
+    sections: Vec<(Addr, String)>,
+}
+
+impl ModuleInfo {
+    fn load(sysfs: &Path, name: &str) -> Result<Self> {
+        let dir = sysfs.join("module").join(name);
+        let build_id = read_build_id_notes(&dir.join("notes").join(".note.gnu.build-id"))?;
+        let sections = read_module_sections(&dir.join("sections"))?;
+        Ok(Self { build_id, sections })
+    }
+}
+
+
+/// Normalize kernel addresses using the information provided at
+/// `paths`.
+pub(crate) fn normalize_kernel_addrs_with_paths(
+    addrs: &[Addr],
+    paths: &KernelPaths<'_>,
+) -> Result<NormalizedKernelAddrs> {
+    let image = find_kernel_image(paths.kallsyms)?;
+    if image.is_none() {
+        warn!(
+            "failed to find kernel image in {}; kernel addresses cannot be normalized",
+            paths.kallsyms.display()
+        );
+    }
+
+    let modules = parse_modules(paths.modules).unwrap_or_else(|err| {
+        warn!(
+            "failed to read kernel modules from {}: {err}; ignoring...",
+            paths.modules.display()
+        );
+        Vec::new()
+    });
+
+    let mut normalized = NormalizedKernelAddrs {
+        addrs: Vec::with_capacity(addrs.len()),
+        meta: Vec::new(),
+    };
+    let mut unknown_idx = None;
+    let mut kernel_idx = None;
+    let mut module_infos = HashMap::<&str, ModuleInfo>::new();
+    // Module meta information is keyed by module name and the base
+    // address offsets are relative to.
+    let mut module_idxs = HashMap::<(&str, Addr), usize>::new();
+
+    for addr in addrs.iter().copied() {
+        if let Some(LoadedModule { name, range }) =
+            modules.iter().find(|module| module.range.contains(&addr))
+        {
+            let info = match module_infos.entry(name) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(ModuleInfo::load(paths.sysfs, name)?),
+            };
+
+            // Find the section with the highest load address not above
+            // `addr`. Lacking section information, we fall back to
+            // using the module's load address.
+            let (base, section) = info
+                .sections
+                .iter()
+                .rev()
+                .find(|(start, _name)| (range.start..=addr).contains(start))
+                .map(|(start, name)| (*start, Some(name.as_str())))
+                .unwrap_or((range.start, None));
+
+            let meta_idx = *module_idxs.entry((name, base)).or_insert_with(|| {
+                let module = KernelModule {
+                    name: name.clone(),
+                    section: section.map(str::to_string),
+                    build_id: info.build_id.clone(),
+                    _non_exhaustive: (),
+                };
+                let () = normalized.meta.push(KernelAddrMeta::Module(module));
+                normalized.meta.len() - 1
+            });
+            let () = normalized.addrs.push((addr - base, meta_idx));
+            continue
+        }
+
+        match image {
+            Some((start, end)) if addr >= start && end.map(|end| addr < end).unwrap_or(true) => {
+                let meta_idx = if let Some(meta_idx) = kernel_idx {
+                    meta_idx
+                } else {
+                    let kernel = Kernel {
+                        build_id: read_build_id_notes(&paths.sysfs.join("kernel").join("notes"))?,
+                        text_addr: start,
+                        _non_exhaustive: (),
+                    };
+                    let () = normalized.meta.push(KernelAddrMeta::Kernel(kernel));
+                    *kernel_idx.insert(normalized.meta.len() - 1)
+                };
+                let () = normalized.addrs.push((addr - start, meta_idx));
+            }
+            _ => {
+                unknown_idx = normalized.add_unknown_addr(addr, unknown_idx);
+            }
+        }
+    }
+
+    Ok(normalized)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::create_dir_all;
+    use std::fs::write as write_file;
+    use std::path::PathBuf;
+
+    use tempfile::tempdir;
+    use tempfile::TempDir;
+    use test_log::test;
+
+    use crate::elf::make_note;
+    use crate::normalize::Unknown;
+
+
+    /// Create a fake kernel environment inside a temporary directory.
+    fn make_kernel() -> (TempDir, PathBuf, PathBuf, PathBuf) {
+        let dir = tempdir().unwrap();
+        let kallsyms = dir.path().join("kallsyms");
+        let () = write_file(
+            &kallsyms,
+            "ffffffffa9000000 T _stext
+ffffffffa9000000 T _text
+ffffffffa9001000 T do_one_initcall
+ffffffffab000000 B _end
+ffffffffc0a01010 t test_fn\t[test_kmod]
+",
+        )
+        .unwrap();
+
+        let modules = dir.path().join("modules");
+        let () = write_file(
+            &modules,
+            "test_kmod 16384 0 - Live 0xffffffffc0a01000 (OE)
+hidden_kmod 8192 0 - Live 0xffffffffc0b00000
+",
+        )
+        .unwrap();
+
+        let sysfs = dir.path().join("sys");
+        let () = create_dir_all(sysfs.join("kernel")).unwrap();
+        let mut notes = make_note(b"Xen\0", 1, &[1, 2, 3, 4], 4);
+        let () = notes.extend(make_note(b"GNU\0", NT_GNU_BUILD_ID, &[0xaa, 0xbb, 0xcc], 4));
+        let () = write_file(sysfs.join("kernel").join("notes"), notes).unwrap();
+
+        let module = sysfs.join("module").join("test_kmod");
+        let () = create_dir_all(module.join("notes")).unwrap();
+        let () = create_dir_all(module.join("sections")).unwrap();
+        let () = write_file(
+            module.join("notes").join(".note.gnu.build-id"),
+            make_note(b"GNU\0", NT_GNU_BUILD_ID, &[0x12, 0x34], 4),
+        )
+        .unwrap();
+        let sections = module.join("sections");
+        let () = write_file(sections.join(".text"), "0xffffffffc0a01000\n").unwrap();
+        let () = write_file(sections.join(".rodata"), "0xffffffffc0a03000\n").unwrap();
+        let () = write_file(sections.join(".init.text"), "0x0000000000000000\n").unwrap();
+
+        (dir, kallsyms, modules, sysfs)
+    }
+
+    /// Check that we can normalize kernel addresses.
+    #[test]
+    fn kernel_addr_normalization() {
+        let (_dir, kallsyms, modules, sysfs) = make_kernel();
+        let paths = KernelPaths {
+            kallsyms: &kallsyms,
+            modules: &modules,
+            sysfs: &sysfs,
+        };
+
+        let addrs = [
+            0xffffffffa9001234,
+            0xffffffffc0a01010,
+            0xffffffffc0a03008,
+            0x1234,
+            0xffffffffc0b00020,
+            0xffffffffa9000000,
+            0xffffffffc0a01fff,
+            0xffffffffbb000000,
+        ];
+        let normalized = normalize_kernel_addrs_with_paths(&addrs, &paths).unwrap();
+        assert_eq!(
+            normalized.addrs,
+            vec![
+                (0x1234, 0),
+                (0x10, 1),
+                (0x8, 2),
+                (0x1234, 3),
+                (0x20, 4),
+                (0x0, 0),
+                (0xfff, 1),
+                (0xffffffffbb000000, 3),
+            ]
+        );
+
+        let kernel = normalized.meta[0].kernel().unwrap();
+        assert_eq!(kernel.build_id, Some(vec![0xaa, 0xbb, 0xcc]));
+        assert_eq!(kernel.text_addr, 0xffffffffa9000000);
+
+        let module = normalized.meta[1].module().unwrap();
+        assert_eq!(module.name, "test_kmod");
+        assert_eq!(module.section.as_deref(), Some(".text"));
+        assert_eq!(module.build_id, Some(vec![0x12, 0x34]));
+
+        let module = normalized.meta[2].module().unwrap();
+        assert_eq!(module.name, "test_kmod");
+        assert_eq!(module.section.as_deref(), Some(".rodata"));
+
+        assert_eq!(normalized.meta[3], KernelAddrMeta::from(Unknown::default()));
+
+        // Without any sysfs information, offsets are relative to the
+        // module's load address.
+        let module = normalized.meta[4].module().unwrap();
+        assert_eq!(module.name, "hidden_kmod");
+        assert_eq!(module.section, None);
+        assert_eq!(module.build_id, None);
+    }
+
+    /// Check that addresses are reported as unknown if the kernel's
+    /// symbol addresses are hidden.
+    #[test]
+    fn hidden_kallsyms_normalization() {
+        let dir = tempdir().unwrap();
+        let kallsyms = dir.path().join("kallsyms");
+        let () = write_file(&kallsyms, "0000000000000000 T _text\n").unwrap();
+        let paths = KernelPaths {
+            kallsyms: &kallsyms,
+            modules: &dir.path().join("modules"),
+            sysfs: dir.path(),
+        };
+
+        let normalized = normalize_kernel_addrs_with_paths(&[0xffffffff81000000], &paths).unwrap();
+        assert_eq!(normalized.addrs, vec![(0xffffffff81000000, 0)]);
+        assert_eq!(normalized.meta, vec![Unknown::default().into()]);
+    }
+
+    /// Check that we can normalize the address of the running kernel's
+    /// `_text` symbol, if it is visible to us.
+    #[test]
+    fn running_kernel_normalization() {
+        let paths = KernelPaths::default();
+        let (text_addr, _end) = match find_kernel_image(paths.kallsyms) {
+            Ok(Some(image)) => image,
+            _ => return,
+        };
+
+        let normalized = normalize_kernel_addrs_with_paths(&[text_addr], &paths).unwrap();
+        assert_eq!(normalized.addrs, vec![(0, 0)]);
+        let kernel = normalized.meta[0].kernel().unwrap();
+        assert_eq!(kernel.text_addr, text_addr);
+    }
+}
@@ -0,0 +1,344 @@
+//! Support for reading ELF information of mapped binaries from process
+//! memory.
+//!
+//! Binaries may get replaced or removed on disk after a process mapped
+//! them, and their `/proc/<pid>/map_files` entries may not be
+//! accessible (e.g., because of lacking permissions or when crossing
+//! container boundaries). In such cases we read the ELF and program
+//! headers as well as notes from the memory of the process instead.
+
+use std::fs::File;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::mem::size_of;
+use std::ops::Range;
+use std::os::unix::fs::FileExt as _;
+
+use crate::elf::types::Elf32_Ehdr;
+use crate::elf::types::Elf32_Phdr;
+use crate::elf::types::Elf64_Ehdr;
+use crate::elf::types::Elf64_Phdr;
+use crate::elf::types::EI_CLASS;
+use crate::elf::types::ELFCLASS32;
+use crate::elf::types::PT_LOAD;
+use crate::elf::types::PT_NOTE;
+use crate::elf::ElfParser;
+use crate::log::warn;
+use crate::maps::PathMapsEntry;
+use crate::mmap::Mmap;
+use crate::util::ReadRaw as _;
+use crate::Addr;
+use crate::Pid;
+
+
+/// The file offset up to which we read program headers from process
+/// memory. The limit guards against bogus ELF headers making us
+/// allocate large amounts of memory.
+const MAX_PHDRS_END: usize = 64 * 1024;
+/// The file offset up to which we read note segments from process
+/// memory. Notes are small and usually follow the program headers
+/// closely; the limit guards against bogus headers making us allocate
+/// large amounts of memory.
+const MAX_NOTES_END: usize = 64 * 1024;
+
+
+/// Check whether an error indicates that a file could not be accessed,
+/// in which case we may fall back to reading from process memory.
+pub(crate) fn is_inaccessible(err: &Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::NotFound | ErrorKind::PermissionDenied
+    )
+}
+
+
+/// Read `len` bytes at `addr` from the process memory represented by
+/// `mem`.
+fn read_memory(mem: &File, addr: Addr, len: usize) -> Result<Vec<u8>> {
+    let mut data = vec![0; len];
+    let () = mem.read_exact_at(&mut data, addr as u64)?;
+    Ok(data)
+}
+
+/// Determine the file offset range of the program headers of the ELF
+/// file whose ELF header is contained in `data`.
+fn phdrs_range(data: &[u8]) -> Result<Range<usize>> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "ELF header is truncated");
+    let mut data = data;
+    let (phoff, phentsize, phnum) = if data.get(EI_CLASS) == Some(&ELFCLASS32) {
+        let ehdr = data.read_pod::<Elf32_Ehdr>().ok_or_else(invalid)?;
+        let phentsize = usize::from(ehdr.e_phentsize).max(size_of::<Elf32_Phdr>());
+        (u64::from(ehdr.e_phoff), phentsize, ehdr.e_phnum)
+    } else {
+        let ehdr = data.read_pod::<Elf64_Ehdr>().ok_or_else(invalid)?;
+        let phentsize = usize::from(ehdr.e_phentsize).max(size_of::<Elf64_Phdr>());
+        (ehdr.e_phoff, phentsize, ehdr.e_phnum)
+    };
+
+    let start = usize::try_from(phoff).ok();
+    let end = start.and_then(|start| start.checked_add(phentsize * usize::from(phnum)));
+    match (start, end) {
+        (Some(start), Some(end)) if end <= MAX_PHDRS_END => Ok(start..end),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("program headers at offset {phoff:#x} are out of bounds"),
+        )),
+    }
+}
+
+/// Clear the section header information in the ELF header contained in
+/// `data`, as we do not read section headers from memory (they are
+/// typically not mapped at all).
+fn clear_section_headers(data: &mut [u8]) -> Result<()> {
+    let is_32bit = data.get(EI_CLASS) == Some(&ELFCLASS32);
+    // The offsets and sizes of `e_shoff` as well as `e_shnum` and
+    // `e_shstrndx` in the respective ELF header.
+    let (shoff, shoff_len, shnum) = if is_32bit {
+        (0x20, 4, 0x30)
+    } else {
+        (0x28, 8, 0x3c)
+    };
+
+    let () = data
+        .get_mut(shoff..shoff + shoff_len)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "ELF header is truncated"))?
+        .fill(0);
+    let () = data
+        .get_mut(shnum..shnum + 4)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "ELF header is truncated"))?
+        .fill(0);
+    Ok(())
+}
+
+/// Create an [`ElfParser`] for the binary backing `entry` in process
+/// `pid`, based on the ELF headers and notes read from the memory of the
+/// process.
+///
+/// `header` is the address range of the mapping containing the start of
+/// the file (i.e., its ELF header), as found in the process' proc maps.
+///
+/// The resulting parser provides access to program headers and notes
+/// (e.g., to read the build ID), but not to section information.
+pub(crate) fn memory_elf_parser(
+    pid: Pid,
+    entry: &PathMapsEntry,
+    header: &Range<Addr>,
+) -> Result<ElfParser> {
+    let (base, len) = (header.start, header.len());
+    let mem = File::open(format!("/proc/{pid}/mem"))?;
+
+    // Read the ELF header first and then exactly the program headers
+    // it references, wherever they are located.
+    let mut image = read_memory(&mem, base, len.min(size_of::<Elf64_Ehdr>()))?;
+    if !image.starts_with(b"\x7fELF") {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "memory at {base:#x} does not contain an ELF header for {}",
+                entry.path.symbolic_path.display()
+            ),
+        ))
+    }
+    let range = phdrs_range(&image)?;
+    let data = read_memory(&mem, base.wrapping_add(range.start as Addr), range.len())?;
+    if image.len() < range.end {
+        let () = image.resize(range.end, 0);
+    }
+    let () = image[range].copy_from_slice(&data);
+    let () = clear_section_headers(&mut image)?;
+
+    let phdrs = {
+        let parser = ElfParser::from_mmap(Mmap::from_bytes(&image)?);
+        parser
+            .program_headers()?
+            .iter()
+            .map(|phdr| (phdr.p_type, phdr.p_offset, phdr.p_vaddr, phdr.p_filesz))
+            .collect::<Vec<_>>()
+    };
+
+    // The ELF header is part of the first loadable segment. Its address
+    // tells us where the binary got loaded.
+    let load_addr = phdrs
+        .iter()
+        .find(|(typ, offset, ..)| *typ == PT_LOAD && *offset == 0)
+        .map(|(_typ, _offset, vaddr, _size)| base.wrapping_sub(*vaddr as Addr))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "failed to find loadable segment containing ELF header",
+            )
+        })?;
+
+    for (_typ, offset, vaddr, size) in phdrs.iter().filter(|(typ, ..)| *typ == PT_NOTE) {
+        let (offset, size) = match (usize::try_from(*offset), usize::try_from(*size)) {
+            (Ok(offset), Ok(size)) => (offset, size),
+            _ => continue,
+        };
+        let end = offset.checked_add(size).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "encountered invalid note segment")
+        })?;
+        if end > MAX_NOTES_END {
+            warn!(
+                "note segment of {} at offset {offset:#x} with size {size:#x} is out of bounds; ignoring...",
+                entry.path.symbolic_path.display()
+            );
+            continue
+        }
+        let notes = read_memory(&mem, load_addr.wrapping_add(*vaddr as Addr), size)?;
+        if image.len() < end {
+            let () = image.resize(end, 0);
+        }
+        let () = image[offset..end].copy_from_slice(&notes);
+    }
+
+    let mmap = Mmap::from_bytes(&image)?;
+    Ok(ElfParser::from_mmap(mmap))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use test_log::test;
+
+    use crate::maps;
+    use crate::maps::EntryPath;
+    use crate::maps::PathName;
+    use crate::normalize::read_elf_build_id;
+
+
+    /// Find the proc maps entry of our process containing `addr` along
+    /// with the address range of the mapping of the corresponding ELF
+    /// header.
+    fn find_entry(addr: Addr) -> (PathMapsEntry, Range<Addr>) {
+        let entry = maps::parse(Pid::Slf)
+            .unwrap()
+            .filter_map(|entry| maps::filter_map_relevant(entry.unwrap()))
+            .find(|entry| entry.range.contains(&addr))
+            .unwrap();
+        let header = maps::parse(Pid::Slf)
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|maps_entry| maps_entry.offset == 0 && maps_entry.range.start <= addr)
+            .filter(|maps_entry| {
+                matches!(
+                    &maps_entry.path_name,
+                    Some(PathName::Path(path)) if path.symbolic_path == entry.path.symbolic_path
+                )
+            })
+            .last()
+            .unwrap()
+            .range;
+        (entry, header)
+    }
+
+
+    /// Check that we can read the ELF headers and build ID of a binary
+    /// from process memory.
+    #[test]
+    fn memory_build_id_reading() {
+        let test_so = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("libtest-so.so");
+        let mmap = Mmap::builder().exec().open(&test_so).unwrap();
+        let addr = mmap.as_ptr() as Addr;
+
+        let (entry, header) = find_entry(addr);
+        let parser = memory_elf_parser(Pid::Slf, &entry, &header).unwrap();
+        let file_parser = ElfParser::open(&test_so).unwrap();
+        assert_eq!(
+            read_elf_build_id(&parser).unwrap(),
+            read_elf_build_id(&file_parser).unwrap()
+        );
+        assert!(read_elf_build_id(&parser).unwrap().is_some());
+        assert_eq!(
+            parser.program_headers().unwrap().len(),
+            file_parser.program_headers().unwrap().len()
+        );
+        assert_eq!(parser.section_headers().unwrap().len(), 0);
+    }
+
+    /// Check that we read program headers located beyond the first page
+    /// of a binary.
+    #[test]
+    fn memory_reading_distant_phdrs() {
+        fn put(image: &mut [u8], offset: usize, data: &[u8]) {
+            let () = image[offset..offset + data.len()].copy_from_slice(data);
+        }
+
+        const PHOFF: usize = 0x1000;
+        const NOTE_OFF: usize = 0x1800;
+
+        let mut image = vec![0u8; 0x2000];
+        // ELF header of a 64 bit little endian shared object with two
+        // program headers.
+        let () = put(&mut image, 0, b"\x7fELF\x02\x01\x01");
+        let () = put(&mut image, 0x10, &3u16.to_le_bytes());
+        let () = put(&mut image, 0x20, &(PHOFF as u64).to_le_bytes());
+        let () = put(&mut image, 0x36, &56u16.to_le_bytes());
+        let () = put(&mut image, 0x38, &2u16.to_le_bytes());
+
+        let note = [
+            &4u32.to_le_bytes()[..],
+            &4u32.to_le_bytes(),
+            &3u32.to_le_bytes(),
+            b"GNU\0",
+            &[0xaa, 0xbb, 0xcc, 0xdd],
+        ]
+        .concat();
+        let () = put(&mut image, NOTE_OFF, &note);
+
+        let phdrs = [(PT_LOAD, 0, image.len()), (PT_NOTE, NOTE_OFF, note.len())];
+        for (idx, (typ, offset, size)) in phdrs.iter().enumerate() {
+            let phdr = PHOFF + idx * 56;
+            let () = put(&mut image, phdr, &typ.to_le_bytes());
+            let () = put(&mut image, phdr + 0x08, &(*offset as u64).to_le_bytes());
+            let () = put(&mut image, phdr + 0x10, &(*offset as u64).to_le_bytes());
+            let () = put(&mut image, phdr + 0x20, &(*size as u64).to_le_bytes());
+            let () = put(&mut image, phdr + 0x28, &(*size as u64).to_le_bytes());
+        }
+
+        let base = image.as_ptr() as Addr;
+        let header = base..base + image.len() as Addr;
+        let entry = PathMapsEntry {
+            range: header.clone(),
+            _mode: 0,
+            offset: 0,
+            path: EntryPath {
+                maps_file: PathBuf::from("/dev/null"),
+                symbolic_path: PathBuf::from("distant-phdrs.so"),
+            },
+        };
+        let parser = memory_elf_parser(Pid::Slf, &entry, &header).unwrap();
+        assert_eq!(parser.program_headers().unwrap().len(), 2);
+        assert_eq!(
+            read_elf_build_id(&parser).unwrap().as_deref(),
+            Some([0xaa, 0xbb, 0xcc, 0xdd].as_slice())
+        );
+
+        // Program headers extending past the limit are rejected.
+        let () = put(&mut image, 0x20, &(MAX_PHDRS_END as u64).to_le_bytes());
+        let err = memory_elf_parser(Pid::Slf, &entry, &header).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    /// Check that we fail reading from memory regions not containing an
+    /// ELF header.
+    #[test]
+    fn memory_reading_non_elf() {
+        let file = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test-inlined.gsym");
+        let mmap = Mmap::builder().exec().open(&file).unwrap();
+        let addr = mmap.as_ptr() as Addr;
+
+        let (entry, header) = find_entry(addr);
+        let err = memory_elf_parser(Pid::Slf, &entry, &header).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use crate::Addr;
+
 
 /// A GNU build ID, as raw bytes.
 type BuildId = Vec<u8>;
@@ -8,6 +10,7 @@ type BuildId = Vec<u8>;
 /// Meta information about a user space binary (executable, shared object, APK,
 /// ...).
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Binary {
     /// The canonical absolute path to the binary, including its name.
     pub path: PathBuf,
@@ -15,6 +18,23 @@ pub struct Binary {
     pub build_id: Option<BuildId>,
     /// The struct is non-exhaustive and open to extension.
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) _non_exhaustive: (),
+}
+
+
+/// Meta information about the vDSO of a process.
+///
+/// Addresses belonging to the vDSO are normalized to addresses as they
+/// appear in the vDSO ELF image.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Vdso {
+    /// The vDSO's build ID, if available.
+    pub build_id: Option<BuildId>,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) _non_exhaustive: (),
 }
 
@@ -23,9 +43,11 @@ pub struct Binary {
 /// belonging to a specific component. Such an address will be reported
 /// in non-normalized form (as provided by the user).
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Unknown {
     /// The struct is non-exhaustive and open to extension.
     #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) _non_exhaustive: (),
 }
 
@@ -38,9 +60,11 @@ impl From<Unknown> for UserAddrMeta {
 
 /// Meta information for an address.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[non_exhaustive]
 pub enum UserAddrMeta {
     Binary(Binary),
+    Vdso(Vdso),
     Unknown(Unknown),
 }
 
@@ -52,4 +76,94 @@ impl UserAddrMeta {
             _ => None,
         }
     }
+
+    /// Retrieve the [`Vdso`] of this enum, if this variant is active.
+    pub fn vdso(&self) -> Option<&Vdso> {
+        match self {
+            Self::Vdso(vdso) => Some(vdso),
+            _ => None,
+        }
+    }
+}
+
+
+/// Meta information about the kernel image.
+///
+/// Addresses belonging to the kernel image are normalized to offsets
+/// relative to its `_text` symbol. Doing so removes the effects of
+/// kernel address space layout randomization (KASLR).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Kernel {
+    /// The kernel's build ID, if available.
+    pub build_id: Option<BuildId>,
+    /// The run time address of the kernel's `_text` symbol.
+    ///
+    /// The difference between this address and the address of `_text`
+    /// in the kernel image (e.g., `vmlinux`) is the KASLR offset.
+    pub text_addr: Addr,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) _non_exhaustive: (),
+}
+
+
+/// Meta information about a section of a loaded kernel module.
+///
+/// Addresses belonging to a kernel module are normalized to offsets
+/// relative to the start of the section containing them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct KernelModule {
+    /// The name of the module, as reported by the kernel.
+    pub name: String,
+    /// The name of the section the offsets are relative to.
+    ///
+    /// If section addresses are not available (they are only reported
+    /// to privileged users), this member is `None` and offsets are
+    /// relative to the load address of the module instead.
+    pub section: Option<String>,
+    /// The module's build ID, if available.
+    pub build_id: Option<BuildId>,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) _non_exhaustive: (),
+}
+
+
+/// Meta information for a kernel address.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
+pub enum KernelAddrMeta {
+    Kernel(Kernel),
+    Module(KernelModule),
+    Unknown(Unknown),
+}
+
+impl KernelAddrMeta {
+    /// Retrieve the [`Kernel`] of this enum, if this variant is active.
+    pub fn kernel(&self) -> Option<&Kernel> {
+        match self {
+            Self::Kernel(kernel) => Some(kernel),
+            _ => None,
+        }
+    }
+
+    /// Retrieve the [`KernelModule`] of this enum, if this variant is
+    /// active.
+    pub fn module(&self) -> Option<&KernelModule> {
+        match self {
+            Self::Module(module) => Some(module),
+            _ => None,
+        }
+    }
+}
+
+impl From<Unknown> for KernelAddrMeta {
+    fn from(unknown: Unknown) -> Self {
+        Self::Unknown(unknown)
+    }
 }
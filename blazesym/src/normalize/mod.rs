@@ -19,15 +19,30 @@
 //! let norm_addrs = normalizer.normalize_user_addrs(&addrs, pid).unwrap();
 //! ```
 
+mod encode;
+mod kernel;
+mod memory;
 mod meta;
 mod normalizer;
 
+pub use encode::NormalizedUserAddrsReader;
+pub use encode::UserAddrMetaRef;
+pub use kernel::NormalizedKernelAddrs;
 pub use meta::Binary;
+pub use meta::Kernel;
+pub use meta::KernelAddrMeta;
+pub use meta::KernelModule;
 pub use meta::Unknown;
 pub use meta::UserAddrMeta;
+pub use meta::Vdso;
+pub use normalizer::Builder;
+pub use normalizer::NormalizedAddrs;
 pub use normalizer::NormalizedUserAddrs;
 pub use normalizer::Normalizer;
 
 pub(crate) use normalizer::normalize_elf_addr;
+pub(crate) use normalizer::normalize_elf_offset_with_parser;
 pub(crate) use normalizer::normalize_sorted_user_addrs_with_entries;
+pub(crate) use normalizer::normalize_vdso_addr;
+pub(crate) use normalizer::read_elf_build_id;
 pub(crate) use normalizer::Handler;
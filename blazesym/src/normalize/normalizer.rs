@@ -1,25 +1,40 @@
 use std::collections::HashMap;
+use std::fmt::Debug;
 use std::fs::File;
 use std::io::Error;
 use std::io::ErrorKind;
 use std::io::Result;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 
 use crate::elf;
-use crate::elf::types::Elf64_Nhdr;
+use crate::elf::types::NT_GNU_BUILD_ID;
 use crate::elf::ElfParser;
+use crate::log::debug;
 use crate::log::warn;
 use crate::maps;
 use crate::maps::PathMapsEntry;
+use crate::maps::PathName;
 use crate::util;
-use crate::util::ReadRaw as _;
+use crate::vdso::vdso_parser;
 use crate::Addr;
 use crate::Pid;
 
+use super::kernel::normalize_kernel_addrs_with_paths;
+use super::kernel::KernelPaths;
+use super::kernel::NormalizedKernelAddrs;
+use super::memory::is_inaccessible;
+use super::memory::memory_elf_parser;
 use super::meta::Binary;
 use super::meta::Unknown;
 use super::meta::UserAddrMeta;
+use super::meta::Vdso;
+
+#[cfg(doc)]
+use super::meta::Kernel;
+#[cfg(doc)]
+use super::meta::KernelModule;
 
 
 /// A typedef for functions reading build IDs.
@@ -30,8 +45,10 @@ type BuildIdFn = dyn Fn(&Path) -> Result<Option<Vec<u8>>>;
 ///
 /// This type enables "remote" symbolization. That is to say, it represents the
 /// input necessary for addresses to be symbolized on a system other than where
-/// they were recorded.
+/// they were recorded, by means of
+/// [`Symbolizer::symbolize_normalized`][crate::symbolize::Symbolizer::symbolize_normalized].
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct NormalizedAddrs<M> {
     /// Normalized addresses along with an index into `meta` for retrieval of
     /// the corresponding [`AddrMeta`] information.
@@ -47,73 +64,33 @@ pub struct NormalizedAddrs<M> {
 pub type NormalizedUserAddrs = NormalizedAddrs<UserAddrMeta>;
 
 
-/// A type representing a build ID note.
-///
-/// In the ELF file, this header is typically followed by the variable sized
-/// build ID.
-#[repr(C)]
-struct BuildIdNote {
-    /// ELF note header.
-    header: Elf64_Nhdr,
-    /// NUL terminated string representing the name.
-    name: [u8; 4],
-}
-
-// SAFETY: `BuildIdNote` is valid for any bit pattern.
-unsafe impl crate::util::Pod for BuildIdNote {}
-
-
 /// Attempt to read an ELF binary's build ID.
-// TODO: Currently look up is always performed based on section name, but there
-//       is also the possibility of iterating notes and checking checking
-//       Elf64_Nhdr.n_type for NT_GNU_BUILD_ID, specifically.
 fn read_build_id(path: &Path) -> Result<Option<Vec<u8>>> {
-    let build_id_section = ".note.gnu.build-id";
     let file = File::open(path)?;
     let parser = ElfParser::open_file(file)?;
+    read_elf_build_id(&parser)
+}
 
-    // The build ID is contained in the `.note.gnu.build-id` section. See
-    // elf(5).
-    if let Ok(idx) = parser.find_section(build_id_section) {
-        // SANITY: We just found the index so the section should always be
-        //         found.
-        let shdr = parser.section_headers()?.get(idx).unwrap();
-        if shdr.sh_type != elf::types::SHT_NOTE {
-            warn!(
-                "build ID section {build_id_section} of {} is of unsupported type ({})",
-                path.display(),
-                shdr.sh_type
-            );
-            return Ok(None)
-        }
-
-        // SANITY: We just found the index so the section should always be
-        //         found.
-        let mut bytes = parser.section_data(idx).unwrap();
-        let header = bytes.read_pod_ref::<BuildIdNote>().ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "failed to read build ID section header",
-            )
-        })?;
-        if &header.name != b"GNU\0" {
-            warn!(
-                "encountered unsupported build ID type {:?}; ignoring",
-                header.name
-            );
-            Ok(None)
-        } else {
-            // Every byte following the header is part of the build ID.
-            let build_id = bytes.to_vec();
-            Ok(Some(build_id))
-        }
-    } else {
-        Ok(None)
-    }
+/// Attempt to read the build ID of the ELF file represented by `parser`.
+///
+/// The build ID is contained in a note of type `NT_GNU_BUILD_ID` owned
+/// by "GNU" (see elf(5)), which may be part of any note section or
+/// segment.
+pub(crate) fn read_elf_build_id(parser: &ElfParser) -> Result<Option<Vec<u8>>> {
+    let build_id = parser
+        .notes()?
+        .find(|note| note.typ == NT_GNU_BUILD_ID && note.name == b"GNU")
+        .map(|note| note.desc.to_vec());
+    Ok(build_id)
 }
 
 
-fn normalize_elf_offset_with_parser(offset: u64, parser: &ElfParser) -> Result<Option<Addr>> {
+/// Normalize a file offset in the ELF file represented by `parser` to the
+/// virtual address it would be loaded at.
+pub(crate) fn normalize_elf_offset_with_parser(
+    offset: u64,
+    parser: &ElfParser,
+) -> Result<Option<Addr>> {
     let phdrs = parser.program_headers()?;
     let addr = phdrs.iter().find_map(|phdr| {
         if phdr.p_type == elf::types::PT_LOAD {
@@ -130,9 +107,19 @@ fn normalize_elf_offset_with_parser(offset: u64, parser: &ElfParser) -> Result<O
 /// Normalize a virtual address belonging to an ELF file represented by the
 /// provided [`PathMapsEntry`].
 pub(crate) fn normalize_elf_addr(virt_addr: Addr, entry: &PathMapsEntry) -> Result<Addr> {
-    let file_off = virt_addr as u64 - entry.range.start as u64 + entry.offset;
     let parser = ElfParser::open(&entry.path.maps_file)?;
-    let addr = normalize_elf_offset_with_parser(file_off, &parser)?.ok_or_else(|| {
+    normalize_elf_addr_with_parser(virt_addr, entry, &parser)
+}
+
+/// Normalize a virtual address belonging to an ELF file represented by the
+/// provided [`PathMapsEntry`], using `parser` for parsing the ELF file.
+fn normalize_elf_addr_with_parser(
+    virt_addr: Addr,
+    entry: &PathMapsEntry,
+    parser: &ElfParser,
+) -> Result<Addr> {
+    let file_off = virt_addr as u64 - entry.range.start as u64 + entry.offset;
+    let addr = normalize_elf_offset_with_parser(file_off, parser)?.ok_or_else(|| {
         Error::new(
             ErrorKind::InvalidInput,
             format!(
@@ -146,8 +133,31 @@ pub(crate) fn normalize_elf_addr(virt_addr: Addr, entry: &PathMapsEntry) -> Resu
     Ok(addr)
 }
 
+/// Normalize a virtual address belonging to the vDSO mapped at `range`,
+/// with `parser` representing the vDSO's ELF image.
+pub(crate) fn normalize_vdso_addr(
+    virt_addr: Addr,
+    range: &Range<Addr>,
+    parser: &ElfParser,
+) -> Result<Addr> {
+    // The vDSO is always mapped in its entirety, starting at file
+    // offset zero.
+    let file_off = (virt_addr - range.start) as u64;
+    let addr = normalize_elf_offset_with_parser(file_off, parser)?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("failed to find vDSO segment that contains offset 0x{file_off:x}"),
+        )
+    })?;
+
+    Ok(addr)
+}
 
-impl NormalizedUserAddrs {
+
+impl<M> NormalizedAddrs<M>
+where
+    M: Debug + From<Unknown> + PartialEq,
+{
     /// Add an unknown (non-normalizable) address to this object.
     ///
     /// This function accepts `unknown_idx` which, if not `None`, should
@@ -157,14 +167,18 @@ impl NormalizedUserAddrs {
     /// It returns the index of the inserted [`Unknown`] variant. The
     /// return type is an `Option` only for convenience of callers.
     /// Returned is always a `Some`.
-    fn add_unknown_addr(&mut self, addr: Addr, unknown_idx: Option<usize>) -> Option<usize> {
+    pub(crate) fn add_unknown_addr(
+        &mut self,
+        addr: Addr,
+        unknown_idx: Option<usize>,
+    ) -> Option<usize> {
         let unknown_idx = if let Some(unknown_idx) = unknown_idx {
             debug_assert_eq!(self.meta[unknown_idx], Unknown::default().into());
             unknown_idx
         } else {
             let unknown_idx = self.meta.len();
             let unknown = Unknown::default();
-            let () = self.meta.push(UserAddrMeta::Unknown(unknown));
+            let () = self.meta.push(M::from(unknown));
             unknown_idx
         };
 
@@ -175,15 +189,54 @@ impl NormalizedUserAddrs {
 
 
 pub(crate) trait Handler {
+    /// Inspect a proc maps entry, irrespective of whether any address
+    /// falls into it. Entries are reported in order, before addresses
+    /// residing in them are handled.
+    fn handle_maps_entry(&mut self, _entry: &maps::MapsEntry) {}
+
     /// Handle an unknown address.
     fn handle_unknown_addr(&mut self, addr: Addr) -> Result<()>;
 
     /// Handle an address residing in the provided [`PathMapsEntry`].
     fn handle_entry_addr(&mut self, addr: Addr, entry: &PathMapsEntry) -> Result<()>;
+
+    /// Handle an address residing in the vDSO, which is mapped at
+    /// `range`.
+    fn handle_vdso_addr(&mut self, addr: Addr, range: &Range<Addr>) -> Result<()>;
+}
+
+
+/// A proc maps entry of relevance to address normalization.
+enum Entry {
+    /// An entry backed by a file.
+    Path(PathMapsEntry),
+    /// The entry representing the vDSO.
+    Vdso(Range<Addr>),
+}
+
+impl Entry {
+    /// Convert a `MapsEntry` into an `Entry`, if it is relevant.
+    fn from_maps_entry(entry: maps::MapsEntry) -> Option<Self> {
+        if maps::is_vdso(&entry) {
+            Some(Self::Vdso(entry.range))
+        } else {
+            maps::filter_map_relevant(entry).map(Self::Path)
+        }
+    }
+
+    /// Retrieve the virtual address range covered by this entry.
+    fn range(&self) -> &Range<Addr> {
+        match self {
+            Self::Path(entry) => &entry.range,
+            Self::Vdso(range) => range,
+        }
+    }
 }
 
 
 struct NormalizationHandler {
+    /// The process the addresses belong to.
+    pid: Pid,
     /// The normalized user addresses we are building up.
     normalized: NormalizedUserAddrs,
     /// Lookup table from path (as used in each proc maps entry) to index into
@@ -194,12 +247,31 @@ struct NormalizationHandler {
     unknown_idx: Option<usize>,
     /// The function used for retrieving build IDs.
     get_build_id: &'static BuildIdFn,
+    /// The index of the `Vdso` entry in `meta_lookup` along with a
+    /// parser for the vDSO image, loaded on first use. `None` in the
+    /// inner `Option` signals that the vDSO could not be loaded.
+    vdso: Option<Option<(usize, ElfParser)>>,
+    /// Whether to read ELF information from process memory for binaries
+    /// whose files cannot be accessed.
+    memory_fallback: bool,
+    /// Parsers for ELF images read from process memory, keyed by path.
+    memory_parsers: HashMap<PathBuf, ElfParser>,
+    /// The address ranges of the mappings containing the ELF headers
+    /// (i.e., file offset zero) of binaries, keyed by path. Only
+    /// populated if `memory_fallback` is enabled.
+    elf_headers: HashMap<PathBuf, Range<Addr>>,
 }
 
 impl NormalizationHandler {
     /// Instantiate a new `NormalizationHandler` object.
-    fn new(addr_count: usize, get_build_id: &'static BuildIdFn) -> Self {
+    fn new(
+        pid: Pid,
+        addr_count: usize,
+        get_build_id: &'static BuildIdFn,
+        memory_fallback: bool,
+    ) -> Self {
         Self {
+            pid,
             normalized: NormalizedUserAddrs {
                 addrs: Vec::with_capacity(addr_count),
                 meta: Vec::new(),
@@ -207,11 +279,53 @@ impl NormalizationHandler {
             meta_lookup: HashMap::<PathBuf, usize>::new(),
             unknown_idx: None,
             get_build_id,
+            vdso: None,
+            memory_fallback,
+            memory_parsers: HashMap::new(),
+            elf_headers: HashMap::new(),
+        }
+    }
+
+    /// Retrieve a parser for the ELF image of the binary backing
+    /// `entry`, as read from process memory.
+    fn memory_parser(&mut self, entry: &PathMapsEntry, err: Error) -> Result<&ElfParser> {
+        if !self.memory_fallback || !is_inaccessible(&err) {
+            return Err(err)
+        }
+
+        let path = &entry.path.symbolic_path;
+        if !self.memory_parsers.contains_key(path) {
+            debug!(
+                "failed to access {}: {err}; reading ELF information from process memory",
+                path.display()
+            );
+            let header = self.elf_headers.get(path).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("failed to find ELF header mapping of {}", path.display()),
+                )
+            })?;
+            let parser = memory_elf_parser(self.pid, entry, header)?;
+            let _prev = self.memory_parsers.insert(path.to_path_buf(), parser);
         }
+        // SANITY: We just made sure that the parser is present.
+        Ok(self.memory_parsers.get(path).unwrap())
     }
 }
 
 impl Handler for NormalizationHandler {
+    fn handle_maps_entry(&mut self, entry: &maps::MapsEntry) {
+        if !self.memory_fallback || entry.offset != 0 {
+            return
+        }
+
+        if let Some(PathName::Path(path)) = &entry.path_name {
+            let _prev = self
+                .elf_headers
+                .insert(path.symbolic_path.clone(), entry.range.clone());
+        }
+    }
+
     fn handle_unknown_addr(&mut self, addr: Addr) -> Result<()> {
         self.unknown_idx = self.normalized.add_unknown_addr(addr, self.unknown_idx);
         Ok(())
@@ -221,9 +335,13 @@ impl Handler for NormalizationHandler {
         let meta_idx = if let Some(meta_idx) = self.meta_lookup.get(&entry.path.symbolic_path) {
             *meta_idx
         } else {
+            let build_id = match (self.get_build_id)(&entry.path.maps_file) {
+                Ok(build_id) => build_id,
+                Err(err) => read_elf_build_id(self.memory_parser(entry, err)?)?,
+            };
             let binary = Binary {
                 path: entry.path.symbolic_path.to_path_buf(),
-                build_id: (self.get_build_id)(&entry.path.maps_file)?,
+                build_id,
                 _non_exhaustive: (),
             };
 
@@ -235,16 +353,85 @@ impl Handler for NormalizationHandler {
             meta_idx
         };
 
-        let normalized_addr = normalize_elf_addr(addr, entry)?;
+        let normalized_addr = match normalize_elf_addr(addr, entry) {
+            Ok(addr) => addr,
+            Err(err) => {
+                let parser = self.memory_parser(entry, err)?;
+                normalize_elf_addr_with_parser(addr, entry, parser)?
+            }
+        };
         let () = self.normalized.addrs.push((normalized_addr, meta_idx));
         Ok(())
     }
+
+    fn handle_vdso_addr(&mut self, addr: Addr, range: &Range<Addr>) -> Result<()> {
+        if self.vdso.is_none() {
+            let pid = self.pid;
+            let result = vdso_parser(pid, range).and_then(|parser| {
+                let build_id = read_elf_build_id(&parser)?;
+                Ok((build_id, parser))
+            });
+            let vdso = match result {
+                Ok((build_id, parser)) => {
+                    let vdso = Vdso {
+                        build_id,
+                        _non_exhaustive: (),
+                    };
+                    let meta_idx = self.normalized.meta.len();
+                    let () = self.normalized.meta.push(UserAddrMeta::Vdso(vdso));
+                    Some((meta_idx, parser))
+                }
+                Err(err) => {
+                    warn!("failed to read vDSO of process {pid}: {err}; reporting addresses as unknown");
+                    None
+                }
+            };
+            self.vdso = Some(vdso);
+        }
+
+        // SANITY: We made sure to load the vDSO above.
+        let (meta_idx, parser) = match self.vdso.as_ref().unwrap() {
+            Some((meta_idx, parser)) => (*meta_idx, parser),
+            None => return self.handle_unknown_addr(addr),
+        };
+
+        match normalize_vdso_addr(addr, range, parser) {
+            Ok(normalized_addr) => {
+                let () = self.normalized.addrs.push((normalized_addr, meta_idx));
+                Ok(())
+            }
+            Err(err) => {
+                warn!("failed to normalize vDSO address 0x{addr:x}: {err}; reporting as unknown");
+                self.handle_unknown_addr(addr)
+            }
+        }
+    }
 }
 
 
+/// Retrieve the next entry of relevance from `entries`, reporting every
+/// proc maps entry encountered on the way to `handler`.
+fn next_entry<E, H>(entries: &mut E, handler: &mut H) -> Option<Result<Entry>>
+where
+    E: Iterator<Item = Result<maps::MapsEntry>>,
+    H: Handler,
+{
+    for result in entries {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        let () = handler.handle_maps_entry(&entry);
+        if let Some(entry) = Entry::from_maps_entry(entry) {
+            return Some(Ok(entry))
+        }
+    }
+    None
+}
+
 pub(crate) fn normalize_sorted_user_addrs_with_entries<A, E, H>(
     addrs: A,
-    entries: E,
+    mut entries: E,
     mut handler: H,
 ) -> Result<H>
 where
@@ -252,12 +439,7 @@ where
     E: Iterator<Item = Result<maps::MapsEntry>>,
     H: Handler,
 {
-    let mut entries = entries.filter_map(|result| match result {
-        Ok(entry) => maps::filter_map_relevant(entry).map(Ok),
-        Err(err) => Some(Err(err)),
-    });
-
-    let mut entry = entries.next().ok_or_else(|| {
+    let mut entry = next_entry(&mut entries, &mut handler).ok_or_else(|| {
         Error::new(
             ErrorKind::UnexpectedEof,
             "proc maps does not contain relevant entries",
@@ -277,8 +459,8 @@ where
         }
         prev_addr = addr;
 
-        while addr >= entry.range.end {
-            entry = if let Some(entry) = entries.next() {
+        while addr >= entry.range().end {
+            entry = if let Some(entry) = next_entry(&mut entries, &mut handler) {
                 entry?
             } else {
                 // If there are no proc maps entries left to check, we
@@ -295,18 +477,59 @@ where
         // that means that we cannot find a suitable entry. This could
         // happen, for example, if an ELF object was unmapped between
         // address capture and normalization.
-        if addr < entry.range.start {
+        if addr < entry.range().start {
             let () = handler.handle_unknown_addr(addr)?;
             continue 'main
         }
 
-        let () = handler.handle_entry_addr(addr, &entry)?;
+        let () = match &entry {
+            Entry::Path(entry) => handler.handle_entry_addr(addr, entry)?,
+            Entry::Vdso(range) => handler.handle_vdso_addr(addr, range)?,
+        };
     }
 
     Ok(handler)
 }
 
 
+/// A builder for configurable construction of [`Normalizer`] objects.
+///
+/// By default all features are enabled.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    /// Whether to read ELF information from process memory for binaries
+    /// whose files cannot be accessed.
+    memory_fallback: bool,
+}
+
+impl Builder {
+    /// Enable/disable reading of ELF information from process memory.
+    ///
+    /// If enabled, ELF headers and build ID notes of binaries whose
+    /// files cannot be opened (e.g., because they got replaced on disk
+    /// after the process mapped them and `/proc/<pid>/map_files` is not
+    /// accessible) are read from the memory of the process instead.
+    pub fn enable_memory_fallback(mut self, enable: bool) -> Builder {
+        self.memory_fallback = enable;
+        self
+    }
+
+    /// Create the [`Normalizer`] object.
+    pub fn build(self) -> Normalizer {
+        let Builder { memory_fallback } = self;
+        Normalizer { memory_fallback }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            memory_fallback: true,
+        }
+    }
+}
+
+
 /// A normalizer for addresses.
 ///
 /// Address normalization is the process of taking virtual absolute
@@ -315,15 +538,26 @@ where
 /// things) and converting them to "normalized" virtual addresses as
 /// they are present in, say, an ELF binary or a DWARF debug info file,
 /// and one would be able to see them using tools such as readelf(1).
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Normalizer {
-    _private: (),
+    /// Whether to read ELF information from process memory for binaries
+    /// whose files cannot be accessed.
+    memory_fallback: bool,
 }
 
 impl Normalizer {
     /// Create a new `Normalizer`.
+    ///
+    /// This method is a short-hand for creating a [`Builder`] with its
+    /// default configuration and building the `Normalizer` from it.
     pub fn new() -> Self {
-        Self { _private: () }
+        Builder::default().build()
+    }
+
+    /// Retrieve a [`Builder`] object for configurable construction of a
+    /// [`Normalizer`].
+    pub fn builder() -> Builder {
+        Builder::default()
     }
 
     /// Normalize all `addrs` in a given process. The `addrs` array has to
@@ -338,6 +572,9 @@ impl Normalizer {
     /// - they belonged to an ELF object that has been unmapped since the
     ///   address was captured
     ///
+    /// Addresses inside the process' vDSO are normalized to addresses in
+    /// the vDSO image and reported as [`Vdso`] meta entries.
+    ///
     /// The process' ID should be provided in `pid`. To normalize addresses of the
     /// calling processes, `0` can be provided as a sentinel for the current
     /// process' ID.
@@ -349,7 +586,8 @@ impl Normalizer {
         A: ExactSizeIterator<Item = Addr> + Clone,
     {
         let entries = maps::parse(pid)?;
-        let handler = NormalizationHandler::new(addrs.len(), &read_build_id);
+        let handler =
+            NormalizationHandler::new(pid, addrs.len(), &read_build_id, self.memory_fallback);
         let handler = normalize_sorted_user_addrs_with_entries(addrs, entries, handler)?;
         Ok(handler.normalized)
     }
@@ -368,6 +606,9 @@ impl Normalizer {
     /// - they belonged to an ELF object that has been unmapped since the
     ///   address was captured
     ///
+    /// Addresses inside the process' vDSO are normalized to addresses in
+    /// the vDSO image and reported as [`Vdso`] meta entries.
+    ///
     /// The process' ID should be provided in `pid`. To normalize addresses of the
     /// calling processes, `0` can be provided as a sentinel for the current
     /// process' ID.
@@ -396,6 +637,33 @@ impl Normalizer {
             |sorted_addrs| self.normalize_user_addrs_sorted_impl(sorted_addrs, pid),
         )
     }
+
+    /// Normalize kernel addresses of the running kernel.
+    ///
+    /// Addresses inside the kernel image are normalized to offsets
+    /// relative to its `_text` symbol, as found in `/proc/kallsyms`,
+    /// which removes the kernel's KASLR offset. They are reported with
+    /// [`Kernel`] meta entries. Addresses inside a loaded kernel module
+    /// are normalized to offsets relative to the start of the module
+    /// section containing them and reported with [`KernelModule`] meta
+    /// entries. Build IDs are read from `/sys/kernel/notes` and
+    /// `/sys/module/<module>/notes`, respectively.
+    ///
+    /// Note that section and symbol addresses are typically only
+    /// reported to privileged users. Addresses that could not be
+    /// normalized are reported as [`Unknown`] meta entries.
+    ///
+    /// Normalized addresses are reported in the exact same order in
+    /// which the non-normalized ones were provided.
+    pub fn normalize_kernel_addrs(&self, addrs: &[Addr]) -> Result<NormalizedKernelAddrs> {
+        normalize_kernel_addrs_with_paths(addrs, &KernelPaths::default())
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 
@@ -403,11 +671,13 @@ impl Normalizer {
 mod tests {
     use super::*;
 
+    use std::fs::read as read_file;
     use std::mem::transmute;
 
     use crate::inspect::FindAddrOpts;
     use crate::inspect::SymType;
     use crate::mmap::Mmap;
+    use crate::vdso::tests::find_vdso_clock_gettime;
 
 
     /// Check that we can read a binary's build ID.
@@ -429,6 +699,82 @@ mod tests {
         assert_eq!(build_id, None);
     }
 
+    /// Check that we can read the build ID of a 32 bit ELF file.
+    #[test]
+    fn build_id_reading_elf32() {
+        let elf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("libtest-so-32.so");
+
+        let build_id = read_build_id(&elf).unwrap().unwrap();
+        assert_eq!(build_id.len(), 20, "'{build_id:?}'");
+    }
+
+    /// Check that we can read the build ID of ELF files lacking either
+    /// section or program headers.
+    #[test]
+    fn build_id_reading_without_headers() {
+        let elf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("libtest-so.so");
+        let data = read_file(&elf).unwrap();
+        let build_id = read_build_id(&elf).unwrap().unwrap();
+
+        // Clear `e_shnum`, so that the file appears to have no section
+        // headers, as is the case for some stripped binaries.
+        let mut no_shdrs = data.clone();
+        no_shdrs[0x3c..0x3e].copy_from_slice(&[0, 0]);
+        let parser = ElfParser::from_mmap(Mmap::from_bytes(&no_shdrs).unwrap());
+        assert_eq!(parser.section_headers().unwrap().len(), 0);
+        assert_eq!(read_elf_build_id(&parser).unwrap(), Some(build_id.clone()));
+
+        // Clear `e_phnum` and rename the build ID section, so that we
+        // have to find the note by iterating over note sections.
+        let mut no_phdrs = data;
+        no_phdrs[0x38..0x3a].copy_from_slice(&[0, 0]);
+        let name = b".note.gnu.build-id\0";
+        let idx = no_phdrs
+            .windows(name.len())
+            .position(|window| window == name)
+            .unwrap();
+        no_phdrs[idx..idx + name.len()].copy_from_slice(b".note\0\0\0\0\0\0\0\0\0\0\0\0\0\0");
+        let parser = ElfParser::from_mmap(Mmap::from_bytes(&no_phdrs).unwrap());
+        assert_eq!(parser.program_headers().unwrap().len(), 0);
+        assert!(parser.find_section(".note.gnu.build-id").is_err());
+        assert_eq!(read_elf_build_id(&parser).unwrap(), Some(build_id));
+    }
+
+    /// Check that we can normalize a file offset in a 32 bit ELF file.
+    #[test]
+    fn elf32_offset_normalization() {
+        let elf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("libtest-so-32.so");
+        let parser = ElfParser::open(&elf).unwrap();
+        let opts = FindAddrOpts {
+            sym_type: SymType::Function,
+            ..Default::default()
+        };
+        let syms = parser.find_addr("the_answer", &opts).unwrap();
+        assert_eq!(syms.len(), 1);
+        let sym = syms.first().unwrap();
+
+        let phdr = parser
+            .program_headers()
+            .unwrap()
+            .iter()
+            .find(|phdr| {
+                phdr.p_type == elf::types::PT_LOAD
+                    && (phdr.p_vaddr..phdr.p_vaddr + phdr.p_memsz).contains(&(sym.addr as u64))
+            })
+            .unwrap();
+        let offset = sym.addr as u64 - phdr.p_vaddr + phdr.p_offset;
+        let addr = normalize_elf_offset_with_parser(offset, &parser)
+            .unwrap()
+            .unwrap();
+        assert_eq!(addr, sym.addr);
+    }
+
     /// Check that we detect unsorted input addresses.
     #[test]
     fn user_address_normalization_unsorted() {
@@ -553,6 +899,98 @@ mod tests {
         assert_eq!(meta, &UserAddrMeta::Binary(expected_binary));
     }
 
+    /// Check that we fall back to reading ELF information from process
+    /// memory if a binary's file cannot be accessed.
+    #[test]
+    fn user_address_normalization_memory_fallback() {
+        let test_so = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("libtest-so.so");
+        let mmap = Mmap::builder().exec().open(&test_so).unwrap();
+        let parser = ElfParser::from_mmap(mmap.clone());
+        let opts = FindAddrOpts {
+            sym_type: SymType::Function,
+            ..Default::default()
+        };
+        let symbols = parser.find_addr("the_answer", &opts).unwrap();
+        let symbol = symbols.first().unwrap();
+        let the_answer_addr = unsafe { mmap.as_ptr().add(symbol.addr) } as Addr;
+
+        // Make all `map_files` paths inaccessible.
+        let entries = || {
+            maps::parse(Pid::Slf).unwrap().map(|result| {
+                result.map(|mut entry| {
+                    if let Some(maps::PathName::Path(path)) = &mut entry.path_name {
+                        path.maps_file = PathBuf::from("/does/not/exist");
+                    }
+                    entry
+                })
+            })
+        };
+        let addrs = [the_answer_addr];
+
+        let handler = NormalizationHandler::new(Pid::Slf, addrs.len(), &read_build_id, true);
+        let norm_addrs =
+            normalize_sorted_user_addrs_with_entries(addrs.iter().copied(), entries(), handler)
+                .unwrap()
+                .normalized;
+        assert_eq!(norm_addrs.addrs, vec![(symbol.addr, 0)]);
+        let expected_binary = Binary {
+            build_id: Some(read_build_id(&test_so).unwrap().unwrap()),
+            path: test_so,
+            _non_exhaustive: (),
+        };
+        assert_eq!(norm_addrs.meta, vec![UserAddrMeta::Binary(expected_binary)]);
+
+        let handler = NormalizationHandler::new(Pid::Slf, addrs.len(), &read_build_id, false);
+        let err =
+            normalize_sorted_user_addrs_with_entries(addrs.iter().copied(), entries(), handler)
+                .err()
+                .unwrap();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    /// Check that we can normalize addresses in the vDSO.
+    #[test]
+    fn user_address_normalization_vdso() {
+        let (addr, vdso_addr) = if let Some(addrs) = find_vdso_clock_gettime() {
+            addrs
+        } else {
+            return
+        };
+
+        let normalizer = Normalizer::new();
+        let norm_addrs = normalizer
+            .normalize_user_addrs_sorted([addr].as_slice(), Pid::Slf)
+            .unwrap();
+        assert_eq!(norm_addrs.addrs.len(), 1);
+        assert_eq!(norm_addrs.meta.len(), 1);
+
+        let norm_addr = norm_addrs.addrs[0];
+        assert_eq!(norm_addr.0, vdso_addr);
+        let meta = &norm_addrs.meta[norm_addr.1];
+        assert!(meta.vdso().is_some(), "{meta:?}");
+    }
+
+    /// Check that addresses in a vDSO that cannot be read are reported
+    /// as unknown instead of failing normalization altogether.
+    #[test]
+    fn user_address_normalization_unreadable_vdso() {
+        fn read_no_build_id(_path: &Path) -> Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        // An address range that is not mapped in our process.
+        let range = 0x1000..0x2000;
+        let mut handler = NormalizationHandler::new(Pid::Slf, 2, &read_no_build_id, false);
+        let () = handler.handle_vdso_addr(0x1100, &range).unwrap();
+        let () = handler.handle_vdso_addr(0x1200, &range).unwrap();
+
+        let norm_addrs = handler.normalized;
+        assert_eq!(norm_addrs.addrs, vec![(0x1100, 0), (0x1200, 0)]);
+        assert_eq!(norm_addrs.meta, vec![Unknown::default().into()]);
+    }
+
     /// Check that we correctly handle normalization of an address not
     /// in any executable segment.
     #[test]
@@ -593,7 +1031,7 @@ mod tests {
             let entries = maps::parse_file(maps.as_bytes(), pid);
             let addrs = [unknown_addr as Addr];
 
-            let handler = NormalizationHandler::new(addrs.len(), &read_no_build_id);
+            let handler = NormalizationHandler::new(pid, addrs.len(), &read_no_build_id, false);
             let norm_addrs = normalize_sorted_user_addrs_with_entries(
                 addrs.as_slice().iter().copied(),
                 entries,
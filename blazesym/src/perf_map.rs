@@ -0,0 +1,560 @@
+//! Support for symbol information emitted by JIT compilers.
+//!
+//! Runtimes generating code at run time (e.g., the JVM, V8, ART, or
+//! LuaJIT) can emit information about that code in one of two formats
+//! understood by `perf`:
+//! - "perf map" files, by convention located at `/tmp/perf-<pid>.map`,
+//!   with one `<start> <size> <name>` line per symbol
+//! - "jitdump" files, a binary format that, in addition to symbols, may
+//!   carry source code location information
+
+use std::collections::HashMap;
+use std::fs::read as read_file;
+use std::fs::read_to_string;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::inspect::FindAddrOpts;
+use crate::inspect::SymInfo;
+use crate::log::debug;
+use crate::log::warn;
+use crate::symbolize::AddrLineInfo;
+use crate::symbolize::InlinedFn;
+use crate::util::ReadRaw as _;
+use crate::Addr;
+use crate::Pid;
+use crate::SymResolver;
+
+
+/// The magic number at the start of a jitdump file ("JiTD").
+const JITDUMP_MAGIC: u32 = 0x4a695444;
+/// The size of the header every jitdump record starts with.
+const JIT_RECORD_HEADER_SIZE: usize = 16;
+
+const JIT_CODE_LOAD: u32 = 0;
+const JIT_CODE_MOVE: u32 = 1;
+const JIT_CODE_DEBUG_INFO: u32 = 2;
+
+
+/// Parse the ID of a process in its innermost PID namespace from the
+/// contents of its `/proc/<pid>/status` file.
+fn parse_ns_pid(status: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("NSpid:"))?
+        .split_whitespace()
+        .last()?
+        .parse()
+        .ok()
+}
+
+/// Retrieve the conventional path of the perf map file of the process
+/// with the given ID.
+///
+/// The file is named after the ID of the process in its own PID
+/// namespace and located in the `/tmp` directory of its mount
+/// namespace, so that processes running in containers are covered as
+/// well.
+pub(crate) fn perf_map_path(pid: Pid) -> PathBuf {
+    let path = format!("/proc/{pid}/status");
+    let ns_pid = match read_to_string(&path) {
+        Ok(status) => parse_ns_pid(&status),
+        Err(err) => {
+            debug!("failed to read {path}: {err}; ignoring...");
+            None
+        }
+    };
+    // Kernels before 4.1 do not report `NSpid`.
+    let ns_pid = ns_pid.unwrap_or_else(|| match pid {
+        Pid::Slf => std::process::id(),
+        Pid::Pid(pid) => pid.get(),
+    });
+    PathBuf::from(format!("/proc/{pid}/root/tmp/perf-{ns_pid}.map"))
+}
+
+
+/// Source code location information for a JIT-ed instruction.
+#[derive(Debug, PartialEq)]
+struct JitLine {
+    /// The address of the first instruction the information applies to.
+    addr: Addr,
+    /// The source file.
+    file: String,
+    /// The line number.
+    line: usize,
+}
+
+
+/// A symbol for a piece of JIT-ed code.
+#[derive(Debug, PartialEq)]
+struct JitSym {
+    /// The start address of the code.
+    addr: Addr,
+    /// The size of the code, in bytes.
+    size: usize,
+    /// The name of the symbol.
+    name: String,
+    /// Source code location information, sorted by address.
+    lines: Vec<JitLine>,
+}
+
+impl JitSym {
+    fn contains(&self, addr: Addr) -> bool {
+        if self.size == 0 {
+            addr == self.addr
+        } else {
+            (self.addr..self.addr + self.size).contains(&addr)
+        }
+    }
+}
+
+
+fn invalid_data(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+fn parse_hex(s: &str) -> Option<Addr> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    Addr::from_str_radix(s, 16).ok()
+}
+
+/// Parse the contents of a perf map file.
+///
+/// Malformed lines are skipped over.
+fn parse_perf_map(data: &str) -> Vec<JitSym> {
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            // Each line has the form:
+            // <start> <size> <name>
+            // where <name> may contain spaces.
+            let mut tokens = line.trim().splitn(3, ' ');
+            let sym =
+                match (tokens.next(), tokens.next(), tokens.next()) {
+                    (Some(addr), Some(size), Some(name)) => parse_hex(addr)
+                        .zip(parse_hex(size))
+                        .map(|(addr, size)| JitSym {
+                            addr,
+                            size,
+                            name: name.to_string(),
+                            lines: Vec::new(),
+                        }),
+                    _ => None,
+                };
+
+            if sym.is_none() {
+                warn!("encountered malformed perf map line: {line}; ignoring...");
+            }
+            sym
+        })
+        .collect()
+}
+
+/// Parse the contents of a jitdump file.
+fn parse_jitdump(data: &[u8]) -> Result<Vec<JitSym>> {
+    fn parse_impl(mut data: &[u8]) -> Option<Result<Vec<JitSym>>> {
+        let full = data;
+        let magic = data.read_u32()?;
+        if magic != JITDUMP_MAGIC {
+            return Some(Err(invalid_data(format!(
+                "encountered unsupported jitdump magic: {magic:#x}"
+            ))))
+        }
+        let _version = data.read_u32()?;
+        let header_size = data.read_u32()? as usize;
+
+        let mut data = full.get(header_size..)?;
+        let mut syms = Vec::<JitSym>::new();
+        // Debug information is emitted before the code load record it
+        // belongs to, keyed by the code's address.
+        let mut pending_lines = HashMap::<u64, Vec<JitLine>>::new();
+        // Mapping from code address to symbol index.
+        let mut sym_idxs = HashMap::<u64, usize>::new();
+
+        while !data.is_empty() {
+            let mut record = data;
+            let id = record.read_u32()?;
+            let size = record.read_u32()? as usize;
+            let _timestamp = record.read_u64()?;
+            if size < JIT_RECORD_HEADER_SIZE {
+                return Some(Err(invalid_data(format!(
+                    "encountered invalid jitdump record size: {size}"
+                ))))
+            }
+            let mut body = record.read_slice(size - JIT_RECORD_HEADER_SIZE)?;
+            data = record;
+
+            match id {
+                JIT_CODE_LOAD => {
+                    let _pid = body.read_u32()?;
+                    let _tid = body.read_u32()?;
+                    let _vma = body.read_u64()?;
+                    let code_addr = body.read_u64()?;
+                    let code_size = body.read_u64()?;
+                    let _code_index = body.read_u64()?;
+                    let name = body.read_cstr()?.to_string_lossy().into_owned();
+                    let lines = pending_lines.remove(&code_addr).unwrap_or_default();
+
+                    let _prev = sym_idxs.insert(code_addr, syms.len());
+                    let () = syms.push(JitSym {
+                        addr: code_addr as Addr,
+                        size: code_size as usize,
+                        name,
+                        lines,
+                    });
+                }
+                JIT_CODE_MOVE => {
+                    let _pid = body.read_u32()?;
+                    let _tid = body.read_u32()?;
+                    let _vma = body.read_u64()?;
+                    let old_code_addr = body.read_u64()?;
+                    let new_code_addr = body.read_u64()?;
+                    let code_size = body.read_u64()?;
+
+                    if let Some(idx) = sym_idxs.get(&old_code_addr).copied() {
+                        let old = &syms[idx];
+                        let offset = new_code_addr.wrapping_sub(old_code_addr) as Addr;
+                        let sym = JitSym {
+                            addr: new_code_addr as Addr,
+                            size: code_size as usize,
+                            name: old.name.clone(),
+                            lines: old
+                                .lines
+                                .iter()
+                                .map(|line| JitLine {
+                                    addr: line.addr.wrapping_add(offset),
+                                    file: line.file.clone(),
+                                    line: line.line,
+                                })
+                                .collect(),
+                        };
+                        let _prev = sym_idxs.insert(new_code_addr, syms.len());
+                        let () = syms.push(sym);
+                    }
+                }
+                JIT_CODE_DEBUG_INFO => {
+                    let code_addr = body.read_u64()?;
+                    let count = body.read_u64()?;
+                    let mut lines = Vec::with_capacity(count.min(1024) as usize);
+                    for _ in 0..count {
+                        let addr = body.read_u64()?;
+                        let line = body.read_u32()?;
+                        let _discrim = body.read_u32()?;
+                        let file = body.read_cstr()?.to_string_lossy().into_owned();
+                        let () = lines.push(JitLine {
+                            addr: addr as Addr,
+                            file,
+                            line: line as usize,
+                        });
+                    }
+                    let () = lines.sort_by_key(|line| line.addr);
+                    let _prev = pending_lines.insert(code_addr, lines);
+                }
+                // Other records (e.g., unwinding information) are of no
+                // interest to us.
+                _ => (),
+            }
+        }
+        Some(Ok(syms))
+    }
+
+    parse_impl(data).unwrap_or_else(|| Err(invalid_data("encountered truncated jitdump data")))
+}
+
+
+/// A resolver for symbols of JIT-ed code, as described by a perf map or
+/// jitdump file.
+#[derive(Debug)]
+pub(crate) struct PerfMapResolver {
+    /// All symbols, sorted by address. Symbols with the same address are
+    /// kept in the order they were emitted in.
+    syms: Vec<JitSym>,
+    /// The path of the file the symbols were read from.
+    path: PathBuf,
+}
+
+impl PerfMapResolver {
+    /// Load the perf map or jitdump file at `path`, with the format
+    /// being detected automatically.
+    pub fn open(path: &Path) -> Result<Self> {
+        let data = read_file(path)?;
+        let mut syms = if data.starts_with(&JITDUMP_MAGIC.to_ne_bytes()) {
+            parse_jitdump(&data)?
+        } else {
+            let data = String::from_utf8(data).map_err(|err| {
+                invalid_data(format!(
+                    "perf map {} is not valid UTF-8: {err}",
+                    path.display()
+                ))
+            })?;
+            parse_perf_map(&data)
+        };
+
+        // Note that the sort is stable, which we rely on for picking
+        // the most recent symbol for a given address.
+        let () = syms.sort_by_key(|sym| sym.addr);
+
+        let slf = Self {
+            syms,
+            path: path.to_path_buf(),
+        };
+        Ok(slf)
+    }
+
+    /// Find the symbol containing `addr`.
+    ///
+    /// JIT compilers may reuse memory once code is discarded. In case of
+    /// multiple symbols starting at the same address, the one emitted
+    /// last is reported.
+    fn find_sym(&self, addr: Addr) -> Option<&JitSym> {
+        let idx = self.syms.partition_point(|sym| sym.addr <= addr);
+        let sym = self.syms.get(idx.checked_sub(1)?)?;
+        sym.contains(addr).then_some(sym)
+    }
+}
+
+impl SymResolver for PerfMapResolver {
+    fn find_symbols(&self, addr: Addr) -> Vec<(&str, Addr)> {
+        self.find_sym(addr)
+            .map(|sym| vec![(sym.name.as_str(), sym.addr)])
+            .unwrap_or_default()
+    }
+
+    fn find_addr(&self, _name: &str, _opts: &FindAddrOpts) -> Option<Vec<SymInfo>> {
+        None
+    }
+
+    fn find_line_info(&self, addr: Addr) -> Option<AddrLineInfo> {
+        let sym = self.find_sym(addr)?;
+        let idx = sym.lines.partition_point(|line| line.addr <= addr);
+        let line = sym.lines.get(idx.checked_sub(1)?)?;
+        Some(AddrLineInfo {
+            path: PathBuf::from(&line.file),
+            line: line.line,
+            column: 0,
+        })
+    }
+
+    fn find_inlined_fns(&self, _addr: Addr) -> Vec<InlinedFn> {
+        Vec::new()
+    }
+
+    fn addr_file_off(&self, _addr: Addr) -> Option<u64> {
+        None
+    }
+
+    fn get_obj_file_name(&self) -> &Path {
+        &self.path
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write as _;
+
+    use tempfile::NamedTempFile;
+
+    use test_log::test;
+
+
+    /// A builder for jitdump data, for testing purposes.
+    struct JitDump {
+        data: Vec<u8>,
+    }
+
+    impl JitDump {
+        fn new() -> Self {
+            let mut data = Vec::new();
+            let () = data.extend_from_slice(&JITDUMP_MAGIC.to_ne_bytes());
+            // version
+            let () = data.extend_from_slice(&1u32.to_ne_bytes());
+            // total_size
+            let () = data.extend_from_slice(&40u32.to_ne_bytes());
+            // elf_mach, pad1, pid
+            let () = data.extend_from_slice(&[0; 12]);
+            // timestamp, flags
+            let () = data.extend_from_slice(&[0; 16]);
+            Self { data }
+        }
+
+        fn record(&mut self, id: u32, body: &[u8]) {
+            let size = (JIT_RECORD_HEADER_SIZE + body.len()) as u32;
+            let () = self.data.extend_from_slice(&id.to_ne_bytes());
+            let () = self.data.extend_from_slice(&size.to_ne_bytes());
+            let () = self.data.extend_from_slice(&0u64.to_ne_bytes());
+            let () = self.data.extend_from_slice(body);
+        }
+
+        fn code_load(&mut self, addr: u64, size: u64, name: &str) {
+            let mut body = Vec::new();
+            let () = body.extend_from_slice(&[0; 8]);
+            let () = body.extend_from_slice(&addr.to_ne_bytes());
+            let () = body.extend_from_slice(&addr.to_ne_bytes());
+            let () = body.extend_from_slice(&size.to_ne_bytes());
+            let () = body.extend_from_slice(&0u64.to_ne_bytes());
+            let () = body.extend_from_slice(name.as_bytes());
+            let () = body.push(0);
+            // The code itself.
+            let () = body.extend(vec![0xcc; size as usize]);
+            self.record(JIT_CODE_LOAD, &body)
+        }
+
+        fn code_move(&mut self, old_addr: u64, new_addr: u64, size: u64) {
+            let mut body = Vec::new();
+            let () = body.extend_from_slice(&[0; 8]);
+            let () = body.extend_from_slice(&new_addr.to_ne_bytes());
+            let () = body.extend_from_slice(&old_addr.to_ne_bytes());
+            let () = body.extend_from_slice(&new_addr.to_ne_bytes());
+            let () = body.extend_from_slice(&size.to_ne_bytes());
+            let () = body.extend_from_slice(&0u64.to_ne_bytes());
+            self.record(JIT_CODE_MOVE, &body)
+        }
+
+        fn debug_info(&mut self, addr: u64, lines: &[(u64, u32, &str)]) {
+            let mut body = Vec::new();
+            let () = body.extend_from_slice(&addr.to_ne_bytes());
+            let () = body.extend_from_slice(&(lines.len() as u64).to_ne_bytes());
+            for (addr, line, file) in lines {
+                let () = body.extend_from_slice(&addr.to_ne_bytes());
+                let () = body.extend_from_slice(&line.to_ne_bytes());
+                let () = body.extend_from_slice(&0u32.to_ne_bytes());
+                let () = body.extend_from_slice(file.as_bytes());
+                let () = body.push(0);
+            }
+            self.record(JIT_CODE_DEBUG_INFO, &body)
+        }
+
+        fn finish(self) -> Vec<u8> {
+            self.data
+        }
+    }
+
+
+    /// Check that we can parse and look up symbols in perf map files.
+    #[test]
+    fn perf_map_lookup() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(
+            file,
+            "7f0000001000 40 LazyCompile:~main /app/main.js:1
+0x7f0000001040 0x20 Interpreter
+7f0000002000 10 old
+7f0000002000 10 new"
+        )
+        .unwrap();
+
+        let resolver = PerfMapResolver::open(file.path()).unwrap();
+        assert_eq!(
+            resolver.find_symbols(0x7f0000001000),
+            vec![("LazyCompile:~main /app/main.js:1", 0x7f0000001000)]
+        );
+        assert_eq!(
+            resolver.find_symbols(0x7f000000105f),
+            vec![("Interpreter", 0x7f0000001040)]
+        );
+        assert_eq!(
+            resolver.find_symbols(0x7f0000002008),
+            vec![("new", 0x7f0000002000)]
+        );
+        assert!(resolver.find_symbols(0x7f0000001060).is_empty());
+        assert!(resolver.find_symbols(0x7f0000000fff).is_empty());
+        assert!(resolver.find_line_info(0x7f0000001000).is_none());
+    }
+
+    /// Check that malformed perf map lines are skipped over.
+    #[test]
+    fn perf_map_malformed_lines() {
+        let mut file = NamedTempFile::new().unwrap();
+        let () = writeln!(
+            file,
+            "7f0000001000 40
+7f0000002000 10 valid
+xyz 10 invalid-addr
+7f0000003000 zz invalid-size
+7f0000004000 8 also valid"
+        )
+        .unwrap();
+
+        let resolver = PerfMapResolver::open(file.path()).unwrap();
+        assert_eq!(resolver.syms.len(), 2);
+        assert_eq!(
+            resolver.find_symbols(0x7f0000002000),
+            vec![("valid", 0x7f0000002000)]
+        );
+        assert_eq!(
+            resolver.find_symbols(0x7f0000004004),
+            vec![("also valid", 0x7f0000004000)]
+        );
+    }
+
+    /// Check that we can parse the namespace PID of a process.
+    #[test]
+    fn ns_pid_parsing() {
+        let status = "Name:\tjava\nTgid:\t4242\nPid:\t4242\nNSpid:\t4242\t7\nNStgid:\t4242\t7\n";
+        assert_eq!(parse_ns_pid(status), Some(7));
+        assert_eq!(parse_ns_pid("NSpid:\t4242\n"), Some(4242));
+        assert_eq!(parse_ns_pid("Pid:\t4242\n"), None);
+    }
+
+    /// Check that the perf map path of our own process is what we
+    /// expect.
+    #[test]
+    fn perf_map_path_self() {
+        let pid = std::process::id();
+        assert_eq!(
+            perf_map_path(Pid::Slf),
+            PathBuf::from(format!("/proc/self/root/tmp/perf-{pid}.map"))
+        );
+    }
+
+    /// Check that we can parse and look up symbols and source code
+    /// locations in jitdump files.
+    #[test]
+    fn jitdump_lookup() {
+        let mut dump = JitDump::new();
+        let () = dump.debug_info(
+            0x1000,
+            &[(0x1000, 10, "Main.java"), (0x1010, 12, "Main.java")],
+        );
+        let () = dump.code_load(0x1000, 0x20, "Main::run");
+        let () = dump.code_load(0x2000, 0x8, "Main::helper");
+        let () = dump.code_move(0x1000, 0x3000, 0x20);
+
+        let mut file = NamedTempFile::new().unwrap();
+        let () = file.write_all(&dump.finish()).unwrap();
+
+        let resolver = PerfMapResolver::open(file.path()).unwrap();
+        assert_eq!(resolver.find_symbols(0x1004), vec![("Main::run", 0x1000)]);
+        assert_eq!(
+            resolver.find_symbols(0x2007),
+            vec![("Main::helper", 0x2000)]
+        );
+        assert!(resolver.find_symbols(0x2008).is_empty());
+
+        let info = resolver.find_line_info(0x1004).unwrap();
+        assert_eq!(info.path, Path::new("Main.java"));
+        assert_eq!(info.line, 10);
+        let info = resolver.find_line_info(0x1018).unwrap();
+        assert_eq!(info.line, 12);
+        assert!(resolver.find_line_info(0x2000).is_none());
+
+        // Moved code retains its symbol and line information.
+        assert_eq!(resolver.find_symbols(0x3014), vec![("Main::run", 0x3000)]);
+        let info = resolver.find_line_info(0x3014).unwrap();
+        assert_eq!(info.line, 12);
+
+        // Truncated data should be reported as such.
+        let mut dump = JitDump::new();
+        let () = dump.code_load(0x1000, 0x20, "Main::run");
+        let mut data = dump.finish();
+        let () = data.truncate(data.len() - 1);
+        let err = parse_jitdump(&data).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
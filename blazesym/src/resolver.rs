@@ -4,6 +4,7 @@ use std::path::Path;
 use crate::inspect::FindAddrOpts;
 use crate::inspect::SymInfo;
 use crate::symbolize::AddrLineInfo;
+use crate::symbolize::InlinedFn;
 use crate::Addr;
 
 
@@ -22,6 +23,9 @@ where
     fn find_addr(&self, name: &str, opts: &FindAddrOpts) -> Option<Vec<SymInfo>>;
     /// Find the file name and the line number of an address.
     fn find_line_info(&self, addr: Addr) -> Option<AddrLineInfo>;
+    /// Find the functions inlined at an address, ordered from the
+    /// outermost to the innermost one.
+    fn find_inlined_fns(&self, addr: Addr) -> Vec<InlinedFn>;
     /// Translate an address (virtual) in a process to the file offset
     /// in the object file.
     fn addr_file_off(&self, addr: Addr) -> Option<u64>;
@@ -0,0 +1,403 @@
+//! Functionality for retrieving source code snippets.
+//!
+//! Symbolization reports source code locations as a path and line
+//! number, with the path being the one recorded at build time. This
+//! module provides means for mapping such a path to a file on the local
+//! system and for loading the source code surrounding the line in
+//! question.
+//!
+//! ```no_run
+//! use blazesym::snippet::SnippetLoader;
+//! use blazesym::symbolize::Process;
+//! use blazesym::symbolize::Source;
+//! use blazesym::symbolize::Symbolizer;
+//! use blazesym::Addr;
+//!
+//! let src = Source::Process(Process::new(std::process::id().into()));
+//! let symbolizer = Symbolizer::new();
+//! let results = symbolizer.symbolize(&src, &[0xdeadbeef as Addr]).unwrap();
+//!
+//! let loader = SnippetLoader::builder()
+//!     .set_src_roots(["/home/user/checkout"])
+//!     .add_path_substitution("/build/project", "/home/user/checkout")
+//!     .build();
+//!
+//! for result in results.iter().flatten() {
+//!     if let Ok(snippet) = loader.load_symbolized(result) {
+//!         for (idx, line) in snippet.lines.iter().enumerate() {
+//!             println!("{:>5}: {line}", snippet.first_line + idx);
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::fs::read as read_file;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Result;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::symbolize::SymbolizedResult;
+
+
+/// The number of lines to report before and after the line of interest,
+/// by default.
+const DEFAULT_CONTEXT_LINES: usize = 3;
+
+
+/// A builder for configurable construction of [`SnippetLoader`] objects.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    /// The directories to search for source files.
+    src_roots: Vec<PathBuf>,
+    /// Path prefix substitutions, as `(from, to)` pairs.
+    substitutions: Vec<(PathBuf, PathBuf)>,
+    /// The number of lines to report before and after the line of
+    /// interest.
+    context_lines: usize,
+}
+
+impl Builder {
+    /// Set the directories to search for source files.
+    ///
+    /// Relative source paths are interpreted relative to each of these
+    /// directories. Absolute source paths that do not exist on the
+    /// local system are looked up in these directories by successively
+    /// shorter suffixes, e.g., `/build/src/main.c` is searched for as
+    /// `<root>/build/src/main.c`, `<root>/src/main.c`, and
+    /// `<root>/main.c`.
+    pub fn set_src_roots<D, P>(mut self, src_roots: D) -> Builder
+    where
+        D: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.src_roots = src_roots
+            .into_iter()
+            .map(|dir| dir.as_ref().to_path_buf())
+            .collect();
+        self
+    }
+
+    /// Add a substitution rule replacing the path prefix `from` with
+    /// `to`.
+    ///
+    /// Similar to GDB's `set substitute-path`, rules are checked in the
+    /// order in which they were added and the first one matching is
+    /// applied. Prefixes are matched on a path component basis, i.e.,
+    /// `/usr/src` matches `/usr/src/main.c` but not `/usr/source/main.c`.
+    pub fn add_path_substitution<F, T>(mut self, from: F, to: T) -> Builder
+    where
+        F: AsRef<Path>,
+        T: AsRef<Path>,
+    {
+        let () = self
+            .substitutions
+            .push((from.as_ref().to_path_buf(), to.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Set the number of lines to report before and after the line of
+    /// interest.
+    pub fn set_context_lines(mut self, context_lines: usize) -> Builder {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Create the [`SnippetLoader`] object.
+    pub fn build(self) -> SnippetLoader {
+        let Builder {
+            src_roots,
+            substitutions,
+            context_lines,
+        } = self;
+
+        SnippetLoader {
+            src_roots,
+            substitutions,
+            context_lines,
+        }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            src_roots: Vec::new(),
+            substitutions: Vec::new(),
+            context_lines: DEFAULT_CONTEXT_LINES,
+        }
+    }
+}
+
+
+/// A snippet of source code surrounding a line of interest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snippet {
+    /// The path of the source file on the local system.
+    pub path: PathBuf,
+    /// The (1-based) number of the line of interest.
+    pub line: usize,
+    /// The (1-based) number of the first line in `lines`.
+    pub first_line: usize,
+    /// The lines of source code, without line terminators.
+    ///
+    /// The line of interest is `lines[line - first_line]`.
+    pub lines: Vec<String>,
+}
+
+
+/// A type for loading source code snippets for source code locations.
+#[derive(Debug)]
+pub struct SnippetLoader {
+    src_roots: Vec<PathBuf>,
+    substitutions: Vec<(PathBuf, PathBuf)>,
+    context_lines: usize,
+}
+
+impl SnippetLoader {
+    /// Create a new [`SnippetLoader`] with the default configuration.
+    pub fn new() -> Self {
+        Builder::default().build()
+    }
+
+    /// Retrieve a [`Builder`] object for configurable construction of a
+    /// [`SnippetLoader`].
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Apply the first matching path substitution rule to `path`.
+    fn substitute(&self, path: &Path) -> Option<PathBuf> {
+        self.substitutions
+            .iter()
+            .find_map(|(from, to)| path.strip_prefix(from).ok().map(|suffix| to.join(suffix)))
+    }
+
+    /// Map the source code path `path`, as reported by symbolization,
+    /// to a file on the local system.
+    ///
+    /// `None` is returned if no such file could be found.
+    pub fn resolve_path(&self, path: &Path) -> Option<PathBuf> {
+        let path = self.substitute(path).unwrap_or_else(|| path.to_path_buf());
+
+        if path.is_absolute() || self.src_roots.is_empty() {
+            if path.is_file() {
+                return Some(path)
+            }
+        }
+
+        let components = path
+            .components()
+            .filter(|component| matches!(component, Component::Normal(..)))
+            .collect::<Vec<_>>();
+
+        self.src_roots.iter().find_map(|root| {
+            (0..components.len()).find_map(|idx| {
+                let candidate = components[idx..]
+                    .iter()
+                    .fold(root.clone(), |path, component| path.join(component));
+                candidate.is_file().then_some(candidate)
+            })
+        })
+    }
+
+    /// Load the source code surrounding `line` of the source file
+    /// `path`, as reported by symbolization.
+    ///
+    /// `path` is mapped to a file on the local system as per
+    /// [`SnippetLoader::resolve_path`].
+    pub fn load(&self, path: &Path, line: usize) -> Result<Snippet> {
+        if line == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "line numbers start at one",
+            ))
+        }
+
+        let local_path = self.resolve_path(path).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("failed to find source file {}", path.display()),
+            )
+        })?;
+        let data = read_file(&local_path)?;
+        let text = String::from_utf8_lossy(&data);
+        let count = text.lines().count();
+        if line > count {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "line {line} is out of bounds of {} ({count} lines)",
+                    local_path.display()
+                ),
+            ))
+        }
+
+        let first_line = line.saturating_sub(self.context_lines).max(1);
+        let last_line = line.saturating_add(self.context_lines).min(count);
+        let lines = text
+            .lines()
+            .skip(first_line - 1)
+            .take(last_line - first_line + 1)
+            .map(str::to_string)
+            .collect();
+
+        let snippet = Snippet {
+            path: local_path,
+            line,
+            first_line,
+            lines,
+        };
+        Ok(snippet)
+    }
+
+    /// Load the source code surrounding the location of a
+    /// [`SymbolizedResult`].
+    ///
+    /// This is a convenience wrapper around [`SnippetLoader::load`].
+    pub fn load_symbolized(&self, result: &SymbolizedResult) -> Result<Snippet> {
+        if result.path.as_os_str().is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("symbol {} has no source code location", result.symbol),
+            ))
+        }
+        self.load(&result.path, result.line)
+    }
+}
+
+impl Default for SnippetLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::create_dir_all;
+    use std::fs::write as write_file;
+
+    use tempfile::tempdir;
+    use test_log::test;
+
+
+    /// Create a source file with `count` lines at `path`.
+    fn make_src(path: &Path, count: usize) {
+        let () = create_dir_all(path.parent().unwrap()).unwrap();
+        let text = (1..=count)
+            .map(|line| format!("line {line}\n"))
+            .collect::<String>();
+        let () = write_file(path, text).unwrap();
+    }
+
+    /// Check that we can load snippets, including ones close to the
+    /// start and end of a file.
+    #[test]
+    fn snippet_loading() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("main.c");
+        let () = make_src(&src, 10);
+
+        let loader = SnippetLoader::builder().set_context_lines(2).build();
+        let snippet = loader.load(&src, 5).unwrap();
+        assert_eq!(snippet.path, src);
+        assert_eq!(snippet.line, 5);
+        assert_eq!(snippet.first_line, 3);
+        assert_eq!(
+            snippet.lines,
+            ["line 3", "line 4", "line 5", "line 6", "line 7"]
+        );
+
+        let snippet = loader.load(&src, 1).unwrap();
+        assert_eq!(snippet.first_line, 1);
+        assert_eq!(snippet.lines, ["line 1", "line 2", "line 3"]);
+
+        let snippet = loader.load(&src, 10).unwrap();
+        assert_eq!(snippet.first_line, 8);
+        assert_eq!(snippet.lines, ["line 8", "line 9", "line 10"]);
+
+        let err = loader.load(&src, 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = loader.load(&src, 11).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = loader.load(&dir.path().join("foo.c"), 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    /// Check that we map build time paths to local ones as expected.
+    #[test]
+    fn path_resolution() {
+        let dir = tempdir().unwrap();
+        let checkout = dir.path().join("checkout");
+        let src = checkout.join("src").join("main.c");
+        let () = make_src(&src, 1);
+
+        // Without any configuration only existing paths are found.
+        let loader = SnippetLoader::new();
+        assert_eq!(loader.resolve_path(&src), Some(src.clone()));
+        assert_eq!(loader.resolve_path(Path::new("/build/src/main.c")), None);
+
+        let loader = SnippetLoader::builder()
+            .add_path_substitution("/build/proj", &checkout)
+            .add_path_substitution("/build", "/nonexistent")
+            .build();
+        assert_eq!(
+            loader.resolve_path(Path::new("/build/proj/src/main.c")),
+            Some(src.clone())
+        );
+        // Prefixes are matched on a per-component basis.
+        assert_eq!(
+            loader.resolve_path(Path::new("/build/project/src/main.c")),
+            None
+        );
+
+        let loader = SnippetLoader::builder()
+            .set_src_roots([dir.path().join("other"), checkout.clone()])
+            .build();
+        assert_eq!(
+            loader.resolve_path(Path::new("src/main.c")),
+            Some(src.clone())
+        );
+        assert_eq!(
+            loader.resolve_path(Path::new("/build/proj/src/main.c")),
+            Some(src.clone())
+        );
+        assert_eq!(loader.resolve_path(Path::new("src/foo.c")), None);
+    }
+
+    /// Check that we can load a snippet for a [`SymbolizedResult`].
+    #[test]
+    fn symbolized_result_loading() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("main.c");
+        let () = make_src(&src, 4);
+
+        let mut result = SymbolizedResult {
+            symbol: "main".to_string(),
+            raw_symbol: "main".to_string(),
+            addr: 0x1000,
+            path: PathBuf::from("/build/main.c"),
+            line: 2,
+            column: 0,
+            module: None,
+            inlined: Vec::new(),
+        };
+
+        let loader = SnippetLoader::builder()
+            .add_path_substitution("/build", dir.path())
+            .set_context_lines(0)
+            .build();
+        let snippet = loader.load_symbolized(&result).unwrap();
+        assert_eq!(snippet.path, src);
+        assert_eq!(snippet.lines, ["line 2"]);
+
+        result.path = PathBuf::new();
+        let err = loader.load_symbolized(&result).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
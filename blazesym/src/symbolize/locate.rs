@@ -0,0 +1,228 @@
+use std::fmt::Debug;
+use std::io::Result;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::util::to_hex;
+
+#[cfg(doc)]
+use super::Symbolizer;
+
+
+/// A trait for locating binaries on the local system.
+///
+/// Locators are used by [`Symbolizer::symbolize_normalized`] for
+/// mapping the binaries referenced by normalized addresses, which may
+/// have been captured on a different system, to local files.
+pub trait Locator: Debug {
+    /// Locate the binary with the given `path` (as it was found on the
+    /// system where addresses were normalized) and/or `build_id`.
+    ///
+    /// Implementations should return `Ok(None)` if the binary cannot
+    /// be found, so that other locators can be tried.
+    fn locate(&self, path: Option<&Path>, build_id: Option<&[u8]>) -> Result<Option<PathBuf>>;
+}
+
+
+/// A [`Locator`] using binaries at the very path at which they were
+/// found during normalization.
+///
+/// This locator is useful when symbolizing on the same system or on one
+/// with an identical file system layout.
+#[derive(Clone, Debug, Default)]
+pub struct LocalPath {
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl LocalPath {
+    /// Create a new [`LocalPath`] object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Locator for LocalPath {
+    fn locate(&self, path: Option<&Path>, _build_id: Option<&[u8]>) -> Result<Option<PathBuf>> {
+        let path = path.filter(|path| path.is_file()).map(Path::to_path_buf);
+        Ok(path)
+    }
+}
+
+
+/// A [`Locator`] looking up binaries by build ID in a `.build-id`
+/// directory hierarchy.
+///
+/// A binary with build ID `abcdef...` is searched for as
+/// `<dir>/.build-id/ab/cdef...` and `<dir>/.build-id/ab/cdef....debug`,
+/// which is the layout used, for example, for `/usr/lib/debug`.
+#[derive(Clone, Debug)]
+pub struct BuildIdDir {
+    /// The directory containing the `.build-id` sub-directory.
+    pub dir: PathBuf,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl BuildIdDir {
+    /// Create a new [`BuildIdDir`] object, referencing the provided
+    /// directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl Locator for BuildIdDir {
+    fn locate(&self, _path: Option<&Path>, build_id: Option<&[u8]>) -> Result<Option<PathBuf>> {
+        let build_id = match build_id {
+            Some(build_id) if build_id.len() >= 2 => build_id,
+            _ => return Ok(None),
+        };
+
+        let hex = to_hex(build_id);
+        let (dir, file) = hex.split_at(2);
+        let dir = self.dir.join(".build-id").join(dir);
+        let path = [dir.join(file), dir.join(format!("{file}.debug"))]
+            .into_iter()
+            .find(|path| path.is_file());
+        Ok(path)
+    }
+}
+
+
+/// A [`Locator`] looking up binaries by build ID in a local store with
+/// the layout used by the `debuginfod` client cache.
+///
+/// A binary with build ID `abcdef...` is searched for as
+/// `<dir>/abcdef.../executable` and `<dir>/abcdef.../debuginfo`. The
+/// client cache is typically located at `~/.cache/debuginfod_client`.
+#[derive(Clone, Debug)]
+pub struct DebuginfodCache {
+    /// The directory containing the cached files.
+    pub dir: PathBuf,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl DebuginfodCache {
+    /// Create a new [`DebuginfodCache`] object, referencing the provided
+    /// directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl Locator for DebuginfodCache {
+    fn locate(&self, _path: Option<&Path>, build_id: Option<&[u8]>) -> Result<Option<PathBuf>> {
+        let build_id = match build_id {
+            Some(build_id) if !build_id.is_empty() => build_id,
+            _ => return Ok(None),
+        };
+
+        let dir = self.dir.join(to_hex(build_id));
+        let path = [dir.join("executable"), dir.join("debuginfo")]
+            .into_iter()
+            .find(|path| path.is_file());
+        Ok(path)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::create_dir_all;
+    use std::fs::write as write_file;
+
+    use tempfile::tempdir;
+    use test_log::test;
+
+
+    /// Check that the [`LocalPath`] locator works as expected.
+    #[test]
+    fn local_path_locating() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("libfoo.so");
+        let () = write_file(&file, b"").unwrap();
+
+        let locator = LocalPath::new();
+        assert_eq!(
+            locator.locate(Some(&file), Some(&[1, 2])).unwrap(),
+            Some(file)
+        );
+        assert_eq!(locator.locate(Some(dir.path()), None).unwrap(), None);
+        assert_eq!(locator.locate(None, Some(&[1, 2])).unwrap(), None);
+    }
+
+    /// Check that the [`BuildIdDir`] locator works as expected.
+    #[test]
+    fn build_id_dir_locating() {
+        let dir = tempdir().unwrap();
+        let build_id_dir = dir.path().join(".build-id").join("ab");
+        let () = create_dir_all(&build_id_dir).unwrap();
+        let debug = build_id_dir.join("cdef.debug");
+        let () = write_file(&debug, b"").unwrap();
+
+        let locator = BuildIdDir::new(dir.path());
+        let path = Path::new("/usr/lib/libfoo.so");
+        assert_eq!(
+            locator
+                .locate(Some(path), Some(&[0xab, 0xcd, 0xef]))
+                .unwrap(),
+            Some(debug)
+        );
+
+        // The binary itself is preferred over a debug file.
+        let binary = build_id_dir.join("cdef");
+        let () = write_file(&binary, b"").unwrap();
+        assert_eq!(
+            locator
+                .locate(Some(path), Some(&[0xab, 0xcd, 0xef]))
+                .unwrap(),
+            Some(binary)
+        );
+
+        assert_eq!(
+            locator.locate(Some(path), Some(&[0xab, 0xcd])).unwrap(),
+            None
+        );
+        assert_eq!(locator.locate(Some(path), Some(&[0xab])).unwrap(), None);
+        assert_eq!(locator.locate(Some(path), None).unwrap(), None);
+    }
+
+    /// Check that the [`DebuginfodCache`] locator works as expected.
+    #[test]
+    fn debuginfod_cache_locating() {
+        let dir = tempdir().unwrap();
+        let entry_dir = dir.path().join("abcdef");
+        let () = create_dir_all(&entry_dir).unwrap();
+        let debuginfo = entry_dir.join("debuginfo");
+        let () = write_file(&debuginfo, b"").unwrap();
+
+        let locator = DebuginfodCache::new(dir.path());
+        assert_eq!(
+            locator.locate(None, Some(&[0xab, 0xcd, 0xef])).unwrap(),
+            Some(debuginfo)
+        );
+
+        let executable = entry_dir.join("executable");
+        let () = write_file(&executable, b"").unwrap();
+        assert_eq!(
+            locator.locate(None, Some(&[0xab, 0xcd, 0xef])).unwrap(),
+            Some(executable)
+        );
+
+        assert_eq!(locator.locate(None, Some(&[0xab, 0xcd])).unwrap(), None);
+        assert_eq!(locator.locate(None, None).unwrap(), None);
+    }
+}
@@ -9,6 +9,7 @@
 //! use blazesym::symbolize::Symbolizer;
 //! use blazesym::symbolize::Source;
 //! use blazesym::symbolize::Process;
+//! use blazesym::symbolize::InlinedFn;
 //! use blazesym::symbolize::SymbolizedResult;
 //!
 //! let process_id: u32 = std::process::id(); // <some process id>
@@ -33,27 +34,41 @@
 //!     println!("0x{addr:016x} ({} entries)", sym_results.len());
 //!
 //!     for result in sym_results {
-//!       let SymbolizedResult {symbol, addr, path, line, column} = result;
+//!       let SymbolizedResult {symbol, addr, path, line, ..} = result;
 //!       println!("    {symbol}@0x{addr:016x} {}:{line}", path.display());
 //!     }
 //!   } else {
-//!     let SymbolizedResult {symbol, addr, path, line, column} = &sym_results[0];
+//!     let SymbolizedResult {symbol, addr, path, line, inlined, ..} = &sym_results[0];
 //!     println!("0x{addr:016x} {symbol}@0x{addr:016x} {}:{line}", path.display());
+//!     // Functions inlined at the address, from the outermost one inward.
+//!     for InlinedFn {name, call_path, call_line, ..} in inlined {
+//!       println!("    {name} (inlined at {}:{call_line})", call_path.display());
+//!     }
 //!   }
 //! }
 //! ```
 
+mod locate;
+mod snapshot;
 mod source;
 mod symbolizer;
 
 use std::path::PathBuf;
 
+pub use locate::BuildIdDir;
+pub use locate::DebuginfodCache;
+pub use locate::LocalPath;
+pub use locate::Locator;
+pub use snapshot::ProcessSnapshot;
+pub use source::Apk;
 pub use source::Elf;
 pub use source::Gsym;
 pub use source::Kernel;
+pub use source::PerfMap;
 pub use source::Process;
 pub use source::Source;
 pub use symbolizer::Builder;
+pub use symbolizer::InlinedFn;
 pub use symbolizer::SymbolizedResult;
 pub use symbolizer::Symbolizer;
 
@@ -0,0 +1,145 @@
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::elf::ElfResolver;
+use crate::perf_map::PerfMapResolver;
+use crate::Addr;
+use crate::Pid;
+
+
+/// The means by which addresses in a [`SnapshotEntry`] are symbolized.
+#[derive(Debug)]
+pub(crate) enum SnapshotResolver {
+    /// An ELF file backing the mapping, possibly contained in an APK.
+    Elf {
+        /// The offset of the start of the mapping, relative to the start
+        /// of the ELF file.
+        offset: u64,
+        /// The resolver for the ELF file.
+        resolver: Arc<ElfResolver>,
+    },
+    /// The process' vDSO.
+    Vdso(Arc<ElfResolver>),
+    /// JIT-ed code, as described by the process' perf map.
+    PerfMap(Arc<PerfMapResolver>),
+}
+
+
+/// A single executable memory mapping captured as part of a
+/// [`ProcessSnapshot`].
+#[derive(Debug)]
+pub(crate) struct SnapshotEntry {
+    /// The virtual address range covered by the mapping.
+    pub range: Range<Addr>,
+    /// The resolver for the mapping, if it could be loaded.
+    pub resolver: Option<SnapshotResolver>,
+}
+
+
+/// A snapshot of the executable memory mappings of a process.
+///
+/// A snapshot captures the state of `/proc/<pid>/maps` at the time of
+/// its creation and keeps all ELF files backing executable mappings
+/// open and parsed, along with the process' vDSO and perf map. Use
+/// [`Symbolizer::snapshot_process`] to create one and
+/// [`Symbolizer::symbolize_snapshot`] to symbolize addresses using it.
+///
+/// Symbolizing many batches of addresses using a snapshot avoids
+/// reparsing the process' memory mappings for each of them.
+/// Furthermore, a snapshot stays usable after the process has exited.
+///
+/// A snapshot is not updated implicitly. Memory mappings created after
+/// the snapshot was taken are unknown to it and addresses in them will
+/// not be symbolized. It is up to the user to decide when to refresh a
+/// snapshot using [`Symbolizer::refresh_snapshot`], e.g., based on its
+/// [`age`][ProcessSnapshot::age].
+///
+/// [`Symbolizer::snapshot_process`]: crate::symbolize::Symbolizer::snapshot_process
+/// [`Symbolizer::symbolize_snapshot`]: crate::symbolize::Symbolizer::symbolize_snapshot
+/// [`Symbolizer::refresh_snapshot`]: crate::symbolize::Symbolizer::refresh_snapshot
+#[derive(Debug)]
+pub struct ProcessSnapshot {
+    /// The ID of the process the snapshot was taken of.
+    pid: Pid,
+    /// Snapshotted mappings, sorted by start address.
+    entries: Vec<SnapshotEntry>,
+    /// The time at which the snapshot was taken.
+    created: Instant,
+}
+
+impl ProcessSnapshot {
+    pub(crate) fn new(pid: Pid, entries: Vec<SnapshotEntry>) -> Self {
+        debug_assert!(entries
+            .windows(2)
+            .all(|entries| entries[0].range.start <= entries[1].range.start));
+
+        Self {
+            pid,
+            entries,
+            created: Instant::now(),
+        }
+    }
+
+    /// Find the entry containing `addr`, if any.
+    pub(crate) fn find_entry(&self, addr: Addr) -> Option<&SnapshotEntry> {
+        let idx = self
+            .entries
+            .partition_point(|entry| entry.range.start <= addr);
+        let entry = self.entries.get(idx.checked_sub(1)?)?;
+        entry.range.contains(&addr).then_some(entry)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn entries(&self) -> &[SnapshotEntry] {
+        &self.entries
+    }
+
+    /// Retrieve the ID of the process the snapshot was taken of.
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    /// Retrieve the time that passed since the snapshot was taken.
+    pub fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_log::test;
+
+
+    /// Check that we can find the entry containing an address.
+    #[test]
+    fn entry_lookup() {
+        let entry = |range| SnapshotEntry {
+            range,
+            resolver: None,
+        };
+        let snapshot = ProcessSnapshot::new(
+            Pid::Slf,
+            vec![
+                entry(0x1000..0x2000),
+                entry(0x2000..0x2800),
+                entry(0x4000..0x5000),
+            ],
+        );
+
+        assert!(snapshot.find_entry(0x0).is_none());
+        assert!(snapshot.find_entry(0xfff).is_none());
+        assert_eq!(snapshot.find_entry(0x1000).unwrap().range.start, 0x1000);
+        assert_eq!(snapshot.find_entry(0x1fff).unwrap().range.start, 0x1000);
+        assert_eq!(snapshot.find_entry(0x2000).unwrap().range.start, 0x2000);
+        assert!(snapshot.find_entry(0x2800).is_none());
+        assert!(snapshot.find_entry(0x3000).is_none());
+        assert_eq!(snapshot.find_entry(0x4abc).unwrap().range.start, 0x4000);
+        assert!(snapshot.find_entry(0x5000).is_none());
+        assert!(snapshot.find_entry(usize::MAX).is_none());
+    }
+}
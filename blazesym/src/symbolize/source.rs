@@ -55,6 +55,19 @@ pub struct Kernel {
     /// kernel image of the running kernel in `"/boot/"` or
     /// `"/usr/lib/debug/boot/"`.
     pub kernel_image: Option<PathBuf>,
+    /// The path of a directory containing kernel modules.
+    ///
+    /// Addresses inside of loaded kernel modules are symbolized using
+    /// the module's `.ko` file, which is looked up via the directory's
+    /// `modules.dep` file or, lacking that, searched for recursively in
+    /// this directory. gzip (`.ko.gz`) and zstd (`.ko.zst`) compressed
+    /// modules are supported if the `zlib` and `zstd` features,
+    /// respectively, are enabled; xz (`.ko.xz`) compressed ones are
+    /// not. A `None` value will use the modules directory of the
+    /// running kernel, `"/lib/modules/<release>/"`, unless
+    /// [`Kernel::kallsyms`] is set, in which case addresses inside of
+    /// kernel modules are symbolized based on kallsyms alone.
+    pub modules_dir: Option<PathBuf>,
     /// The struct is non-exhaustive and open to extension.
     #[doc(hidden)]
     pub(crate) _non_exhaustive: (),
@@ -125,10 +138,78 @@ impl From<Gsym> for Source {
 }
 
 
+/// An APK file (or, more generally, a zip archive) containing uncompressed ELF
+/// files.
+///
+/// The corresponding addresses supplied to [`Symbolizer::symbolize`] are
+/// expected to be file offsets relative to the start of the APK. Each offset
+/// is attributed to the archive entry containing it and symbolized using
+/// that ELF file.
+#[derive(Clone, Debug)]
+pub struct Apk {
+    /// The path to the APK file.
+    pub path: PathBuf,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl Apk {
+    /// Create a new [`Apk`] object, referencing the provided path.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl From<Apk> for Source {
+    fn from(apk: Apk) -> Self {
+        Source::Apk(apk)
+    }
+}
+
+
+/// A perf map or jitdump file, as emitted by JIT compilers.
+///
+/// The file format is detected automatically. Addresses are expected
+/// to be absolute addresses as valid within the process whose JIT-ed
+/// code the file describes. Note that symbolization of addresses in
+/// anonymous executable mappings via [`Process`] consults the process'
+/// perf map file at `/tmp/perf-<pid>.map` automatically, with `/tmp`
+/// being that of the process' mount namespace and `<pid>` its ID in
+/// its own PID namespace.
+#[derive(Clone, Debug)]
+pub struct PerfMap {
+    /// The path to the perf map or jitdump file.
+    pub path: PathBuf,
+    /// The struct is non-exhaustive and open to extension.
+    #[doc(hidden)]
+    pub(crate) _non_exhaustive: (),
+}
+
+impl PerfMap {
+    /// Create a new [`PerfMap`] object, referencing the provided path.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            _non_exhaustive: (),
+        }
+    }
+}
+
+impl From<PerfMap> for Source {
+    fn from(perf_map: PerfMap) -> Self {
+        Source::PerfMap(perf_map)
+    }
+}
+
+
 /// The description of a source of symbols and debug information.
 ///
 /// The source of symbols and debug information can be an ELF file, kernel
-/// image, or process.
+/// image, process, gsym file, APK, or perf map.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
 pub enum Source {
@@ -140,4 +221,8 @@ pub enum Source {
     Process(Process),
     /// A gsym file.
     Gsym(Gsym),
+    /// An APK file.
+    Apk(Apk),
+    /// A perf map or jitdump file.
+    PerfMap(PerfMap),
 }
@@ -1,33 +1,180 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
 use std::fmt::Debug;
+use std::fs::File;
+use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Result;
+use std::ops::Range;
+use std::os::unix::io::AsRawFd as _;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use crate::bpf::parse_prog_tag;
+use crate::bpf::BpfProgCache;
+use crate::demangle::demangle;
+use crate::elf::ElfBackend;
 use crate::elf::ElfCache;
 use crate::elf::ElfResolver;
+use crate::elf::DEFAULT_DEBUG_DIRS;
 use crate::gsym::GsymResolver;
+use crate::kernel::decompress_module;
+use crate::kernel::is_compressed_module;
 use crate::kernel::KernelResolver;
+use crate::kernel::LoadedModulesCache;
+use crate::kernel::ModuleFilesCache;
+use crate::kernel::PROC_MODULES;
 use crate::ksym::KSymCache;
 use crate::ksym::KALLSYMS;
 use crate::log;
 use crate::maps;
+use crate::maps::EntryPath;
 use crate::maps::PathMapsEntry;
+use crate::mmap::Mmap;
 use crate::normalize;
 use crate::normalize::normalize_elf_addr;
+use crate::normalize::normalize_elf_offset_with_parser;
 use crate::normalize::normalize_sorted_user_addrs_with_entries;
+use crate::normalize::normalize_vdso_addr;
+use crate::normalize::read_elf_build_id;
+use crate::normalize::NormalizedUserAddrs;
+use crate::normalize::UserAddrMeta;
+use crate::perf_map::perf_map_path;
+use crate::perf_map::PerfMapResolver;
 use crate::util;
+use crate::util::fstat;
 use crate::util::uname_release;
+use crate::vdso::vdso_parser;
+use crate::vdso::VDSO_MAPS_COMPONENT;
+use crate::zip::Archive;
 use crate::Addr;
 use crate::Pid;
 use crate::SymResolver;
 
+use super::locate::Locator;
+use super::snapshot::ProcessSnapshot;
+use super::snapshot::SnapshotEntry;
+use super::snapshot::SnapshotResolver;
+use super::source::Apk;
 use super::source::Elf;
 use super::source::Gsym;
 use super::source::Kernel;
+use super::source::PerfMap;
 use super::source::Process;
 use super::source::Source;
 
 
+/// Check whether the file at `path` is an APK, judging by its extension.
+fn is_apk(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("apk"))
+}
+
+/// Load the perf map of the process `pid`, if it has one.
+fn load_perf_map(pid: Pid) -> Option<PerfMapResolver> {
+    let path = perf_map_path(pid);
+    PerfMapResolver::open(&path)
+        .map_err(|err| log::debug!("failed to load perf map {}: {err}", path.display()))
+        .ok()
+}
+
+/// Load the vDSO of the process `pid`, which is mapped at `range`.
+fn load_vdso(pid: Pid, range: &Range<Addr>) -> Option<ElfResolver> {
+    let parser = vdso_parser(pid, range)
+        .map_err(|err| log::warn!("failed to read vDSO of process {pid}: {err}"))
+        .ok()?;
+    let backend = ElfBackend::Elf(Arc::new(parser));
+    ElfResolver::with_backend(Path::new(VDSO_MAPS_COMPONENT), backend).ok()
+}
+
+
+/// An entry of an [`ApkFile`].
+#[derive(Debug)]
+struct ApkEntry {
+    /// The path to the file inside the archive.
+    path: PathBuf,
+    /// The entry's compression method. 0 means data is uncompressed.
+    compression: u16,
+    /// The range of the entry's data, relative to the start of the
+    /// archive.
+    data: Range<usize>,
+}
+
+
+/// An APK opened for symbolization.
+///
+/// The archive's entries are read once, on open, and kept sorted by
+/// their data offset, so that the entry containing a file offset can be
+/// found quickly.
+#[derive(Debug)]
+struct ApkFile {
+    /// The path to the APK.
+    path: PathBuf,
+    /// Status information about the APK file, used for validating
+    /// cached ELF data of its entries.
+    stat: libc::stat,
+    /// The memory mapped archive.
+    archive: Archive,
+    /// The archive's entries, sorted by data offset.
+    entries: Vec<ApkEntry>,
+}
+
+impl ApkFile {
+    /// Open the APK at `path` and read its entry table.
+    fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let stat = fstat(file.as_raw_fd())?;
+        let archive = Archive::with_mmap(Mmap::map(&file)?)?;
+        let mut entries = archive
+            .entries()
+            .map(|result| {
+                result.map(|entry| ApkEntry {
+                    path: entry.path.to_path_buf(),
+                    compression: entry.compression,
+                    data: entry.data_offset..entry.data_offset + entry.data.len(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let () = entries.sort_by_key(|entry| entry.data.start);
+
+        let slf = Self {
+            path: path.to_path_buf(),
+            stat,
+            archive,
+            entries,
+        };
+        Ok(slf)
+    }
+
+    /// Find the entry whose data contains the given file offset.
+    fn find_entry(&self, file_off: u64) -> Option<&ApkEntry> {
+        let file_off = usize::try_from(file_off).ok()?;
+        let idx = self
+            .entries
+            .partition_point(|entry| entry.data.start <= file_off);
+        let entry = self.entries.get(idx.checked_sub(1)?)?;
+        entry.data.contains(&file_off).then_some(entry)
+    }
+}
+
+
+/// A function that got inlined at the address being symbolized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlinedFn {
+    /// The name of the inlined function.
+    pub name: String,
+    /// The source path of the call site, i.e., the location the function
+    /// got inlined at.
+    pub call_path: PathBuf,
+    /// The line number of the call site.
+    pub call_line: usize,
+    /// The column number of the call site.
+    pub call_column: usize,
+}
+
+
 /// The result of symbolization by Symbolizer.
 ///
 /// [`Symbolizer::symbolize()`] returns a list of lists of
@@ -39,7 +186,13 @@ use super::source::Source;
 #[derive(Clone, Debug)]
 pub struct SymbolizedResult {
     /// The symbol name that an address may belong to.
+    ///
+    /// If demangling is enabled, this is the demangled name of the
+    /// symbol (if it was mangled to begin with).
     pub symbol: String,
+    /// The symbol name as it is found in the symbol source, i.e.,
+    /// before any demangling.
+    pub raw_symbol: String,
     /// The address where the symbol is located within the process.
     ///
     /// The address is in the target process, not the offset from the
@@ -54,12 +207,23 @@ pub struct SymbolizedResult {
     /// (function).
     pub line: usize,
     pub column: usize,
+    /// The name of the kernel module the symbol belongs to, if any.
+    pub module: Option<String>,
+    /// The functions inlined at the address.
+    ///
+    /// The list is ordered from the function inlined directly into
+    /// `symbol` to the innermost one. The call site of each function
+    /// is located in its predecessor (or in `symbol`, for the first
+    /// one), while `path`, `line`, and `column` refer to the innermost
+    /// function.
+    pub inlined: Vec<InlinedFn>,
 }
 
 
 /// A builder for configurable construction of [`Symbolizer`] objects.
 ///
-/// By default all features are enabled.
+/// By default all features but the reporting of inlined functions are
+/// enabled.
 #[derive(Clone, Debug)]
 pub struct Builder {
     /// Whether to enable usage of debug symbols.
@@ -69,6 +233,14 @@ pub struct Builder {
     /// This setting implies usage of debug symbols and forces the corresponding
     /// flag to `true`.
     src_location: bool,
+    /// Whether to report functions inlined at symbolized addresses.
+    ///
+    /// This setting requires usage of debug symbols.
+    inlined_fns: bool,
+    /// Whether to demangle symbol names.
+    demangle: bool,
+    /// The directories to search for separate debug files.
+    debug_dirs: Vec<PathBuf>,
 }
 
 impl Builder {
@@ -86,19 +258,68 @@ impl Builder {
         self
     }
 
+    /// Enable/disable reporting of inlined functions.
+    ///
+    /// Inlined functions are only reported if debug symbols are used.
+    /// Reporting is disabled by default, because it requires parsing
+    /// of all of `.debug_info` for any symbolized DWARF-backed binary.
+    pub fn enable_inlined_fns(mut self, enable: bool) -> Builder {
+        self.inlined_fns = enable;
+        self
+    }
+
+    /// Enable/disable demangling of symbol names.
+    ///
+    /// Rust and C++ symbol names are supported. Demangling requires the
+    /// `demangle` feature to be enabled and is a no-op otherwise.
+    pub fn enable_demangling(mut self, enable: bool) -> Builder {
+        self.demangle = enable;
+        self
+    }
+
+    /// Set the directories to search for separate debug files.
+    ///
+    /// Debug information of ELF files not containing any is looked up
+    /// in the `.build-id` sub-directories of these directories, based
+    /// on the file's build ID, as well as based on its
+    /// `.gnu_debuglink` section. By default, `/usr/lib/debug` is
+    /// searched.
+    pub fn set_debug_dirs<D, P>(mut self, debug_dirs: D) -> Builder
+    where
+        D: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.debug_dirs = debug_dirs
+            .into_iter()
+            .map(|dir| dir.as_ref().to_path_buf())
+            .collect();
+        self
+    }
+
     /// Create the [`Symbolizer`] object.
     pub fn build(self) -> Symbolizer {
         let Builder {
             debug_syms,
             src_location,
+            inlined_fns,
+            demangle,
+            debug_dirs,
         } = self;
         let ksym_cache = KSymCache::new();
-        let elf_cache = ElfCache::new(src_location, debug_syms);
+        let elf_cache = ElfCache::new(src_location, debug_syms, debug_dirs);
+        let loaded_modules = LoadedModulesCache::new();
+        let module_files = ModuleFilesCache::new();
+        let bpf_progs = BpfProgCache::new();
 
         Symbolizer {
             ksym_cache,
             elf_cache,
+            loaded_modules,
+            module_files,
+            bpf_progs,
             src_location,
+            inlined_fns,
+            demangle,
         }
     }
 }
@@ -108,17 +329,33 @@ impl Default for Builder {
         Self {
             src_location: true,
             debug_syms: true,
+            inlined_fns: false,
+            demangle: true,
+            debug_dirs: DEFAULT_DEBUG_DIRS.iter().map(PathBuf::from).collect(),
         }
     }
 }
 
 
 /// Symbolizer provides an interface to symbolize addresses.
+///
+/// A `Symbolizer` is `Send` and `Sync`. All internal caches are
+/// synchronized, so that a single instance can be shared between
+/// threads (e.g., by means of an [`Arc`][std::sync::Arc]) and used
+/// to symbolize addresses concurrently.
 #[derive(Debug)]
 pub struct Symbolizer {
     ksym_cache: KSymCache,
     elf_cache: ElfCache,
+    /// The kernel modules loaded into the running kernel.
+    loaded_modules: LoadedModulesCache,
+    /// The kernel module files available in module directories.
+    module_files: ModuleFilesCache,
+    /// Information about JIT-ed BPF programs of the running kernel.
+    bpf_progs: BpfProgCache,
     src_location: bool,
+    inlined_fns: bool,
+    demangle: bool,
 }
 
 impl Symbolizer {
@@ -133,6 +370,15 @@ impl Symbolizer {
         Builder::default()
     }
 
+    /// Demangle a symbol name, if demangling is enabled.
+    fn maybe_demangle(&self, name: &str) -> String {
+        if self.demangle {
+            demangle(name).unwrap_or_else(|| name.to_string())
+        } else {
+            name.to_string()
+        }
+    }
+
     /// Symbolize an address using the provided [`SymResolver`].
     fn symbolize_with_resolver(
         &self,
@@ -145,14 +391,26 @@ impl Symbolizer {
         } else {
             None
         };
+        let inlined = if self.inlined_fns {
+            let mut inlined = resolver.find_inlined_fns(addr);
+            let () = inlined
+                .iter_mut()
+                .for_each(|inlined| inlined.name = self.maybe_demangle(&inlined.name));
+            inlined
+        } else {
+            Vec::new()
+        };
         if res_syms.is_empty() {
             if let Some(linfo) = linfo {
                 vec![SymbolizedResult {
                     symbol: "".to_string(),
+                    raw_symbol: "".to_string(),
                     addr: 0,
                     path: linfo.path,
                     line: linfo.line,
                     column: linfo.column,
+                    module: None,
+                    inlined,
                 }]
             } else {
                 vec![]
@@ -163,20 +421,26 @@ impl Symbolizer {
                 if let Some(ref linfo) = linfo {
                     let (sym, start) = sym;
                     results.push(SymbolizedResult {
-                        symbol: String::from(sym),
+                        symbol: self.maybe_demangle(sym),
+                        raw_symbol: String::from(sym),
                         addr: start,
                         path: linfo.path.clone(),
                         line: linfo.line,
                         column: linfo.column,
+                        module: None,
+                        inlined: inlined.clone(),
                     });
                 } else {
                     let (sym, start) = sym;
                     results.push(SymbolizedResult {
-                        symbol: String::from(sym),
+                        symbol: self.maybe_demangle(sym),
+                        raw_symbol: String::from(sym),
                         addr: start,
                         path: PathBuf::new(),
                         line: 0,
                         column: 0,
+                        module: None,
+                        inlined: inlined.clone(),
                     });
                 }
             }
@@ -196,41 +460,205 @@ impl Symbolizer {
             .collect()
     }
 
-    fn resolve_addr_in_binary(&self, addr: Addr, path: &Path) -> Result<Vec<SymbolizedResult>> {
-        let backend = self.elf_cache.find(path)?;
-        let resolver = ElfResolver::with_backend(path, backend)?;
+    /// Symbolize an address in the binary backing a memory mapping.
+    fn resolve_addr_in_binary(
+        &self,
+        addr: Addr,
+        path: &EntryPath,
+    ) -> Result<Vec<SymbolizedResult>> {
+        let backend = self
+            .elf_cache
+            .find_mapped(&path.maps_file, &path.symbolic_path)?;
+        let resolver = ElfResolver::with_backend(&path.maps_file, backend)?;
         let symbols = self.symbolize_with_resolver(addr, &resolver);
         Ok(symbols)
     }
 
+    /// Create an [`ElfResolver`] for the ELF file containing the given file
+    /// offset inside the provided APK.
+    ///
+    /// On success, the resolver is returned alongside the offset of the ELF
+    /// file inside the APK.
+    fn apk_elf_resolver(&self, file_off: u64, apk: &ApkFile) -> Result<Option<(ElfResolver, u64)>> {
+        let entry = if let Some(entry) = apk.find_entry(file_off) {
+            entry
+        } else {
+            log::warn!(
+                "no entry in APK {} contains file offset 0x{file_off:x}",
+                apk.path.display()
+            );
+            return Ok(None)
+        };
+
+        if entry.compression != 0 {
+            log::warn!(
+                "APK entry {} in {} is compressed; unable to symbolize",
+                entry.path.display(),
+                apk.path.display()
+            );
+            return Ok(None)
+        }
+
+        // We use `<apk>!/<entry>` as the name of an embedded ELF file, which
+        // is the same convention as used by Android itself.
+        let mut elf_path = apk.path.as_os_str().to_os_string();
+        let () = elf_path.push("!/");
+        let () = elf_path.push(&entry.path);
+        let elf_path = PathBuf::from(elf_path);
+
+        // SANITY: The entry's data is guaranteed to be part of the archive's
+        //         memory mapping.
+        let mmap = apk.archive.mmap().constrain(entry.data.clone()).unwrap();
+        let backend = self.elf_cache.find_apk_entry(&apk.stat, &elf_path, mmap)?;
+        let resolver = ElfResolver::with_backend(&elf_path, backend)?;
+        Ok(Some((resolver, entry.data.start as u64)))
+    }
+
+    /// Symbolize the given file offset inside the provided APK.
+    ///
+    /// The offset is attributed to the archive entry containing it and then
+    /// symbolized as part of that (uncompressed) ELF file.
+    fn resolve_offset_in_apk(&self, file_off: u64, apk: &ApkFile) -> Result<Vec<SymbolizedResult>> {
+        let (resolver, data_offset) = if let Some(result) = self.apk_elf_resolver(file_off, apk)? {
+            result
+        } else {
+            return Ok(Vec::new())
+        };
+
+        let elf_off = file_off - data_offset;
+        let addr = normalize_elf_offset_with_parser(elf_off, resolver.get_parser())?;
+        let symbols = if let Some(addr) = addr {
+            self.symbolize_with_resolver(addr, &resolver)
+        } else {
+            log::warn!(
+                "failed to find ELF segment in {} that contains file offset 0x{elf_off:x}",
+                resolver.get_obj_file_name().display()
+            );
+            Vec::new()
+        };
+        Ok(symbols)
+    }
+
+    /// Symbolize a list of file offsets inside the APK at `path`.
+    fn symbolize_apk_offsets(
+        &self,
+        offsets: &[Addr],
+        path: &Path,
+    ) -> Result<Vec<Vec<SymbolizedResult>>> {
+        let apk = ApkFile::open(path)?;
+        offsets
+            .iter()
+            .map(|offset| self.resolve_offset_in_apk(*offset as u64, &apk))
+            .collect()
+    }
+
+    /// Symbolize an address residing in the vDSO mapped at `range`, with
+    /// `resolver` representing the vDSO's ELF image.
+    fn symbolize_vdso_addr(
+        &self,
+        addr: Addr,
+        range: &Range<Addr>,
+        resolver: &ElfResolver,
+    ) -> Result<Vec<SymbolizedResult>> {
+        let norm_addr = normalize_vdso_addr(addr, range, resolver.get_parser())?;
+        Ok(self.symbolize_with_resolver(norm_addr, resolver))
+    }
+
     /// Symbolize the given list of user space addresses in the provided
     /// process.
     fn symbolize_user_addrs(&self, addrs: &[Addr], pid: Pid) -> Result<Vec<Vec<SymbolizedResult>>> {
         struct SymbolizeHandler<'sym> {
             /// The "outer" `Symbolizer` instance.
             symbolizer: &'sym Symbolizer,
+            /// The process the addresses belong to.
+            pid: Pid,
+            /// The address ranges of anonymous executable mappings.
+            anon_exec: Vec<Range<Addr>>,
+            /// The process' perf map resolver, loaded on first use.
+            perf_map: Option<Option<PerfMapResolver>>,
+            /// The resolver for the process' vDSO, loaded on first use.
+            vdso: Option<Option<ElfResolver>>,
+            /// APKs opened so far, keyed by their path.
+            apks: HashMap<PathBuf, ApkFile>,
             /// Symbols representing the symbolized addresses.
             all_symbols: Vec<Vec<SymbolizedResult>>,
         }
 
+        impl SymbolizeHandler<'_> {
+            /// Symbolize an address in JIT-ed code using the process'
+            /// perf map, if one is available.
+            fn symbolize_jit_addr(&mut self, addr: Addr) -> Vec<SymbolizedResult> {
+                let pid = self.pid;
+                let resolver = self.perf_map.get_or_insert_with(|| load_perf_map(pid));
+
+                if let Some(resolver) = resolver {
+                    self.symbolizer.symbolize_with_resolver(addr, resolver)
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+
         impl normalize::Handler for SymbolizeHandler<'_> {
-            fn handle_unknown_addr(&mut self, _addr: Addr) -> Result<()> {
-                let () = self.all_symbols.push(Vec::new());
+            fn handle_unknown_addr(&mut self, addr: Addr) -> Result<()> {
+                let symbols = if self.anon_exec.iter().any(|range| range.contains(&addr)) {
+                    self.symbolize_jit_addr(addr)
+                } else {
+                    Vec::new()
+                };
+                let () = self.all_symbols.push(symbols);
                 Ok(())
             }
 
             fn handle_entry_addr(&mut self, addr: Addr, entry: &PathMapsEntry) -> Result<()> {
                 let path = &entry.path.maps_file;
-                let norm_addr = normalize_elf_addr(addr, entry)?;
-                let symbols = self.symbolizer.resolve_addr_in_binary(norm_addr, path)?;
+                let symbols = if maps::is_memfd(&entry.path.symbolic_path) {
+                    self.symbolize_jit_addr(addr)
+                } else if is_apk(&entry.path.symbolic_path) {
+                    let file_off = addr as u64 - entry.range.start as u64 + entry.offset;
+                    let apk = match self.apks.entry(path.clone()) {
+                        hash_map::Entry::Occupied(occupied) => occupied.into_mut(),
+                        hash_map::Entry::Vacant(vacant) => vacant.insert(ApkFile::open(path)?),
+                    };
+                    self.symbolizer.resolve_offset_in_apk(file_off, apk)?
+                } else {
+                    let norm_addr = normalize_elf_addr(addr, entry)?;
+                    self.symbolizer
+                        .resolve_addr_in_binary(norm_addr, &entry.path)?
+                };
+                let () = self.all_symbols.push(symbols);
+                Ok(())
+            }
+
+            fn handle_vdso_addr(&mut self, addr: Addr, range: &Range<Addr>) -> Result<()> {
+                let pid = self.pid;
+                let resolver = self.vdso.get_or_insert_with(|| load_vdso(pid, range));
+
+                let symbols = if let Some(resolver) = resolver {
+                    self.symbolizer.symbolize_vdso_addr(addr, range, resolver)?
+                } else {
+                    Vec::new()
+                };
                 let () = self.all_symbols.push(symbols);
                 Ok(())
             }
         }
 
-        let entries = maps::parse(pid)?;
+        let entries = maps::parse(pid)?.collect::<Result<Vec<_>>>()?;
+        let anon_exec = entries
+            .iter()
+            .filter(|entry| maps::is_anon_exec(entry))
+            .map(|entry| entry.range.clone())
+            .collect();
+        let entries = entries.into_iter().map(Ok);
+
         let handler = SymbolizeHandler {
             symbolizer: self,
+            pid,
+            anon_exec,
+            perf_map: None,
+            vdso: None,
+            apks: HashMap::new(),
             all_symbols: Vec::with_capacity(addrs.len()),
         };
 
@@ -242,6 +670,180 @@ impl Symbolizer {
         Ok(handler.all_symbols)
     }
 
+    /// Create a [`SnapshotResolver`] for the given file backed proc maps
+    /// entry.
+    ///
+    /// `resolvers` is used for sharing resolvers between entries backed by
+    /// the same file, `apks` for sharing opened APKs.
+    fn snapshot_elf_resolver(
+        &self,
+        entry: PathMapsEntry,
+        resolvers: &mut HashMap<PathBuf, Arc<ElfResolver>>,
+        apks: &mut HashMap<PathBuf, ApkFile>,
+    ) -> Result<Option<SnapshotResolver>> {
+        let PathMapsEntry {
+            range: _,
+            _mode,
+            offset,
+            path,
+        } = entry;
+
+        let (key, offset) = if is_apk(&path.symbolic_path) {
+            let apk = match apks.entry(path.maps_file.clone()) {
+                hash_map::Entry::Occupied(occupied) => occupied.into_mut(),
+                hash_map::Entry::Vacant(vacant) => vacant.insert(ApkFile::open(&path.maps_file)?),
+            };
+            let (resolver, data_offset) =
+                if let Some(result) = self.apk_elf_resolver(offset, apk)? {
+                    result
+                } else {
+                    return Ok(None)
+                };
+            let key = resolver.get_obj_file_name().to_path_buf();
+            let _resolver = resolvers
+                .entry(key.clone())
+                .or_insert_with(|| Arc::new(resolver));
+            (key, offset - data_offset)
+        } else {
+            if !resolvers.contains_key(&path.symbolic_path) {
+                let backend = self
+                    .elf_cache
+                    .find_mapped(&path.maps_file, &path.symbolic_path)?;
+                let resolver = ElfResolver::with_backend(&path.maps_file, backend)?;
+                let _resolver = resolvers.insert(path.symbolic_path.clone(), Arc::new(resolver));
+            }
+            (path.symbolic_path, offset)
+        };
+
+        let resolver = SnapshotResolver::Elf {
+            offset,
+            // SANITY: We made sure to insert the resolver above.
+            resolver: Arc::clone(resolvers.get(&key).unwrap()),
+        };
+        Ok(Some(resolver))
+    }
+
+    /// Take a snapshot of the executable memory mappings of the process
+    /// identified by `pid`, for later use with
+    /// [`Symbolizer::symbolize_snapshot`].
+    ///
+    /// All ELF files backing executable mappings are loaded eagerly, as
+    /// are the process' vDSO and its perf map, if any. Files that fail
+    /// to load are logged and addresses in them will not be symbolized.
+    pub fn snapshot_process(&self, pid: Pid) -> Result<ProcessSnapshot> {
+        let mut resolvers = HashMap::new();
+        let mut apks = HashMap::new();
+        let mut perf_map = None;
+        let mut perf_map_resolver = || {
+            perf_map
+                .get_or_insert_with(|| load_perf_map(pid).map(Arc::new))
+                .clone()
+                .map(SnapshotResolver::PerfMap)
+        };
+        let mut entries = Vec::new();
+
+        for result in maps::parse(pid)? {
+            let entry = result?;
+            let range = entry.range.clone();
+            let resolver = if maps::is_vdso(&entry) {
+                load_vdso(pid, &range).map(|resolver| SnapshotResolver::Vdso(Arc::new(resolver)))
+            } else if maps::is_anon_exec(&entry) {
+                perf_map_resolver()
+            } else if let Some(entry) = maps::filter_map_relevant(entry) {
+                let symbolic_path = entry.path.symbolic_path.clone();
+                self.snapshot_elf_resolver(entry, &mut resolvers, &mut apks)
+                    .unwrap_or_else(|err| {
+                        log::warn!(
+                            "failed to load {} for snapshot of process {pid}: {err}; ignoring...",
+                            symbolic_path.display()
+                        );
+                        None
+                    })
+            } else {
+                continue
+            };
+
+            let () = entries.push(SnapshotEntry { range, resolver });
+        }
+
+        Ok(ProcessSnapshot::new(pid, entries))
+    }
+
+    /// Refresh `snapshot` by taking it anew, so that it reflects the
+    /// current memory mappings of the process.
+    ///
+    /// A snapshot is never updated implicitly. Use this method whenever
+    /// it may have become stale, e.g., because the process loaded
+    /// additional shared objects or once it exceeds a certain
+    /// [`age`][ProcessSnapshot::age]. If taking the new snapshot fails,
+    /// `snapshot` is left unchanged.
+    pub fn refresh_snapshot(&self, snapshot: &mut ProcessSnapshot) -> Result<()> {
+        *snapshot = self.snapshot_process(snapshot.pid())?;
+        Ok(())
+    }
+
+    /// Symbolize an address in a snapshotted mapping backed by the ELF
+    /// file represented by `resolver`, with the mapping starting at file
+    /// offset `offset`.
+    fn symbolize_snapshot_elf_addr(
+        &self,
+        addr: Addr,
+        entry: &SnapshotEntry,
+        offset: u64,
+        resolver: &ElfResolver,
+    ) -> Result<Vec<SymbolizedResult>> {
+        let file_off = (addr - entry.range.start) as u64 + offset;
+        let norm_addr = normalize_elf_offset_with_parser(file_off, resolver.get_parser())?;
+        let symbols = if let Some(norm_addr) = norm_addr {
+            self.symbolize_with_resolver(norm_addr, resolver)
+        } else {
+            log::warn!(
+                "failed to find ELF segment in {} that contains file offset 0x{file_off:x}",
+                resolver.get_obj_file_name().display()
+            );
+            Vec::new()
+        };
+        Ok(symbols)
+    }
+
+    /// Symbolize a list of addresses using a previously taken
+    /// [`ProcessSnapshot`].
+    ///
+    /// The addresses are expected to be absolute addresses as valid within
+    /// the snapshotted process. In contrast to symbolization via
+    /// [`Source::Process`], the process' memory mappings are not parsed
+    /// again and the process is not required to still be alive.
+    pub fn symbolize_snapshot(
+        &self,
+        snapshot: &ProcessSnapshot,
+        addrs: &[Addr],
+    ) -> Result<Vec<Vec<SymbolizedResult>>> {
+        addrs
+            .iter()
+            .map(|addr| {
+                let entry = if let Some(entry) = snapshot.find_entry(*addr) {
+                    entry
+                } else {
+                    return Ok(Vec::new())
+                };
+
+                let symbols = match &entry.resolver {
+                    Some(SnapshotResolver::Elf { offset, resolver }) => {
+                        self.symbolize_snapshot_elf_addr(*addr, entry, *offset, resolver)?
+                    }
+                    Some(SnapshotResolver::Vdso(resolver)) => {
+                        self.symbolize_vdso_addr(*addr, &entry.range, resolver)?
+                    }
+                    Some(SnapshotResolver::PerfMap(resolver)) => {
+                        self.symbolize_with_resolver(*addr, resolver.as_ref())
+                    }
+                    None => Vec::new(),
+                };
+                Ok(symbols)
+            })
+            .collect()
+    }
+
     fn symbolize_kernel_addrs(
         &self,
         addrs: &[Addr],
@@ -250,6 +852,7 @@ impl Symbolizer {
         let Kernel {
             kallsyms,
             kernel_image,
+            modules_dir,
             _non_exhaustive: (),
         } = src;
 
@@ -310,11 +913,254 @@ impl Symbolizer {
             }
         };
 
-        let resolver = KernelResolver::new(ksym_resolver, elf_resolver)?;
-        let symbols = self.symbolize_addrs(addrs, &resolver);
+        // Only the running kernel's modules can be looked up in
+        // /proc/modules.
+        let modules = if kallsyms.is_none() {
+            self.loaded_modules
+                .get_modules(Path::new(PROC_MODULES))
+                .unwrap_or_else(|err| {
+                    log::warn!(
+                        "failed to read kernel modules from {PROC_MODULES}: {err}; ignoring..."
+                    );
+                    Arc::new(Vec::new())
+                })
+        } else {
+            Arc::new(Vec::new())
+        };
+
+        let module_names = addrs
+            .iter()
+            .filter_map(|addr| {
+                let ksym = ksym_resolver
+                    .as_ref()
+                    .and_then(|resolver| resolver.find_addresses_ksym(*addr).next());
+                if let Some(module) = ksym.and_then(|ksym| ksym.module.as_ref()) {
+                    Some(module.clone())
+                } else {
+                    modules
+                        .iter()
+                        .find(|module| module.range.contains(addr))
+                        .map(|module| module.name.clone())
+                }
+            })
+            .collect::<HashSet<_>>();
+
+        let modules_dir = if module_names.is_empty() {
+            None
+        } else if let Some(dir) = modules_dir {
+            Some(dir.clone())
+        } else if kallsyms.is_none() {
+            let release = uname_release()?.to_str().unwrap().to_string();
+            Some(Path::new("/lib/modules").join(release))
+        } else {
+            // The modules directory of the running kernel is of no use
+            // for a kallsyms copy, which may stem from a different
+            // kernel.
+            None
+        };
+
+        let module_resolvers = if let Some(modules_dir) = modules_dir {
+            let module_files = self.module_files.get_files(&modules_dir);
+            module_names
+                .into_iter()
+                .filter_map(|name| {
+                    let path = module_files.get(&name)?;
+                    let backend = if is_compressed_module(path) {
+                        self.elf_cache
+                            .find_compressed(path, |data| decompress_module(path, data))
+                    } else {
+                        self.elf_cache.find(path)
+                    };
+                    let result =
+                        backend.and_then(|backend| ElfResolver::with_backend(path, backend));
+                    match result {
+                        Ok(resolver) => Some((name, resolver)),
+                        Err(err) => {
+                            log::warn!(
+                                "failed to load kernel module {}: {err}; ignoring...",
+                                path.display()
+                            );
+                            None
+                        }
+                    }
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        // Only the running kernel's BPF programs can be queried. Loading
+        // their information is comparatively expensive and requires
+        // privileges, so only do it for the programs that addresses
+        // actually belong to.
+        let bpf_addrs = if kallsyms.is_none() {
+            ksym_resolver
+                .as_ref()
+                .map(|resolver| {
+                    addrs
+                        .iter()
+                        .filter_map(|addr| {
+                            let ksym = resolver.find_addresses_ksym(*addr).next()?;
+                            let tag = parse_prog_tag(&ksym.name)?;
+                            Some((tag, *addr))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let bpf_resolver = if !bpf_addrs.is_empty() {
+            match self.bpf_progs.get_resolver(&bpf_addrs) {
+                Ok(resolver) => Some(resolver),
+                Err(err) => {
+                    log::warn!("failed to load BPF program information: {err}; ignoring...");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let resolver = KernelResolver::new(
+            ksym_resolver,
+            elf_resolver,
+            modules,
+            module_resolvers,
+            bpf_resolver,
+        )?;
+        let symbols = addrs
+            .iter()
+            .map(|addr| {
+                let mut symbols = self.symbolize_with_resolver(*addr, &resolver);
+                if let Some(module) = resolver.find_module(*addr) {
+                    let () = symbols
+                        .iter_mut()
+                        .for_each(|symbol| symbol.module = Some(module.to_string()));
+                }
+                symbols
+            })
+            .collect();
         Ok(symbols)
     }
 
+    /// Find the binary with the given `path` and/or `build_id` using
+    /// the first of `locators` able to locate it, and create an
+    /// [`ElfResolver`] for it.
+    ///
+    /// If a build ID is provided, located binaries with a different one
+    /// are skipped over. Failures of individual locators are logged and
+    /// the next locator is tried.
+    fn locate_binary(
+        &self,
+        path: Option<&Path>,
+        build_id: Option<&[u8]>,
+        locators: &[&dyn Locator],
+    ) -> Option<ElfResolver> {
+        for locator in locators {
+            let local_path = match locator.locate(path, build_id) {
+                Ok(Some(local_path)) => local_path,
+                Ok(None) => continue,
+                Err(err) => {
+                    log::warn!("{locator:?} failed to locate binary: {err}; ignoring...");
+                    continue
+                }
+            };
+
+            let result = self.elf_cache.find(&local_path).and_then(|backend| {
+                let resolver = ElfResolver::with_backend(&local_path, backend)?;
+                let matches = if let Some(build_id) = build_id {
+                    read_elf_build_id(resolver.get_parser())?.as_deref() == Some(build_id)
+                } else {
+                    true
+                };
+                Ok((resolver, matches))
+            });
+
+            match result {
+                Ok((resolver, true)) => return Some(resolver),
+                Ok((_resolver, false)) => {
+                    log::warn!(
+                        "build ID of {} (located by {locator:?}) does not match; ignoring...",
+                        local_path.display()
+                    );
+                }
+                Err(err) => {
+                    log::warn!(
+                        "failed to open {} (located by {locator:?}): {err}; ignoring...",
+                        local_path.display()
+                    );
+                }
+            }
+        }
+
+        log::warn!(
+            "failed to locate binary (path: {path:?}, build ID: {:?})",
+            build_id.map(util::to_hex)
+        );
+        None
+    }
+
+    /// Symbolize normalized user space addresses.
+    ///
+    /// This function enables "remote" symbolization: addresses may be
+    /// normalized on one system, using
+    /// [`Normalizer`][crate::normalize::Normalizer], and symbolized on
+    /// another. The binaries referenced by `normalized` are mapped to
+    /// local files by means of `locators`, which are tried in order.
+    /// Binaries whose build ID is known are only used if the located
+    /// file's build ID matches.
+    ///
+    /// Results are reported in the order of `normalized.addrs`.
+    /// Addresses belonging to binaries that could not be located, as
+    /// well as unknown ones, are reported without any symbols.
+    pub fn symbolize_normalized(
+        &self,
+        normalized: &NormalizedUserAddrs,
+        locators: &[&dyn Locator],
+    ) -> Result<Vec<Vec<SymbolizedResult>>> {
+        let mut resolvers = (0..normalized.meta.len())
+            .map(|_| None)
+            .collect::<Vec<Option<Option<ElfResolver>>>>();
+
+        normalized
+            .addrs
+            .iter()
+            .map(|(addr, meta_idx)| {
+                let meta = normalized.meta.get(*meta_idx).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("normalized address 0x{addr:x} has invalid meta index {meta_idx}"),
+                    )
+                })?;
+
+                let resolver = if let Some(resolver) = &resolvers[*meta_idx] {
+                    resolver
+                } else {
+                    let resolver = match meta {
+                        UserAddrMeta::Binary(binary) => self.locate_binary(
+                            Some(&binary.path),
+                            binary.build_id.as_deref(),
+                            locators,
+                        ),
+                        UserAddrMeta::Vdso(vdso) => {
+                            self.locate_binary(None, vdso.build_id.as_deref(), locators)
+                        }
+                        UserAddrMeta::Unknown(..) => None,
+                    };
+                    resolvers[*meta_idx].insert(resolver)
+                };
+
+                let symbols = if let Some(resolver) = resolver {
+                    self.symbolize_with_resolver(*addr, resolver)
+                } else {
+                    Vec::new()
+                };
+                Ok(symbols)
+            })
+            .collect()
+    }
+
     /// Symbolize a list of addresses.
     ///
     /// Symbolize a list of addresses according to the configuration
@@ -343,6 +1189,18 @@ impl Symbolizer {
                 let symbols = self.symbolize_addrs(addrs, &resolver);
                 Ok(symbols)
             }
+            Source::Apk(Apk {
+                path,
+                _non_exhaustive: (),
+            }) => self.symbolize_apk_offsets(addrs, path),
+            Source::PerfMap(PerfMap {
+                path,
+                _non_exhaustive: (),
+            }) => {
+                let resolver = PerfMapResolver::open(path)?;
+                let symbols = self.symbolize_addrs(addrs, &resolver);
+                Ok(symbols)
+            }
         }
     }
 }
@@ -352,3 +1210,235 @@ impl Default for Symbolizer {
         Self::new()
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::copy;
+    use std::io::Read as _;
+    use std::io::Write as _;
+    use std::process::Command;
+    use std::process::Stdio;
+
+    use tempfile::tempdir;
+    use test_log::test;
+
+    use crate::elf::ElfParser;
+    use crate::inspect::FindAddrOpts;
+    use crate::inspect::SymType;
+    use crate::mmap::Mmap;
+    use crate::vdso::tests::find_vdso_clock_gettime;
+
+
+    /// Check that the `Symbolizer` type can be shared between threads.
+    #[test]
+    fn symbolizer_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        let () = assert_send_sync::<Symbolizer>();
+    }
+
+    /// Check that we can look up the entries of an APK by file offset.
+    #[test]
+    fn apk_entry_lookup() {
+        let test_zip = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test.zip");
+        let apk = ApkFile::open(&test_zip).unwrap();
+        let archive = Archive::open(&test_zip).unwrap();
+
+        let mut count = 0;
+        for entry in archive.entries() {
+            let entry = entry.unwrap();
+            if entry.data.is_empty() {
+                continue
+            }
+
+            let first = entry.data_offset as u64;
+            let last = (entry.data_offset + entry.data.len() - 1) as u64;
+            for file_off in [first, last] {
+                let found = apk.find_entry(file_off).unwrap();
+                assert_eq!(found.path, entry.path);
+            }
+            count += 1;
+        }
+        assert!(count > 0);
+
+        // The local file header of the first entry is not part of any
+        // entry's data.
+        assert!(apk.find_entry(0).is_none());
+        assert!(apk.find_entry(u64::MAX).is_none());
+    }
+
+    /// Check that we can symbolize an address residing in a shared object
+    /// stored inside an APK mapped into our process.
+    #[test]
+    fn symbolize_process_apk_addr() {
+        let test_zip = Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .join("test.zip");
+        // We rely on the `.apk` extension for detecting APKs, so copy the
+        // archive accordingly.
+        let dir = tempdir().unwrap();
+        let test_apk = dir.path().join("test.apk");
+        let _size = copy(test_zip, &test_apk).unwrap();
+
+        let mmap = Mmap::builder().exec().open(&test_apk).unwrap();
+        let archive = Archive::with_mmap(mmap.clone()).unwrap();
+        let so = archive
+            .entries()
+            .find_map(|entry| {
+                let entry = entry.unwrap();
+                (entry.path == Path::new("libtest-so.so")).then_some(entry)
+            })
+            .unwrap();
+
+        let elf_mmap = mmap
+            .constrain(so.data_offset..so.data_offset + so.data.len())
+            .unwrap();
+        let parser = ElfParser::from_mmap(elf_mmap);
+        let resolver =
+            ElfResolver::with_backend(&test_apk, ElfBackend::Elf(Arc::new(parser))).unwrap();
+        let opts = FindAddrOpts {
+            sym_type: SymType::Function,
+            ..Default::default()
+        };
+        let symbols = resolver.find_addr("the_answer", &opts).unwrap();
+        assert_eq!(symbols.len(), 1);
+        let symbol = symbols.first().unwrap();
+        let file_off = resolver.addr_file_off(symbol.addr).unwrap();
+
+        let the_answer_addr =
+            unsafe { mmap.as_ptr().add(so.data_offset + file_off as usize) } as Addr;
+
+        let src = Source::Process(Process::new(Pid::Slf));
+        let symbolizer = Symbolizer::new();
+        let results = symbolizer
+            .symbolize(&src, &[the_answer_addr])
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+
+        let result = results.first().unwrap();
+        assert_eq!(result.symbol, "the_answer");
+        assert_eq!(result.addr, symbol.addr);
+    }
+
+    /// Check that we can symbolize an address residing in the vDSO of
+    /// our process.
+    #[test]
+    fn symbolize_process_vdso_addr() {
+        let (addr, vdso_addr) = if let Some(addrs) = find_vdso_clock_gettime() {
+            addrs
+        } else {
+            return
+        };
+
+        let src = Source::Process(Process::new(Pid::Slf));
+        let symbolizer = Symbolizer::new();
+        let results = symbolizer
+            .symbolize(&src, &[addr])
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+
+        let result = results.first().unwrap();
+        assert!(
+            result.symbol.ends_with("clock_gettime"),
+            "{}",
+            result.symbol
+        );
+        assert_eq!(result.addr, vdso_addr);
+    }
+
+    /// Check that a snapshot of our process covers its vDSO.
+    #[test]
+    fn symbolize_snapshot_vdso_addr() {
+        let (addr, vdso_addr) = if let Some(addrs) = find_vdso_clock_gettime() {
+            addrs
+        } else {
+            return
+        };
+
+        let symbolizer = Symbolizer::new();
+        let snapshot = symbolizer.snapshot_process(Pid::Slf).unwrap();
+        let results = symbolizer
+            .symbolize_snapshot(&snapshot, &[addr])
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+
+        let result = results.first().unwrap();
+        assert!(
+            result.symbol.ends_with("clock_gettime"),
+            "{}",
+            result.symbol
+        );
+        assert_eq!(result.addr, vdso_addr);
+    }
+
+    /// Check that we can symbolize addresses using a snapshot of a
+    /// process that exited in the meantime.
+    #[test]
+    fn symbolize_snapshot_after_exit() {
+        // `cat` will block reading from its standard input until we close
+        // it.
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // Wait for `cat` to echo data back, at which point it is guaranteed
+        // to be fully loaded.
+        let () = child.stdin.as_mut().unwrap().write_all(b"x").unwrap();
+        let mut buf = [0; 1];
+        let () = child.stdout.as_mut().unwrap().read_exact(&mut buf).unwrap();
+        let symbolizer = Symbolizer::new();
+        let snapshot = symbolizer.snapshot_process(Pid::from(child.id())).unwrap();
+        let () = drop(child.stdin.take());
+        let _status = child.wait().unwrap();
+
+        // Find the address at which `abort` was mapped in the process,
+        // using the ELF files referenced by the snapshot.
+        let opts = FindAddrOpts {
+            sym_type: SymType::Function,
+            ..Default::default()
+        };
+        let (addr, abort) = snapshot
+            .entries()
+            .iter()
+            .find_map(|entry| {
+                let (offset, resolver) = match entry.resolver.as_ref()? {
+                    SnapshotResolver::Elf { offset, resolver } => (*offset, resolver),
+                    _ => return None,
+                };
+                let abort = resolver.find_addr("abort", &opts)?.into_iter().next()?;
+                let file_off = resolver.addr_file_off(abort.addr)?;
+                let len = (entry.range.end - entry.range.start) as u64;
+                let range = offset..offset + len;
+                range
+                    .contains(&file_off)
+                    .then(|| (entry.range.start + (file_off - offset) as Addr, abort))
+            })
+            .unwrap();
+
+        let results = symbolizer
+            .symbolize_snapshot(&snapshot, &[addr])
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+
+        let result = results.first().unwrap();
+        assert_eq!(result.addr, abort.addr);
+    }
+}
@@ -63,6 +63,41 @@ pub(crate) unsafe fn slice_from_user_array<'t, T>(items: *const T, num_items: us
     unsafe { slice::from_raw_parts(items, num_items) }
 }
 
+/// Format `data` as a lower case hexadecimal string.
+pub(crate) fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Calculate the CRC-32 checksum of `data`, using the polynomial used by
+/// zlib (and in `.gnu_debuglink` sections, for example).
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    const fn make_table() -> [u32; 256] {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    0xedb88320 ^ (crc >> 1)
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    }
+
+    const TABLE: [u32; 256] = make_table();
+
+    !data.iter().fold(!0, |crc, byte| {
+        TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
 pub(crate) fn fstat(fd: RawFd) -> Result<libc::stat> {
     let mut dst = MaybeUninit::uninit();
     let rc = unsafe { libc::fstat(fd, dst.as_mut_ptr()) };
@@ -89,6 +124,88 @@ pub(crate) fn uname_release() -> Result<CString> {
     Ok(release)
 }
 
+/// Match a character against the bracket expression (e.g., `[a-z]` or
+/// `[!0-9]`) at the start of `pattern`.
+///
+/// Returns whether the character matched along with the length of the
+/// expression, or `None` if `pattern` does not start with a complete
+/// bracket expression.
+fn glob_match_class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut idx = 1;
+    let negate = matches!(pattern.get(idx), Some('!' | '^'));
+    if negate {
+        idx += 1;
+    }
+
+    let start = idx;
+    let mut matched = false;
+    loop {
+        match *pattern.get(idx)? {
+            ']' if idx > start => break,
+            lo => {
+                if let (Some('-'), Some(&hi)) = (pattern.get(idx + 1), pattern.get(idx + 2)) {
+                    if hi != ']' {
+                        matched |= (lo..=hi).contains(&c);
+                        idx += 3;
+                        continue
+                    }
+                }
+                matched |= lo == c;
+                idx += 1;
+            }
+        }
+    }
+    Some((matched != negate, idx + 1))
+}
+
+/// Check whether `s` matches the shell style glob `pattern`.
+///
+/// Supported are `*` (matching any sequence of characters), `?`
+/// (matching any single character), and bracket expressions such as
+/// `[abc]`, `[a-z]`, or `[!abc]`.
+pub(crate) fn glob_match(pattern: &str, s: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+    let mut p_idx = 0;
+    let mut s_idx = 0;
+    // The position of the last `*` seen in the pattern along with the
+    // position in `s` that it is currently assumed to match up to.
+    let mut star = None;
+
+    while s_idx < s.len() {
+        let c = s[s_idx];
+        let advance = match pattern.get(p_idx) {
+            Some('*') => {
+                star = Some((p_idx, s_idx));
+                p_idx += 1;
+                continue
+            }
+            Some('?') => Some(1),
+            Some('[') => match glob_match_class(&pattern[p_idx..], c) {
+                Some((true, len)) => Some(len),
+                Some((false, _)) => None,
+                None => (c == '[').then_some(1),
+            },
+            Some(p) => (*p == c).then_some(1),
+            None => None,
+        };
+
+        if let Some(len) = advance {
+            p_idx += len;
+            s_idx += 1;
+        } else if let Some((star_p_idx, star_s_idx)) = star {
+            // Backtrack and let the last `*` consume one more character.
+            p_idx = star_p_idx + 1;
+            s_idx = star_s_idx + 1;
+            star = Some((star_p_idx, star_s_idx + 1));
+        } else {
+            return false
+        }
+    }
+
+    pattern[p_idx..].iter().all(|p| *p == '*')
+}
+
 /// See `find_match_or_lower_bound`, but allow the user to pass in a comparison
 /// function for increased flexibility.
 pub(crate) fn find_match_or_lower_bound_by<T, U, F>(slice: &[T], item: U, mut f: F) -> Option<usize>
@@ -470,6 +587,13 @@ mod tests {
     }
 
 
+    /// Check that we calculate CRC-32 checksums correctly.
+    #[test]
+    fn crc32_calculation() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+
     /// Make sure that we can detect sorted slices.
     #[test]
     fn sorted_check() {
@@ -707,4 +831,34 @@ mod tests {
         assert_eq!(find_match_or_lower_bound(&data, 100), Some(4));
         assert_eq!(find_match_or_lower_bound(&data, 1337), Some(4));
     }
+
+    /// Check that our glob matching works as expected.
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "vfs_read"));
+        assert!(glob_match("vfs_read", "vfs_read"));
+        assert!(!glob_match("vfs_read", "vfs_reads"));
+        assert!(glob_match("vfs_*", "vfs_read"));
+        assert!(!glob_match("vfs_*", "do_vfs_read"));
+        assert!(glob_match("*vfs_*", "do_vfs_read"));
+        assert!(glob_match("*_read*", "ksys_read_iter"));
+        assert!(glob_match("do_sys_open?", "do_sys_open2"));
+        assert!(!glob_match("do_sys_open?", "do_sys_open"));
+        assert!(glob_match("*a*b*c", "xaxxbxxxc"));
+        assert!(!glob_match("*a*b*c", "xaxxbxxxcx"));
+        assert!(glob_match("sys_[rw]*", "sys_read"));
+        assert!(glob_match("sys_[rw]*", "sys_write"));
+        assert!(!glob_match("sys_[rw]*", "sys_open"));
+        assert!(glob_match("sys_[!rw]*", "sys_open"));
+        assert!(glob_match("func[0-9]", "func7"));
+        assert!(!glob_match("func[0-9]", "funcx"));
+        assert!(glob_match("a[]]b", "a]b"));
+        assert!(glob_match("a[-x]b", "a-b"));
+        // Incomplete bracket expressions match literally.
+        assert!(glob_match("a[b", "a[b"));
+        assert!(!glob_match("a[b", "ab"));
+    }
 }
@@ -0,0 +1,144 @@
+//! Support for the virtual dynamic shared object (vDSO).
+//!
+//! The vDSO is an ELF image provided by the kernel and mapped into
+//! every process. It is not backed by a file, so we read it from
+//! memory instead.
+
+use std::fs::File;
+use std::io::Result;
+use std::ops::Range;
+use std::os::unix::fs::FileExt as _;
+use std::slice;
+
+use crate::elf::ElfParser;
+use crate::maps;
+use crate::mmap::Mmap;
+use crate::Addr;
+use crate::Pid;
+
+
+/// The name of the proc maps component representing the vDSO.
+pub(crate) const VDSO_MAPS_COMPONENT: &str = "[vdso]";
+
+
+/// Retrieve the vDSO image of the calling process.
+///
+/// The image is located via the `AT_SYSINFO_EHDR` auxiliary vector
+/// entry and its extent is determined by the corresponding proc maps
+/// entry.
+fn local_vdso() -> Result<Option<&'static [u8]>> {
+    // SAFETY: `getauxval` is always safe to call.
+    let base = unsafe { libc::getauxval(libc::AT_SYSINFO_EHDR) } as Addr;
+    if base == 0 {
+        return Ok(None)
+    }
+
+    for result in maps::parse(Pid::Slf)? {
+        let entry = result?;
+        if entry.range.start == base {
+            // SAFETY: The vDSO stays mapped for the lifetime of the
+            //         process and it is always readable.
+            let image = unsafe { slice::from_raw_parts(base as *const u8, entry.range.len()) };
+            return Ok(Some(image))
+        }
+    }
+    Ok(None)
+}
+
+/// Read the vDSO image of process `pid`, which is mapped at `range`.
+///
+/// For the calling process the image is accessed directly, for other
+/// processes it is read via `/proc/<pid>/mem`. Note that we do not fall
+/// back to the image of the calling process if the latter fails: the
+/// vDSO differs between kinds of processes (e.g., 32 bit and 64 bit
+/// ones) and we have no way of telling whether the images match.
+pub(crate) fn read_vdso(pid: Pid, range: &Range<Addr>) -> Result<Vec<u8>> {
+    if let Pid::Slf = pid {
+        if let Some(image) = local_vdso()? {
+            if image.as_ptr() as Addr == range.start && image.len() == range.len() {
+                return Ok(image.to_vec())
+            }
+        }
+    }
+
+    let file = File::open(format!("/proc/{pid}/mem"))?;
+    let mut image = vec![0; range.len()];
+    let () = file.read_exact_at(&mut image, range.start as u64)?;
+    Ok(image)
+}
+
+/// Create an [`ElfParser`] for the vDSO of process `pid`, which is
+/// mapped at `range`.
+pub(crate) fn vdso_parser(pid: Pid, range: &Range<Addr>) -> Result<ElfParser> {
+    let image = read_vdso(pid, range)?;
+    let mmap = Mmap::from_bytes(&image)?;
+    Ok(ElfParser::from_mmap(mmap))
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use test_log::test;
+
+    use crate::elf::types::PT_LOAD;
+    use crate::elf::types::STT_FUNC;
+
+
+    /// Look up the `clock_gettime` implementation in the vDSO of the
+    /// calling process.
+    ///
+    /// The vDSO exports the function under an architecture specific
+    /// name. On success, the virtual address of the function along with
+    /// its address inside the vDSO image is returned.
+    pub(crate) fn find_vdso_clock_gettime() -> Option<(Addr, Addr)> {
+        let entry = maps::parse(Pid::Slf)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .find(maps::is_vdso)?;
+        let parser = vdso_parser(Pid::Slf, &entry.range).unwrap();
+        let sym = ["__vdso_clock_gettime", "__kernel_clock_gettime"]
+            .iter()
+            .find_map(|name| {
+                parser
+                    .find_addr(name, &Default::default())
+                    .unwrap()
+                    .into_iter()
+                    .next()
+            })?;
+
+        let phdr = parser.program_headers().unwrap().iter().find(|phdr| {
+            phdr.p_type == PT_LOAD
+                && (phdr.p_vaddr..phdr.p_vaddr + phdr.p_memsz).contains(&(sym.addr as u64))
+        })?;
+        let offset = sym.addr as u64 - phdr.p_vaddr + phdr.p_offset;
+        Some((entry.range.start + offset as Addr, sym.addr))
+    }
+
+    /// Check that we can read and parse the vDSO of the calling
+    /// process, both directly and via `/proc/<pid>/mem`.
+    #[test]
+    fn vdso_reading() {
+        let image = if let Some(image) = local_vdso().unwrap() {
+            image
+        } else {
+            return
+        };
+        assert_eq!(&image[..4], b"\x7fELF");
+
+        let base = image.as_ptr() as Addr;
+        let range = base..base + image.len();
+        let pid = Pid::from(std::process::id());
+        assert_eq!(read_vdso(pid, &range).unwrap(), image);
+        assert_eq!(read_vdso(Pid::Slf, &range).unwrap(), image);
+
+        let (addr, vdso_addr) = find_vdso_clock_gettime().unwrap();
+        assert!(range.contains(&addr));
+
+        let parser = vdso_parser(Pid::Slf, &range).unwrap();
+        let (name, sym_addr) = parser.find_symbol(vdso_addr, STT_FUNC).unwrap();
+        assert!(name.ends_with("clock_gettime"), "{name}");
+        assert_eq!(sym_addr, vdso_addr);
+    }
+}
@@ -8,14 +8,12 @@
 /// operate on pointers to such structures and their members, we
 /// declare the types as packed.
 use std::ffi::OsStr;
-use std::fs::File;
 use std::io::Error;
 use std::io::ErrorKind;
 use std::io::Result;
 use std::mem::size_of;
 use std::os::unix::ffi::OsStrExt as _;
 use std::path::Path;
-use std::rc::Rc;
 
 use crate::mmap::Mmap;
 use crate::util::Pod;
@@ -199,10 +197,9 @@ impl<'archive> EntryIter<'archive> {
                 )))
             }
 
-            let name = iter
+            let _name = iter
                 .cd_record_data
                 .read_slice(cdfh.file_name_length.into())?;
-            let name = OsStr::from_bytes(name);
 
             let _extra = iter
                 .cd_record_data
@@ -253,6 +250,7 @@ pub(crate) struct Archive {
 
 impl Archive {
     /// Open a zip archive at the provided `path`.
+    #[cfg(test)]
     pub fn open<P>(path: P) -> Result<Self>
     where
         P: AsRef<Path>,
@@ -1,20 +1,34 @@
 #![allow(clippy::let_and_return, clippy::let_unit_value)]
 
 use std::ffi::CString;
+use std::fs::copy;
+use std::fs::create_dir;
+use std::fs::create_dir_all;
 use std::fs::read as read_file;
+use std::fs::File;
 use std::io::Error;
 use std::io::ErrorKind;
+use std::io::Result;
+use std::io::Write as _;
 use std::os::unix::ffi::OsStringExt as _;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
 
 use blazesym::inspect;
 use blazesym::inspect::Inspector;
 use blazesym::normalize::Normalizer;
+use blazesym::normalize::UserAddrMeta;
+use blazesym::snippet::SnippetLoader;
 use blazesym::symbolize;
 use blazesym::symbolize::Symbolizer;
 use blazesym::Addr;
 use blazesym::Pid;
 
+use tempfile::tempdir;
+use tempfile::NamedTempFile;
+
 
 /// Make sure that we fail symbolization when providing a non-existent source.
 #[test]
@@ -74,6 +88,379 @@ fn symbolize_dwarf() {
     assert_eq!(result.symbol, "factorial");
 }
 
+/// Check that we can symbolize an address in a 32 bit ELF file.
+#[test]
+fn symbolize_elf32() {
+    let test_elf32 = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-stable-addresses-32.bin");
+    let src = symbolize::Source::Elf(symbolize::Elf::new(test_elf32));
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &[0x2000100])
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+
+    let result = results.first().unwrap();
+    assert_eq!(result.symbol, "factorial");
+    assert_eq!(result.path.file_name().unwrap(), "test-stable-addresses.c");
+    assert_ne!(result.line, 0);
+}
+
+/// Check that we can load the source code surrounding a symbolized
+/// address, with its build time path mapped to a local one.
+#[test]
+fn symbolize_dwarf_snippet() {
+    let test_dwarf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-dwarf.bin");
+    let src = symbolize::Source::Elf(symbolize::Elf::new(test_dwarf));
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &[0x2000100])
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    let result = results.first().unwrap();
+
+    // Emulate a local checkout in a location different from the one
+    // the binary was built in.
+    let file_name = result.path.file_name().unwrap();
+    let dir = tempdir().unwrap();
+    let local_src = dir.path().join(file_name);
+    let data_src = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join(file_name);
+    let _size = copy(data_src, &local_src).unwrap();
+
+    let loader = SnippetLoader::builder()
+        .add_path_substitution(result.path.parent().unwrap(), dir.path())
+        .set_context_lines(1)
+        .build();
+    let snippet = loader.load_symbolized(result).unwrap();
+    assert_eq!(snippet.path, local_src);
+    assert_eq!(snippet.line, result.line);
+    assert_eq!(snippet.first_line, result.line - 1);
+    assert_eq!(snippet.lines.len(), 3);
+    assert_eq!(snippet.lines[1], "factorial(unsigned int n) {");
+}
+
+/// Check that a single `Symbolizer` can be used from multiple threads
+/// concurrently.
+#[test]
+fn symbolize_from_multiple_threads() {
+    let test_dwarf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-dwarf.bin");
+    let symbolizer = Arc::new(Symbolizer::new());
+
+    let threads = (0..4)
+        .map(|_| {
+            let symbolizer = Arc::clone(&symbolizer);
+            let test_dwarf = test_dwarf.clone();
+            thread::spawn(move || {
+                let src = symbolize::Source::Elf(symbolize::Elf::new(test_dwarf));
+                let results = symbolizer
+                    .symbolize(&src, &[0x2000100])
+                    .unwrap()
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                assert_eq!(results.len(), 1);
+
+                let result = results.first().unwrap();
+                assert_eq!(result.symbol, "factorial");
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        let () = thread.join().unwrap();
+    }
+}
+
+/// Check that we report functions inlined at an address.
+#[test]
+fn symbolize_dwarf_inlined_fns() {
+    let test_inlined = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-inlined-dwarf-v4.bin");
+
+    let src = inspect::Source::Elf(inspect::Elf::new(&test_inlined));
+    let inspector = Inspector::new();
+    let results = inspector
+        .lookup(&["main"], &src)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    let main = results.first().unwrap();
+    let addrs = (main.addr..main.addr + main.size).collect::<Vec<_>>();
+
+    let src = symbolize::Source::Elf(symbolize::Elf::new(&test_inlined));
+    let symbolizer = Symbolizer::builder().enable_inlined_fns(true).build();
+    let results = symbolizer
+        .symbolize(&src, &addrs)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let result = results
+        .iter()
+        .find(|result| result.inlined.len() == 2)
+        .unwrap();
+    assert_eq!(result.symbol, "main");
+
+    let names = result
+        .inlined
+        .iter()
+        .map(|inlined| inlined.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["inlined_outer", "inlined_call"]);
+    let lines = result
+        .inlined
+        .iter()
+        .map(|inlined| inlined.call_line)
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [23, 17]);
+
+    // No inlined functions should be reported by default.
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &addrs)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|result| result.inlined.is_empty()));
+}
+
+/// Check that we report functions inlined at an address using Gsym inline
+/// information.
+#[test]
+fn symbolize_gsym_inlined_fns() {
+    let data_dir = Path::new(&env!("CARGO_MANIFEST_DIR")).join("data");
+    let src = inspect::Source::Elf(inspect::Elf::new(
+        data_dir.join("test-inlined-dwarf-v4.bin"),
+    ));
+    let inspector = Inspector::new();
+    let results = inspector
+        .lookup(&["main"], &src)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    let main = results.first().unwrap();
+    let addrs = (main.addr..main.addr + main.size).collect::<Vec<_>>();
+
+    let src = symbolize::Source::Gsym(symbolize::Gsym::new(data_dir.join("test-inlined.gsym")));
+    let symbolizer = Symbolizer::builder().enable_inlined_fns(true).build();
+    let results = symbolizer
+        .symbolize(&src, &addrs)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let result = results
+        .iter()
+        .find(|result| result.inlined.len() == 2)
+        .unwrap();
+    assert_eq!(result.symbol, "main");
+
+    let names = result
+        .inlined
+        .iter()
+        .map(|inlined| inlined.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["inlined_outer", "inlined_call"]);
+    let lines = result
+        .inlined
+        .iter()
+        .map(|inlined| inlined.call_line)
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [23, 17]);
+}
+
+/// Check that we demangle symbol names, if requested.
+#[test]
+fn symbolize_demangled() {
+    let test_mangled = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-mangled.bin");
+
+    let src = inspect::Source::Elf(inspect::Elf::new(&test_mangled));
+    let inspector = Inspector::new();
+    let addrs = inspector
+        .lookup(
+            &[
+                "_ZN4test12cpp_functionEi",
+                "_RNvCs15kBYyAo9fc_4test13rust_function",
+            ],
+            &src,
+        )
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .map(|sym| sym.addr)
+        .collect::<Vec<_>>();
+    assert_eq!(addrs.len(), 2);
+
+    let src = symbolize::Source::Elf(symbolize::Elf::new(&test_mangled));
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &addrs)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].symbol, "test::cpp_function(int)");
+    assert_eq!(results[0].raw_symbol, "_ZN4test12cpp_functionEi");
+    assert_eq!(results[1].symbol, "test::rust_function");
+    assert_eq!(
+        results[1].raw_symbol,
+        "_RNvCs15kBYyAo9fc_4test13rust_function"
+    );
+
+    let symbolizer = Symbolizer::builder().enable_demangling(false).build();
+    let results = symbolizer
+        .symbolize(&src, &addrs)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|result| result.symbol == result.raw_symbol));
+}
+
+/// Check that we pick up debug information from a separate debug file
+/// referenced via `.gnu_debuglink`.
+#[test]
+fn symbolize_debug_link() {
+    let test_dbg = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-debug-link.dbg");
+    let test_bin = test_dbg.with_file_name("test-debug-link.bin");
+
+    let src = inspect::Source::Elf(inspect::Elf::new(&test_dbg));
+    let inspector = Inspector::new();
+    let results = inspector
+        .lookup(&["fibonacci"], &src)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    let fibonacci = results.first().unwrap();
+
+    // The binary itself is stripped, so both the symbol and the source
+    // location have to come from the debug file.
+    let src = symbolize::Source::Elf(symbolize::Elf::new(&test_bin));
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &[fibonacci.addr])
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+
+    let result = results.first().unwrap();
+    assert_eq!(result.symbol, "fibonacci");
+    assert_eq!(result.path.file_name().unwrap(), "test-exe.c");
+    assert_ne!(result.line, 0);
+}
+
+/// Check that we pick up a separate debug file referenced via
+/// `.gnu_debuglink` when symbolizing addresses in a process.
+#[test]
+fn symbolize_process_debug_link() {
+    let test_so = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("libtest-so-debug-link.so");
+    let so_cstr = CString::new(test_so.clone().into_os_string().into_vec()).unwrap();
+    let handle = unsafe { libc::dlopen(so_cstr.as_ptr(), libc::RTLD_NOW) };
+    assert!(!handle.is_null());
+
+    let the_answer_addr = unsafe { libc::dlsym(handle, "the_answer\0".as_ptr().cast()) };
+    assert!(!the_answer_addr.is_null());
+
+    let src = symbolize::Source::Process(symbolize::Process::new(Pid::Slf));
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &[the_answer_addr as Addr])
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let rc = unsafe { libc::dlclose(handle) };
+    assert_eq!(rc, 0, "{}", Error::last_os_error());
+    assert_eq!(results.len(), 1);
+
+    // The shared object is stripped of debug information, so the
+    // source location has to come from the debug file next to it.
+    let result = results.first().unwrap();
+    assert_eq!(result.symbol, "the_answer");
+    assert_eq!(result.path.file_name().unwrap(), "test-so.c");
+    assert_ne!(result.line, 0);
+}
+
+/// Check that we can symbolize a file offset inside an APK.
+#[test]
+fn symbolize_apk() {
+    let test_so = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("libtest-so.so");
+    let test_zip = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test.zip");
+
+    // The shared object is stored uncompressed inside the archive, so we
+    // can find its offset by looking for its contents.
+    let so_data = read_file(&test_so).unwrap();
+    let zip_data = read_file(&test_zip).unwrap();
+    let so_offset = zip_data
+        .windows(so_data.len())
+        .position(|window| window == so_data)
+        .unwrap();
+
+    let src = inspect::Source::Elf(inspect::Elf::new(&test_so));
+    let inspector = Inspector::new();
+    let results = inspector
+        .lookup(&["the_answer"], &src)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    let the_answer = results.first().unwrap();
+
+    let src = symbolize::Source::Apk(symbolize::Apk::new(test_zip));
+    let symbolizer = Symbolizer::new();
+    let offset = so_offset + the_answer.file_offset as usize;
+    let results = symbolizer
+        .symbolize(&src, &[offset as Addr])
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+
+    let result = results.first().unwrap();
+    assert_eq!(result.symbol, "the_answer");
+    assert_eq!(result.addr, the_answer.addr);
+}
+
 /// Check that we can symbolize addresses inside our own process.
 #[test]
 fn symbolize_process() {
@@ -92,7 +479,279 @@ fn symbolize_process() {
     assert!(result.symbol.contains("symbolize_process"), "{result:x?}");
 
     let result = &results[1];
-    assert!(result.symbol.contains("Symbolizer3new"), "{result:x?}");
+    assert_eq!(
+        result.symbol, "blazesym::symbolize::symbolizer::Symbolizer::new",
+        "{result:x?}"
+    );
+    assert!(result.raw_symbol.contains("Symbolizer3new"), "{result:x?}");
+}
+
+/// Check that we can symbolize addresses inside our own process using a
+/// snapshot of its memory mappings.
+#[test]
+fn symbolize_process_snapshot() {
+    let symbolizer = Symbolizer::new();
+    let snapshot = symbolizer.snapshot_process(Pid::Slf).unwrap();
+    let addrs = [symbolize_process_snapshot as Addr, Symbolizer::new as Addr];
+
+    // Symbolize more than once, to make sure that the snapshot can be
+    // reused.
+    for _ in 0..2 {
+        let results = symbolizer
+            .symbolize_snapshot(&snapshot, &addrs)
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+
+        let result = &results[0];
+        assert!(
+            result.symbol.contains("symbolize_process_snapshot"),
+            "{result:x?}"
+        );
+
+        let result = &results[1];
+        assert_eq!(
+            result.symbol, "blazesym::symbolize::symbolizer::Symbolizer::new",
+            "{result:x?}"
+        );
+    }
+}
+
+/// Check that we can symbolize addresses using a perf map file.
+#[test]
+fn symbolize_perf_map() {
+    let mut file = NamedTempFile::new().unwrap();
+    let () = writeln!(
+        file,
+        "7f0000001000 40 LazyCompile:~main /app/main.js:1
+7f0000001040 20 Builtin:ArgumentsAdaptorTrampoline"
+    )
+    .unwrap();
+
+    let src = symbolize::Source::PerfMap(symbolize::PerfMap::new(file.path()));
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(&src, &[0x7f0000001010, 0x7f0000001044, 0x7f0000002000])
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].len(), 1);
+    assert_eq!(results[0][0].symbol, "LazyCompile:~main /app/main.js:1");
+    assert_eq!(results[0][0].addr, 0x7f0000001000);
+    assert_eq!(results[1].len(), 1);
+    assert_eq!(results[1][0].symbol, "Builtin:ArgumentsAdaptorTrampoline");
+    assert!(results[2].is_empty());
+}
+
+/// Check that addresses in anonymous executable mappings of a process
+/// are symbolized using the process' perf map.
+#[test]
+fn symbolize_process_perf_map() {
+    let size = 4096;
+    let addr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            libc::PROT_READ | libc::PROT_EXEC,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    assert_ne!(addr, libc::MAP_FAILED);
+    let addr = addr as Addr;
+
+    let path = format!("/tmp/perf-{}.map", std::process::id());
+    let mut file = std::fs::File::create(&path).unwrap();
+    let () = writeln!(file, "{addr:x} 100 jitted_function").unwrap();
+
+    let src = symbolize::Source::Process(symbolize::Process::new(Pid::Slf));
+    let symbolizer = Symbolizer::new();
+    let result = symbolizer.symbolize(&src, &[addr + 0x10, addr + 0x200]);
+    let () = std::fs::remove_file(&path).unwrap();
+    let rc = unsafe { libc::munmap(addr as *mut _, size) };
+    assert_eq!(rc, 0);
+
+    let results = result.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].len(), 1);
+    assert_eq!(results[0][0].symbol, "jitted_function");
+    assert_eq!(results[0][0].addr, addr);
+    assert!(results[1].is_empty());
+}
+
+/// Check that process snapshots cover anonymous executable mappings
+/// and pick up new mappings when refreshed.
+#[test]
+fn symbolize_process_snapshot_perf_map() {
+    let symbolizer = Symbolizer::new();
+    let mut snapshot = symbolizer.snapshot_process(Pid::Slf).unwrap();
+
+    let size = 4096;
+    let addr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            libc::PROT_READ | libc::PROT_EXEC,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    assert_ne!(addr, libc::MAP_FAILED);
+    let addr = addr as Addr;
+
+    let path = format!("/tmp/perf-{}.map", std::process::id());
+    let mut file = File::create(&path).unwrap();
+    let () = writeln!(file, "{addr:x} 100 jitted_function").unwrap();
+
+    // The mapping was created after the snapshot was taken and so it
+    // is unknown to it.
+    let stale = symbolizer.symbolize_snapshot(&snapshot, &[addr + 0x10]);
+    let refreshed = symbolizer
+        .refresh_snapshot(&mut snapshot)
+        .and_then(|()| symbolizer.symbolize_snapshot(&snapshot, &[addr + 0x10, addr + 0x200]));
+    let () = std::fs::remove_file(&path).unwrap();
+    let rc = unsafe { libc::munmap(addr as *mut _, size) };
+    assert_eq!(rc, 0);
+
+    let results = stale.unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_empty());
+
+    let results = refreshed.unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].len(), 1);
+    assert_eq!(results[0][0].symbol, "jitted_function");
+    assert_eq!(results[0][0].addr, addr);
+    assert!(results[1].is_empty());
+}
+
+/// Symbolize addresses inside of the kernel module `module`, which is
+/// expected to be a (possibly compressed) version of `test-kmod.ko`.
+///
+/// If `module` is `None`, no modules directory is provided and
+/// symbolization has to rely on kallsyms alone.
+fn symbolize_kernel_module_impl(module: Option<&str>) {
+    let ko = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-kmod.ko");
+
+    // Determine the addresses of the module's functions, to base our
+    // fake kallsyms on.
+    let inspector = Inspector::new();
+    let src = inspect::Source::Elf(inspect::Elf::new(&ko));
+    let results = inspector
+        .lookup(&["kmod_add", "kmod_work", "kmod_init"], &src)
+        .unwrap();
+    let addrs = results
+        .iter()
+        .map(|syms| syms.first().unwrap().addr)
+        .collect::<Vec<_>>();
+    let (add, work, init) = (addrs[0], addrs[1], addrs[2]);
+
+    // Load the module's text and init text sections at unrelated
+    // addresses.
+    let text_base = 0xffffffffc0a00000 - add;
+    let init_base = 0xffffffffc0b00000 - init;
+    let mut kallsyms = NamedTempFile::new().unwrap();
+    let () = writeln!(
+        kallsyms,
+        "ffffffff81000000 T _stext
+{:x} T kmod_add\t[test_kmod]
+{:x} T kmod_work\t[test_kmod]
+{:x} t kmod_init\t[test_kmod]",
+        text_base + add,
+        text_base + work,
+        init_base + init,
+    )
+    .unwrap();
+
+    let modules_dir = tempdir().unwrap();
+    let () = create_dir(modules_dir.path().join("extra")).unwrap();
+    if let Some(module) = module {
+        let _size = copy(
+            ko.with_file_name(module),
+            modules_dir.path().join("extra").join(module),
+        )
+        .unwrap();
+    }
+
+    let mut kernel = symbolize::Kernel::default();
+    kernel.kallsyms = Some(kallsyms.path().to_path_buf());
+    kernel.modules_dir = module.map(|_| modules_dir.path().to_path_buf());
+    let src = symbolize::Source::Kernel(kernel);
+    let symbolizer = Symbolizer::new();
+    let results = symbolizer
+        .symbolize(
+            &src,
+            &[
+                0xffffffff81000010,
+                text_base + work + 4,
+                init_base + init + 2,
+            ],
+        )
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+
+    let result = &results[0];
+    assert_eq!(result.symbol, "_stext");
+    assert_eq!(result.module, None);
+
+    let result = &results[1];
+    assert_eq!(result.symbol, "kmod_work");
+    assert_eq!(result.addr, text_base + work);
+    assert_eq!(result.module.as_deref(), Some("test_kmod"));
+    if module.is_some() {
+        assert!(result.path.ends_with("test-kmod.c"), "{result:?}");
+        assert!((15..=18).contains(&result.line), "{result:?}");
+    } else {
+        assert_eq!(result.path, Path::new(""), "{result:?}");
+    }
+
+    let result = &results[2];
+    assert_eq!(result.symbol, "kmod_init");
+    assert_eq!(result.addr, init_base + init);
+    assert_eq!(result.module.as_deref(), Some("test_kmod"));
+    if module.is_some() {
+        assert!(result.path.ends_with("test-kmod.c"), "{result:?}");
+        assert!((21..=23).contains(&result.line), "{result:?}");
+    } else {
+        assert_eq!(result.path, Path::new(""), "{result:?}");
+    }
+}
+
+/// Check that we can symbolize addresses inside of kernel modules.
+#[test]
+fn symbolize_kernel_module() {
+    symbolize_kernel_module_impl(Some("test-kmod.ko"))
+}
+
+/// Check that we can symbolize addresses inside of kernel modules based
+/// on kallsyms alone, if no modules directory is provided.
+#[test]
+fn symbolize_kernel_module_kallsyms_only() {
+    symbolize_kernel_module_impl(None)
+}
+
+/// Check that we can symbolize addresses inside of gzip compressed
+/// kernel modules.
+#[cfg(feature = "zlib")]
+#[test]
+fn symbolize_kernel_module_gzip() {
+    symbolize_kernel_module_impl(Some("test-kmod.ko.gz"))
+}
+
+/// Check that we can symbolize addresses inside of zstd compressed
+/// kernel modules.
+#[cfg(feature = "zstd")]
+#[test]
+fn symbolize_kernel_module_zstd() {
+    symbolize_kernel_module_impl(Some("test-kmod.ko.zst"))
 }
 
 /// Check that we can normalize user addresses in our own shared object.
@@ -141,6 +800,125 @@ fn normalize_user_addr() {
 }
 
 
+/// Check that we can symbolize normalized addresses, locating the
+/// binaries they belong to via different means.
+#[test]
+fn symbolize_normalized() {
+    let test_so = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("libtest-so.so");
+    let so_cstr = CString::new(test_so.clone().into_os_string().into_vec()).unwrap();
+    let handle = unsafe { libc::dlopen(so_cstr.as_ptr(), libc::RTLD_NOW) };
+    assert!(!handle.is_null());
+
+    let the_answer_addr = unsafe { libc::dlsym(handle, "the_answer\0".as_ptr().cast()) };
+    assert!(!the_answer_addr.is_null());
+
+    // The very first page of the address space should never be mapped.
+    let addrs = [0x500 as Addr, the_answer_addr as Addr];
+    let normalizer = Normalizer::new();
+    let mut norm_addrs = normalizer
+        .normalize_user_addrs_sorted(addrs.as_slice(), Pid::Slf)
+        .unwrap();
+    let rc = unsafe { libc::dlclose(handle) };
+    assert_eq!(rc, 0, "{}", Error::last_os_error());
+
+    let symbolizer = Symbolizer::new();
+    let check = |results: Vec<Vec<symbolize::SymbolizedResult>>, found: bool| {
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_empty());
+        if found {
+            assert_eq!(results[1].len(), 1);
+            assert_eq!(results[1][0].symbol, "the_answer");
+        } else {
+            assert!(results[1].is_empty());
+        }
+    };
+
+    let local = symbolize::LocalPath::new();
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&local])
+        .unwrap();
+    let () = check(results, true);
+
+    // Pretend the binary was found at a path that does not exist
+    // locally.
+    let meta_idx = norm_addrs.addrs[1].1;
+    let build_id = if let UserAddrMeta::Binary(binary) = &mut norm_addrs.meta[meta_idx] {
+        binary.path = Path::new("/does-not-exist/libtest-so.so").to_path_buf();
+        binary.build_id.clone().unwrap()
+    } else {
+        panic!("unexpected meta: {:?}", norm_addrs.meta[meta_idx])
+    };
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&local])
+        .unwrap();
+    let () = check(results, false);
+
+    let hex = build_id
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    let dir = tempdir().unwrap();
+    let build_id_dir = dir.path().join(".build-id").join(&hex[..2]);
+    let () = create_dir_all(&build_id_dir).unwrap();
+    let _size = copy(&test_so, build_id_dir.join(&hex[2..])).unwrap();
+    let build_id_locator = symbolize::BuildIdDir::new(dir.path());
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&local, &build_id_locator])
+        .unwrap();
+    let () = check(results, true);
+
+    // A binary with a mismatching build ID should be ignored.
+    let cache_dir = dir.path().join("debuginfod");
+    let () = create_dir_all(cache_dir.join(&hex)).unwrap();
+    let other_so = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("libtest-so-no-separate-code.so");
+    let _size = copy(other_so, cache_dir.join(&hex).join("executable")).unwrap();
+    let cache_locator = symbolize::DebuginfodCache::new(&cache_dir);
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&cache_locator])
+        .unwrap();
+    let () = check(results, false);
+
+    let _size = copy(&test_so, cache_dir.join(&hex).join("executable")).unwrap();
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&cache_locator])
+        .unwrap();
+    let () = check(results, true);
+
+    // Failing locators as well as located files that are not valid
+    // ELF binaries should be skipped over.
+    #[derive(Debug)]
+    struct Failing;
+
+    impl symbolize::Locator for Failing {
+        fn locate(
+            &self,
+            _path: Option<&Path>,
+            _build_id: Option<&[u8]>,
+        ) -> Result<Option<PathBuf>> {
+            Err(Error::new(ErrorKind::Other, "locator failure"))
+        }
+    }
+
+    let invalid_dir = dir.path().join("invalid");
+    let () = create_dir_all(invalid_dir.join(&hex)).unwrap();
+    let mut file = File::create(invalid_dir.join(&hex).join("executable")).unwrap();
+    let () = file.write_all(b"not an ELF file").unwrap();
+    let invalid_locator = symbolize::DebuginfodCache::new(&invalid_dir);
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&Failing, &invalid_locator])
+        .unwrap();
+    let () = check(results, false);
+
+    let results = symbolizer
+        .symbolize_normalized(&norm_addrs, &[&Failing, &invalid_locator, &cache_locator])
+        .unwrap();
+    let () = check(results, true);
+}
+
 /// Check that we can look up an address.
 #[test]
 fn inspect() {
@@ -180,6 +958,129 @@ fn inspect() {
 }
 
 
+/// Check that we can look up symbols by their demangled names.
+#[test]
+fn inspect_demangled() {
+    let test_mangled = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-mangled.bin");
+    let src = inspect::Source::Elf(inspect::Elf::new(test_mangled));
+    let inspector = Inspector::new();
+    let results = inspector
+        .lookup(
+            &[
+                "_ZN4test12cpp_functionEi",
+                "test::cpp_function(int)",
+                "test::rust_function",
+                "test::does_not_exist",
+                "test::cpp_function",
+            ],
+            &src,
+        )
+        .unwrap();
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0].len(), 1);
+    assert_eq!(results[1].len(), 1);
+    assert_eq!(results[0][0].addr, results[1][0].addr);
+    assert_eq!(results[1][0].name, "test::cpp_function(int)");
+    assert_eq!(results[2].len(), 1);
+    assert_ne!(results[2][0].addr, results[0][0].addr);
+    assert!(results[3].is_empty());
+    assert_eq!(results[4].len(), 1);
+    assert_eq!(results[4][0].addr, results[0][0].addr);
+}
+
+
+/// Check that we can look up kernel symbols by name.
+#[test]
+fn inspect_kernel() {
+    let mut kallsyms = NamedTempFile::new().unwrap();
+    let () = writeln!(
+        kallsyms,
+        "ffffffff81000000 T _stext
+ffffffff81000100 t vfs_read
+ffffffff81000180 T vfs_write
+ffffffff81000200 t helper
+ffffffff81000280 t helper
+ffffffff82000000 D init_task
+ffffffffc0a01000 t kmod_add\t[test_kmod]
+ffffffffc0a01020 T kmod_work\t[test_kmod]"
+    )
+    .unwrap();
+
+    let test_elf = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-stable-addresses.bin");
+    let mut kernel = inspect::Kernel::default();
+    kernel.kallsyms = Some(kallsyms.path().to_path_buf());
+    kernel.kernel_image = Some(test_elf.clone());
+    let src = inspect::Source::Kernel(kernel);
+
+    let inspector = Inspector::new();
+    let results = inspector
+        .lookup(
+            &[
+                "vfs_read",
+                "helper",
+                "init_task",
+                "kmod_add",
+                "factorial",
+                "does_not_exist",
+            ],
+            &src,
+        )
+        .unwrap();
+    assert_eq!(results.len(), 6);
+
+    assert_eq!(results[0].len(), 1);
+    let sym = &results[0][0];
+    assert_eq!(sym.name, "vfs_read");
+    assert_eq!(sym.addr, 0xffffffff81000100);
+    assert_eq!(sym.size, 0x80);
+    assert_eq!(sym.sym_type, inspect::SymType::Function);
+    assert_eq!(sym.module, None);
+    assert_eq!(sym.obj_file_name.as_deref(), Some(kallsyms.path()));
+
+    // Both instances of static functions with the same name are
+    // reported.
+    assert_eq!(results[1].len(), 2);
+    assert_ne!(results[1][0].addr, results[1][1].addr);
+
+    assert_eq!(results[2].len(), 1);
+    assert_eq!(results[2][0].sym_type, inspect::SymType::Variable);
+
+    assert_eq!(results[3].len(), 1);
+    assert_eq!(results[3][0].module.as_deref(), Some("test_kmod"));
+
+    // Symbols not present in kallsyms are looked up in the kernel
+    // image.
+    assert_eq!(results[4].len(), 1);
+    assert_eq!(results[4][0].addr, 0x2000100);
+    assert_eq!(
+        results[4][0].obj_file_name.as_deref(),
+        Some(test_elf.as_path())
+    );
+
+    assert!(results[5].is_empty());
+
+    let results = inspector
+        .lookup_glob(&["vfs_*", "kmod_[a-z]*", "factoria?", "nothing*"], &src)
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    fn names(syms: &[inspect::SymInfo]) -> Vec<&str> {
+        syms.iter().map(|sym| sym.name.as_str()).collect()
+    }
+    assert_eq!(names(&results[0]), vec!["vfs_read", "vfs_write"]);
+    assert_eq!(names(&results[1]), vec!["kmod_add", "kmod_work"]);
+    assert_eq!(names(&results[2]), vec!["factorial"]);
+    assert!(results[3].is_empty());
+
+    let src = inspect::Source::Elf(inspect::Elf::new(test_elf));
+    let err = inspector.lookup_glob(&["fact*"], &src).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
+
+
 /// Read four bytes at the given `offset` in the file identified by `path`.
 fn read_4bytes_at(path: &Path, offset: u64) -> [u8; 4] {
     let offset = offset as usize;
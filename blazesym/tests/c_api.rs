@@ -2,6 +2,7 @@
 
 use std::ffi::CStr;
 use std::ffi::CString;
+use std::os::unix::ffi::OsStringExt as _;
 use std::path::Path;
 use std::ptr;
 use std::slice;
@@ -9,15 +10,24 @@ use std::slice;
 use blazesym::inspect;
 
 use blazesym::c_api::blaze_inspect_elf_src;
+use blazesym::c_api::blaze_inspect_gsym_src;
 use blazesym::c_api::blaze_inspect_syms_elf;
 use blazesym::c_api::blaze_inspect_syms_free;
+use blazesym::c_api::blaze_inspect_syms_gsym;
 use blazesym::c_api::blaze_inspector_free;
 use blazesym::c_api::blaze_inspector_new;
 use blazesym::c_api::blaze_normalize_user_addrs;
 use blazesym::c_api::blaze_normalize_user_addrs_sorted;
 use blazesym::c_api::blaze_normalizer_free;
 use blazesym::c_api::blaze_normalizer_new;
+use blazesym::c_api::blaze_path_subst;
 use blazesym::c_api::blaze_result_free;
+use blazesym::c_api::blaze_snippet_free;
+use blazesym::c_api::blaze_snippet_load;
+use blazesym::c_api::blaze_snippet_loader_free;
+use blazesym::c_api::blaze_snippet_loader_new;
+use blazesym::c_api::blaze_snippet_loader_new_opts;
+use blazesym::c_api::blaze_snippet_loader_opts;
 use blazesym::c_api::blaze_symbolize_elf;
 use blazesym::c_api::blaze_symbolize_gsym;
 use blazesym::c_api::blaze_symbolize_process;
@@ -44,9 +54,15 @@ fn symbolizer_creation() {
 /// provided options.
 #[test]
 fn symbolizer_creation_with_opts() {
+    let debug_dir = CString::new("/usr/lib/debug").unwrap();
+    let debug_dirs = [debug_dir.as_ptr()];
     let opts = blaze_symbolizer_opts {
         debug_syms: true,
         src_location: false,
+        inlined_fns: true,
+        demangle: true,
+        debug_dirs: debug_dirs.as_ptr(),
+        debug_dirs_len: debug_dirs.len(),
     };
     let symbolizer = unsafe { blaze_symbolizer_new_opts(&opts) };
     let () = unsafe { blaze_symbolizer_free(symbolizer) };
@@ -83,6 +99,74 @@ fn symbolize_from_elf() {
         unsafe { CStr::from_ptr(sym.symbol) },
         CStr::from_bytes_with_nul(b"factorial\0").unwrap()
     );
+    assert_eq!(
+        unsafe { CStr::from_ptr(sym.raw_symbol) },
+        CStr::from_bytes_with_nul(b"factorial\0").unwrap()
+    );
+
+    let () = unsafe { blaze_result_free(result) };
+    let () = unsafe { blaze_symbolizer_free(symbolizer) };
+}
+
+
+/// Make sure that we report inlined functions through the C API.
+#[test]
+fn symbolize_inlined_fns_from_elf() {
+    let test_inlined = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test-inlined-dwarf-v4.bin");
+    let test_inlined_c = CString::new(test_inlined.to_str().unwrap()).unwrap();
+
+    let src = inspect::Source::Elf(inspect::Elf::new(&test_inlined));
+    let inspector = inspect::Inspector::new();
+    let main = inspector
+        .lookup(&["main"], &src)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .next()
+        .unwrap();
+    let addrs = (main.addr..main.addr + main.size).collect::<Vec<_>>();
+
+    let elf_src = blaze_symbolize_src_elf {
+        path: test_inlined_c.as_ptr(),
+    };
+    let opts = blaze_symbolizer_opts {
+        debug_syms: true,
+        src_location: true,
+        inlined_fns: true,
+        demangle: true,
+        debug_dirs: ptr::null(),
+        debug_dirs_len: 0,
+    };
+    let symbolizer = unsafe { blaze_symbolizer_new_opts(&opts) };
+    let result = unsafe { blaze_symbolize_elf(symbolizer, &elf_src, addrs.as_ptr(), addrs.len()) };
+    assert!(!result.is_null());
+
+    let result = unsafe { &*result };
+    assert_eq!(result.size, addrs.len());
+    let entries = unsafe { slice::from_raw_parts(result.entries.as_ptr(), result.size) };
+    let sym = entries
+        .iter()
+        .flat_map(|entry| unsafe { slice::from_raw_parts(entry.syms, entry.size) })
+        .find(|sym| sym.inlined_cnt == 2)
+        .unwrap();
+    assert_eq!(
+        unsafe { CStr::from_ptr(sym.symbol) },
+        CStr::from_bytes_with_nul(b"main\0").unwrap()
+    );
+
+    let inlined = unsafe { slice::from_raw_parts(sym.inlined, sym.inlined_cnt) };
+    assert_eq!(
+        unsafe { CStr::from_ptr(inlined[0].name) },
+        CStr::from_bytes_with_nul(b"inlined_outer\0").unwrap()
+    );
+    assert_eq!(inlined[0].call_line, 23);
+    assert_eq!(
+        unsafe { CStr::from_ptr(inlined[1].name) },
+        CStr::from_bytes_with_nul(b"inlined_call\0").unwrap()
+    );
+    assert_eq!(inlined[1].call_line, 17);
 
     let () = unsafe { blaze_result_free(result) };
     let () = unsafe { blaze_symbolizer_free(symbolizer) };
@@ -254,3 +338,89 @@ fn lookup_dwarf() {
     let () = unsafe { blaze_inspect_syms_free(result) };
     let () = unsafe { blaze_inspector_free(inspector) };
 }
+
+
+/// Make sure that we can lookup a function's address in a Gsym file.
+#[test]
+fn lookup_gsym() {
+    let test_gsym = Path::new(&env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("test.gsym");
+
+    let src = blaze_inspect_gsym_src::from(inspect::Gsym::new(test_gsym));
+    let factorial = CString::new("factorial").unwrap();
+    let names = [factorial.as_ptr()];
+
+    let inspector = blaze_inspector_new();
+    let result = unsafe { blaze_inspect_syms_gsym(inspector, &src, names.as_ptr(), names.len()) };
+    let _src = inspect::Gsym::from(src);
+
+    let sym_infos = unsafe { slice::from_raw_parts(result, names.len()) };
+    let sym_info = unsafe { &*sym_infos[0] };
+    assert_eq!(
+        unsafe { CStr::from_ptr(sym_info.name) },
+        CStr::from_bytes_with_nul(b"factorial\0").unwrap()
+    );
+    assert_eq!(sym_info.addr, 0x2000100);
+    assert_ne!(sym_info.size, 0);
+
+    let () = unsafe { blaze_inspect_syms_free(result) };
+    let () = unsafe { blaze_inspector_free(inspector) };
+}
+
+
+/// Check that we can load source code snippets via the C API.
+#[test]
+fn snippet_loading() {
+    let data_dir = CString::new(
+        Path::new(&env!("CARGO_MANIFEST_DIR"))
+            .join("data")
+            .into_os_string()
+            .into_vec(),
+    )
+    .unwrap();
+    let from = CString::new("/build").unwrap();
+    let substitutions = [blaze_path_subst {
+        from: from.as_ptr(),
+        to: data_dir.as_ptr(),
+    }];
+    let opts = blaze_snippet_loader_opts {
+        src_roots: ptr::null(),
+        src_roots_len: 0,
+        substitutions: substitutions.as_ptr(),
+        substitutions_len: substitutions.len(),
+        context_lines: 1,
+    };
+    let loader = unsafe { blaze_snippet_loader_new_opts(&opts) };
+    assert!(!loader.is_null());
+
+    let path = CString::new("/build/test-stable-addresses.c").unwrap();
+    let snippet = unsafe { blaze_snippet_load(loader, path.as_ptr(), 8) };
+    assert!(!snippet.is_null());
+
+    let snippet_ref = unsafe { &*snippet };
+    assert_eq!(snippet_ref.line, 8);
+    assert_eq!(snippet_ref.first_line, 7);
+    assert_eq!(snippet_ref.line_cnt, 3);
+    let local_path = unsafe { CStr::from_ptr(snippet_ref.path) };
+    assert!(local_path
+        .to_str()
+        .unwrap()
+        .ends_with("data/test-stable-addresses.c"));
+    let lines = unsafe { slice::from_raw_parts(snippet_ref.lines, snippet_ref.line_cnt) };
+    let line = unsafe { CStr::from_ptr(lines[1]) };
+    assert_eq!(line.to_str().unwrap(), "factorial(unsigned int n) {");
+    let () = unsafe { blaze_snippet_free(snippet) };
+
+    // Out of bounds lines are reported as errors.
+    let snippet = unsafe { blaze_snippet_load(loader, path.as_ptr(), 1000) };
+    assert!(snippet.is_null());
+    let () = unsafe { blaze_snippet_loader_free(loader) };
+
+    // Without substitution the file cannot be found.
+    let loader = blaze_snippet_loader_new();
+    let path = CString::new("/build/test-stable-addresses.c").unwrap();
+    let snippet = unsafe { blaze_snippet_load(loader, path.as_ptr(), 8) };
+    assert!(snippet.is_null());
+    let () = unsafe { blaze_snippet_loader_free(loader) };
+}
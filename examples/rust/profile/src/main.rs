@@ -1,6 +1,7 @@
 use std::u64;
 
 use std::boxed::Box;
+use std::collections::HashMap;
 use std::io::Error;
 use std::mem;
 use std::result::Result;
@@ -72,8 +73,60 @@ fn attach_perf_event(
         .collect()
 }
 
+// Snapshots of the memory mappings of sampled processes, so that we do
+// not have to parse /proc/<pid>/maps again for every sample.
+struct Snapshots {
+    snapshots: HashMap<u32, symbolize::ProcessSnapshot>,
+    // The age after which a snapshot is considered outdated and retaken.
+    max_age: Duration,
+}
+
+impl Snapshots {
+    fn new(max_age: Duration) -> Self {
+        Self {
+            snapshots: HashMap::new(),
+            max_age,
+        }
+    }
+
+    fn get(
+        &mut self,
+        symbolizer: &symbolize::Symbolizer,
+        pid: u32,
+    ) -> Option<&symbolize::ProcessSnapshot> {
+        let max_age = self.max_age;
+        let outdated = self
+            .snapshots
+            .get(&pid)
+            .map(|snapshot| snapshot.age() > max_age)
+            .unwrap_or(true);
+
+        if outdated {
+            // Drop all outdated snapshots, including those of processes
+            // that have exited in the meantime.
+            self.snapshots.retain(|_, snapshot| snapshot.age() <= max_age);
+
+            match symbolizer.snapshot_process(pid.into()) {
+                Ok(snapshot) => {
+                    self.snapshots.insert(pid, snapshot);
+                }
+                Err(err) => {
+                    eprintln!("Failed to snapshot process {}: {}", pid, err);
+                    return None;
+                }
+            }
+        }
+        self.snapshots.get(&pid)
+    }
+}
+
 // Pid 0 means a kernel space stack.
-fn show_stack_trace(stack: &[u64], symbolizer: &symbolize::Symbolizer, pid: u32) {
+fn show_stack_trace(
+    stack: &[u64],
+    symbolizer: &symbolize::Symbolizer,
+    snapshots: &mut Snapshots,
+    pid: u32,
+) {
     let converted_stack;
     // The kernel always reports `u64` addresses, whereas blazesym uses `usize`.
     // Convert the stack trace as necessary.
@@ -90,13 +143,18 @@ fn show_stack_trace(stack: &[u64], symbolizer: &symbolize::Symbolizer, pid: u32)
         unsafe { mem::transmute::<_, &[blazesym::Addr]>(stack) }
     };
 
-    let src = if pid == 0 {
-        symbolize::Source::from(symbolize::Kernel::default())
+    let syms = if pid == 0 {
+        let src = symbolize::Source::from(symbolize::Kernel::default());
+        symbolizer.symbolize(&src, stack)
+    } else if let Some(snapshot) = snapshots.get(symbolizer, pid) {
+        symbolizer.symbolize_snapshot(snapshot, stack)
     } else {
-        symbolize::Source::from(symbolize::Process::new(pid.into()))
+        Ok(Vec::new())
     };
-
-    let syms = symbolizer.symbolize(&src, stack).unwrap();
+    let syms = syms.unwrap_or_else(|err| {
+        eprintln!("Failed to symbolize stack: {}", err);
+        Vec::new()
+    });
     for i in 0..stack.len() {
         if syms.len() <= i || syms[i].len() == 0 {
             println!("  {} [<{:016x}>]", i, stack[i]);
@@ -145,7 +203,11 @@ fn show_stack_trace(stack: &[u64], symbolizer: &symbolize::Symbolizer, pid: u32)
     }
 }
 
-fn event_handler(symbolizer: &symbolize::Symbolizer, data: &[u8]) -> ::std::os::raw::c_int {
+fn event_handler(
+    symbolizer: &symbolize::Symbolizer,
+    snapshots: &mut Snapshots,
+    data: &[u8],
+) -> ::std::os::raw::c_int {
     if data.len() != mem::size_of::<stacktrace_event>() {
         eprintln!(
             "Invalid size {} != {}",
@@ -171,6 +233,7 @@ fn event_handler(symbolizer: &symbolize::Symbolizer, data: &[u8]) -> ::std::os::
         show_stack_trace(
             &event.kstack[0..(event.kstack_size as usize / mem::size_of::<u64>())],
             symbolizer,
+            snapshots,
             0,
         );
     } else {
@@ -182,6 +245,7 @@ fn event_handler(symbolizer: &symbolize::Symbolizer, data: &[u8]) -> ::std::os::
         show_stack_trace(
             &event.ustack[0..(event.ustack_size as usize / mem::size_of::<u64>())],
             symbolizer,
+            snapshots,
             event.pid,
         );
     } else {
@@ -197,6 +261,9 @@ struct Args {
     /// Sampling frequency
     #[clap(short, default_value_t = 1)]
     freq: u64,
+    /// Maximum age of cached process memory mapping snapshots, in seconds
+    #[clap(long, default_value_t = 10)]
+    snapshot_max_age: u64,
 }
 
 fn main() -> Result<(), Error> {
@@ -204,6 +271,7 @@ fn main() -> Result<(), Error> {
     let freq = if args.freq < 1 { 1 } else { args.freq };
 
     let symbolizer = symbolize::Symbolizer::new();
+    let mut snapshots = Snapshots::new(Duration::from_secs(args.snapshot_max_age));
 
     let skel_builder = ProfileSkelBuilder::default();
     let open_skel = skel_builder.open().unwrap();
@@ -215,7 +283,7 @@ fn main() -> Result<(), Error> {
     let mut builder = libbpf_rs::RingBufferBuilder::new();
     builder
         .add(skel.maps().events(), move |data| {
-            event_handler(&symbolizer, data)
+            event_handler(&symbolizer, &mut snapshots, data)
         })
         .unwrap();
     let ringbuf = builder.build().unwrap();